@@ -33,6 +33,8 @@ mod ast;
 mod logger;
 
 use air::execute_air;
+use air::execute_air_multi;
+use air::finalize_signing as finalize_signing_impl;
 use air::InterpreterOutcome;
 use air::RunParameters;
 use marine_rs_sdk::marine;
@@ -55,6 +57,22 @@ pub fn invoke(
     execute_air(air, prev_data, data, params, call_results.into())
 }
 
+#[marine]
+pub fn invoke_multi(
+    air: String,
+    prev_data: Vec<u8>,
+    data: Vec<Vec<u8>>,
+    params: RunParameters,
+    call_results: Vec<u8>,
+) -> InterpreterOutcome {
+    execute_air_multi(air, prev_data, data, params, call_results.into())
+}
+
+#[marine]
+pub fn finalize_signing(data: Vec<u8>, params: RunParameters, signature: Vec<u8>) -> InterpreterOutcome {
+    finalize_signing_impl(data, params, signature)
+}
+
 #[allow(clippy::too_many_arguments)]
 #[marine]
 pub fn invoke_tracing(
@@ -0,0 +1,34 @@
+use air_interpreter_data::verification::DataVerifier;
+use air_interpreter_signatures::KeyFormatWhitelist;
+use air_test_utils::key_utils::dummy_keypairs;
+use air_test_utils::key_utils::signed_data;
+
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+
+const SALT: &str = "particle_id";
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("signature_verification");
+
+    for signers_count in [10, 100, 1000] {
+        // each signer has a single call result in the trace
+        let data = signed_data(&dummy_keypairs(signers_count), SALT, None);
+
+        group.bench_with_input(BenchmarkId::from_parameter(signers_count), &data, |b, data| {
+            b.iter(|| {
+                DataVerifier::new(data, SALT, KeyFormatWhitelist::default())
+                    .unwrap()
+                    .verify()
+                    .unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
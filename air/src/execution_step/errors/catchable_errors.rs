@@ -101,6 +101,11 @@ pub enum CatchableError {
     /// Stream map related errors.
     #[error(transparent)]
     StreamMapError(#[from] StreamMapError),
+
+    /// A call was rejected by the host call policy before a call request was made,
+    /// its error code is `CALL_NOT_AUTHORIZED`.
+    #[error("call is not authorized by the call policy, error message is '{0}'")]
+    CallNotAuthorized(Rc<String>),
 }
 
 impl From<LambdaError> for Rc<CatchableError> {
@@ -29,6 +29,7 @@ use air_interpreter_cid::CidCalculationError;
 use air_interpreter_cid::CidRef;
 use air_interpreter_data::ValueRef;
 use air_interpreter_interface::CallArgumentsRepr;
+use air_interpreter_interface::ProvenanceRepr;
 use air_interpreter_interface::TetrapletsRepr;
 use air_interpreter_sede::Representation;
 use air_trace_handler::GenerationCompactificationError;
@@ -143,6 +144,9 @@ pub enum UncatchableError {
 
     #[error("failed to serialize call arguments {0}")]
     CallArgumentsSerializationFailed(<CallArgumentsRepr as Representation>::SerializeError),
+
+    #[error("failed to serialize argument provenance {0}")]
+    ProvenanceSerializationFailed(<ProvenanceRepr as Representation>::SerializeError),
 }
 
 impl ToErrorCode for UncatchableError {
@@ -27,11 +27,17 @@ use air_interpreter_data::CanonCidAggregate;
 use air_interpreter_data::CanonResultCidAggregate;
 use air_interpreter_data::CidInfo;
 use air_interpreter_data::CidTracker;
+use air_interpreter_data::Provenance;
 use air_interpreter_data::RawValue;
 use air_interpreter_data::ServiceResultCidAggregate;
 use air_interpreter_data::TracePos;
+use air_interpreter_interface::ArgumentProvenance;
+use air_interpreter_interface::CallProvenance;
+use air_interpreter_interface::CanonProvenance;
+use air_interpreter_interface::MAX_PROVENANCE_DEPTH;
 use polyplets::SecurityTetraplet;
 
+use std::collections::VecDeque;
 use std::rc::Rc;
 
 #[derive(Debug, Default, Clone)]
@@ -72,12 +78,11 @@ impl ExecutionCidState {
 
     pub fn track_service_result(
         &mut self,
-        value: JValue,
+        value: impl Into<RawValue>,
         tetraplet: RcSecurityTetraplet,
         argument_hash: Rc<str>,
     ) -> Result<CID<ServiceResultCidAggregate>, UncatchableError> {
-        let vm_value = RawValue::from_value(value);
-        let value_cid = self.value_tracker.track_raw_value(vm_value);
+        let value_cid = self.value_tracker.track_raw_value(value.into());
         let tetraplet_cid = self.tetraplet_tracker.track_value(tetraplet)?;
         let service_result_agg = ServiceResultCidAggregate::new(value_cid, argument_hash, tetraplet_cid);
 
@@ -168,6 +173,92 @@ impl ExecutionCidState {
             service_result_aggregate,
         })
     }
+
+    /// Follow provenances of call arguments through service results and canons down to their
+    /// sources, elements of canons nested deeper than `MAX_PROVENANCE_DEPTH` aren't followed.
+    pub(crate) fn resolve_call_provenance(
+        &self,
+        provenances: &[Provenance],
+    ) -> Result<CallProvenance, UncatchableError> {
+        let mut call_provenance = CallProvenance::default();
+        // canons are expanded breadth-first, so a shared one is expanded at its lowest depth
+        let mut canons = VecDeque::new();
+
+        call_provenance.arguments = provenances
+            .iter()
+            .map(|provenance| self.refer_provenance(provenance, 0, &mut call_provenance, &mut canons))
+            .collect::<Result<_, _>>()?;
+
+        while let Some((cid, depth)) = canons.pop_front() {
+            let cid_key = cid.get_inner().to_string();
+            if call_provenance.canons.contains_key(&cid_key) {
+                continue;
+            }
+
+            let canon_result = self.get_canon_result_by_cid(&cid)?;
+            let tetraplet = self.get_tetraplet_by_cid(&canon_result.tetraplet)?;
+            let values = if depth < MAX_PROVENANCE_DEPTH {
+                let values = canon_result
+                    .values
+                    .iter()
+                    .map(|value_cid| {
+                        let canon_aggregate = self.canon_element_tracker.get(value_cid).ok_or_else(|| {
+                            UncatchableError::ValueForCidNotFound("canon aggregate", value_cid.get_inner())
+                        })?;
+                        self.refer_provenance(
+                            &canon_aggregate.provenance,
+                            depth + 1,
+                            &mut call_provenance,
+                            &mut canons,
+                        )
+                    })
+                    .collect::<Result<_, _>>()?;
+                Some(values)
+            } else {
+                None
+            };
+
+            let canon_provenance = CanonProvenance {
+                tetraplet: tetraplet.as_ref().clone(),
+                values,
+            };
+            call_provenance.canons.insert(cid_key, canon_provenance);
+        }
+
+        Ok(call_provenance)
+    }
+
+    /// Make a reference to the provenance source, a service result is put into the table at once,
+    /// a canon is queued for expansion at the given depth.
+    fn refer_provenance(
+        &self,
+        provenance: &Provenance,
+        depth: usize,
+        call_provenance: &mut CallProvenance,
+        canons: &mut VecDeque<(CID<CanonResultCidAggregate>, usize)>,
+    ) -> Result<ArgumentProvenance, UncatchableError> {
+        match provenance {
+            Provenance::Literal => Ok(ArgumentProvenance::Literal),
+            Provenance::ServiceResult { cid } => {
+                let cid_key = cid.get_inner().to_string();
+                if !call_provenance.service_results.contains_key(&cid_key) {
+                    let service_result_aggregate = self.get_service_result_agg_by_cid(cid)?;
+                    let tetraplet = self.get_tetraplet_by_cid(&service_result_aggregate.tetraplet_cid)?;
+                    call_provenance
+                        .service_results
+                        .insert(cid_key.clone(), tetraplet.as_ref().clone());
+                }
+
+                Ok(ArgumentProvenance::ServiceResult { cid: cid_key })
+            }
+            Provenance::Canon { cid } => {
+                canons.push_back((cid.clone(), depth));
+                let cid_key = cid.get_inner().to_string();
+
+                Ok(ArgumentProvenance::Canon { cid: cid_key })
+            }
+        }
+    }
 }
 
 pub(crate) struct ResolvedServiceInfo {
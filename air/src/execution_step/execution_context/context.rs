@@ -33,10 +33,12 @@ use air_interpreter_cid::CID;
 use air_interpreter_data::CanonResultCidAggregate;
 use air_interpreter_data::CidInfo;
 use air_interpreter_data::ServiceResultCidAggregate;
+use air_interpreter_data::Versions;
 use air_interpreter_interface::*;
 use air_interpreter_signatures::PeerCidTracker;
 use air_interpreter_signatures::SignatureStore;
 
+use std::collections::HashMap;
 use std::rc::Rc;
 
 /// Contains all necessary state needed to execute AIR script.
@@ -83,6 +85,9 @@ pub(crate) struct ExecutionCtx<'i> {
     /// Tracks all functions that should be called from services.
     pub(crate) call_requests: CallRequests,
 
+    /// Ids of call requests by their contents, it's used to emit identical requests once.
+    call_request_ids: HashMap<CallRequestKey, u32>,
+
     /// CID-to-something trackers.
     pub(crate) cid_state: ExecutionCidState,
 
@@ -95,17 +100,27 @@ pub(crate) struct ExecutionCtx<'i> {
     ///
     /// It gathers current peer's CIDs (call results and canon results) for further signing.
     pub(crate) peer_cid_tracker: PeerCidTracker,
+
+    /// Versions of the produced data, they define the salt of its signatures.
+    pub(crate) produced_versions: Versions,
+
+    /// Local services the script is allowed to call.
+    pub(crate) call_policy: CallPolicy,
 }
 
 impl<'i> ExecutionCtx<'i> {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         prev_ingredients: ExecCtxIngredients,
         current_ingredients: ExecCtxIngredients,
         call_results: CallResults,
         signature_store: SignatureStore,
         run_parameters: &RunParameters,
+        salt: &str,
+        produced_versions: Versions,
+        call_policy: CallPolicy,
     ) -> Self {
-        let run_parameters = RcRunParameters::from_run_parameters(run_parameters);
+        let run_parameters = RcRunParameters::from_run_parameters(run_parameters, salt);
         let streams = Streams::new();
 
         let cid_state = ExecutionCidState::from_cid_info(prev_ingredients.cid_info, current_ingredients.cid_info);
@@ -128,6 +143,9 @@ impl<'i> ExecutionCtx<'i> {
             error_descriptor: <_>::default(),
             tracker: <_>::default(),
             call_requests: <_>::default(),
+            call_request_ids: <_>::default(),
+            produced_versions,
+            call_policy,
         }
     }
 
@@ -144,6 +162,40 @@ impl<'i> ExecutionCtx<'i> {
         self.last_call_request_id
     }
 
+    /// Track a call request, if call deduplication is enabled and an identical request was
+    /// already made in this run, the call id is added to its fan-out list instead, so the host
+    /// executes it once.
+    pub(crate) fn add_call_request(&mut self, call_id: u32, request_params: CallRequestParams, argument_hash: Rc<str>) {
+        use std::collections::hash_map::Entry;
+
+        if !self.run_parameters.call_deduplication_enabled {
+            self.call_requests.insert(call_id, request_params);
+            return;
+        }
+
+        let key = CallRequestKey {
+            service_id: request_params.service_id.clone(),
+            function_name: request_params.function_name.clone(),
+            argument_hash,
+            tetraplets: request_params.tetraplets.clone(),
+            provenance: request_params.provenance.clone(),
+        };
+
+        match self.call_request_ids.entry(key) {
+            Entry::Occupied(entry) => {
+                let primary_request = self
+                    .call_requests
+                    .get_mut(entry.get())
+                    .expect("tracked call request ids should be present in call requests");
+                primary_request.fan_out_call_ids.push(call_id);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(call_id);
+                self.call_requests.insert(call_id, request_params);
+            }
+        }
+    }
+
     pub(crate) fn record_call_cid(&mut self, peer_id: &str, cid: &CID<ServiceResultCidAggregate>) {
         self.peer_cid_tracker.register(peer_id, cid);
     }
@@ -153,6 +205,16 @@ impl<'i> ExecutionCtx<'i> {
     }
 }
 
+/// Identifies call requests with the same service, function, arguments and their origin.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct CallRequestKey {
+    service_id: String,
+    function_name: String,
+    argument_hash: Rc<str>,
+    tetraplets: SerializedTetraplets,
+    provenance: SerializedProvenance,
+}
+
 impl ExecutionCtx<'_> {
     pub(crate) fn make_subgraph_incomplete(&mut self) {
         self.subgraph_completeness = false;
@@ -229,16 +291,20 @@ pub(crate) struct RcRunParameters {
     pub(crate) salt: Rc<str>,
     pub(crate) timestamp: u64,
     pub(crate) ttl: u32,
+    pub(crate) call_provenance_enabled: bool,
+    pub(crate) call_deduplication_enabled: bool,
 }
 
 impl RcRunParameters {
-    pub(crate) fn from_run_parameters(run_parameters: &RunParameters) -> Self {
+    pub(crate) fn from_run_parameters(run_parameters: &RunParameters, salt: &str) -> Self {
         Self {
             init_peer_id: run_parameters.init_peer_id.as_str().into(),
             current_peer_id: Rc::new(run_parameters.current_peer_id.clone()),
-            salt: run_parameters.particle_id.as_str().into(),
+            salt: salt.into(),
             timestamp: run_parameters.timestamp,
             ttl: run_parameters.ttl,
+            call_provenance_enabled: run_parameters.call_provenance_enabled,
+            call_deduplication_enabled: run_parameters.call_deduplication_enabled,
         }
     }
 }
@@ -17,6 +17,8 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+mod authorization;
+pub(crate) mod builtins;
 pub(crate) mod call_result_setter;
 mod prev_result_handler;
 mod resolved_call;
@@ -0,0 +1,117 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::execution_step::RcSecurityTetraplets;
+use crate::SecurityTetraplet;
+
+use air_interpreter_interface::CallPolicy;
+use air_interpreter_interface::CallRule;
+
+/// Check a call against the host call policy, a rejection reason is returned on violation.
+pub(super) fn authorize_call(
+    policy: &CallPolicy,
+    tetraplet: &SecurityTetraplet,
+    arg_tetraplets: &[RcSecurityTetraplets],
+) -> Result<(), String> {
+    if policy.is_empty() {
+        return Ok(());
+    }
+
+    let service_id = tetraplet.service_id.as_str();
+    let function_name = tetraplet.function_name.as_str();
+
+    let mut rejection = None;
+    for rule in policy.rules.iter() {
+        if !matches_pattern(&rule.service_id, service_id) || !matches_pattern(&rule.function_name, function_name) {
+            continue;
+        }
+        match check_argument_peers(rule, arg_tetraplets) {
+            Ok(()) => return Ok(()),
+            Err(reason) => {
+                rejection.get_or_insert(reason);
+            }
+        }
+    }
+
+    Err(rejection
+        .unwrap_or_else(|| format!("call of '{service_id}' '{function_name}' is not allowed by the call policy")))
+}
+
+fn check_argument_peers(rule: &CallRule, arg_tetraplets: &[RcSecurityTetraplets]) -> Result<(), String> {
+    if rule.argument_peers.is_empty() {
+        return Ok(());
+    }
+
+    for (position, tetraplets) in arg_tetraplets.iter().enumerate() {
+        for tetraplet in tetraplets {
+            if !rule.argument_peers.iter().any(|peer_id| peer_id == &tetraplet.peer_pk) {
+                return Err(format!(
+                    "argument {position} originates from '{}' that is not allowed by the call policy",
+                    tetraplet.peer_pk
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Match the whole value, `*` matches any sequence of characters.
+fn matches_pattern(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always yields at least one part
+    let first = parts.next().unwrap();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+
+    // there is no `*` in the pattern
+    rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches_pattern;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("service", "service"));
+        assert!(!matches_pattern("service", "service2"));
+        assert!(!matches_pattern("service", "my_service"));
+        assert!(matches_pattern("*", ""));
+        assert!(matches_pattern("*", "service"));
+        assert!(matches_pattern("get_*", "get_value"));
+        assert!(!matches_pattern("get_*", "set_value"));
+        assert!(matches_pattern("*_value", "get_value"));
+        assert!(matches_pattern("g*t*e", "get_value"));
+        assert!(!matches_pattern("g*t*x", "get_value"));
+        assert!(!matches_pattern("ab*ba", "aba"));
+    }
+}
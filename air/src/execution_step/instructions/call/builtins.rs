@@ -0,0 +1,139 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::JValue;
+
+use air_interpreter_interface::CallServiceResult;
+use air_interpreter_interface::CALL_SERVICE_SUCCESS;
+
+/// Reserved id of the service with generic built-in functions.
+pub const BUILTIN_OP_SERVICE_ID: &str = "aquavm.op";
+
+/// Reserved id of the service with built-in string functions.
+pub const BUILTIN_STRING_SERVICE_ID: &str = "aquavm.string";
+
+/// A ret code of built-in calls rejected because of invalid arguments.
+pub const BUILTIN_CALL_FAILED: i32 = i32::MAX - 2;
+
+/// A deterministic function executed by the interpreter itself without a host round trip.
+pub(super) type BuiltinFn = fn(&[JValue]) -> Result<JValue, String>;
+
+/// Look up a built-in function by a reserved service id and a function name.
+pub(super) fn find_builtin(service_id: &str, function_name: &str) -> Option<BuiltinFn> {
+    let builtin: BuiltinFn = match (service_id, function_name) {
+        (BUILTIN_OP_SERVICE_ID, "noop") => noop,
+        (BUILTIN_OP_SERVICE_ID, "identity") => identity,
+        (BUILTIN_OP_SERVICE_ID, "array") => array,
+        (BUILTIN_OP_SERVICE_ID, "concat") => concat,
+        (BUILTIN_OP_SERVICE_ID, "array_length") => array_length,
+        (BUILTIN_STRING_SERVICE_ID, "concat") => concat_strings,
+        (BUILTIN_STRING_SERVICE_ID, "join") => join_strings,
+        _ => return None,
+    };
+
+    Some(builtin)
+}
+
+/// Run a built-in function and wrap its outcome the way a host wraps service results.
+pub(super) fn call_builtin(builtin: BuiltinFn, arguments: &[JValue]) -> CallServiceResult {
+    let (ret_code, result) = match builtin(arguments) {
+        Ok(result) => (CALL_SERVICE_SUCCESS, serde_json::to_string(&result)),
+        Err(message) => (BUILTIN_CALL_FAILED, serde_json::to_string(&message)),
+    };
+
+    CallServiceResult {
+        ret_code,
+        result: result.expect("serializer shouldn't fail"),
+        binary_result: None,
+    }
+}
+
+fn noop(_arguments: &[JValue]) -> Result<JValue, String> {
+    Ok(JValue::Null)
+}
+
+fn identity(arguments: &[JValue]) -> Result<JValue, String> {
+    match arguments {
+        [] => Ok(JValue::Null),
+        [value] => Ok(value.clone()),
+        _ => Err(format!("identity expects at most 1 argument, got {}", arguments.len())),
+    }
+}
+
+fn array(arguments: &[JValue]) -> Result<JValue, String> {
+    Ok(JValue::from(arguments))
+}
+
+fn concat(arguments: &[JValue]) -> Result<JValue, String> {
+    let mut result = Vec::new();
+    for (position, argument) in arguments.iter().enumerate() {
+        let array = argument
+            .as_array()
+            .ok_or_else(|| format!("concat expects arrays, argument {position} is '{argument}'"))?;
+        result.extend_from_slice(array);
+    }
+
+    Ok(JValue::from(result))
+}
+
+fn array_length(arguments: &[JValue]) -> Result<JValue, String> {
+    match arguments {
+        [value] => value
+            .as_array()
+            .map(|array| JValue::from(array.len()))
+            .ok_or_else(|| format!("array_length expects an array, got '{value}'")),
+        _ => Err(format!("array_length expects 1 argument, got {}", arguments.len())),
+    }
+}
+
+fn concat_strings(arguments: &[JValue]) -> Result<JValue, String> {
+    let mut result = String::new();
+    for (position, argument) in arguments.iter().enumerate() {
+        let string = argument
+            .as_str()
+            .ok_or_else(|| format!("concat expects strings, argument {position} is '{argument}'"))?;
+        result.push_str(string);
+    }
+
+    Ok(JValue::from(result))
+}
+
+fn join_strings(arguments: &[JValue]) -> Result<JValue, String> {
+    let (array, separator) = match arguments {
+        [array, separator] => (array, separator),
+        _ => return Err(format!("join expects 2 arguments, got {}", arguments.len())),
+    };
+
+    let separator = separator
+        .as_str()
+        .ok_or_else(|| format!("join expects a string separator, got '{separator}'"))?;
+    let strings = array
+        .as_array()
+        .ok_or_else(|| format!("join expects an array of strings, got '{array}'"))?
+        .iter()
+        .map(|value| {
+            value
+                .as_str()
+                .map(|string| string.as_ref())
+                .ok_or_else(|| format!("join expects an array of strings, got an element '{value}'"))
+        })
+        .collect::<Result<Vec<&str>, _>>()?;
+
+    Ok(JValue::from(strings.join(separator)))
+}
@@ -26,6 +26,7 @@ use crate::UncatchableError;
 
 use air_interpreter_cid::value_to_json_cid;
 use air_interpreter_data::CallResult;
+use air_interpreter_data::RawValue;
 use air_interpreter_data::TracePos;
 use air_interpreter_data::ValueRef;
 use air_parser::ast::CallOutputValue;
@@ -34,6 +35,7 @@ use air_trace_handler::TraceHandler;
 
 pub(crate) fn populate_context_from_peer_service_result<'i>(
     executed_result: ServiceResultAggregate,
+    raw_result: RawValue,
     output: &CallOutputValue<'i>,
     tetraplet: RcSecurityTetraplet,
     argument_hash: Rc<str>,
@@ -45,7 +47,7 @@ pub(crate) fn populate_context_from_peer_service_result<'i>(
             let service_result_agg_cid =
                 exec_ctx
                     .cid_state
-                    .track_service_result(executed_result.result.clone(), tetraplet, argument_hash)?;
+                    .track_service_result(raw_result, tetraplet, argument_hash)?;
             let executed_result = ValueAggregate::from_service_result(executed_result, service_result_agg_cid.clone());
 
             exec_ctx.scalars.set_scalar_value(scalar.name, executed_result)?;
@@ -57,7 +59,7 @@ pub(crate) fn populate_context_from_peer_service_result<'i>(
             let service_result_agg_cid =
                 exec_ctx
                     .cid_state
-                    .track_service_result(executed_result.result.clone(), tetraplet, argument_hash)?;
+                    .track_service_result(raw_result, tetraplet, argument_hash)?;
 
             let executed_result = ValueAggregate::from_service_result(executed_result, service_result_agg_cid.clone());
 
@@ -26,6 +26,7 @@ use crate::UncatchableError;
 
 use air_interpreter_data::CallResult;
 use air_interpreter_data::CallServiceFailed;
+use air_interpreter_data::RawValue;
 use air_interpreter_data::Sender;
 use air_interpreter_interface::CallServiceResult;
 use air_parser::ast::CallOutputValue;
@@ -79,7 +80,11 @@ pub(super) fn handle_prev_state<'i>(
             trace_ctx.meet_call_end(met_result.result);
 
             let err_msg = call_service_failed.message;
-            Err(CatchableError::LocalServiceError(call_service_failed.ret_code, err_msg).into())
+            if call_service_failed.not_authorized {
+                Err(CatchableError::CallNotAuthorized(err_msg).into())
+            } else {
+                Err(CatchableError::LocalServiceError(call_service_failed.ret_code, err_msg).into())
+            }
         }
         RequestSentBy(Sender::PeerIdWithCallId { ref peer_id, call_id })
             if peer_id.as_str() == exec_ctx.run_parameters.current_peer_id.as_str() =>
@@ -149,7 +154,7 @@ use super::call_result_setter::*;
 use crate::execution_step::ServiceResultAggregate;
 use crate::JValue;
 
-fn update_state_with_service_result<'i>(
+pub(super) fn update_state_with_service_result<'i>(
     tetraplet: RcSecurityTetraplet,
     argument_hash: Rc<str>,
     output: &CallOutputValue<'i>,
@@ -167,13 +172,19 @@ fn update_state_with_service_result<'i>(
     )?;
 
     // try to get service result from call service result
-    let result = try_to_service_result(service_result, &argument_hash, &tetraplet, exec_ctx, trace_ctx)?;
+    let raw_result = try_to_service_result(service_result, &argument_hash, &tetraplet, exec_ctx, trace_ctx)?;
 
     let trace_pos = trace_ctx.trace_pos().map_err(UncatchableError::from)?;
 
-    let executed_result = ServiceResultAggregate::new(result, tetraplet.clone(), trace_pos);
-    let new_call_result =
-        populate_context_from_peer_service_result(executed_result, output, tetraplet, argument_hash, exec_ctx)?;
+    let executed_result = ServiceResultAggregate::new(raw_result.get_value(), tetraplet.clone(), trace_pos);
+    let new_call_result = populate_context_from_peer_service_result(
+        executed_result,
+        raw_result,
+        output,
+        tetraplet,
+        argument_hash,
+        exec_ctx,
+    )?;
     trace_ctx.meet_call_end(new_call_result);
 
     Ok(())
@@ -209,14 +220,56 @@ fn handle_service_error(
     Err(error.into())
 }
 
+/// Save a call rejected by the call policy to data, so that it fails the same way on replay.
+pub(super) fn handle_not_authorized_call(
+    reason: String,
+    argument_hash: Rc<str>,
+    tetraplet: RcSecurityTetraplet,
+    exec_ctx: &mut ExecutionCtx<'_>,
+    trace_ctx: &mut TraceHandler,
+) -> ExecutionResult<()> {
+    use CallResult::Failed;
+
+    // the reason is saved like a service error message
+    let error_message = Rc::new(serde_json::to_string(&reason).expect("a string should be serializable"));
+    let failed_value = CallServiceFailed::not_authorized(error_message.clone()).to_value();
+
+    let peer_id = tetraplet.peer_pk.clone();
+    let service_result_agg_cid = exec_ctx
+        .cid_state
+        .track_service_result(failed_value, tetraplet, argument_hash)?;
+
+    exec_ctx.record_call_cid(&peer_id, &service_result_agg_cid);
+    trace_ctx.meet_call_end(Failed(service_result_agg_cid));
+
+    Err(CatchableError::CallNotAuthorized(error_message).into())
+}
+
 fn try_to_service_result(
     service_result: CallServiceResult,
     argument_hash: &Rc<str>,
     tetraplet: &RcSecurityTetraplet,
     exec_ctx: &mut ExecutionCtx<'_>,
     trace_ctx: &mut TraceHandler,
-) -> ExecutionResult<JValue> {
-    match serde_json::from_str(&service_result.result) {
+) -> ExecutionResult<RawValue> {
+    use air_interpreter_interface::CallResultValue;
+    use air_interpreter_interface::CallResultValueRepr;
+    use air_interpreter_sede::FromSerialized;
+
+    let result = match &service_result.binary_result {
+        Some(binary_result) => CallResultValueRepr
+            .deserialize(binary_result)
+            .map(|value| match value {
+                CallResultValue::Value(value) => RawValue::from_value(value),
+                CallResultValue::Bytes(bytes) => RawValue::from_bytes(bytes),
+            })
+            .map_err(|e| e.to_string()),
+        None => serde_json::from_str::<JValue>(&service_result.result)
+            .map(RawValue::from_value)
+            .map_err(|e| e.to_string()),
+    };
+
+    match result {
         Ok(result) => Ok(result),
         Err(e) => {
             let error_msg = format!(
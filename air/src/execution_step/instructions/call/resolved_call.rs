@@ -19,6 +19,8 @@
 
 #![allow(unused_unsafe)] // for wasm_bindgen target where calling FFI is safe
 
+use super::authorization::authorize_call;
+use super::builtins;
 use super::call_result_setter::*;
 use super::prev_result_handler::*;
 use super::triplet::resolve;
@@ -34,9 +36,12 @@ use crate::SecurityTetraplet;
 use air_interpreter_cid::value_to_json_cid;
 use air_interpreter_cid::CidRef;
 use air_interpreter_data::CallResult;
+use air_interpreter_data::Provenance;
 use air_interpreter_interface::CallArgumentsRepr;
 use air_interpreter_interface::CallRequestParams;
+use air_interpreter_interface::ProvenanceRepr;
 use air_interpreter_interface::SerializedCallArguments;
+use air_interpreter_interface::SerializedProvenance;
 use air_interpreter_interface::TetrapletsRepr;
 use air_parser::ast;
 use air_trace_handler::merger::MergerCallResult;
@@ -56,6 +61,14 @@ pub(super) struct ResolvedCall<'i> {
 struct ResolvedArguments {
     call_arguments: SerializedCallArguments,
     tetraplets: Vec<RcSecurityTetraplets>,
+    provenance: SerializedProvenance,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CollectedArguments {
+    values: Vec<JValue>,
+    tetraplets: Vec<RcSecurityTetraplets>,
+    provenances: Vec<Provenance>,
 }
 
 #[derive(Debug)]
@@ -108,8 +121,9 @@ impl<'i> ResolvedCall<'i> {
             CheckArgsResult::Ok(args) => Some(args),
             CheckArgsResult::Joinable(_) => None,
         };
-        let argument_hash: Option<Rc<CidRef>> =
-            checked_args.map(|args| value_to_json_cid(&args).expect("serializer shouldn't fail").get_inner());
+        let argument_hash: Option<Rc<CidRef>> = checked_args
+            .as_ref()
+            .map(|args| value_to_json_cid(args).expect("serializer shouldn't fail").get_inner());
 
         let state = self.prepare_current_executed_state(raw_call, argument_hash.as_ref(), exec_ctx, trace_ctx)?;
 
@@ -127,8 +141,8 @@ impl<'i> ResolvedCall<'i> {
 
         // TODO we are recalculating params here for the second time.
         // we might extend the `checked_args`, but we have to proove that the value is same.
-        let request_params = match self.prepare_request_params(exec_ctx, tetraplet) {
-            Ok(params) => params,
+        let resolved_args = match self.resolve_args(exec_ctx) {
+            Ok(args) => args,
             Err(e) if e.is_joinable() => {
                 // to keep states on join behaviour
                 state.maybe_set_prev_state(trace_ctx);
@@ -139,9 +153,34 @@ impl<'i> ResolvedCall<'i> {
             }
         };
 
+        if let Err(reason) = authorize_call(&exec_ctx.call_policy, tetraplet, &resolved_args.tetraplets) {
+            // arguments are resolved, so they were checked successfully
+            let argument_hash = argument_hash.expect("arguments should have been checked");
+            return handle_not_authorized_call(reason, argument_hash, tetraplet.clone(), exec_ctx, trace_ctx);
+        }
+
+        if let Some(builtin) = builtins::find_builtin(&tetraplet.service_id, &tetraplet.function_name) {
+            // builtins are deterministic, so they are executed right away instead of a host round trip
+            let arguments = checked_args.expect("arguments should have been checked");
+            let argument_hash = argument_hash.expect("arguments should have been checked");
+            let service_result = builtins::call_builtin(builtin, &arguments);
+            exec_ctx.tracker.meet_executed_call();
+
+            return update_state_with_service_result(
+                tetraplet.clone(),
+                argument_hash,
+                &self.output,
+                service_result,
+                exec_ctx,
+                trace_ctx,
+            );
+        }
+
+        let request_params = self.prepare_request_params(resolved_args, tetraplet)?;
         let call_id = exec_ctx.next_call_request_id();
 
-        exec_ctx.call_requests.insert(call_id, request_params);
+        let argument_hash = argument_hash.expect("arguments should have been checked");
+        exec_ctx.add_call_request(call_id, request_params, argument_hash);
 
         exec_ctx.make_subgraph_incomplete();
         trace_ctx.meet_call_end(CallResult::sent_peer_id_with_call_id(
@@ -159,7 +198,7 @@ impl<'i> ResolvedCall<'i> {
     #[tracing::instrument(level = "trace", skip_all)]
     fn prepare_request_params(
         &self,
-        exec_ctx: &ExecutionCtx<'_>,
+        resolved_args: ResolvedArguments,
         tetraplet: &SecurityTetraplet,
     ) -> ExecutionResult<CallRequestParams> {
         use air_interpreter_sede::ToSerialized;
@@ -167,7 +206,8 @@ impl<'i> ResolvedCall<'i> {
         let ResolvedArguments {
             call_arguments,
             tetraplets,
-        } = self.resolve_args(exec_ctx)?;
+            provenance,
+        } = resolved_args;
 
         let serialized_tetraplets = TetrapletsRepr
             .serialize(&tetraplets)
@@ -178,6 +218,7 @@ impl<'i> ResolvedCall<'i> {
             tetraplet.function_name.to_string(),
             call_arguments,
             serialized_tetraplets,
+            provenance,
         );
 
         Ok(request_params)
@@ -209,15 +250,29 @@ impl<'i> ResolvedCall<'i> {
     fn resolve_args(&self, exec_ctx: &ExecutionCtx<'i>) -> ExecutionResult<ResolvedArguments> {
         use air_interpreter_sede::ToSerialized;
 
-        let (call_arguments, tetraplets) = self.collect_args(exec_ctx)?;
+        let CollectedArguments {
+            values,
+            tetraplets,
+            provenances,
+        } = self.collect_args(exec_ctx)?;
 
         let call_arguments = CallArgumentsRepr
-            .serialize(&call_arguments)
+            .serialize(&values)
             .map_err(UncatchableError::CallArgumentsSerializationFailed)?;
 
+        let provenance = if exec_ctx.run_parameters.call_provenance_enabled {
+            let call_provenance = exec_ctx.cid_state.resolve_call_provenance(&provenances)?;
+            ProvenanceRepr
+                .serialize(&call_provenance)
+                .map_err(UncatchableError::ProvenanceSerializationFailed)?
+        } else {
+            <_>::default()
+        };
+
         let resolved_arguments = ResolvedArguments {
             call_arguments,
             tetraplets,
+            provenance,
         };
 
         Ok(resolved_arguments)
@@ -227,20 +282,27 @@ impl<'i> ResolvedCall<'i> {
     fn check_args(&self, exec_ctx: &ExecutionCtx<'i>) -> ExecutionResult<CheckArgsResult<Vec<JValue>>> {
         let fun_result = self.collect_args(exec_ctx);
 
-        CheckArgsResult::new(fun_result.map(|values| values.0))
+        CheckArgsResult::new(fun_result.map(|args| args.values))
     }
 
-    fn collect_args(&self, exec_ctx: &ExecutionCtx<'i>) -> ExecutionResult<(Vec<JValue>, Vec<RcSecurityTetraplets>)> {
+    fn collect_args(&self, exec_ctx: &ExecutionCtx<'i>) -> ExecutionResult<CollectedArguments> {
         let function_args = self.function_arg_paths.iter();
-        let mut call_arguments = Vec::with_capacity(function_args.len());
+        let mut values = Vec::with_capacity(function_args.len());
         let mut tetraplets = Vec::with_capacity(function_args.len());
+        let mut provenances = Vec::with_capacity(function_args.len());
 
         for instruction_value in function_args {
-            let (arg, tetraplet, _) = instruction_value.resolve(exec_ctx)?;
-            call_arguments.push(arg);
+            let (arg, tetraplet, provenance) = instruction_value.resolve(exec_ctx)?;
+            values.push(arg);
             tetraplets.push(tetraplet);
+            provenances.push(provenance);
         }
-        Ok((call_arguments, tetraplets))
+
+        Ok(CollectedArguments {
+            values,
+            tetraplets,
+            provenances,
+        })
     }
 }
 
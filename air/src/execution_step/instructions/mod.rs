@@ -40,6 +40,7 @@ mod par;
 mod seq;
 mod xor;
 
+pub(crate) use call::builtins;
 pub(crate) use call::triplet::resolve_peer_id_to_string;
 pub(crate) use fold::FoldState;
 
@@ -33,6 +33,9 @@ pub use errors::CatchableError;
 pub use errors::ExecutionError;
 pub use errors::UncatchableError;
 pub use execution_context::ErrorObjectError;
+pub use instructions::builtins::BUILTIN_CALL_FAILED;
+pub use instructions::builtins::BUILTIN_OP_SERVICE_ID;
+pub use instructions::builtins::BUILTIN_STRING_SERVICE_ID;
 pub use lambda_applier::LambdaError;
 
 pub mod errors_prelude {
@@ -20,17 +20,19 @@
 use super::FarewellError;
 use crate::execution_step::ExecutionCtx;
 use crate::execution_step::TraceHandler;
+use crate::signing_step::DataSigner;
 use crate::ExecutionError;
 use crate::InterpreterOutcome;
 use crate::ToErrorCode;
 use crate::INTERPRETER_SUCCESS;
 
+use air_interpreter_data::InterpreterData;
 use air_interpreter_data::InterpreterDataEnvelope;
+use air_interpreter_data::Versions;
 use air_interpreter_interface::CallRequests;
 use air_interpreter_interface::CallRequestsRepr;
 use air_interpreter_interface::SoftLimitsTriggering;
 use air_interpreter_sede::ToSerialized;
-use air_interpreter_signatures::KeyPair;
 use air_utils::measure;
 use fluence_keypair::error::SigningError;
 
@@ -38,15 +40,135 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::rc::Rc;
 
-/// Create InterpreterOutcome from supplied execution context and trace handler,
+/// Data of a pass outcome, it's serialized only once all passes are done.
+#[derive(Debug, Clone)]
+pub(crate) enum PassData {
+    /// Data as it was supplied to the interpreter.
+    Serialized(Rc<Vec<u8>>),
+
+    /// Data produced by a pass.
+    Deserialized {
+        data: Box<InterpreterData>,
+        versions: Versions,
+        /// A payload the host has to sign in case of remote signing, it's empty otherwise.
+        signing_payload: Vec<u8>,
+    },
+
+    /// Data produced by a previous pass that was consumed by a pass failed with an uncatchable error.
+    Consumed,
+
+    /// Data lost due to an internal error, empty data is returned then.
+    Lost,
+}
+
+/// Outcome of execution with one current data.
+#[derive(Debug)]
+pub(crate) struct PassOutcome {
+    pub(crate) ret_code: i64,
+    pub(crate) error_message: String,
+    pub(crate) data: PassData,
+    pub(crate) next_peer_pks: Vec<String>,
+    pub(crate) call_requests: CallRequests,
+    pub(crate) soft_limits_triggering: SoftLimitsTriggering,
+}
+
+impl Default for PassData {
+    fn default() -> Self {
+        Self::Serialized(<_>::default())
+    }
+}
+
+impl PassData {
+    pub(crate) fn deserialized(&self) -> Option<&InterpreterData> {
+        match self {
+            PassData::Deserialized { data, .. } => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Serialize data, returning it with a signing payload.
+    fn serialize(self) -> (Vec<u8>, Vec<u8>) {
+        match self {
+            PassData::Serialized(data) => (Rc::unwrap_or_clone(data), vec![]),
+            PassData::Deserialized {
+                data,
+                versions,
+                signing_payload,
+            } => {
+                let data = InterpreterDataEnvelope::from_interpreter_data(&data, versions);
+                let data = measure!(
+                    data.serialize().expect("default serializer shouldn't fail"),
+                    tracing::Level::INFO,
+                    "InterpreterDataEnv::serialize"
+                );
+                (data, signing_payload)
+            }
+            PassData::Consumed => unreachable!("passes that consumed data are skipped"),
+            PassData::Lost => (vec![], vec![]),
+        }
+    }
+}
+
+impl PassOutcome {
+    /// Merges outcome of the next pass into this one. Data is taken from the next pass,
+    /// call requests and next peers are united, and the first error is preserved.
+    pub(crate) fn merge(mut self, next: PassOutcome) -> Self {
+        if self.ret_code == INTERPRETER_SUCCESS {
+            self.ret_code = next.ret_code;
+            self.error_message = next.error_message;
+        }
+        self.data = next.data;
+        self.next_peer_pks.extend(next.next_peer_pks);
+        self.call_requests.extend(next.call_requests);
+        self.soft_limits_triggering = merge_soft_limits(self.soft_limits_triggering, next.soft_limits_triggering);
+
+        self
+    }
+
+    /// Outcome of a pass failed with an uncatchable error, without its data.
+    pub(crate) fn error_outcome(&self) -> Self {
+        Self {
+            ret_code: self.ret_code,
+            error_message: self.error_message.clone(),
+            data: PassData::default(),
+            next_peer_pks: vec![],
+            call_requests: CallRequests::new(),
+            soft_limits_triggering: self.soft_limits_triggering,
+        }
+    }
+
+    pub(crate) fn into_interpreter_outcome(self) -> InterpreterOutcome {
+        let (data, signing_payload) = self.data.serialize();
+        let next_peer_pks = dedup(self.next_peer_pks);
+        let call_requests = measure!(
+            CallRequestsRepr
+                .serialize(&self.call_requests)
+                .expect("default serializer shouldn't fail"),
+            tracing::Level::INFO,
+            "CallRequestsRepr.serialize",
+        );
+
+        InterpreterOutcome::new(
+            self.ret_code,
+            self.error_message,
+            data,
+            next_peer_pks,
+            call_requests,
+            self.soft_limits_triggering,
+            signing_payload,
+        )
+    }
+}
+
+/// Create PassOutcome from supplied execution context and trace handler,
 /// set ret_code to INTERPRETER_SUCCESS.
 #[tracing::instrument(skip_all)]
 pub(crate) fn from_success_result(
     exec_ctx: ExecutionCtx<'_>,
     trace_handler: TraceHandler,
-    keypair: &KeyPair,
+    signer: &DataSigner,
     soft_limits_triggering: SoftLimitsTriggering,
-) -> Result<InterpreterOutcome, InterpreterOutcome> {
+) -> Result<PassOutcome, PassOutcome> {
     let (ret_code, error_message) = if exec_ctx.call_results.is_empty() {
         (INTERPRETER_SUCCESS, String::new())
     } else {
@@ -59,111 +181,96 @@ pub(crate) fn from_success_result(
         trace_handler,
         ret_code,
         error_message,
-        keypair,
+        signer,
         soft_limits_triggering,
     );
     Ok(outcome)
 }
 
-/// Create InterpreterOutcome from supplied data and error,
+/// Create PassOutcome from supplied data and error,
 /// set ret_code based on the error.
 #[tracing::instrument]
 pub(crate) fn from_uncatchable_error(
-    data: impl Into<Vec<u8>> + Debug,
+    data: PassData,
     error: impl ToErrorCode + ToString + Debug,
     soft_limits_triggering: SoftLimitsTriggering,
-) -> InterpreterOutcome {
-    let ret_code = error.to_error_code();
-    let data = data.into();
-    let call_requests = CallRequestsRepr
-        .serialize(&CallRequests::new())
-        .expect("default serializer shouldn't fail");
-
-    InterpreterOutcome::new(
-        ret_code,
-        error.to_string(),
+) -> PassOutcome {
+    PassOutcome {
+        ret_code: error.to_error_code(),
+        error_message: error.to_string(),
         data,
-        vec![],
-        call_requests,
+        next_peer_pks: vec![],
+        call_requests: CallRequests::new(),
         soft_limits_triggering,
-    )
+    }
 }
 
-/// Create InterpreterOutcome from supplied execution context, trace handler, and error,
+/// Create PassOutcome from supplied execution context, trace handler, and error,
 /// set ret_code based on the error.
-#[tracing::instrument(skip(exec_ctx, trace_handler, keypair))]
+#[tracing::instrument(skip(exec_ctx, trace_handler, signer))]
 pub(crate) fn from_execution_error(
     exec_ctx: ExecutionCtx<'_>,
     trace_handler: TraceHandler,
     error: impl ToErrorCode + ToString + Debug,
-    keypair: &KeyPair,
+    signer: &DataSigner,
     soft_limits_triggering: SoftLimitsTriggering,
-) -> InterpreterOutcome {
+) -> PassOutcome {
     populate_outcome_from_contexts(
         exec_ctx,
         trace_handler,
         error.to_error_code(),
         error.to_string(),
-        keypair,
+        signer,
         soft_limits_triggering,
     )
 }
 
-#[tracing::instrument(skip(exec_ctx, trace_handler, keypair), level = "info")]
+#[tracing::instrument(skip(exec_ctx, trace_handler, signer), level = "info")]
 fn populate_outcome_from_contexts(
     mut exec_ctx: ExecutionCtx<'_>,
     mut trace_handler: TraceHandler,
     ret_code: i64,
     error_message: String,
-    keypair: &KeyPair,
+    signer: &DataSigner,
     soft_limits_triggering: SoftLimitsTriggering,
-) -> InterpreterOutcome {
+) -> PassOutcome {
     match compactify_streams(&mut exec_ctx, &mut trace_handler, soft_limits_triggering) {
         Ok(()) => {}
         Err(outcome) => return outcome,
     };
 
-    match sign_result(&mut exec_ctx, keypair, soft_limits_triggering) {
-        Ok(()) => {}
+    let signing_payload = match sign_result(&mut exec_ctx, signer, soft_limits_triggering) {
+        Ok(signing_payload) => signing_payload,
         Err(outcome) => return outcome,
     };
 
-    let data = InterpreterDataEnvelope::from_execution_result(
-        trace_handler.into_result_trace(),
-        exec_ctx.cid_state.into(),
-        exec_ctx.signature_store,
-        exec_ctx.last_call_request_id,
-        semver::Version::parse(env!("CARGO_PKG_VERSION")).expect("cargo version is valid"),
-    );
-    let data = measure!(
-        data.serialize().expect("default serializer shouldn't fail"),
-        tracing::Level::INFO,
-        "InterpreterDataEnv::serialize"
-    );
+    let data = InterpreterData {
+        trace: trace_handler.into_result_trace(),
+        last_call_request_id: exec_ctx.last_call_request_id,
+        cid_info: exec_ctx.cid_state.into(),
+        signatures: exec_ctx.signature_store,
+    };
 
-    let next_peer_pks = dedup(exec_ctx.next_peer_pks);
-    let call_requests = measure!(
-        CallRequestsRepr
-            .serialize(&exec_ctx.call_requests)
-            .expect("default serializer shouldn't fail"),
-        tracing::Level::INFO,
-        "CallRequestsRepr.serialize",
-    );
-    InterpreterOutcome::new(
+    PassOutcome {
         ret_code,
         error_message,
-        data,
-        next_peer_pks,
-        call_requests,
+        data: PassData::Deserialized {
+            data: Box::new(data),
+            versions: exec_ctx.produced_versions,
+            signing_payload,
+        },
+        next_peer_pks: exec_ctx.next_peer_pks,
+        call_requests: exec_ctx.call_requests,
         soft_limits_triggering,
-    )
+    }
 }
 
+#[allow(clippy::result_large_err)]
 fn compactify_streams(
     exec_ctx: &mut ExecutionCtx<'_>,
     trace_ctx: &mut TraceHandler,
     soft_limits_triggering: SoftLimitsTriggering,
-) -> Result<(), InterpreterOutcome> {
+) -> Result<(), PassOutcome> {
     exec_ctx
         .streams
         .compactify(trace_ctx)
@@ -171,47 +278,51 @@ fn compactify_streams(
         .map_err(|err| execution_error_into_outcome(err, soft_limits_triggering))
 }
 
+#[allow(clippy::result_large_err)]
 fn sign_result(
     exec_ctx: &mut ExecutionCtx<'_>,
-    keypair: &KeyPair,
+    signer: &DataSigner,
     soft_limits_triggering: SoftLimitsTriggering,
-) -> Result<(), InterpreterOutcome> {
-    let current_signature = exec_ctx
-        .peer_cid_tracker
-        .gen_signature(&exec_ctx.run_parameters.salt, keypair)
+) -> Result<Vec<u8>, PassOutcome> {
+    let salt = &exec_ctx.run_parameters.salt;
+    signer
+        .sign(&exec_ctx.peer_cid_tracker, &mut exec_ctx.signature_store, salt)
         .map_err(|err| signing_error_into_outcome(err, soft_limits_triggering))?;
 
-    let current_pubkey = keypair.public();
-    exec_ctx.signature_store.put(current_pubkey, current_signature);
-
-    Ok(())
+    Ok(signer.signing_payload(&exec_ctx.peer_cid_tracker, salt))
 }
 
 // these methods are called only if there is an internal error in the interpreter and
 // new execution trace was corrupted
-fn execution_error_into_outcome(
-    error: ExecutionError,
+fn execution_error_into_outcome(error: ExecutionError, soft_limits_triggering: SoftLimitsTriggering) -> PassOutcome {
+    internal_error_into_outcome(error.to_error_code(), error.to_string(), soft_limits_triggering)
+}
+
+fn signing_error_into_outcome(error: SigningError, soft_limits_triggering: SoftLimitsTriggering) -> PassOutcome {
+    internal_error_into_outcome(error.to_error_code(), error.to_string(), soft_limits_triggering)
+}
+
+fn internal_error_into_outcome(
+    ret_code: i64,
+    error_message: String,
     soft_limits_triggering: SoftLimitsTriggering,
-) -> InterpreterOutcome {
-    InterpreterOutcome::new(
-        error.to_error_code(),
-        error.to_string(),
-        vec![],
-        vec![],
-        <_>::default(),
+) -> PassOutcome {
+    PassOutcome {
+        ret_code,
+        error_message,
+        data: PassData::Lost,
+        next_peer_pks: vec![],
+        call_requests: CallRequests::new(),
         soft_limits_triggering,
-    )
+    }
 }
 
-fn signing_error_into_outcome(error: SigningError, soft_limits_triggering: SoftLimitsTriggering) -> InterpreterOutcome {
-    InterpreterOutcome::new(
-        error.to_error_code(),
-        error.to_string(),
-        vec![],
-        vec![],
-        <_>::default(),
-        soft_limits_triggering,
-    )
+fn merge_soft_limits(lhs: SoftLimitsTriggering, rhs: SoftLimitsTriggering) -> SoftLimitsTriggering {
+    SoftLimitsTriggering {
+        air_size_limit_exceeded: lhs.air_size_limit_exceeded || rhs.air_size_limit_exceeded,
+        particle_size_limit_exceeded: lhs.particle_size_limit_exceeded || rhs.particle_size_limit_exceeded,
+        call_result_size_limit_exceeded: lhs.call_result_size_limit_exceeded || rhs.call_result_size_limit_exceeded,
+    }
 }
 
 /// Deduplicate values in a supplied vector.
@@ -59,16 +59,23 @@ pub use execution_step::ErrorObjectError;
 pub use execution_step::ExecutionError;
 pub use execution_step::LambdaError;
 pub use execution_step::UncatchableError;
+pub use execution_step::BUILTIN_CALL_FAILED;
+pub use execution_step::BUILTIN_OP_SERVICE_ID;
+pub use execution_step::BUILTIN_STRING_SERVICE_ID;
 pub use farewell_step::FarewellError;
 pub use polyplets::ResolvedTriplet;
 pub use polyplets::SecurityTetraplet;
 pub use preparation_step::interpreter_version;
 pub use preparation_step::min_supported_version;
 pub use preparation_step::PreparationError;
+pub use preparation_step::TrustPolicyViolation;
+pub use signing_step::finalize_signing;
+pub use signing_step::RemoteSigningError;
 pub use utils::ToErrorCode;
 
 pub use crate::human_readable_data::to_human_readable_data;
 pub use crate::runner::execute_air;
+pub use crate::runner::execute_air_multi;
 
 pub mod interpreter_data {
     pub use air_interpreter_data::*;
@@ -23,6 +23,7 @@ use air_interpreter_data::verification::DataVerifierError;
 use air_interpreter_data::CidStoreVerificationError;
 use air_interpreter_data::DataDeserializationError;
 use air_interpreter_data::Versions;
+use air_interpreter_interface::CallPolicyDeserializeError;
 use air_interpreter_interface::CallResultsDeserializeError;
 use strum::IntoEnumIterator;
 use strum_macros::EnumDiscriminants;
@@ -68,7 +69,7 @@ pub enum PreparationError {
     )]
     EnvelopeDeFailedWithVersions {
         error: DataDeserializationError,
-        versions: Versions,
+        versions: Box<Versions>,
     },
 
     /// Error occurred on call results deserialization.
@@ -97,6 +98,25 @@ pub enum PreparationError {
     /// RAM limits are excedeed.
     #[error(transparent)]
     SizeLimitsExceded(#[from] SizeLimitsExceded),
+
+    /// Remote signer public key doesn't belong to the current peer.
+    #[error("signer public key belongs to {key_peer_id}, but the current peer id is {current_peer_id}")]
+    SignerPeerIdMismatch {
+        key_peer_id: String,
+        current_peer_id: String,
+    },
+
+    /// Current data was produced by peers the host doesn't trust.
+    #[error(transparent)]
+    TrustPolicyViolation(#[from] TrustPolicyViolation),
+
+    /// Error occurred on call policy deserialization.
+    #[error("error occurred while deserialize call policy: {error:?}.")]
+    MalformedCallPolicy { error: CallPolicyDeserializeError },
+
+    /// Produced data can't be signed without a particle signature to salt signatures with.
+    #[error("particle signature is empty, it's required to sign produced data")]
+    NoParticleSignature,
 }
 
 impl ToErrorCode for PreparationError {
@@ -116,7 +136,10 @@ impl PreparationError {
     }
 
     pub fn env_de_failed_with_versions(error: DataDeserializationError, versions: Versions) -> Self {
-        Self::EnvelopeDeFailedWithVersions { error, versions }
+        Self::EnvelopeDeFailedWithVersions {
+            error,
+            versions: Box::new(versions),
+        }
     }
 
     pub fn call_results_de_failed(error: CallResultsDeserializeError) -> Self {
@@ -157,3 +180,22 @@ pub enum SizeLimitsExceded {
     #[error("Call result size is bigger than the limit allowed: {0} bytes")]
     CallResult(u64),
 }
+
+#[derive(Debug, ThisError)]
+pub enum TrustPolicyViolation {
+    /// A peer is not in the allow list.
+    #[error("data produced by {peer_id} is not allowed by the trust policy")]
+    PeerNotAllowed { peer_id: String },
+
+    /// A peer is in the deny list.
+    #[error("data produced by {peer_id} is denied by the trust policy")]
+    PeerDenied { peer_id: String },
+
+    /// A peer signed data with a key unknown to the host.
+    #[error("data produced by {peer_id} is signed with an unknown key {key}")]
+    UnknownKey { peer_id: String, key: String },
+
+    /// A peer's signature is salted with the particle id denied by the trust policy.
+    #[error("data produced by {peer_id} is salted with the particle id denied by the trust policy")]
+    ParticleIdSaltDenied { peer_id: String },
+}
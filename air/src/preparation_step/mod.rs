@@ -23,12 +23,19 @@ mod preparation;
 mod sizes_limits_check;
 
 pub use errors::PreparationError;
+pub use errors::TrustPolicyViolation;
 pub use interpreter_versions::interpreter_version;
 pub use interpreter_versions::min_supported_version;
 
 pub(crate) use preparation::check_version_compatibility;
+#[cfg(feature = "check_signatures")]
+pub(crate) use preparation::key_format_whitelist;
+pub(crate) use preparation::parse_current_data;
 pub(crate) use preparation::parse_data;
 pub(crate) use preparation::prepare;
+pub(crate) use preparation::signer_public_key;
+pub(crate) use preparation::try_to_data;
+pub(crate) use preparation::try_to_envelope;
 pub(crate) use preparation::ParsedDataPair;
 pub(crate) use preparation::PreparationDescriptor;
 pub(crate) use sizes_limits_check::check_against_size_limits;
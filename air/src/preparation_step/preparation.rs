@@ -21,18 +21,24 @@ use super::PreparationError;
 use crate::execution_step::execution_context::ExecCtxIngredients;
 use crate::execution_step::ExecutionCtx;
 use crate::execution_step::TraceHandler;
+use crate::signing_step::data_salt;
+use crate::signing_step::DataSigner;
 
 use air_interpreter_data::DataDeserializationError;
 use air_interpreter_data::InterpreterData;
 use air_interpreter_data::InterpreterDataEnvelope;
 use air_interpreter_data::Versions;
+use air_interpreter_interface::CallPolicy;
+use air_interpreter_interface::CallPolicyRepr;
 use air_interpreter_interface::CallResultsRepr;
 use air_interpreter_interface::RunParameters;
 use air_interpreter_interface::SerializedCallResults;
 use air_interpreter_interface::SoftLimitsTriggering;
 use air_interpreter_sede::FromSerialized;
 use air_interpreter_signatures::KeyError;
+use air_interpreter_signatures::KeyFormatWhitelist;
 use air_interpreter_signatures::KeyPair;
+use air_interpreter_signatures::PublicKey;
 use air_interpreter_signatures::SignatureStore;
 use air_parser::ast::Instruction;
 use air_utils::measure;
@@ -45,12 +51,14 @@ pub(crate) struct PreparationDescriptor<'ctx, 'i> {
     pub(crate) exec_ctx: ExecutionCtx<'ctx>,
     pub(crate) trace_handler: TraceHandler,
     pub(crate) air: Instruction<'i>,
-    pub(crate) keypair: KeyPair,
+    pub(crate) signer: DataSigner,
 }
 
 pub(crate) struct ParsedDataPair {
     pub(crate) prev_data: InterpreterData,
+    pub(crate) prev_versions: Versions,
     pub(crate) current_data: InterpreterData,
+    pub(crate) current_versions: Versions,
 }
 
 /// Parse data and check its version.
@@ -66,11 +74,24 @@ pub(crate) fn parse_data(prev_data: &[u8], current_data: &[u8]) -> PreparationRe
 
     Ok(ParsedDataPair {
         prev_data,
+        prev_versions: prev_envelope.versions,
         current_data,
+        current_versions: current_envelope.versions,
     })
 }
 
+/// Parse current data and check its version, it's used when prev data is already parsed.
+#[tracing::instrument(skip_all)]
+pub(crate) fn parse_current_data(current_data: &[u8]) -> PreparationResult<(InterpreterData, Versions)> {
+    let current_envelope = try_to_envelope(current_data)?;
+    check_version_compatibility(&current_envelope.versions)?;
+
+    let current_data = try_to_data(&current_envelope.inner_data)?;
+    Ok((current_data, current_envelope.versions))
+}
+
 /// Parse and prepare supplied data and AIR script.
+#[allow(clippy::too_many_arguments)]
 #[tracing::instrument(skip_all)]
 pub(crate) fn prepare<'i>(
     prev_data: InterpreterData,
@@ -79,6 +100,7 @@ pub(crate) fn prepare<'i>(
     call_results: &SerializedCallResults,
     run_parameters: RunParameters,
     signature_store: SignatureStore,
+    produced_versions: Versions,
     soft_limits_triggering: &mut SoftLimitsTriggering,
 ) -> PreparationResult<PreparationDescriptor<'static, 'i>> {
     let air: Instruction<'i> = air_parser::parse(raw_air).map_err(PreparationError::AIRParseError)?;
@@ -99,23 +121,60 @@ pub(crate) fn prepare<'i>(
         call_results,
         signature_store,
         &run_parameters,
+        produced_versions,
         soft_limits_triggering,
     )?;
     let trace_handler = TraceHandler::from_trace(prev_data.trace, current_data.trace);
 
-    let key_format = KeyFormat::try_from(run_parameters.key_format).map_err(KeyError::from)?;
-    let keypair = KeyPair::from_secret_key(run_parameters.secret_key_bytes, key_format)?;
+    let signer = make_signer(&run_parameters)?;
 
     let result = PreparationDescriptor {
         exec_ctx,
         trace_handler,
         air,
-        keypair,
+        signer,
     };
 
     Ok(result)
 }
 
+/// Make a signer of the produced data: either a key pair, or the host in case of remote signing.
+fn make_signer(run_parameters: &RunParameters) -> PreparationResult<DataSigner> {
+    if !run_parameters.signer_public_key.is_empty() {
+        return signer_public_key(run_parameters).map(DataSigner::Host);
+    }
+
+    let key_format = KeyFormat::try_from(run_parameters.key_format).map_err(KeyError::from)?;
+    if !key_format_whitelist(run_parameters)?.contains(key_format) {
+        return Err(KeyError::AlgorithmNotWhitelisted(key_format).into());
+    }
+
+    let keypair = KeyPair::from_secret_key(run_parameters.secret_key_bytes.clone(), key_format)?;
+    Ok(DataSigner::KeyPair(keypair))
+}
+
+/// Decode the remote signer public key and check that it belongs to the current peer.
+pub(crate) fn signer_public_key(run_parameters: &RunParameters) -> PreparationResult<PublicKey> {
+    let key_formats = key_format_whitelist(run_parameters)?;
+    let public_key = PublicKey::try_from_bytes(&run_parameters.signer_public_key, key_formats)?;
+
+    let key_peer_id = public_key.to_peer_id()?;
+    if key_peer_id != run_parameters.current_peer_id {
+        return Err(PreparationError::SignerPeerIdMismatch {
+            key_peer_id,
+            current_peer_id: run_parameters.current_peer_id.clone(),
+        });
+    }
+
+    Ok(public_key)
+}
+
+/// Key algorithms allowed by the host.
+pub(crate) fn key_format_whitelist(run_parameters: &RunParameters) -> PreparationResult<KeyFormatWhitelist> {
+    let whitelist = KeyFormatWhitelist::from_codes(&run_parameters.allowed_key_formats)?;
+    Ok(whitelist)
+}
+
 pub(crate) fn try_to_envelope(raw_env_data: &[u8]) -> PreparationResult<InterpreterDataEnvelope<'_>> {
     // treat empty slice as an empty data,
     // it allows abstracting from an internal format for an empty data
@@ -149,6 +208,7 @@ fn make_exec_ctx(
     call_results: &SerializedCallResults,
     signature_store: SignatureStore,
     run_parameters: &RunParameters,
+    produced_versions: Versions,
     soft_limits_triggering: &mut SoftLimitsTriggering,
 ) -> PreparationResult<ExecutionCtx<'static>> {
     use crate::preparation_step::sizes_limits_check::handle_limit_exceeding;
@@ -164,7 +224,7 @@ fn make_exec_ctx(
     // This is a part of argument size limit check where we check the size of every call result.
     if call_results
         .values()
-        .any(|call_result| call_result.result.len() as u64 > run_parameters.call_result_size_limit)
+        .any(|call_result| call_result.result_size() as u64 > run_parameters.call_result_size_limit)
     {
         let error: PreparationError = PreparationError::call_result_size_limit(run_parameters.call_result_size_limit);
         handle_limit_exceeding(
@@ -174,16 +234,32 @@ fn make_exec_ctx(
         )?;
     }
 
+    let call_policy = parse_call_policy(run_parameters)?;
+    let salt = data_salt(&produced_versions, run_parameters)?;
+
     let ctx = ExecutionCtx::new(
         prev_ingredients,
         current_ingredients,
         call_results,
         signature_store,
         run_parameters,
+        &salt,
+        produced_versions,
+        call_policy,
     );
     Ok(ctx)
 }
 
+fn parse_call_policy(run_parameters: &RunParameters) -> PreparationResult<CallPolicy> {
+    if run_parameters.call_policy.is_empty() {
+        return Ok(<_>::default());
+    }
+
+    CallPolicyRepr
+        .deserialize(&run_parameters.call_policy)
+        .map_err(|error| PreparationError::MalformedCallPolicy { error })
+}
+
 pub(crate) fn check_version_compatibility(versions: &Versions) -> PreparationResult<()> {
     if &versions.interpreter_version < super::min_supported_version() {
         return Err(PreparationError::UnsupportedInterpreterVersion {
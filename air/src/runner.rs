@@ -19,21 +19,33 @@
 
 use crate::execution_step::ExecutableInstruction;
 use crate::farewell_step as farewell;
+use crate::farewell_step::PassData;
+use crate::farewell_step::PassOutcome;
+use crate::preparation_step::parse_current_data;
 use crate::preparation_step::parse_data;
 use crate::preparation_step::prepare;
 use crate::preparation_step::ParsedDataPair;
 use crate::preparation_step::PreparationDescriptor;
+use crate::signing_step::produced_data_versions;
 use crate::signing_step::sign_produced_cids;
 use crate::verification_step::verify;
 
+use air_interpreter_data::verification::MergedSignatures;
+use air_interpreter_interface::CallResults;
+use air_interpreter_interface::CallResultsRepr;
 use air_interpreter_interface::InterpreterOutcome;
 use air_interpreter_interface::RunParameters;
 use air_interpreter_interface::SerializedCallResults;
 use air_interpreter_interface::SoftLimitsTriggering;
+use air_interpreter_sede::ToSerialized;
 use air_log_targets::RUN_PARAMS;
 use air_utils::farewell_if_fail;
 use air_utils::measure;
 
+use std::rc::Rc;
+
+const PREV_DATA_IS_PARSED: &str = "prev data is parsed";
+
 #[tracing::instrument(skip_all)]
 pub fn execute_air(
     air: String,
@@ -42,8 +54,34 @@ pub fn execute_air(
     params: RunParameters,
     call_results: SerializedCallResults,
 ) -> InterpreterOutcome {
-    use std::convert::identity;
+    log_run_parameters(&params);
+
+    execute_air_impl(air, prev_data, vec![data], params, call_results)
+}
+
+/// Executes AIR script with several current data at once.
+///
+/// The outcome is the same as of sequential `execute_air` calls with every current data in order,
+/// where each call gets data returned by the previous one as prev data, and call results are
+/// supplied to the first call only. Intermediate data is neither serialized nor copied until
+/// a call fails with an uncatchable error, then the calls are executed again without its current
+/// data, and the data of the following calls is copied, so they continue from the last good one.
+/// Call requests and next peers of all calls are united, and the error of the first failed call
+/// is reported, unless an internal error loses the data, then the rest of calls is not executed.
+#[tracing::instrument(skip_all)]
+pub fn execute_air_multi(
+    air: String,
+    prev_data: Vec<u8>,
+    data: Vec<Vec<u8>>,
+    params: RunParameters,
+    call_results: SerializedCallResults,
+) -> InterpreterOutcome {
+    log_run_parameters(&params);
+
+    execute_air_impl(air, prev_data, data, params, call_results)
+}
 
+fn log_run_parameters(params: &RunParameters) {
     log::trace!(
         target: RUN_PARAMS,
         "air interpreter version is {}, run parameters:\
@@ -53,66 +91,219 @@ pub fn execute_air(
         params.init_peer_id,
         params.current_peer_id,
     );
-
-    execute_air_impl(air, prev_data, data, params, call_results).unwrap_or_else(identity)
 }
 
-#[allow(clippy::result_large_err)]
 fn execute_air_impl(
     air: String,
     raw_prev_data: Vec<u8>,
-    raw_current_data: Vec<u8>,
+    raw_current_data: Vec<Vec<u8>>,
     params: RunParameters,
     call_results: SerializedCallResults,
-) -> Result<InterpreterOutcome, InterpreterOutcome> {
+) -> InterpreterOutcome {
+    // no current data is the same as an empty one
+    let raw_current_data = match raw_current_data.is_empty() {
+        true => vec![vec![]],
+        false => raw_current_data,
+    };
+    let raw_prev_data = Rc::new(raw_prev_data);
+
+    // a pass consumes data of the previous pass instead of keeping a copy to return in case of
+    // uncatchable errors; such errors are rare, so then the passes are executed again once
+    // without it, keeping copies of data for the following passes
+    let passes_result = execute_passes(
+        &air,
+        Rc::clone(&raw_prev_data),
+        &raw_current_data,
+        None,
+        &params,
+        &call_results,
+    );
+    let outcome = passes_result.unwrap_or_else(|failed_pass| {
+        execute_passes(
+            &air,
+            Rc::clone(&raw_prev_data),
+            &raw_current_data,
+            Some(failed_pass),
+            &params,
+            &call_results,
+        )
+        .unwrap_or_else(|_| unreachable!("passes following a failed one keep copies of their data"))
+    });
+
+    // if all passes failed, the prev data is returned without copying
+    drop(raw_prev_data);
+    outcome.into_interpreter_outcome()
+}
+
+fn merge_lost_data_outcome(merged_outcome: Option<PassOutcome>, lost_data_outcome: PassOutcome) -> PassOutcome {
+    let ret_code = lost_data_outcome.ret_code;
+    let error_message = lost_data_outcome.error_message.clone();
+
+    let mut outcome = match merged_outcome {
+        Some(merged_outcome) => merged_outcome.merge(lost_data_outcome),
+        None => lost_data_outcome,
+    };
+    outcome.ret_code = ret_code;
+    outcome.error_message = error_message;
+    outcome.data = PassData::Lost;
+    outcome
+}
+
+/// A pass failed with an uncatchable error after it had consumed the prev data.
+struct FailedPass {
+    pass_id: usize,
+    outcome: PassOutcome,
+}
+
+/// Executes passes with all current data except of the failed one, merging its error instead.
+///
+/// Passes preceding the failed one succeeded before, passes following it keep a copy of their
+/// prev data, so if they fail too, the next pass continues from the copy.
+#[allow(clippy::result_large_err)]
+fn execute_passes(
+    air: &str,
+    raw_prev_data: Rc<Vec<u8>>,
+    raw_current_data: &[Vec<u8>],
+    failed_pass: Option<FailedPass>,
+    params: &RunParameters,
+    call_results: &SerializedCallResults,
+) -> Result<PassOutcome, FailedPass> {
+    use std::convert::identity;
+
+    let no_call_results = CallResultsRepr
+        .serialize(&CallResults::new())
+        .expect("default serializer shouldn't fail");
+
+    let mut prev_data = PassData::Serialized(raw_prev_data);
+    let mut merged_outcome: Option<PassOutcome> = None;
+    let failed_pass_id = failed_pass.as_ref().map(|failed_pass| failed_pass.pass_id);
+    for (pass_id, raw_current_data) in raw_current_data.iter().enumerate() {
+        let outcome = match &failed_pass {
+            Some(failed_pass) if failed_pass.pass_id == pass_id => failed_pass.outcome.error_outcome(),
+            _ => {
+                let call_results = match pass_id {
+                    0 => call_results,
+                    _ => &no_call_results,
+                };
+
+                let checkpoint = failed_pass_id
+                    .filter(|failed_pass_id| *failed_pass_id < pass_id)
+                    .map(|_| prev_data.clone());
+                let mut outcome = execute_pass(air, prev_data, raw_current_data, params.clone(), call_results)
+                    .unwrap_or_else(identity);
+                prev_data = match (std::mem::take(&mut outcome.data), checkpoint) {
+                    // the pass is skipped as if its current data wasn't supplied
+                    (PassData::Consumed, Some(checkpoint)) => checkpoint,
+                    (PassData::Consumed, None) => return Err(FailedPass { pass_id, outcome }),
+                    // the data is lost, so the next passes can't be executed and the error is reported
+                    // regardless of errors of the previous passes
+                    (PassData::Lost, _) => return Ok(merge_lost_data_outcome(merged_outcome, outcome)),
+                    (data, _) => data,
+                };
+                outcome
+            }
+        };
+
+        merged_outcome = Some(match merged_outcome {
+            Some(merged_outcome) => merged_outcome.merge(outcome),
+            None => outcome,
+        });
+    }
+
+    let mut outcome = merged_outcome.expect("there is at least one pass");
+    outcome.data = prev_data;
+    Ok(outcome)
+}
+
+#[allow(clippy::result_large_err)]
+fn execute_pass(
+    air: &str,
+    prev_data: PassData,
+    raw_current_data: &[u8],
+    params: RunParameters,
+    call_results: &SerializedCallResults,
+) -> Result<PassOutcome, PassOutcome> {
     use crate::preparation_step::check_against_size_limits;
 
     let mut soft_limits_triggering = farewell_if_fail!(
-        check_against_size_limits(&params, &air, &raw_current_data),
-        raw_prev_data,
+        check_against_size_limits(&params, air, raw_current_data),
+        prev_data,
         SoftLimitsTriggering::default()
     );
 
     farewell_if_fail!(
-        check_against_size_limits(&params, &air, &raw_current_data),
-        raw_prev_data,
+        check_against_size_limits(&params, air, raw_current_data),
+        prev_data,
         soft_limits_triggering
     );
 
-    let ParsedDataPair {
-        prev_data,
-        current_data,
+    // prev data is kept untouched to be returned in case of uncatchable errors
+    let (parsed_prev_data, prev_versions, current_data, current_versions) = match &prev_data {
+        PassData::Serialized(raw_prev_data) => {
+            let ParsedDataPair {
+                prev_data,
+                prev_versions,
+                current_data,
+                current_versions,
+            } = farewell_if_fail!(
+                parse_data(raw_prev_data, raw_current_data),
+                prev_data,
+                soft_limits_triggering
+            );
+            (Some(prev_data), prev_versions, current_data, current_versions)
+        }
+        PassData::Deserialized { versions, .. } => {
+            let (current_data, current_versions) =
+                farewell_if_fail!(parse_current_data(raw_current_data), prev_data, soft_limits_triggering);
+            (None, versions.clone(), current_data, current_versions)
+        }
+        PassData::Consumed | PassData::Lost => unreachable!("failed passes don't supply prev data"),
+    };
+
+    let MergedSignatures {
+        signature_store,
+        particle_id_salted_peers,
     } = farewell_if_fail!(
-        parse_data(&raw_prev_data, &raw_current_data),
-        raw_prev_data,
+        verify(
+            parsed_prev_data
+                .as_ref()
+                .or(prev_data.deserialized())
+                .expect(PREV_DATA_IS_PARSED),
+            &prev_versions,
+            &current_data,
+            &current_versions,
+            &params
+        ),
+        prev_data,
         soft_limits_triggering
     );
+    let produced_versions = produced_data_versions(particle_id_salted_peers, &params.current_peer_id);
 
-    // TODO currently we use particle ID, but it should be changed to signature,
-    // as partical ID can be equally replayed
-    let salt = params.particle_id.clone();
-    let signature_store = farewell_if_fail!(
-        verify(&prev_data, &current_data, &salt),
-        raw_prev_data,
-        soft_limits_triggering
-    );
+    // data produced by the previous pass is moved into this one instead of being cloned,
+    // so it can't be returned in case of uncatchable errors from now on
+    let (parsed_prev_data, prev_data) = match (parsed_prev_data, prev_data) {
+        (Some(parsed_prev_data), prev_data) => (parsed_prev_data, prev_data),
+        (None, PassData::Deserialized { data, .. }) => (*data, PassData::Consumed),
+        (None, _) => unreachable!("{}", PREV_DATA_IS_PARSED),
+    };
 
     let PreparationDescriptor {
         mut exec_ctx,
         mut trace_handler,
         air,
-        keypair,
+        signer,
     } = farewell_if_fail!(
         prepare(
-            prev_data,
+            parsed_prev_data,
             current_data,
-            &air,
-            &call_results,
+            air,
+            call_results,
             params,
             signature_store,
+            produced_versions,
             &mut soft_limits_triggering
         ),
-        raw_prev_data,
+        prev_data,
         soft_limits_triggering
     );
 
@@ -128,29 +319,29 @@ fn execute_air_impl(
         sign_produced_cids(
             &mut exec_ctx.peer_cid_tracker,
             &mut exec_ctx.signature_store,
-            &salt,
-            &keypair,
+            &exec_ctx.run_parameters.salt,
+            &signer,
         ),
-        raw_prev_data,
+        prev_data,
         soft_limits_triggering
     );
 
     measure!(
         match exec_result {
-            Ok(_) => farewell::from_success_result(exec_ctx, trace_handler, &keypair, soft_limits_triggering),
+            Ok(_) => farewell::from_success_result(exec_ctx, trace_handler, &signer, soft_limits_triggering),
             // return new collected trace in case of errors
             Err(error) if error.is_catchable() => {
                 Err(farewell::from_execution_error(
                     exec_ctx,
                     trace_handler,
                     error,
-                    &keypair,
+                    &signer,
                     soft_limits_triggering,
                 ))
             }
             // return the prev data in case of any trace errors
             Err(error) => Err(farewell::from_uncatchable_error(
-                raw_prev_data,
+                prev_data,
                 error,
                 soft_limits_triggering
             )),
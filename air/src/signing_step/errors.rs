@@ -0,0 +1,61 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::PreparationError;
+use crate::ToErrorCode;
+
+use air_interpreter_data::verification::DataVerifierError;
+use air_interpreter_signatures::KeyError;
+use air_interpreter_signatures::VerificationError;
+use strum::IntoEnumIterator;
+use strum_macros::EnumDiscriminants;
+use strum_macros::EnumIter;
+use thiserror::Error as ThisError;
+
+/// Errors happened while finalizing remotely signed data.
+#[derive(Debug, EnumDiscriminants, ThisError)]
+#[strum_discriminants(derive(EnumIter))]
+pub enum RemoteSigningError {
+    /// Supplied data or run parameters can't be prepared.
+    #[error(transparent)]
+    Preparation(#[from] PreparationError),
+
+    /// Run parameters don't request remote signing.
+    #[error("remote signing wasn't requested: signer public key is empty")]
+    NoSignerPublicKey,
+
+    /// Signer public key is malformed.
+    #[error("malformed signer public key: {0}")]
+    MalformedKey(#[from] KeyError),
+
+    /// Failed to collect CIDs produced by the current peer.
+    #[error(transparent)]
+    DataVerification(#[from] DataVerifierError),
+
+    /// The host signature doesn't match CIDs produced by the current peer.
+    #[error("host signature doesn't match the data: {0}")]
+    SignatureMismatch(#[from] VerificationError),
+}
+
+impl ToErrorCode for RemoteSigningError {
+    fn to_error_code(&self) -> i64 {
+        use crate::utils::REMOTE_SIGNING_ERRORS_START_ID;
+        crate::generate_to_error_code!(self, RemoteSigningError, REMOTE_SIGNING_ERRORS_START_ID)
+    }
+}
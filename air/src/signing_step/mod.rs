@@ -0,0 +1,102 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+mod errors;
+mod remote_signing;
+mod salt;
+
+pub use errors::RemoteSigningError;
+pub use remote_signing::finalize_signing;
+
+pub(crate) use salt::data_salt;
+pub(crate) use salt::produced_data_versions;
+
+use crate::ExecutionError;
+
+use air_interpreter_signatures::KeyPair;
+use air_interpreter_signatures::PeerCidTracker;
+use air_interpreter_signatures::PublicKey;
+use air_interpreter_signatures::Signature;
+use air_interpreter_signatures::SignatureStore;
+use fluence_keypair::error::SigningError;
+
+/// Signs data produced by the current peer.
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum DataSigner {
+    /// The interpreter signs data with a key pair supplied by the host.
+    KeyPair(KeyPair),
+
+    /// The host signs data itself, the interpreter only provides a payload to sign.
+    Host(PublicKey),
+}
+
+impl DataSigner {
+    /// Put the current peer's signature into the store, or a placeholder in case of remote signing.
+    pub(crate) fn sign(
+        &self,
+        peer_cid_tracker: &PeerCidTracker,
+        signature_store: &mut SignatureStore,
+        salt: &str,
+    ) -> Result<(), SigningError> {
+        match self {
+            DataSigner::KeyPair(keypair) => {
+                let signature = peer_cid_tracker.gen_signature(salt, keypair)?;
+                signature_store.put(keypair.public(), signature);
+            }
+            DataSigner::Host(public_key) => signature_store.put(public_key.clone(), Signature::placeholder()),
+        }
+
+        Ok(())
+    }
+
+    /// A payload the host has to sign, it's empty if the interpreter signs data itself.
+    pub(crate) fn signing_payload(&self, peer_cid_tracker: &PeerCidTracker, salt: &str) -> Vec<u8> {
+        match self {
+            DataSigner::KeyPair(_) => vec![],
+            DataSigner::Host(_) => peer_cid_tracker.signing_payload(salt),
+        }
+    }
+}
+
+#[cfg(feature = "gen_signatures")]
+#[tracing::instrument(skip_all)]
+pub(crate) fn sign_produced_cids(
+    signature_tracker: &mut PeerCidTracker,
+    signature_store: &mut SignatureStore,
+    salt: &str,
+    signer: &DataSigner,
+) -> Result<(), ExecutionError> {
+    use crate::UncatchableError;
+
+    signer
+        .sign(signature_tracker, signature_store, salt)
+        .map_err(UncatchableError::SigningError)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "gen_signatures"))]
+#[tracing::instrument(skip_all)]
+pub(crate) fn sign_produced_cids(
+    _signature_tracker: &mut PeerCidTracker,
+    _signature_store: &mut SignatureStore,
+    _salt: &str,
+    _signer: &DataSigner,
+) -> Result<(), ExecutionError> {
+    Ok(())
+}
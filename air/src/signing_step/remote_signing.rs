@@ -0,0 +1,93 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::data_salt;
+use super::RemoteSigningError;
+use crate::preparation_step::check_version_compatibility;
+use crate::preparation_step::signer_public_key;
+use crate::preparation_step::try_to_data;
+use crate::preparation_step::try_to_envelope;
+use crate::InterpreterOutcome;
+use crate::ToErrorCode;
+use crate::INTERPRETER_SUCCESS;
+
+use air_interpreter_data::verification::DataVerifier;
+use air_interpreter_data::InterpreterDataEnvelope;
+use air_interpreter_interface::CallRequests;
+use air_interpreter_interface::CallRequestsRepr;
+use air_interpreter_interface::RunParameters;
+use air_interpreter_interface::SoftLimitsTriggering;
+use air_interpreter_sede::ToSerialized;
+use air_interpreter_signatures::KeyFormatWhitelist;
+use air_interpreter_signatures::Signature;
+
+/// Finalize data produced with remote signing by putting the host's signature into it.
+///
+/// `params` must be the same as data was produced with, and `signature` is the raw signature
+/// of the `InterpreterOutcome::signing_payload` made with the signer secret key. The signature
+/// is verified against the data before it is put. In case of an error, data is returned intact.
+#[tracing::instrument(skip_all)]
+pub fn finalize_signing(data: Vec<u8>, params: RunParameters, signature: Vec<u8>) -> InterpreterOutcome {
+    let (ret_code, error_message, data) = match finalize_signing_impl(&data, &params, signature) {
+        Ok(signed_data) => (INTERPRETER_SUCCESS, String::new(), signed_data),
+        Err(error) => (error.to_error_code(), error.to_string(), data),
+    };
+
+    let call_requests = CallRequestsRepr
+        .serialize(&CallRequests::new())
+        .expect("default serializer shouldn't fail");
+
+    InterpreterOutcome::new(
+        ret_code,
+        error_message,
+        data,
+        vec![],
+        call_requests,
+        SoftLimitsTriggering::default(),
+        vec![],
+    )
+}
+
+fn finalize_signing_impl(
+    data: &[u8],
+    params: &RunParameters,
+    raw_signature: Vec<u8>,
+) -> Result<Vec<u8>, RemoteSigningError> {
+    if params.signer_public_key.is_empty() {
+        return Err(RemoteSigningError::NoSignerPublicKey);
+    }
+    let public_key = signer_public_key(params)?;
+
+    let envelope = try_to_envelope(data)?;
+    check_version_compatibility(&envelope.versions)?;
+    let mut inner_data = try_to_data(&envelope.inner_data)?;
+
+    let salt = &data_salt(&envelope.versions, params)?;
+    let signature = Signature::from_raw_bytes(public_key.key_format()?, raw_signature);
+
+    // the data was produced by this peer and its keys were checked then
+    let verifier = DataVerifier::new(&inner_data, salt, KeyFormatWhitelist::all())?;
+    public_key.verify(verifier.peer_cids(&params.current_peer_id), salt, &signature)?;
+    drop(verifier);
+
+    inner_data.signatures.put(public_key, signature);
+
+    let envelope = InterpreterDataEnvelope::from_interpreter_data(&inner_data, envelope.versions.clone());
+    Ok(envelope.serialize().expect("default serializer shouldn't fail"))
+}
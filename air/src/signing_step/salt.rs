@@ -0,0 +1,54 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::preparation_step::interpreter_version;
+use crate::PreparationError;
+
+use air_interpreter_data::Versions;
+use air_interpreter_interface::RunParameters;
+use air_interpreter_signatures::particle_signature_salt;
+
+/// Salt of the current peer's signature in data with the given versions.
+///
+/// Data of versions before `particle_signature_salt_data_version` is salted with the particle id,
+/// newer data is always salted with the particle signature, so that all peers agree on the salt.
+/// An empty particle signature is rejected, since its salt would be shared by all particles.
+pub(crate) fn data_salt(versions: &Versions, run_parameters: &RunParameters) -> Result<String, PreparationError> {
+    if versions.has_particle_id_salt() {
+        return Ok(run_parameters.particle_id.clone());
+    }
+
+    if run_parameters.particle_signature.is_empty() {
+        return Err(PreparationError::NoParticleSignature);
+    }
+    Ok(particle_signature_salt(&run_parameters.particle_signature))
+}
+
+/// Versions of data produced from the prev and the current data.
+///
+/// The produced data is always of the current version. Signatures merged from legacy data keep
+/// the particle id salt and their peers are listed in the versions, except the current peer,
+/// whose signature is made anew.
+pub(crate) fn produced_data_versions(mut particle_id_salted_peers: Vec<String>, current_peer_id: &str) -> Versions {
+    particle_id_salted_peers.retain(|peer_id| peer_id != current_peer_id);
+
+    let mut versions = Versions::new(interpreter_version().clone());
+    versions.particle_id_salted_peers = particle_id_salted_peers;
+    versions
+}
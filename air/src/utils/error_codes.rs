@@ -22,3 +22,4 @@ pub(crate) const PREPARATION_ERROR_START_ID: i64 = 1;
 pub(crate) const CATCHABLE_ERRORS_START_ID: i64 = 10000;
 pub(crate) const UNCATCHABLE_ERRORS_START_ID: i64 = 20000;
 pub(crate) const FAREWELL_ERRORS_START_ID: i64 = 30000;
+pub(crate) const REMOTE_SIGNING_ERRORS_START_ID: i64 = 40000;
@@ -17,8 +17,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use air_interpreter_data::verification::MergedSignatures;
 use air_interpreter_data::InterpreterData;
-use air_interpreter_signatures::SignatureStore;
+use air_interpreter_data::Versions;
+use air_interpreter_interface::RunParameters;
 
 // TODO replace with VerificationError
 use crate::PreparationError;
@@ -27,28 +29,104 @@ use crate::PreparationError;
 #[tracing::instrument(skip_all)]
 pub(crate) fn verify(
     prev_data: &InterpreterData,
+    prev_versions: &Versions,
     current_data: &InterpreterData,
-    salt: &str,
-) -> Result<SignatureStore, PreparationError> {
+    current_versions: &Versions,
+    params: &RunParameters,
+) -> Result<MergedSignatures, PreparationError> {
+    use crate::preparation_step::key_format_whitelist;
     use air_interpreter_data::verification;
+    use air_interpreter_signatures::particle_signature_salt;
+    use air_interpreter_signatures::KeyFormatWhitelist;
 
     current_data.cid_info.verify()?;
+    let key_formats = key_format_whitelist(params)?;
 
-    let prev_data_verifier = verification::DataVerifier::new(prev_data, salt)?;
-    let current_data_verifier = verification::DataVerifier::new(current_data, salt)?;
+    let particle_signature_salt = particle_signature_salt(&params.particle_signature);
+    let salts = verification::DataSalts {
+        particle_id: &params.particle_id,
+        particle_signature: &particle_signature_salt,
+    };
+
+    // prev_data keys were checked when it was received, so the host may narrow the whitelist
+    // without breaking particles in flight
+    let prev_data_verifier =
+        verification::DataVerifier::with_versions(prev_data, prev_versions, salts, KeyFormatWhitelist::all())?;
+    let current_data_verifier =
+        verification::DataVerifier::with_versions(current_data, current_versions, salts, key_formats)?;
     // prev_data is always correct, check only current_data
     current_data_verifier.verify()?;
+    check_trust_policy(&current_data_verifier, current_versions, params)?;
 
-    let signature_store = prev_data_verifier.merge(current_data_verifier)?;
-    Ok(signature_store)
+    let merged_signatures = prev_data_verifier.merge(current_data_verifier)?;
+    Ok(merged_signatures)
 }
 
 #[cfg(not(feature = "check_signatures"))]
 #[tracing::instrument(skip_all)]
 pub(crate) fn verify(
     _prev_data: &InterpreterData,
+    _prev_versions: &Versions,
     _current_data: &InterpreterData,
-    _salt: &str,
-) -> Result<SignatureStore, PreparationError> {
+    _current_versions: &Versions,
+    _params: &RunParameters,
+) -> Result<MergedSignatures, PreparationError> {
     Ok(<_>::default())
 }
+
+/// Check that the data producers are trusted by the host; the current peer is always trusted.
+#[cfg(feature = "check_signatures")]
+fn check_trust_policy(
+    verifier: &air_interpreter_data::verification::DataVerifier<'_>,
+    versions: &Versions,
+    params: &RunParameters,
+) -> Result<(), PreparationError> {
+    use crate::TrustPolicyViolation;
+    use air_interpreter_signatures::KeyFormatWhitelist;
+    use air_interpreter_signatures::PublicKey;
+
+    if params.allowed_peers.is_empty()
+        && params.denied_peers.is_empty()
+        && params.known_key_peers.is_empty()
+        && !params.particle_id_salt_denied
+    {
+        return Ok(());
+    }
+
+    let known_keys = params
+        .known_keys
+        .iter()
+        .map(|key| PublicKey::try_from_bytes(key, KeyFormatWhitelist::all()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // sorted to report the same violation on every peer
+    let mut peers = verifier
+        .peers()
+        .filter(|(peer_id, _)| *peer_id != params.current_peer_id)
+        .collect::<Vec<_>>();
+    peers.sort_unstable_by_key(|(peer_id, _)| *peer_id);
+
+    for (peer_id, public_key) in peers {
+        let listed_in = |peers: &[String]| peers.iter().any(|listed_peer_id| listed_peer_id == peer_id);
+
+        if !params.allowed_peers.is_empty() && !listed_in(&params.allowed_peers) {
+            let peer_id = peer_id.to_owned();
+            return Err(TrustPolicyViolation::PeerNotAllowed { peer_id }.into());
+        }
+        if listed_in(&params.denied_peers) {
+            let peer_id = peer_id.to_owned();
+            return Err(TrustPolicyViolation::PeerDenied { peer_id }.into());
+        }
+        if listed_in(&params.known_key_peers) && !known_keys.contains(public_key) {
+            let peer_id = peer_id.to_owned();
+            let key = public_key.to_string();
+            return Err(TrustPolicyViolation::UnknownKey { peer_id, key }.into());
+        }
+        if params.particle_id_salt_denied && versions.peer_has_particle_id_salt(peer_id) {
+            let peer_id = peer_id.to_owned();
+            return Err(TrustPolicyViolation::ParticleIdSaltDenied { peer_id }.into());
+        }
+    }
+
+    Ok(())
+}
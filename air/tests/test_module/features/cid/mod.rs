@@ -18,6 +18,7 @@
  */
 
 mod canon;
+mod size_report;
 
 use air::ExecutionCidState;
 use air::UncatchableError::ValueForCidNotFound;
@@ -0,0 +1,78 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air_interpreter_data::size_report::Producer;
+use air_interpreter_data::size_report::SizeReport;
+use air_test_framework::AirScriptExecutor;
+use air_test_utils::key_utils::at;
+use air_test_utils::prelude::*;
+
+#[tokio::test]
+async fn test_size_report() {
+    let init_peer_name = "init_peer_id";
+    let large_value = "x".repeat(1000);
+
+    let script = format!(
+        r#"
+       (seq
+          (seq
+             (call "{init_peer_name}" ("serv" "func") [] $stream) ; ok = "{large_value}"
+             (call "{init_peer_name}" ("serv" "func") [] $stream)) ; ok = "{large_value}"
+          (canon "{init_peer_name}" $stream #canon))
+    "#
+    );
+
+    let executor = AirScriptExecutor::from_annotated(TestRunParameters::from_init_peer_id(init_peer_name), &script)
+        .await
+        .unwrap();
+    let result = executor.execute_one(init_peer_name).await.unwrap();
+    let data = data_from_result(&result);
+
+    let report = SizeReport::from_data(&data).unwrap();
+    assert_eq!(report.states.len(), data.trace.len());
+    assert_eq!(report.unreferenced_size, 0);
+
+    let attributed_size: usize = report.states.iter().map(|sizes| sizes.total()).sum();
+    assert_eq!(attributed_size + report.overhead_size, report.total_size);
+
+    // the value is stored once and is attributed to the first call only
+    let first_call = &report.states[0];
+    let second_call = &report.states[1];
+    assert!(first_call.values > large_value.len(), "{first_call:?}");
+    assert_eq!(second_call.values, 0);
+    assert!(second_call.shared > large_value.len(), "{second_call:?}");
+
+    let canon = &report.states[2];
+    assert!(canon.canon_results > 0);
+    assert!(canon.canon_elements > 0);
+    assert!(canon.shared > large_value.len(), "{canon:?}");
+
+    let (largest_pos, _) = report.largest_states()[0];
+    assert_eq!(largest_pos, 0.into());
+
+    let init_peer_id = at(init_peer_name);
+    let by_producer = report.by_producer();
+    let producer = Producer {
+        peer_pk: init_peer_id.clone(),
+        service_id: "serv..0".to_owned(),
+        function_name: "func".to_owned(),
+    };
+    assert_eq!(by_producer[&producer], first_call.total());
+    assert_eq!(by_producer.len(), 3, "{by_producer:?}");
+}
@@ -20,3 +20,4 @@
 mod air_basic;
 mod data_merge;
 mod executed_trace_basic;
+mod multi_data;
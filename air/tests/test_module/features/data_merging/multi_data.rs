@@ -0,0 +1,218 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air_interpreter_interface::CallResultsRepr;
+use air_interpreter_interface::RunParameters;
+use air_interpreter_interface::INTERPRETER_SUCCESS;
+use air_interpreter_interface::MAX_AIR_SIZE;
+use air_interpreter_interface::MAX_CALL_RESULT_SIZE;
+use air_interpreter_interface::MAX_PARTICLE_SIZE;
+use air_interpreter_sede::ToSerialized;
+use air_test_utils::key_utils::derive_dummy_keypair;
+use air_test_utils::prelude::*;
+
+const SCRIPT: &str = r#"
+    (seq
+        (par
+            (call "peer_a" ("" "") ["a"] $stream)
+            (par
+                (call "peer_b" ("" "") ["b"] $stream)
+                (call "peer_c" ("" "") ["c"] $stream)))
+        (seq
+            (canon "receiver" $stream #canon)
+            (call "receiver" ("" "") [#canon] x)))
+"#;
+
+fn run_parameters() -> RunParameters {
+    let (keypair, _) = derive_dummy_keypair("receiver");
+
+    RunParameters::new(
+        "peer_a".to_owned(),
+        "receiver".to_owned(),
+        0,
+        0,
+        keypair.key_format().into(),
+        keypair.secret(),
+        "".to_owned(),
+        MAX_AIR_SIZE,
+        MAX_PARTICLE_SIZE,
+        MAX_CALL_RESULT_SIZE,
+        false,
+        vec![],
+        <_>::default(),
+        <_>::default(),
+        TEST_PARTICLE_SIGNATURE.to_vec(),
+        false,
+        false,
+        vec![],
+    )
+}
+
+fn no_call_results() -> Vec<u8> {
+    CallResultsRepr.serialize(&<_>::default()).unwrap().to_vec()
+}
+
+async fn peer_data(peer_name: &str) -> Vec<u8> {
+    let mut vm = create_avm(echo_call_service(), peer_name).await;
+    let result = call_vm!(vm, <_>::default(), SCRIPT, "", "");
+    result.data
+}
+
+fn execute_sequentially(data: &[Vec<u8>]) -> Vec<RawAVMOutcome> {
+    let mut prev_data = vec![];
+    let mut outcomes = vec![];
+    for current_data in data {
+        let outcome = air::execute_air(
+            SCRIPT.to_owned(),
+            prev_data,
+            current_data.clone(),
+            run_parameters(),
+            no_call_results().into(),
+        );
+        let outcome = RawAVMOutcome::from_interpreter_outcome(outcome).unwrap();
+        prev_data = outcome.data.clone();
+        outcomes.push(outcome);
+    }
+
+    outcomes
+}
+
+fn execute_at_once(data: Vec<Vec<u8>>) -> RawAVMOutcome {
+    let outcome = air::execute_air_multi(
+        SCRIPT.to_owned(),
+        vec![],
+        data,
+        run_parameters(),
+        no_call_results().into(),
+    );
+    RawAVMOutcome::from_interpreter_outcome(outcome).unwrap()
+}
+
+#[tokio::test]
+async fn multi_data_matches_sequential_execution() {
+    let data = vec![
+        peer_data("peer_a").await,
+        peer_data("peer_b").await,
+        peer_data("peer_c").await,
+    ];
+
+    let sequential_outcomes = execute_sequentially(&data);
+    let outcome = execute_at_once(data);
+
+    let last_sequential_outcome = sequential_outcomes.last().unwrap();
+    assert_eq!(outcome.ret_code, INTERPRETER_SUCCESS, "{:?}", outcome.error_message);
+    assert_eq!(trace_from_result(&outcome), trace_from_result(last_sequential_outcome));
+
+    let sequential_call_requests: CallRequests = sequential_outcomes
+        .into_iter()
+        .flat_map(|outcome| outcome.call_requests)
+        .collect();
+    assert_eq!(outcome.call_requests, sequential_call_requests);
+    assert_eq!(outcome.call_requests.len(), 1);
+}
+
+#[tokio::test]
+async fn multi_data_with_invalid_data() {
+    let invalid_data = b"invalid data".to_vec();
+    let data = vec![peer_data("peer_a").await, invalid_data, peer_data("peer_c").await];
+
+    let sequential_outcomes = execute_sequentially(&data);
+    let outcome = execute_at_once(data);
+
+    assert_ne!(outcome.ret_code, INTERPRETER_SUCCESS);
+    assert_eq!(outcome.ret_code, sequential_outcomes[1].ret_code);
+    assert_eq!(outcome.error_message, sequential_outcomes[1].error_message);
+    assert_eq!(
+        trace_from_result(&outcome),
+        trace_from_result(sequential_outcomes.last().unwrap())
+    );
+}
+
+#[tokio::test]
+async fn multi_data_with_single_data() {
+    let data = peer_data("peer_b").await;
+
+    let sequential_outcome = execute_sequentially(&[data.clone()]).remove(0);
+    let outcome = execute_at_once(vec![data]);
+
+    assert_eq!(outcome.ret_code, sequential_outcome.ret_code);
+    assert_eq!(trace_from_result(&outcome), trace_from_result(&sequential_outcome));
+    assert_eq!(outcome.call_requests, sequential_outcome.call_requests);
+}
+
+#[tokio::test]
+async fn multi_data_with_incompatible_data() {
+    // the data is valid, but its trace doesn't match the script, so it fails during execution
+    let mut vm = create_avm(echo_call_service(), "peer_b").await;
+    let incompatible_script = r#"(seq (call "peer_b" ("" "") ["b"] x) (null))"#;
+    let incompatible_data = call_vm!(vm, <_>::default(), incompatible_script, "", "").data;
+    let data = vec![peer_data("peer_a").await, incompatible_data, peer_data("peer_c").await];
+
+    let sequential_outcomes = execute_sequentially(&data);
+    assert_eq!(sequential_outcomes[0].ret_code, INTERPRETER_SUCCESS);
+    assert_ne!(sequential_outcomes[1].ret_code, INTERPRETER_SUCCESS);
+    assert_eq!(sequential_outcomes[2].ret_code, INTERPRETER_SUCCESS);
+
+    let outcome = execute_at_once(data);
+    assert_eq!(outcome.ret_code, sequential_outcomes[1].ret_code);
+    assert_eq!(outcome.error_message, sequential_outcomes[1].error_message);
+    assert_eq!(
+        trace_from_result(&outcome),
+        trace_from_result(sequential_outcomes.last().unwrap())
+    );
+
+    let sequential_call_requests: CallRequests = sequential_outcomes
+        .into_iter()
+        .flat_map(|outcome| outcome.call_requests)
+        .collect();
+    assert_eq!(outcome.call_requests, sequential_call_requests);
+}
+
+#[tokio::test]
+async fn multi_data_with_several_incompatible_data() {
+    // passes following the first failed one continue from the last good pass
+    let mut vm = create_avm(echo_call_service(), "peer_b").await;
+    let incompatible_script = r#"(seq (call "peer_b" ("" "") ["b"] x) (null))"#;
+    let incompatible_data = call_vm!(vm, <_>::default(), incompatible_script, "", "").data;
+    let data = vec![
+        peer_data("peer_a").await,
+        incompatible_data.clone(),
+        incompatible_data,
+        peer_data("peer_c").await,
+    ];
+
+    let sequential_outcomes = execute_sequentially(&data);
+    assert_ne!(sequential_outcomes[1].ret_code, INTERPRETER_SUCCESS);
+    assert_ne!(sequential_outcomes[2].ret_code, INTERPRETER_SUCCESS);
+    assert_eq!(sequential_outcomes[3].ret_code, INTERPRETER_SUCCESS);
+
+    let outcome = execute_at_once(data);
+    assert_eq!(outcome.ret_code, sequential_outcomes[1].ret_code);
+    assert_eq!(outcome.error_message, sequential_outcomes[1].error_message);
+    assert_eq!(
+        trace_from_result(&outcome),
+        trace_from_result(sequential_outcomes.last().unwrap())
+    );
+
+    let sequential_call_requests: CallRequests = sequential_outcomes
+        .into_iter()
+        .flat_map(|outcome| outcome.call_requests)
+        .collect();
+    assert_eq!(outcome.call_requests, sequential_call_requests);
+}
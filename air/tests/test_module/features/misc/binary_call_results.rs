@@ -0,0 +1,196 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air_interpreter_interface::CallResults as RawCallResults;
+use air_interpreter_interface::CallResultsRepr;
+use air_interpreter_interface::CallServiceResult as RawCallServiceResult;
+use air_interpreter_interface::RunParameters;
+use air_interpreter_interface::MAX_AIR_SIZE;
+use air_interpreter_interface::MAX_CALL_RESULT_SIZE;
+use air_interpreter_interface::MAX_PARTICLE_SIZE;
+use air_interpreter_sede::Format;
+use air_interpreter_sede::ToSerialized;
+use air_test_utils::key_utils::derive_dummy_keypair;
+use air_test_utils::prelude::*;
+use futures::FutureExt;
+
+const PEER_ID: &str = "peer_id";
+
+fn script() -> String {
+    format!(
+        r#"
+        (seq
+            (call "{PEER_ID}" ("service" "func") [] x)
+            (call "{PEER_ID}" ("echo" "") [x] y))
+        "#
+    )
+}
+
+fn run_parameters() -> RunParameters {
+    let (keypair, _) = derive_dummy_keypair(PEER_ID);
+
+    RunParameters::new(
+        PEER_ID.to_owned(),
+        PEER_ID.to_owned(),
+        0,
+        0,
+        keypair.key_format().into(),
+        keypair.secret(),
+        "".to_owned(),
+        MAX_AIR_SIZE,
+        MAX_PARTICLE_SIZE,
+        MAX_CALL_RESULT_SIZE,
+        false,
+        vec![],
+        <_>::default(),
+        <_>::default(),
+        TEST_PARTICLE_SIGNATURE.to_vec(),
+        false,
+        false,
+        vec![],
+    )
+}
+
+fn execute(prev_data: Vec<u8>, call_results: RawCallResults) -> RawAVMOutcome {
+    let call_results = CallResultsRepr.serialize(&call_results).unwrap();
+    let outcome = air::execute_air(script(), prev_data, vec![], run_parameters(), call_results);
+    RawAVMOutcome::from_interpreter_outcome(outcome).unwrap()
+}
+
+/// Executes the script up to the first call and then passes it the raw result.
+fn execute_with_raw_result(call_result: RawCallServiceResult) -> RawAVMOutcome {
+    let outcome = execute(vec![], <_>::default());
+    let call_results = maplit::hashmap! {"1".to_owned() => call_result};
+    execute(outcome.data, call_results)
+}
+
+#[tokio::test]
+async fn binary_result_has_same_cids_as_json_result() {
+    let value = json!({"a": [1, "b", null, {"c": true}], "d": 1.5, "e": -42});
+
+    let json_value = value.clone();
+    let json_service: CallServiceClosure<'static> = Box::new(move |params| {
+        let result = match params.service_id.as_str() {
+            "echo" => params.arguments[0].clone(),
+            _ => json_value.clone(),
+        };
+        async move { CallServiceResult::ok(result) }.boxed_local()
+    });
+    let binary_service: CallServiceClosure<'static> = Box::new(move |params| {
+        let result = match params.service_id.as_str() {
+            "echo" => params.arguments[0].clone(),
+            _ => value.clone(),
+        };
+        async move { CallServiceResult::ok_binary(result) }.boxed_local()
+    });
+
+    let mut json_vm = create_avm(json_service, PEER_ID).await;
+    let mut binary_vm = create_avm(binary_service, PEER_ID).await;
+    let json_result = checked_call_vm!(json_vm, <_>::default(), script(), "", "");
+    let binary_result = checked_call_vm!(binary_vm, <_>::default(), script(), "", "");
+
+    assert_eq!(trace_from_result(&binary_result), trace_from_result(&json_result));
+}
+
+#[test]
+fn bytes_result_is_seen_as_array_of_numbers() {
+    let bytes = vec![0u8, 1, 127, 128, 255];
+
+    let bytes_outcome = execute_with_raw_result(RawCallServiceResult::ok_bytes(bytes.clone()));
+    let json_outcome = execute_with_raw_result(RawCallServiceResult::ok(&json!(bytes)));
+
+    assert_eq!(bytes_outcome.ret_code, 0, "{}", bytes_outcome.error_message);
+    assert_eq!(bytes_outcome.call_requests.len(), 1);
+    assert_eq!(bytes_outcome.call_requests[&2].arguments, vec![json!(bytes)]);
+    assert_eq!(trace_from_result(&bytes_outcome), trace_from_result(&json_outcome));
+}
+
+#[test]
+fn bytes_result_is_stored_as_bytes() {
+    let bytes = vec![255u8; 1024];
+
+    let bytes_outcome = execute_with_raw_result(RawCallServiceResult::ok_bytes(bytes.clone()));
+    let json_outcome = execute_with_raw_result(RawCallServiceResult::ok(&json!(bytes)));
+
+    assert_eq!(bytes_outcome.ret_code, 0, "{}", bytes_outcome.error_message);
+    // every byte takes four characters in JSON
+    assert!(
+        bytes_outcome.data.len() + 2 * bytes.len() < json_outcome.data.len(),
+        "{} vs {}",
+        bytes_outcome.data.len(),
+        json_outcome.data.len(),
+    );
+}
+
+#[test]
+fn bytes_result_is_read_from_prev_data() {
+    let bytes = vec![0u8, 1, 127, 128, 255];
+    let echo_result = maplit::hashmap! {"2".to_owned() => RawCallServiceResult::ok(&json!(bytes))};
+
+    let bytes_outcome = execute_with_raw_result(RawCallServiceResult::ok_bytes(bytes.clone()));
+    let bytes_outcome = execute(bytes_outcome.data, echo_result.clone());
+    let json_outcome = execute_with_raw_result(RawCallServiceResult::ok(&json!(bytes)));
+    let json_outcome = execute(json_outcome.data, echo_result);
+
+    assert_eq!(bytes_outcome.ret_code, 0, "{}", bytes_outcome.error_message);
+    assert!(bytes_outcome.call_requests.is_empty());
+    assert_eq!(trace_from_result(&bytes_outcome), trace_from_result(&json_outcome));
+}
+
+#[test]
+fn nested_bytes_are_service_error() {
+    let nested_bytes = vec![serde_bytes::ByteBuf::from(vec![1u8])];
+    let call_result = RawCallServiceResult {
+        ret_code: 0,
+        result: String::new(),
+        binary_result: Some(
+            air_interpreter_interface::CallResultsFormat::default()
+                .to_vec(&nested_bytes)
+                .unwrap()
+                .into(),
+        ),
+    };
+
+    let outcome = execute_with_raw_result(call_result);
+
+    assert_ne!(outcome.ret_code, 0);
+    assert!(
+        outcome.error_message.contains("can't be serialized or deserialized"),
+        "{}",
+        outcome.error_message
+    );
+}
+
+#[test]
+fn malformed_binary_result_is_service_error() {
+    let call_result = RawCallServiceResult {
+        ret_code: 0,
+        result: String::new(),
+        binary_result: Some(vec![0xff, 0xff].into()),
+    };
+
+    let outcome = execute_with_raw_result(call_result);
+
+    assert_ne!(outcome.ret_code, 0);
+    assert!(
+        outcome.error_message.contains("can't be serialized or deserialized"),
+        "{}",
+        outcome.error_message
+    );
+}
@@ -0,0 +1,144 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air::CatchableError;
+use air::ToErrorCode;
+use air::BUILTIN_CALL_FAILED;
+use air::BUILTIN_OP_SERVICE_ID;
+use air::BUILTIN_STRING_SERVICE_ID;
+use air_test_utils::prelude::*;
+
+use std::rc::Rc;
+
+#[tokio::test]
+async fn builtins_are_executed_without_host() {
+    let peer_id = "peer_id";
+    // the host service would return "host" for any call
+    let mut vm = create_avm(set_variable_call_service(json!("host")), peer_id).await;
+
+    let script = format!(
+        r#"
+        (seq
+            (seq
+                (call "{peer_id}" ("{BUILTIN_OP_SERVICE_ID}" "array") ["a" "b"] arr)
+                (call "{peer_id}" ("{BUILTIN_OP_SERVICE_ID}" "concat") [arr arr] joined_arr))
+            (seq
+                (call "{peer_id}" ("{BUILTIN_STRING_SERVICE_ID}" "join") [joined_arr ", "] joined)
+                (call "{peer_id}" ("{BUILTIN_OP_SERVICE_ID}" "noop") [])))
+        "#
+    );
+    let result = vm
+        .call_single(&script, "", "", peer_id, 0, 0, None, <_>::default(), "")
+        .await
+        .unwrap();
+    assert!(result.call_requests.is_empty());
+
+    let expected_trace = vec![
+        scalar!(
+            json!(["a", "b"]),
+            peer = peer_id,
+            service = BUILTIN_OP_SERVICE_ID,
+            function = "array",
+            args = ["a", "b"]
+        ),
+        scalar!(
+            json!(["a", "b", "a", "b"]),
+            peer = peer_id,
+            service = BUILTIN_OP_SERVICE_ID,
+            function = "concat",
+            args = [json!(["a", "b"]), json!(["a", "b"])]
+        ),
+        scalar!(
+            "a, b, a, b",
+            peer = peer_id,
+            service = BUILTIN_STRING_SERVICE_ID,
+            function = "join",
+            args = [json!(["a", "b", "a", "b"]), json!(", ")]
+        ),
+        unused!(
+            json!(null),
+            peer = peer_id,
+            service = BUILTIN_OP_SERVICE_ID,
+            function = "noop"
+        ),
+    ];
+    assert_eq!(trace_from_result(&result), ExecutionTrace::from(expected_trace));
+}
+
+#[tokio::test]
+async fn builtin_on_remote_peer_is_sent_to_it() {
+    let peer_id = "peer_id";
+    let remote_peer_id = "remote_peer_id";
+    let mut vm = create_avm(unit_call_service(), peer_id).await;
+    let mut remote_vm = create_avm(set_variable_call_service(json!("host")), remote_peer_id).await;
+
+    let script = format!(r#"(call "{remote_peer_id}" ("{BUILTIN_OP_SERVICE_ID}" "identity") ["a"] x)"#);
+    let result = checked_call_vm!(vm, <_>::default(), &script, "", "");
+    assert_eq!(result.next_peer_pks, vec![remote_peer_id.to_owned()]);
+    assert_eq!(
+        trace_from_result(&result),
+        ExecutionTrace::from(vec![request_sent_by(peer_id)])
+    );
+
+    let result = checked_call_vm!(remote_vm, <_>::default(), &script, "", result.data);
+    let expected_trace = vec![scalar!(
+        "a",
+        peer = remote_peer_id,
+        service = BUILTIN_OP_SERVICE_ID,
+        function = "identity",
+        args = ["a"]
+    )];
+    assert_eq!(trace_from_result(&result), ExecutionTrace::from(expected_trace));
+}
+
+#[tokio::test]
+async fn builtin_failure_is_catchable() {
+    let peer_id = "peer_id";
+    let mut vm = create_avm(echo_call_service(), peer_id).await;
+
+    let script = format!(
+        r#"
+        (xor
+            (call "{peer_id}" ("{BUILTIN_STRING_SERVICE_ID}" "concat") ["a" 1] x)
+            (call "{peer_id}" ("{BUILTIN_OP_SERVICE_ID}" "identity") [%last_error%.$.error_code] code))
+        "#
+    );
+    let result = checked_call_vm!(vm, <_>::default(), &script, "", "");
+
+    let message = "concat expects strings, argument 1 is '1'";
+    let error_code = CatchableError::LocalServiceError(BUILTIN_CALL_FAILED, Rc::new(String::new())).to_error_code();
+    let expected_trace = vec![
+        failed!(
+            BUILTIN_CALL_FAILED,
+            message,
+            peer = peer_id,
+            service = BUILTIN_STRING_SERVICE_ID,
+            function = "concat",
+            args = [json!("a"), json!(1)]
+        ),
+        scalar!(
+            error_code,
+            peer = peer_id,
+            service = BUILTIN_OP_SERVICE_ID,
+            function = "identity",
+            args = [error_code]
+        ),
+    ];
+    assert_eq!(trace_from_result(&result), ExecutionTrace::from(expected_trace));
+}
@@ -0,0 +1,139 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air_test_utils::prelude::*;
+use futures::FutureExt;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Returns an array for the `iterable` function and echoes the first argument otherwise,
+/// the latter calls are counted.
+fn counting_call_service(calls_count: Rc<Cell<u32>>) -> CallServiceClosure<'static> {
+    Box::new(move |mut params| {
+        let result = if params.function_name == "iterable" {
+            json!(["a", "b", "c"])
+        } else {
+            calls_count.set(calls_count.get() + 1);
+            params.arguments.remove(0)
+        };
+        async move { CallServiceResult::ok(result) }.boxed_local()
+    })
+}
+
+fn fold_script(peer_id: &str) -> String {
+    format!(
+        r#"
+        (seq
+            (call "{peer_id}" ("" "iterable") [] iterable)
+            (fold iterable i
+                (par
+                    (call "{peer_id}" ("service" "func") ["same"] $results)
+                    (next i))))
+        "#
+    )
+}
+
+#[tokio::test]
+async fn identical_call_requests_are_emitted_once() {
+    let peer_id = "peer_id";
+    let mut vm = create_avm(echo_call_service(), peer_id).await;
+    vm.runner.set_call_deduplication_enabled(true);
+
+    let script = format!(
+        r#"
+        (par
+            (call "{peer_id}" ("service" "func") ["a"] $results)
+            (par
+                (call "{peer_id}" ("service" "func") ["a"] $results)
+                (par
+                    (call "{peer_id}" ("service" "func") ["b"] $results)
+                    (call "{peer_id}" ("other" "func") ["a"] $results))))
+        "#
+    );
+    let result = vm
+        .call_single(&script, "", "", peer_id, 0, 0, None, <_>::default(), "")
+        .await
+        .unwrap();
+
+    let call_requests = result.call_requests;
+    assert_eq!(call_requests.len(), 3);
+    assert_eq!(call_requests[&1].fan_out_call_ids, vec![2]);
+    assert_eq!(call_requests[&3].fan_out_call_ids, Vec::<u32>::new());
+    assert_eq!(call_requests[&4].fan_out_call_ids, Vec::<u32>::new());
+
+    let groups: Vec<_> = group_by_service(&call_requests)
+        .into_iter()
+        .map(|(service_id, calls)| {
+            (
+                service_id,
+                calls.iter().map(|(call_id, _)| *call_id).collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    assert_eq!(groups, vec![("other", vec![4]), ("service", vec![1, 3])]);
+
+    let mut call_results = CallResults::new();
+    call_results.insert(1, CallServiceResult::ok(json!("a")));
+    call_results.insert(3, CallServiceResult::ok(json!("b")));
+    call_results.insert(4, CallServiceResult::ok(json!("a")));
+    fan_out_call_results(&call_requests, &mut call_results);
+    assert_eq!(call_results[&2], CallServiceResult::ok(json!("a")));
+
+    let result = vm
+        .call_single(&script, result.data, "", peer_id, 0, 0, None, call_results, "")
+        .await
+        .unwrap();
+    assert!(result.call_requests.is_empty());
+    assert_eq!(trace_from_result(&result).len(), 7);
+}
+
+#[tokio::test]
+async fn fold_calls_are_executed_once() {
+    let peer_id = "peer_id";
+    let calls_count = Rc::new(Cell::new(0));
+    let mut vm = create_avm(counting_call_service(calls_count.clone()), peer_id).await;
+    vm.runner.set_call_deduplication_enabled(true);
+
+    let result = checked_call_vm!(vm, <_>::default(), fold_script(peer_id), "", "");
+
+    assert_eq!(calls_count.get(), 1);
+    let trace = trace_from_result(&result);
+    let results_count = trace
+        .iter()
+        .filter(|state| {
+            matches!(
+                state,
+                ExecutedState::Call(CallResult::Executed(ValueRef::Stream { .. }))
+            )
+        })
+        .count();
+    assert_eq!(results_count, 3);
+}
+
+#[tokio::test]
+async fn call_deduplication_is_disabled_by_default() {
+    let peer_id = "peer_id";
+    let calls_count = Rc::new(Cell::new(0));
+    let mut vm = create_avm(counting_call_service(calls_count.clone()), peer_id).await;
+
+    checked_call_vm!(vm, <_>::default(), fold_script(peer_id), "", "");
+
+    assert_eq!(calls_count.get(), 3);
+}
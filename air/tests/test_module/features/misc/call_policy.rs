@@ -0,0 +1,220 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air::CatchableError;
+use air::ToErrorCode;
+use air_test_utils::prelude::*;
+use futures::FutureExt;
+
+use std::rc::Rc;
+
+fn rule(service_id: &str, function_name: &str, argument_peers: &[&str]) -> CallRule {
+    CallRule {
+        service_id: service_id.to_owned(),
+        function_name: function_name.to_owned(),
+        argument_peers: argument_peers.iter().map(|&peer_id| peer_id.to_owned()).collect(),
+    }
+}
+
+fn not_authorized_error(reason: &str) -> CatchableError {
+    let reason = serde_json::to_string(reason).unwrap();
+    CatchableError::CallNotAuthorized(Rc::new(reason))
+}
+
+#[tokio::test]
+async fn call_policy_allows_matching_calls() {
+    let peer_id = "peer_id";
+    let mut vm = create_avm(echo_call_service(), peer_id).await;
+    vm.runner.set_call_policy(CallPolicy {
+        rules: vec![rule("op", "*", &[]), rule("getDataSrv", "get_*", &[])],
+    });
+
+    let script = format!(
+        r#"
+        (seq
+            (call "{peer_id}" ("op" "identity") ["a"] x)
+            (call "{peer_id}" ("getDataSrv" "get_value") [x] y))
+        "#
+    );
+    let result = checked_call_vm!(vm, <_>::default(), &script, "", "");
+
+    let expected_trace = vec![
+        scalar!("a", peer = peer_id, service = "op", function = "identity", args = ["a"]),
+        scalar!(
+            "a",
+            peer = peer_id,
+            service = "getDataSrv",
+            function = "get_value",
+            args = ["a"]
+        ),
+    ];
+    assert_eq!(trace_from_result(&result), ExecutionTrace::from(expected_trace));
+}
+
+#[tokio::test]
+async fn call_policy_rejects_not_matching_call() {
+    let peer_id = "peer_id";
+    let mut vm = create_avm(echo_call_service(), peer_id).await;
+    vm.runner.set_call_policy(CallPolicy {
+        rules: vec![rule("getDataSrv", "get_*", &[])],
+    });
+
+    let script = format!(r#"(call "{peer_id}" ("getDataSrv" "set_value") ["a"] x)"#);
+    let result = call_vm!(vm, <_>::default(), &script, "", "");
+
+    let reason = "call of 'getDataSrv' 'set_value' is not allowed by the call policy";
+    assert_error_eq!(&result, not_authorized_error(reason));
+
+    let expected_trace = vec![not_authorized!(
+        reason,
+        peer = peer_id,
+        service = "getDataSrv",
+        function = "set_value",
+        args = ["a"]
+    )];
+    assert_eq!(trace_from_result(&result), ExecutionTrace::from(expected_trace));
+}
+
+#[tokio::test]
+async fn call_policy_rejection_is_replayed() {
+    let peer_id = "peer_id";
+    let other_peer_id = "other_peer_id";
+    let mut vm = create_avm(echo_call_service(), peer_id).await;
+    vm.runner.set_call_policy(CallPolicy {
+        rules: vec![rule("op", "*", &[])],
+    });
+    let mut other_vm = create_avm(echo_call_service(), other_peer_id).await;
+
+    let script = format!(
+        r#"
+        (seq
+            (call "{peer_id}" ("secret" "get") [] x)
+            (call "{other_peer_id}" ("op" "identity") [] y))
+        "#
+    );
+    let result = call_vm!(vm, <_>::default(), &script, "", "");
+    let other_result = call_vm!(other_vm, <_>::default(), &script, "", result.data);
+
+    let reason = "call of 'secret' 'get' is not allowed by the call policy";
+    assert_error_eq!(&other_result, not_authorized_error(reason));
+}
+
+#[tokio::test]
+async fn call_policy_rejection_is_catchable() {
+    let peer_id = "peer_id";
+    let mut vm = create_avm(echo_call_service(), peer_id).await;
+    vm.runner.set_call_policy(CallPolicy {
+        rules: vec![rule("op", "identity", &[])],
+    });
+
+    let script = format!(
+        r#"
+        (xor
+            (call "{peer_id}" ("secret" "get") [] x)
+            (call "{peer_id}" ("op" "identity") [%last_error%.$.error_code] code))
+        "#
+    );
+    let result = checked_call_vm!(vm, <_>::default(), &script, "", "");
+
+    let error_code = CALL_NOT_AUTHORIZED;
+    assert_eq!(not_authorized_error("").to_error_code(), error_code);
+    let trace = trace_from_result(&result);
+    assert_eq!(
+        trace.last().unwrap(),
+        &scalar!(
+            error_code,
+            peer = peer_id,
+            service = "op",
+            function = "identity",
+            args = [error_code]
+        )
+    );
+}
+
+#[tokio::test]
+async fn call_policy_checks_argument_peers() {
+    let trusted_peer_id = "trusted_peer_id";
+    let other_peer_id = "other_peer_id";
+    let peer_id = "peer_id";
+    let mut trusted_vm = create_avm(set_variable_call_service(json!("trusted")), trusted_peer_id).await;
+    let mut other_vm = create_avm(set_variable_call_service(json!("other")), other_peer_id).await;
+    let mut vm = create_avm(echo_call_service(), peer_id).await;
+    vm.runner.set_call_policy(CallPolicy {
+        rules: vec![rule("op", "identity", &[trusted_peer_id, peer_id])],
+    });
+
+    let script = format!(
+        r#"
+        (seq
+            (seq
+                (call "{trusted_peer_id}" ("" "") [] trusted)
+                (call "{other_peer_id}" ("" "") [] other))
+            (seq
+                (call "{peer_id}" ("op" "identity") [trusted] x)
+                (call "{peer_id}" ("op" "identity") [x other] y)))
+        "#
+    );
+    let test_params = TestRunParameters::from_init_peer_id(peer_id);
+    let trusted_result = checked_call_vm!(trusted_vm, test_params.clone(), &script, "", "");
+    let other_result = checked_call_vm!(other_vm, test_params.clone(), &script, "", trusted_result.data);
+    let result = call_vm!(vm, test_params, &script, "", other_result.data);
+
+    assert_error_eq!(
+        &result,
+        not_authorized_error("argument 1 originates from 'other_peer_id' that is not allowed by the call policy")
+    );
+
+    let trace = trace_from_result(&result);
+    assert_eq!(
+        trace[2.into()],
+        scalar!(
+            "trusted",
+            peer = peer_id,
+            service = "op",
+            function = "identity",
+            args = ["trusted"]
+        )
+    );
+}
+
+#[tokio::test]
+async fn service_error_is_not_a_rejection() {
+    let peer_id = "peer_id";
+    let other_peer_id = "other_peer_id";
+    // the ret code saved for rejected calls
+    let ret_code = i32::MAX;
+    let call_service: CallServiceClosure<'static> =
+        Box::new(move |_| async move { CallServiceResult::err(ret_code, json!("error")) }.boxed_local());
+    let mut vm = create_avm(call_service, peer_id).await;
+    let mut other_vm = create_avm(echo_call_service(), other_peer_id).await;
+
+    let script = format!(
+        r#"
+        (seq
+            (call "{peer_id}" ("service" "function") [] x)
+            (call "{other_peer_id}" ("op" "identity") [] y))
+        "#
+    );
+    let result = call_vm!(vm, <_>::default(), &script, "", "");
+    let other_result = call_vm!(other_vm, <_>::default(), &script, "", result.data.clone());
+
+    let expected_error = CatchableError::LocalServiceError(ret_code, Rc::new(json!("error").to_string()));
+    assert_error_eq!(&result, expected_error.clone());
+    assert_error_eq!(&other_result, expected_error);
+}
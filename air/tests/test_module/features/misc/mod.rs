@@ -17,5 +17,9 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+mod binary_call_results;
+mod builtins;
+mod call_deduplication;
+mod call_policy;
 mod empty_array;
 mod version_check;
@@ -20,8 +20,13 @@
 use air::min_supported_version;
 use air::PreparationError;
 use air_interpreter_interface::INTERPRETER_SUCCESS;
+use air_test_utils::key_utils::derive_dummy_keypair;
 use air_test_utils::prelude::*;
 
+/// Data of the previous release (interpreter 0.64.1, data 0.17.2) with a call result of alice
+/// signed with the particle id salt, `bob` is expected to make the next call.
+const PREVIOUS_RELEASE_DATA: &[u8] = include_bytes!("data/previous_release_data.bin");
+
 #[tokio::test]
 async fn minimal_version_check() {
     let mut vm = create_avm(echo_call_service(), "").await;
@@ -71,3 +76,39 @@ async fn publish_unsupported_version_check() {
 
     assert!(check_error(&result, expected_error));
 }
+
+#[tokio::test]
+async fn previous_release_data_is_loaded() {
+    let (_, alice_peer_id) = derive_dummy_keypair("alice_peer");
+    let (bob_keypair, bob_peer_id) = derive_dummy_keypair("bob_peer");
+    let mut bob_avm =
+        create_avm_with_key::<NativeAirRunner>(bob_keypair, set_variable_call_service(json!("ok")), <_>::default())
+            .await;
+    let script = format!(
+        r#"
+        (seq
+            (call "{alice_peer_id}" ("" "") [] x)
+            (call "{bob_peer_id}" ("" "") [] y))
+        "#
+    );
+
+    let versions = InterpreterDataEnvelope::try_get_versions(PREVIOUS_RELEASE_DATA).unwrap();
+    assert_eq!(versions.interpreter_version, semver::Version::new(0, 64, 1));
+    assert_eq!(versions.data_version, semver::Version::new(0, 17, 2));
+
+    let test_run_params = TestRunParameters::from_init_peer_id(&alice_peer_id)
+        .with_particle_id("particle_id")
+        .with_particle_signature(b"particle signature");
+    let result = bob_avm
+        .call(script, "", PREVIOUS_RELEASE_DATA, test_run_params)
+        .await
+        .unwrap();
+    assert_eq!(result.ret_code, INTERPRETER_SUCCESS, "{:?}", result.error_message);
+
+    let actual_trace = trace_from_result(&result);
+    let expected_trace = vec![
+        scalar!("ok", peer = &alice_peer_id, service = "", function = ""),
+        scalar!("ok", peer = &bob_peer_id, service = "", function = ""),
+    ];
+    assert_eq!(actual_trace, ExecutionTrace::from(expected_trace));
+}
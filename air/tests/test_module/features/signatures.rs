@@ -21,10 +21,22 @@
 mod algorithms;
 #[cfg(feature = "check_signatures")]
 mod attacks;
+#[cfg(feature = "gen_signatures")]
+mod batch_verification;
 #[cfg(feature = "check_signatures")]
 mod corruption;
+#[cfg(feature = "gen_signatures")]
+mod data_validation;
+#[cfg(feature = "gen_signatures")]
+mod inclusion_proof;
+#[cfg(feature = "check_signatures")]
+mod particle_signature;
+#[cfg(feature = "gen_signatures")]
+mod remote_signing;
 
 mod runtime;
 
 #[cfg(feature = "gen_signatures")]
 mod signing;
+#[cfg(feature = "check_signatures")]
+mod trust_policy;
@@ -18,11 +18,15 @@
  */
 
 use air::PreparationError;
+use air_interpreter_data::verification::DataVerifierError;
 use air_interpreter_signatures::KeyError;
+use air_interpreter_signatures::KeyFormatWhitelist;
+use air_interpreter_signatures::PublicKey;
+use air_test_utils::key_utils::derive_dummy_keypair;
 use air_test_utils::{
     assert_error_eq,
-    prelude::unit_call_service,
-    test_runner::{create_avm_with_key, NativeAirRunner, TestRunParameters},
+    prelude::*,
+    test_runner::{create_avm_with_key, NativeAirRunner, TestInitParameters, TestRunParameters},
 };
 use fluence_keypair::KeyFormat;
 
@@ -114,3 +118,166 @@ async fn test_banned_signing_key() {
         PreparationError::MalformedKeyPairData(KeyError::AlgorithmNotWhitelisted(KeyFormat::Secp256k1))
     );
 }
+
+/// Checking that a local key of a whitelisted algorithm is used for signing.
+#[tokio::test]
+async fn test_whitelisted_signing_key() {
+    let air_script = r#"(call %init_peer_id% ("" "") [] x)"#;
+    let keypair = fluence_keypair::KeyPair::generate_secp256k1();
+    let public_key = PublicKey::new(keypair.public());
+    let peer_id = keypair.public().to_peer_id().to_string();
+
+    let test_init_parameters = TestInitParameters::default().with_allowed_key_formats(KeyFormatWhitelist::all());
+    let mut avm =
+        create_avm_with_key::<NativeAirRunner>(keypair, set_variable_call_service(json!("ok")), test_init_parameters)
+            .await;
+    let res = avm
+        .call(air_script, "", "", TestRunParameters::from_init_peer_id(&peer_id))
+        .await
+        .unwrap();
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+
+    let data = data_from_result(&res);
+    assert!(data.signatures.get(&public_key).is_some(), "{:?}", data.signatures);
+}
+
+fn mixed_algorithms_script(ed25519_peer_id: &str, secp256k1_peer_id: &str) -> String {
+    format!(
+        r#"
+        (seq
+            (seq
+                (call "{ed25519_peer_id}" ("" "") [] x)
+                (call "{secp256k1_peer_id}" ("" "") [] y))
+            (call "{ed25519_peer_id}" ("" "") [] z))
+        "#
+    )
+}
+
+/// Checking that peers with keys of different whitelisted algorithms sign and verify the same particle.
+#[tokio::test]
+async fn test_mixed_algorithms_particle() {
+    let (ed25519_keypair, ed25519_peer_id) = derive_dummy_keypair("ed25519_peer");
+    let ed25519_public_key = ed25519_keypair.public();
+    let secp256k1_keypair = fluence_keypair::KeyPair::generate_secp256k1();
+    let secp256k1_public_key = PublicKey::new(secp256k1_keypair.public());
+    let secp256k1_peer_id = secp256k1_keypair.public().to_peer_id().to_string();
+
+    let test_init_parameters = TestInitParameters::default().with_allowed_key_formats(KeyFormatWhitelist::all());
+    let mut ed25519_avm = create_avm_with_key::<NativeAirRunner>(
+        ed25519_keypair,
+        set_variable_call_service(json!("ok")),
+        test_init_parameters,
+    )
+    .await;
+    let mut secp256k1_avm = create_avm_with_key::<NativeAirRunner>(
+        secp256k1_keypair,
+        set_variable_call_service(json!("ok")),
+        test_init_parameters,
+    )
+    .await;
+
+    let air_script = mixed_algorithms_script(&ed25519_peer_id, &secp256k1_peer_id);
+    let run_params = TestRunParameters::from_init_peer_id(&ed25519_peer_id).with_particle_id("particle_id");
+
+    let res1 = ed25519_avm.call(&air_script, "", "", run_params.clone()).await.unwrap();
+    assert_eq!(res1.ret_code, 0, "{:?}", res1);
+
+    let res2 = secp256k1_avm
+        .call(&air_script, "", res1.data.clone(), run_params.clone())
+        .await
+        .unwrap();
+    assert_eq!(res2.ret_code, 0, "{:?}", res2);
+
+    let res3 = ed25519_avm
+        .call(&air_script, res1.data, res2.data, run_params)
+        .await
+        .unwrap();
+    assert_eq!(res3.ret_code, 0, "{:?}", res3);
+
+    let data = data_from_result(&res3);
+    assert_eq!(data.signatures.len(), 2, "{:?}", data.signatures);
+    assert!(data.signatures.get(&ed25519_public_key).is_some());
+    assert!(data.signatures.get(&secp256k1_public_key).is_some());
+}
+
+/// Checking that other peers' keys of algorithms not whitelisted by the host are rejected.
+#[tokio::test]
+async fn test_peer_key_not_whitelisted() {
+    let (ed25519_keypair, ed25519_peer_id) = derive_dummy_keypair("ed25519_peer");
+    let secp256k1_keypair = fluence_keypair::KeyPair::generate_secp256k1();
+    let secp256k1_public_key = PublicKey::new(secp256k1_keypair.public());
+    let secp256k1_peer_id = secp256k1_keypair.public().to_peer_id().to_string();
+
+    let mut secp256k1_avm = create_avm_with_key::<NativeAirRunner>(
+        secp256k1_keypair,
+        set_variable_call_service(json!("ok")),
+        TestInitParameters::default().with_allowed_key_formats(KeyFormatWhitelist::all()),
+    )
+    .await;
+    let mut ed25519_avm =
+        create_avm_with_key::<NativeAirRunner>(ed25519_keypair, set_variable_call_service(json!("ok")), <_>::default())
+            .await;
+
+    let air_script = mixed_algorithms_script(&ed25519_peer_id, &secp256k1_peer_id);
+    let run_params = TestRunParameters::from_init_peer_id(&ed25519_peer_id).with_particle_id("particle_id");
+
+    let res1 = secp256k1_avm
+        .call(&air_script, "", "", run_params.clone())
+        .await
+        .unwrap();
+    assert_eq!(res1.ret_code, 0, "{:?}", res1);
+
+    let res2 = ed25519_avm.call(&air_script, "", res1.data, run_params).await.unwrap();
+    assert_error_eq!(
+        &res2,
+        PreparationError::DataSignatureCheckError(DataVerifierError::MalformedKey {
+            error: KeyError::AlgorithmNotWhitelisted(KeyFormat::Secp256k1),
+            key: secp256k1_public_key.to_string(),
+        })
+    );
+}
+
+/// Checking that a secp256k1 signature made with another salt is rejected.
+#[tokio::test]
+async fn test_secp256k1_signature_mismatch() {
+    let (ed25519_keypair, ed25519_peer_id) = derive_dummy_keypair("ed25519_peer");
+    let secp256k1_keypair = fluence_keypair::KeyPair::generate_secp256k1();
+    let secp256k1_peer_id = secp256k1_keypair.public().to_peer_id().to_string();
+
+    let test_init_parameters = TestInitParameters::default().with_allowed_key_formats(KeyFormatWhitelist::all());
+    let mut secp256k1_avm = create_avm_with_key::<NativeAirRunner>(
+        secp256k1_keypair,
+        set_variable_call_service(json!("ok")),
+        test_init_parameters,
+    )
+    .await;
+    let mut ed25519_avm = create_avm_with_key::<NativeAirRunner>(
+        ed25519_keypair,
+        set_variable_call_service(json!("ok")),
+        test_init_parameters,
+    )
+    .await;
+
+    let air_script = mixed_algorithms_script(&ed25519_peer_id, &secp256k1_peer_id);
+    let run_params = TestRunParameters::from_init_peer_id(&ed25519_peer_id)
+        .with_particle_id("particle_id")
+        .with_particle_signature(b"particle_signature");
+    let replayed_run_params = TestRunParameters::from_init_peer_id(&ed25519_peer_id)
+        .with_particle_id("another_particle")
+        .with_particle_signature(b"another_particle_signature");
+
+    let res1 = secp256k1_avm.call(&air_script, "", "", run_params).await.unwrap();
+    assert_eq!(res1.ret_code, 0, "{:?}", res1);
+
+    let res2 = ed25519_avm
+        .call(&air_script, "", res1.data, replayed_run_params)
+        .await
+        .unwrap();
+    assert_ne!(res2.ret_code, 0, "{:?}", res2);
+    assert!(
+        res2.error_message
+            .contains(&format!("signature mismatch for {secp256k1_peer_id:?}")),
+        "{}",
+        res2.error_message
+    );
+}
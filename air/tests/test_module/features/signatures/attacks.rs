@@ -53,7 +53,9 @@ async fn test_attack_injection_current_peer_scalar() {
     let alice_call_1 = scalar_tracked!("good result", &mut alice_cid_state, peer = &alice_peer_id);
     alice_signature_tracker.register(&*alice_peer_id, &extract_service_result_cid(&alice_call_1));
     let alice_trace = vec![alice_call_1.clone()];
-    let alice_signature = alice_signature_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_signature_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     alice_signature_store.put(alice_keypair.public().into(), alice_signature);
 
     let mut mallory_cid_state = alice_cid_state.clone();
@@ -64,7 +66,9 @@ async fn test_attack_injection_current_peer_scalar() {
     let fake_call_3 = scalar_tracked!("fake result", &mut mallory_cid_state, peer = &alice_peer_id);
     mallory_signature_tracker.register(&*mallory_peer_id, &extract_service_result_cid(&mallory_call_2));
     let mallory_trace = vec![alice_call_1, mallory_call_2, fake_call_3];
-    let mallory_signature = mallory_signature_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_signature_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     mallory_signature_store.put(mallory_keypair.public().into(), mallory_signature);
 
     let alice_data = InterpreterDataEnvelope::from_execution_result(
@@ -123,7 +127,9 @@ async fn test_attack_injection_current_peer_stream() {
     let mut alice_signature_tracker = PeerCidTracker::new(alice_peer_id.clone());
     alice_signature_tracker.register(&*alice_peer_id, &extract_service_result_cid(&alice_call_1));
     let mut alice_signature_store = SignatureStore::new();
-    let alice_signature = alice_signature_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_signature_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     alice_signature_store.put(alice_pk, alice_signature);
 
     let alice_trace = vec![alice_call_1.clone()];
@@ -135,7 +141,9 @@ async fn test_attack_injection_current_peer_stream() {
     let mut mallory_signature_tracker = PeerCidTracker::new(mallory_peer_id.clone());
     mallory_signature_tracker.register(&*mallory_peer_id, &extract_service_result_cid(&mallory_call_2));
     let mut mallory_signature_store = SignatureStore::new();
-    let mallory_signature = mallory_signature_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_signature_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     mallory_signature_store.put(mallory_pk, mallory_signature);
 
     let mallory_trace = vec![alice_call_1, mallory_call_2, fake_call_3];
@@ -202,13 +210,17 @@ async fn test_attack_injection_current_injection_unused() {
 
     let mut alice_cid_tracker = PeerCidTracker::new(alice_peer_id.clone());
     alice_cid_tracker.register(&alice_peer_id, &extract_service_result_cid(&mallory_trace[0]));
-    let alice_signature = alice_cid_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_cid_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     alice_signature_store.put(alice_pk, alice_signature);
 
     let mallory_signature_store = alice_signature_store.clone();
     let mut mallory_cid_tracker = PeerCidTracker::new(mallory_peer_id.clone());
     mallory_cid_tracker.register(&mallory_peer_id, &extract_service_result_cid(&mallory_trace[1]));
-    let mallory_signature = mallory_cid_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_cid_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     alice_signature_store.put(mallory_pk, mallory_signature);
 
     let alice_data = InterpreterDataEnvelope::from_execution_result(
@@ -275,12 +287,16 @@ async fn test_attack_injection_other_peer_scalar() {
 
     let mut alice_cid_tracker = PeerCidTracker::new(alice_peer_id.clone());
     alice_cid_tracker.register(&alice_peer_id, &extract_service_result_cid(&mallory_trace[0]));
-    let alice_signature = alice_cid_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_cid_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     signature_store.put(alice_pk, alice_signature);
 
     let mut mallory_cid_tracker = PeerCidTracker::new(mallory_peer_id.clone());
     mallory_cid_tracker.register(&mallory_peer_id, &extract_service_result_cid(&mallory_trace[1]));
-    let mallory_signature = mallory_cid_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_cid_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     signature_store.put(mallory_pk, mallory_signature);
     let mallory_data = InterpreterDataEnvelope::from_execution_result(
         mallory_trace.into(),
@@ -333,12 +349,16 @@ async fn test_attack_injection_other_peer_stream() {
     let mut signature_store = SignatureStore::new();
     let mut alice_signature_tracker = PeerCidTracker::new(alice_peer_id.clone());
     alice_signature_tracker.register(&*alice_peer_id, &extract_service_result_cid(&alice_call_1));
-    let alice_signature = alice_signature_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_signature_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     signature_store.put(alice_pk, alice_signature);
 
     let mut mallory_signature_tracker = PeerCidTracker::new(mallory_peer_id.clone());
     mallory_signature_tracker.register(&*mallory_peer_id, &extract_service_result_cid(&mallory_call_2));
-    let mallory_signature = mallory_signature_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_signature_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     signature_store.put(mallory_pk, mallory_signature);
 
     let mallory_trace = vec![alice_call_1, mallory_call_2, fake_call_3];
@@ -394,12 +414,16 @@ async fn test_attack_injection_other_peer_unused() {
     let mut signature_store = SignatureStore::new();
     let mut alice_signature_tracker = PeerCidTracker::new(alice_peer_id.clone());
     alice_signature_tracker.register(&*alice_peer_id, &extract_service_result_cid(&alice_call_1));
-    let alice_signature = alice_signature_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_signature_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     signature_store.put(alice_pk, alice_signature);
 
     let mut mallory_signature_tracker = PeerCidTracker::new(mallory_peer_id.clone());
     mallory_signature_tracker.register(&*mallory_peer_id, &extract_service_result_cid(&mallory_call_2));
-    let mallory_signature = mallory_signature_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_signature_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     signature_store.put(mallory_pk, mallory_signature);
 
     let mallory_trace = vec![alice_call_1, mallory_call_2, fake_call_3];
@@ -443,7 +467,9 @@ async fn test_attack_replay() {
     let mut bob_avm =
         create_avm_with_key::<NativeAirRunner>(bob_keypair.clone(), unit_call_service(), <_>::default()).await;
 
-    let run_params1 = TestRunParameters::from_init_peer_id(&alice_peer_id).with_particle_id("first_particle");
+    let run_params1 = TestRunParameters::from_init_peer_id(&alice_peer_id)
+        .with_particle_id("first_particle")
+        .with_particle_signature(b"first_particle_signature");
     let run_params2 = run_params1.clone();
 
     let res1 = alice_avm.call(&air_script, "", "", run_params1.clone()).await.unwrap();
@@ -462,7 +488,9 @@ async fn test_attack_replay() {
         res_bob.error_message
     );
 
-    let mallory_run_params = TestRunParameters::from_init_peer_id(&alice_peer_id).with_particle_id("second_particle");
+    let mallory_run_params = TestRunParameters::from_init_peer_id(&alice_peer_id)
+        .with_particle_id("second_particle")
+        .with_particle_signature(b"second_particle_signature");
 
     let res_replay = bob_avm
         .call(&air_script, "", res1.data, mallory_run_params)
@@ -473,7 +501,7 @@ async fn test_attack_replay() {
     let nested_error = fluence_keypair::error::VerificationError::Ed25519(
         dalek_error,
         // will break if signed data format changes
-        "5KxZZzHbixcH8idzggBJYDgXP87qBBWAHLeFvPvCRVMqyAbf6mrqMdAa1P3iNjAaxXVFuJsxtrrQaL1MKw6C8pER".to_owned(),
+        "5jvM2NW7ffhcRa8pLiyMWSCwGKBwZQYKn7nLd9NzZwuzHcN81Q57j6n6x85VU4MbL1PqRxifrg7NHBsi7Yi7Kvgj".to_owned(),
         "6m3zmtymxDL56KBpNgKqc7QiGRuWuxr82bG2q7dF5xCD".to_owned(),
     );
     let cids: Vec<Rc<CidRef>> = vec!["bagaaihrarsryjavaf4zikqilrc2hzph7rszpfyfxjpopfnczjxlqeb56nbhq".into()];
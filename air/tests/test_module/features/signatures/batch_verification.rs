@@ -0,0 +1,145 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air_interpreter_data::verification::DataVerifier;
+use air_interpreter_data::verification::DataVerifierError;
+use air_interpreter_signatures::BatchVerifier;
+use air_interpreter_signatures::KeyFormatWhitelist;
+use air_interpreter_signatures::KeyPair;
+use air_interpreter_signatures::PublicKey;
+use air_interpreter_signatures::Signature;
+use air_test_utils::key_utils::dummy_keypairs;
+use air_test_utils::key_utils::signed_data;
+use air_test_utils::prelude::*;
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::constants::EIGHT_TORSION;
+use curve25519_dalek::Scalar;
+use fluence_keypair::KeyFormat;
+use sha2::Digest;
+use sha2::Sha512;
+
+const SALT: &str = "particle_id";
+
+fn secp256k1_keypair() -> KeyPair {
+    KeyPair::new(fluence_keypair::KeyPair::generate_secp256k1()).unwrap()
+}
+
+fn verify(data: &InterpreterData) -> Result<(), DataVerifierError> {
+    DataVerifier::new(data, SALT, KeyFormatWhitelist::all())?.verify()
+}
+
+fn assert_signature_mismatch(result: Result<(), DataVerifierError>, expected_peer_id: &str) {
+    match result {
+        Err(DataVerifierError::SignatureMismatch { peer_id, .. }) => assert_eq!(peer_id, expected_peer_id),
+        other => panic!("expected a signature mismatch for {expected_peer_id}, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_batch_verification_many_signers() {
+    let data = signed_data(&dummy_keypairs(50), SALT, None);
+    assert!(verify(&data).is_ok());
+}
+
+#[test]
+fn test_batch_verification_reports_wrong_signer() {
+    let keypairs = dummy_keypairs(50);
+    let wrong_peer_id = keypairs[17].public().to_peer_id().unwrap();
+
+    let data = signed_data(&keypairs, SALT, Some(17));
+    assert_signature_mismatch(verify(&data), &wrong_peer_id);
+}
+
+#[test]
+fn test_batch_verification_mixed_algorithms() {
+    let mut keypairs = dummy_keypairs(10);
+    keypairs.insert(5, secp256k1_keypair());
+
+    let data = signed_data(&keypairs, SALT, None);
+    assert!(verify(&data).is_ok());
+}
+
+#[test]
+fn test_batch_verification_wrong_unbatched_signer() {
+    let mut keypairs = dummy_keypairs(10);
+    keypairs.insert(5, secp256k1_keypair());
+    let wrong_peer_id = keypairs[5].public().to_peer_id().unwrap();
+
+    let data = signed_data(&keypairs, SALT, Some(5));
+    assert_signature_mismatch(verify(&data), &wrong_peer_id);
+}
+
+const VALUE: &str = "value";
+
+/// Sign the value with a nonce having a torsion component of order two: the individual check
+/// always rejects it, while the randomized batch equation accepts it half of the time.
+fn torsioned_signature(signing_key: &ed25519_dalek::SigningKey) -> Signature {
+    let message = borsh::to_vec(&(VALUE, SALT)).unwrap();
+    let nonce = Scalar::from(42u64);
+    let r = (nonce * ED25519_BASEPOINT_POINT + EIGHT_TORSION[4]).compress();
+
+    let hram = Sha512::new()
+        .chain_update(r.as_bytes())
+        .chain_update(signing_key.verifying_key().as_bytes())
+        .chain_update(&message);
+    let k = Scalar::from_bytes_mod_order_wide(&hram.finalize().into());
+    let s = nonce + k * signing_key.to_scalar();
+
+    let bytes = [r.to_bytes(), s.to_bytes()].concat();
+    Signature::from_raw_bytes(KeyFormat::Ed25519, bytes)
+}
+
+/// Verify a signature the way `DataVerifier` does.
+fn batch_verify(public_key: &PublicKey, signature: &Signature) -> bool {
+    let mut batch = BatchVerifier::new();
+    if !batch.push(public_key, VALUE, SALT, signature) {
+        return public_key.verify(VALUE, SALT, signature).is_ok();
+    }
+    batch.verify().is_ok()
+}
+
+#[test]
+fn test_batch_verification_torsioned_signature() {
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+    let keypair = KeyPair::try_from(fluence_keypair::KeyPair::Ed25519(signing_key.clone().into())).unwrap();
+    let public_key = keypair.public();
+    let signature = torsioned_signature(&signing_key);
+
+    let individual_result = public_key.verify(VALUE, SALT, &signature).is_ok();
+    assert!(!individual_result);
+    // the batch is randomized, so it's checked several times
+    for _ in 0..32 {
+        assert_eq!(batch_verify(&public_key, &signature), individual_result);
+    }
+}
+
+#[test]
+fn test_batch_verification_small_order_key() {
+    // the identity point as a key and as a nonce with a zero scalar pass the individual check
+    let mut identity = [0u8; 32];
+    identity[0] = 1;
+    let encoded_key = [&[u8::from(KeyFormat::Ed25519)], identity.as_slice()].concat();
+    let public_key = PublicKey::new(fluence_keypair::PublicKey::decode(&encoded_key).unwrap());
+    let signature = Signature::from_raw_bytes(KeyFormat::Ed25519, [identity, [0; 32]].concat());
+
+    let individual_result = public_key.verify(VALUE, SALT, &signature).is_ok();
+    for _ in 0..32 {
+        assert_eq!(batch_verify(&public_key, &signature), individual_result);
+    }
+}
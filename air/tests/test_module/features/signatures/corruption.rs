@@ -72,12 +72,16 @@ async fn test_attack_replace_value() {
 
     let mut alice_cid_tracker = PeerCidTracker::new(alice_peer_id.clone());
     alice_cid_tracker.register(&alice_peer_id, &extract_service_result_cid(&mallory_trace[0]));
-    let alice_signature = alice_cid_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_cid_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     signature_store.put(alice_pk, alice_signature);
 
     let mut mallory_cid_tracker = PeerCidTracker::new(mallory_peer_id.clone());
     mallory_cid_tracker.register(&mallory_peer_id, &extract_service_result_cid(&mallory_trace[1]));
-    let mallory_signature = mallory_cid_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_cid_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     signature_store.put(mallory_pk, mallory_signature);
 
     let mallory_data = InterpreterDataEnvelope::from_execution_result(
@@ -152,12 +156,16 @@ async fn test_attack_replace_tetraplet() {
 
     let mut alice_cid_tracker = PeerCidTracker::new(alice_peer_id.clone());
     alice_cid_tracker.register(&alice_peer_id, &extract_service_result_cid(&mallory_trace[0]));
-    let alice_signature = alice_cid_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_cid_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     signature_store.put(alice_pk, alice_signature);
 
     let mut mallory_cid_tracker = PeerCidTracker::new(mallory_peer_id.clone());
     mallory_cid_tracker.register(&mallory_peer_id, &extract_service_result_cid(&mallory_trace[1]));
-    let mallory_signature = mallory_cid_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_cid_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     signature_store.put(mallory_pk, mallory_signature);
 
     let mallory_data = InterpreterDataEnvelope::from_execution_result(
@@ -238,12 +246,16 @@ async fn test_attack_replace_call_result() {
 
     let mut alice_cid_tracker = PeerCidTracker::new(alice_peer_id.clone());
     alice_cid_tracker.register(&alice_peer_id, &extract_service_result_cid(&mallory_trace[0]));
-    let alice_signature = alice_cid_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_cid_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     signature_store.put(alice_pk, alice_signature);
 
     let mut mallory_cid_tracker = PeerCidTracker::new(mallory_peer_id.clone());
     mallory_cid_tracker.register(&mallory_peer_id, &extract_service_result_cid(&mallory_trace[1]));
-    let mallory_signature = mallory_cid_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_cid_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     signature_store.put(mallory_pk, mallory_signature);
 
     let mallory_data = InterpreterDataEnvelope::from_execution_result(
@@ -331,12 +343,16 @@ async fn test_attack_replace_canon_value() {
 
     let mut alice_cid_tracker = PeerCidTracker::new(alice_peer_id.clone());
     alice_cid_tracker.register(&alice_peer_id, &extract_canon_result_cid(&mallory_trace[2]));
-    let alice_signature = alice_cid_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_cid_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     signature_store.put(alice_pk, alice_signature);
 
     let mut mallory_cid_tracker = PeerCidTracker::new(mallory_peer_id.clone());
     mallory_cid_tracker.register(&mallory_peer_id, &extract_service_result_cid(&mallory_trace[3]));
-    let mallory_signature = mallory_cid_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_cid_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     signature_store.put(mallory_pk, mallory_signature);
 
     let mallory_data = InterpreterDataEnvelope::from_execution_result(
@@ -433,12 +449,16 @@ async fn test_attack_replace_canon_result_values() {
 
     let mut alice_cid_tracker = PeerCidTracker::new(alice_peer_id.clone());
     alice_cid_tracker.register(&alice_peer_id, &extract_canon_result_cid(&mallory_trace[2]));
-    let alice_signature = alice_cid_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_cid_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     signature_store.put(alice_pk, alice_signature);
 
     let mut mallory_cid_tracker = PeerCidTracker::new(mallory_peer_id.clone());
     mallory_cid_tracker.register(&mallory_peer_id, &extract_service_result_cid(&mallory_trace[3]));
-    let mallory_signature = mallory_cid_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_cid_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     signature_store.put(mallory_pk, mallory_signature);
 
     let mallory_data = InterpreterDataEnvelope::from_execution_result(
@@ -539,12 +559,16 @@ async fn test_attack_replace_canon_result_tetraplet() {
 
     let mut alice_cid_tracker = PeerCidTracker::new(alice_peer_id.clone());
     alice_cid_tracker.register(&alice_peer_id, &extract_canon_result_cid(&mallory_trace[2]));
-    let alice_signature = alice_cid_tracker.gen_signature("", &alice_keypair).unwrap();
+    let alice_signature = alice_cid_tracker
+        .gen_signature(&test_signature_salt(), &alice_keypair)
+        .unwrap();
     signature_store.put(alice_pk, alice_signature);
 
     let mut mallory_cid_tracker = PeerCidTracker::new(mallory_peer_id.clone());
     mallory_cid_tracker.register(&mallory_peer_id, &extract_service_result_cid(&mallory_trace[3]));
-    let mallory_signature = mallory_cid_tracker.gen_signature("", &mallory_keypair).unwrap();
+    let mallory_signature = mallory_cid_tracker
+        .gen_signature(&test_signature_salt(), &mallory_keypair)
+        .unwrap();
     signature_store.put(mallory_pk, mallory_signature);
 
     let mallory_data = InterpreterDataEnvelope::from_execution_result(
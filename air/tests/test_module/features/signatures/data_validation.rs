@@ -0,0 +1,80 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air_interpreter_data::validation::DataValidationError;
+use air_interpreter_data::validation::DataValidator;
+use air_interpreter_data::verification::DataSalts;
+use air_interpreter_signatures::particle_signature_salt;
+use air_test_framework::AirScriptExecutor;
+use air_test_utils::prelude::*;
+use air_test_utils::test_runner::TestRunParameters;
+
+#[tokio::test]
+async fn test_validate_signed_data() {
+    let init_peer_name = "init_peer_id";
+    let other_peer_name = "other_peer_id";
+
+    let air_script = format!(
+        r#"
+    (seq
+       (seq
+          (call "{init_peer_name}" ("serv" "func") [] items) ; ok = [1, 2]
+          (fold items i
+             (par
+                (call "{other_peer_name}" ("serv" "func") [i] $stream) ; ok = "res"
+                (next i))))
+       (seq
+          (canon "{other_peer_name}" $stream #canon)
+          (fold $stream v
+             (seq
+                (ap v $other)
+                (next v)))))
+    "#
+    );
+    let test_run_parameters = TestRunParameters::from_init_peer_id(init_peer_name)
+        .with_particle_id("particle_id")
+        .with_particle_signature(b"particle_signature");
+    let exec = AirScriptExecutor::from_annotated(test_run_parameters, &air_script)
+        .await
+        .unwrap();
+    exec.execute_one(init_peer_name).await.unwrap();
+    let res = exec.execute_one(other_peer_name).await.unwrap();
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+    let data = data_from_result(&res);
+    let versions = InterpreterDataEnvelope::try_get_versions(&res.data).unwrap();
+
+    let salt = particle_signature_salt(b"particle_signature");
+    let salts = DataSalts {
+        particle_id: "particle_id",
+        particle_signature: &salt,
+    };
+    let errors = DataValidator::new(&data).with_signatures(&versions, salts).validate();
+    assert!(errors.is_empty(), "{errors:?}");
+
+    // the data is of the current version, so the particle id is not its salt
+    let salts = DataSalts {
+        particle_id: "particle_id",
+        particle_signature: "particle_id",
+    };
+    let errors = DataValidator::new(&data).with_signatures(&versions, salts).validate();
+    assert!(
+        matches!(&errors[..], [DataValidationError::Signatures(_)]),
+        "{errors:?}"
+    );
+}
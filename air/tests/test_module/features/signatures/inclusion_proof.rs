@@ -0,0 +1,154 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air_interpreter_data::inclusion_proof::CanonResultProof;
+use air_interpreter_data::inclusion_proof::InclusionProofError;
+use air_interpreter_data::inclusion_proof::ServiceResultProof;
+use air_test_framework::AirScriptExecutor;
+use air_test_utils::key_utils::derive_dummy_keypair;
+use air_test_utils::prelude::*;
+use air_test_utils::test_runner::TestRunParameters;
+
+#[tokio::test]
+async fn test_service_result_proof() {
+    let init_peer_name = "init_peer_id";
+    let other_peer_name = "other_peer_id";
+    let (init_keypair, _) = derive_dummy_keypair(init_peer_name);
+    let (other_keypair, _) = derive_dummy_keypair(other_peer_name);
+
+    let air_script = format!(
+        r#"
+    (seq
+       (call "{init_peer_name}" ("" "") [] x) ; ok = "res0"
+       (call "{other_peer_name}" ("serv" "func") [x] y)) ; ok = "res1"
+    "#
+    );
+    let exec = AirScriptExecutor::from_annotated(TestRunParameters::from_init_peer_id(init_peer_name), &air_script)
+        .await
+        .unwrap();
+    exec.execute_one(init_peer_name).await.unwrap();
+    let res = exec.execute_one(other_peer_name).await.unwrap();
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+    let data = data_from_result(&res);
+
+    let other_cid = extract_service_result_cid(&data.trace[TracePos::from(1)]);
+    let proof = ServiceResultProof::from_data(&data, &other_cid).unwrap();
+
+    assert_eq!(proof.payload.value.get_value(), JValue::from(json!("res1")));
+    assert_eq!(proof.payload.tetraplet.function_name, "func");
+    assert_eq!(proof.peer_cids, vec![other_cid.get_inner()]);
+
+    let other_pk = other_keypair.public();
+    proof.verify(&other_pk, &test_signature_salt()).unwrap();
+
+    let init_pk = init_keypair.public();
+    assert!(matches!(
+        proof.verify(&init_pk, &test_signature_salt()),
+        Err(InclusionProofError::PeerIdMismatch { .. })
+    ));
+    assert!(matches!(
+        proof.verify(&other_pk, "another_particle"),
+        Err(InclusionProofError::SignatureMismatch(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_service_result_proof_tampered() {
+    let init_peer_name = "init_peer_id";
+    let (keypair, _) = derive_dummy_keypair(init_peer_name);
+
+    let air_script = format!(
+        r#"
+        (call "{init_peer_name}" ("" "") [] x) ; ok = "res0"
+    "#
+    );
+    let exec = AirScriptExecutor::from_annotated(TestRunParameters::from_init_peer_id(init_peer_name), &air_script)
+        .await
+        .unwrap();
+    let res = exec.execute_one(init_peer_name).await.unwrap();
+    let data = data_from_result(&res);
+
+    let cid = extract_service_result_cid(&data.trace[TracePos::from(0)]);
+    let mut proof = ServiceResultProof::from_data(&data, &cid).unwrap();
+    proof.payload.value = RawValue::from_value(json!("fake")).into();
+
+    let public_key = keypair.public();
+    assert!(matches!(
+        proof.verify(&public_key, &test_signature_salt()),
+        Err(InclusionProofError::NotSigned(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_canon_result_proof() {
+    let init_peer_name = "init_peer_id";
+    let (keypair, _) = derive_dummy_keypair(init_peer_name);
+
+    let air_script = format!(
+        r#"
+       (seq
+          (call "{init_peer_name}" ("serv" "func") [] items) ; ok = [1, 2, 3]
+          (seq
+             (fold items i
+                (seq
+                   (ap i $stream)
+                   (next i)))
+             (canon "{init_peer_name}" $stream #canon)))
+    "#
+    );
+    let exec = AirScriptExecutor::from_annotated(TestRunParameters::from_init_peer_id(init_peer_name), &air_script)
+        .await
+        .unwrap();
+    let res = exec.execute_one(init_peer_name).await.unwrap();
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+    let data = data_from_result(&res);
+
+    let canon_state = data.trace.last().unwrap();
+    let canon_cid = extract_canon_result_cid(canon_state);
+    let proof = CanonResultProof::from_data(&data, &canon_cid).unwrap();
+
+    let values: Vec<_> = proof.payload.values.iter().map(|elt| elt.value.get_value()).collect();
+    assert_eq!(values, vec![JValue::from(1), JValue::from(2), JValue::from(3)]);
+    assert_eq!(proof.peer_cids.len(), 2);
+
+    let public_key = keypair.public();
+    proof.verify(&public_key, &test_signature_salt()).unwrap();
+
+    let serialized = serde_json::to_string(&proof).unwrap();
+    let deserialized: CanonResultProof = serde_json::from_str(&serialized).unwrap();
+    deserialized.verify(&public_key, &test_signature_salt()).unwrap();
+}
+
+#[tokio::test]
+async fn test_proof_of_unknown_cid() {
+    let init_peer_name = "init_peer_id";
+
+    let exec = AirScriptExecutor::from_annotated(TestRunParameters::from_init_peer_id(init_peer_name), "(null)")
+        .await
+        .unwrap();
+    let res = exec.execute_one(init_peer_name).await.unwrap();
+    let data = data_from_result(&res);
+
+    let call_state = scalar!("ok", peer = init_peer_name);
+    let cid = extract_service_result_cid(&call_state);
+    assert!(matches!(
+        ServiceResultProof::from_data(&data, &cid),
+        Err(InclusionProofError::MissingValue { .. })
+    ));
+}
@@ -0,0 +1,252 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air::PreparationError;
+use air_interpreter_data::verification::DataVerifier;
+use air_interpreter_data::InterpreterData;
+use air_interpreter_signatures::particle_signature_salt;
+use air_interpreter_signatures::sign_cids;
+use air_interpreter_signatures::KeyFormatWhitelist;
+use air_interpreter_signatures::KeyPair;
+use air_interpreter_signatures::PeerCidTracker;
+use air_test_utils::key_utils::derive_dummy_keypair;
+use air_test_utils::prelude::*;
+use semver::Version;
+
+const PARTICLE_ID: &str = "particle_id";
+
+async fn run_alice(alice_name: &str, bob_peer_id: &str, particle_signature: &[u8]) -> RawAVMOutcome {
+    let (alice_keypair, alice_peer_id) = derive_dummy_keypair(alice_name);
+    let mut alice_avm =
+        create_avm_with_key::<NativeAirRunner>(alice_keypair, set_variable_call_service(json!("ok")), <_>::default())
+            .await;
+
+    let test_run_params = TestRunParameters::from_init_peer_id(&alice_peer_id)
+        .with_particle_id(PARTICLE_ID)
+        .with_particle_signature(particle_signature);
+    alice_avm
+        .call(script(&alice_peer_id, bob_peer_id), "", "", test_run_params)
+        .await
+        .unwrap()
+}
+
+async fn run_bob(alice_peer_id: &str, bob_name: &str, data: Vec<u8>, particle_signature: &[u8]) -> RawAVMOutcome {
+    let (bob_keypair, bob_peer_id) = derive_dummy_keypair(bob_name);
+    let mut bob_avm =
+        create_avm_with_key::<NativeAirRunner>(bob_keypair, set_variable_call_service(json!("ok")), <_>::default())
+            .await;
+
+    let test_run_params = TestRunParameters::from_init_peer_id(alice_peer_id)
+        .with_particle_id(PARTICLE_ID)
+        .with_particle_signature(particle_signature);
+    bob_avm
+        .call(script(alice_peer_id, &bob_peer_id), "", data, test_run_params)
+        .await
+        .unwrap()
+}
+
+fn script(alice_peer_id: &str, bob_peer_id: &str) -> String {
+    format!(
+        r#"
+        (seq
+            (call "{alice_peer_id}" ("" "") [] x)
+            (call "{bob_peer_id}" ("" "") [] y))
+        "#
+    )
+}
+
+fn with_data_version(data: &[u8], data_version: Version) -> Vec<u8> {
+    let mut envelope = InterpreterDataEnvelope::try_from_slice(data).unwrap();
+    envelope.versions.data_version = data_version;
+    envelope.serialize().unwrap()
+}
+
+// emulates an interpreter without the particle signature support, it salts data with the particle id
+fn into_legacy_data(data: &[u8], keypair: &KeyPair) -> Vec<u8> {
+    let envelope = InterpreterDataEnvelope::try_from_slice(data).unwrap();
+    let mut inner_data = InterpreterData::try_from_slice(&envelope.inner_data).unwrap();
+
+    let peer_id = keypair.public().to_peer_id().unwrap();
+    let verifier = DataVerifier::new(&inner_data, "", KeyFormatWhitelist::all()).unwrap();
+    let signature = sign_cids(verifier.peer_cids(&peer_id).to_vec(), PARTICLE_ID, keypair.as_inner()).unwrap();
+    drop(verifier);
+    inner_data.signatures.put(keypair.public(), signature.into());
+
+    let mut versions = envelope.versions;
+    versions.data_version = Version::new(0, 17, 2);
+    InterpreterDataEnvelope::from_interpreter_data(&inner_data, versions)
+        .serialize()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_signature_salted_with_particle_signature() {
+    let (alice_keypair, alice_peer_id) = derive_dummy_keypair("alice_peer");
+    let (_, bob_peer_id) = derive_dummy_keypair("bob_peer");
+    let particle_signature = b"particle signature";
+
+    let res = run_alice("alice_peer", &bob_peer_id, particle_signature).await;
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+    let data = data_from_result(&res);
+
+    let mut expected_tracker = PeerCidTracker::new(alice_peer_id.clone());
+    expected_tracker.register(&alice_peer_id, &extract_service_result_cid(&data.trace[0.into()]));
+    let salt = particle_signature_salt(particle_signature);
+    let expected_signature = expected_tracker.gen_signature(&salt, &alice_keypair).unwrap();
+
+    let signature = data.signatures.get(&alice_keypair.public());
+    assert_eq!(signature, Some(&expected_signature), "{:?}", data.signatures);
+}
+
+#[tokio::test]
+async fn test_empty_particle_signature_is_rejected() {
+    let (_, bob_peer_id) = derive_dummy_keypair("bob_peer");
+
+    // its salt would be the same for all particles
+    let res = run_alice("alice_peer", &bob_peer_id, b"").await;
+    assert!(check_error(&res, PreparationError::NoParticleSignature), "{:?}", res);
+}
+
+#[tokio::test]
+async fn test_particle_id_replay_is_rejected() {
+    let (_, alice_peer_id) = derive_dummy_keypair("alice_peer");
+    let (_, bob_peer_id) = derive_dummy_keypair("bob_peer");
+
+    let res = run_alice("alice_peer", &bob_peer_id, b"original signature").await;
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+
+    let res_replayed = run_bob(&alice_peer_id, "bob_peer", res.data.clone(), b"replayed signature").await;
+    assert_ne!(res_replayed.ret_code, 0, "{:?}", res_replayed);
+    assert!(
+        res_replayed.error_message.contains("signature mismatch"),
+        "{}",
+        res_replayed.error_message
+    );
+
+    let res_original = run_bob(&alice_peer_id, "bob_peer", res.data, b"original signature").await;
+    assert_eq!(res_original.ret_code, 0, "{:?}", res_original);
+}
+
+#[tokio::test]
+async fn test_legacy_data_salted_with_particle_id() {
+    let (alice_keypair, alice_peer_id) = derive_dummy_keypair("alice_peer");
+    let (_, bob_peer_id) = derive_dummy_keypair("bob_peer");
+
+    let res = run_alice("alice_peer", &bob_peer_id, b"particle signature").await;
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+
+    let legacy_data = into_legacy_data(&res.data, &alice_keypair);
+    let res_legacy = run_bob(&alice_peer_id, "bob_peer", legacy_data.clone(), b"particle signature").await;
+    assert_eq!(res_legacy.ret_code, 0, "{:?}", res_legacy);
+    // produced data is of the current version, alice's signature keeps the legacy salt
+    let versions = InterpreterDataEnvelope::try_get_versions(&res_legacy.data).unwrap();
+    assert_eq!(&versions.data_version, data_version());
+    assert_eq!(versions.particle_id_salted_peers, vec![alice_peer_id.clone()]);
+
+    // the same data of the current version is expected to be salted with the particle signature
+    let current_data = with_data_version(&legacy_data, data_version().clone());
+    let res_current = run_bob(&alice_peer_id, "bob_peer", current_data, b"particle signature").await;
+    assert_ne!(res_current.ret_code, 0, "{:?}", res_current);
+    assert!(
+        res_current.error_message.contains("signature mismatch"),
+        "{}",
+        res_current.error_message
+    );
+}
+
+#[tokio::test]
+async fn test_previous_release_data_salted_with_particle_id() {
+    let (_, alice_peer_id) = derive_dummy_keypair("alice_peer");
+    // produced by the previous release, it salted alice's signature with the particle id
+    let data = include_bytes!("../misc/data/previous_release_data.bin").to_vec();
+
+    let res = run_bob(&alice_peer_id, "bob_peer", data, b"particle signature").await;
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+    let versions = InterpreterDataEnvelope::try_get_versions(&res.data).unwrap();
+    assert_eq!(versions.particle_id_salted_peers, vec![alice_peer_id]);
+}
+
+#[tokio::test]
+async fn test_merged_legacy_and_current_data_verified_by_third_peer() {
+    let (alice_keypair, alice_peer_id) = derive_dummy_keypair("alice_peer");
+    let (bob_keypair, bob_peer_id) = derive_dummy_keypair("bob_peer");
+    let (carol_keypair, carol_peer_id) = derive_dummy_keypair("carol_peer");
+    let particle_signature = b"particle signature";
+
+    let script = format!(
+        r#"
+        (seq
+            (par
+                (call "{alice_peer_id}" ("" "") [] x)
+                (call "{bob_peer_id}" ("" "") [] y))
+            (call "{carol_peer_id}" ("" "") [] z))
+        "#
+    );
+    let test_run_params = TestRunParameters::from_init_peer_id(&alice_peer_id)
+        .with_particle_id(PARTICLE_ID)
+        .with_particle_signature(particle_signature);
+
+    let mut alice_avm = create_avm_with_key::<NativeAirRunner>(
+        alice_keypair.clone(),
+        set_variable_call_service(json!("ok")),
+        <_>::default(),
+    )
+    .await;
+    let mut bob_avm =
+        create_avm_with_key::<NativeAirRunner>(bob_keypair, set_variable_call_service(json!("ok")), <_>::default())
+            .await;
+    let mut carol_avm =
+        create_avm_with_key::<NativeAirRunner>(carol_keypair, set_variable_call_service(json!("ok")), <_>::default())
+            .await;
+
+    let res_alice = alice_avm.call(&script, "", "", test_run_params.clone()).await.unwrap();
+    assert_eq!(res_alice.ret_code, 0, "{:?}", res_alice);
+    let legacy_alice_data = into_legacy_data(&res_alice.data, &alice_keypair);
+
+    let res_bob = bob_avm.call(&script, "", "", test_run_params.clone()).await.unwrap();
+    assert_eq!(res_bob.ret_code, 0, "{:?}", res_bob);
+
+    // bob merges his current data with alice's legacy one
+    let res_merged = bob_avm
+        .call(&script, res_bob.data, legacy_alice_data, test_run_params.clone())
+        .await
+        .unwrap();
+    assert_eq!(res_merged.ret_code, 0, "{:?}", res_merged);
+    let versions = InterpreterDataEnvelope::try_get_versions(&res_merged.data).unwrap();
+    assert_eq!(&versions.data_version, data_version());
+    assert_eq!(versions.particle_id_salted_peers, vec![alice_peer_id.clone()]);
+
+    // carol verifies signatures of both schemes
+    let res_carol = carol_avm
+        .call(&script, "", res_merged.data, test_run_params.clone())
+        .await
+        .unwrap();
+    assert_eq!(res_carol.ret_code, 0, "{:?}", res_carol);
+    let versions = InterpreterDataEnvelope::try_get_versions(&res_carol.data).unwrap();
+    assert_eq!(versions.particle_id_salted_peers, vec![alice_peer_id.clone()]);
+
+    // once alice signs the data again, her signature is salted with the particle signature
+    let res_alice = alice_avm
+        .call(&script, "", res_carol.data, test_run_params)
+        .await
+        .unwrap();
+    assert_eq!(res_alice.ret_code, 0, "{:?}", res_alice);
+    let versions = InterpreterDataEnvelope::try_get_versions(&res_alice.data).unwrap();
+    assert!(versions.particle_id_salted_peers.is_empty(), "{:?}", versions);
+}
@@ -0,0 +1,204 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air::PreparationError;
+use air::RemoteSigningError;
+use air::ToErrorCode;
+use air_interpreter_interface::CallResultsRepr;
+use air_interpreter_interface::InterpreterOutcome;
+use air_interpreter_interface::RunParameters;
+use air_interpreter_interface::INTERPRETER_SUCCESS;
+use air_interpreter_interface::MAX_AIR_SIZE;
+use air_interpreter_interface::MAX_CALL_RESULT_SIZE;
+use air_interpreter_interface::MAX_PARTICLE_SIZE;
+use air_interpreter_sede::ToSerialized;
+use air_interpreter_signatures::KeyFormat;
+use air_interpreter_signatures::KeyPair;
+use air_test_utils::key_utils::derive_dummy_keypair;
+use air_test_utils::prelude::*;
+
+const PARTICLE_ID: &str = "particle_id";
+
+fn script(peer_id: &str) -> String {
+    format!(
+        r#"
+        (seq
+            (seq
+                (ap 1 $stream)
+                (ap 2 $stream))
+            (canon "{peer_id}" $stream #canon))
+        "#
+    )
+}
+
+fn run_parameters(peer_id: &str, secret_key_bytes: Vec<u8>, signer_public_key: Vec<u8>) -> RunParameters {
+    RunParameters::new(
+        peer_id.to_owned(),
+        peer_id.to_owned(),
+        0,
+        0,
+        KeyFormat::Ed25519 as u8,
+        secret_key_bytes,
+        PARTICLE_ID.to_owned(),
+        MAX_AIR_SIZE,
+        MAX_PARTICLE_SIZE,
+        MAX_CALL_RESULT_SIZE,
+        false,
+        vec![],
+        <_>::default(),
+        <_>::default(),
+        TEST_PARTICLE_SIGNATURE.to_vec(),
+        false,
+        false,
+        signer_public_key,
+    )
+}
+
+fn no_call_results() -> Vec<u8> {
+    CallResultsRepr.serialize(&<_>::default()).unwrap().to_vec().to_vec()
+}
+
+fn execute_locally(keypair: &KeyPair, peer_id: &str, data: Vec<Vec<u8>>) -> InterpreterOutcome {
+    let params = run_parameters(peer_id, keypair.secret(), vec![]);
+    air::execute_air_multi(script(peer_id), vec![], data, params, no_call_results().into())
+}
+
+fn execute_remotely(keypair: &KeyPair, peer_id: &str, data: Vec<Vec<u8>>) -> InterpreterOutcome {
+    let params = run_parameters(peer_id, vec![], keypair.as_inner().public().encode());
+    air::execute_air_multi(script(peer_id), vec![], data, params, no_call_results().into())
+}
+
+fn finalize(keypair: &KeyPair, peer_id: &str, outcome: InterpreterOutcome, signature: Vec<u8>) -> InterpreterOutcome {
+    let params = run_parameters(peer_id, vec![], keypair.as_inner().public().encode());
+    air::finalize_signing(outcome.data, params, signature)
+}
+
+fn sign(keypair: &KeyPair, payload: &[u8]) -> Vec<u8> {
+    Vec::from(keypair.as_inner().sign(payload).unwrap().to_vec())
+}
+
+fn data(outcome: &InterpreterOutcome) -> InterpreterData {
+    data_from_result(&RawAVMOutcome::from_interpreter_outcome(outcome.clone()).unwrap())
+}
+
+#[test]
+fn remote_signing_matches_local_signing() {
+    let (keypair, peer_id) = derive_dummy_keypair("peer");
+
+    let local_outcome = execute_locally(&keypair, &peer_id, vec![vec![]]);
+    assert_eq!(
+        local_outcome.ret_code, INTERPRETER_SUCCESS,
+        "{}",
+        local_outcome.error_message
+    );
+    assert!(local_outcome.signing_payload.is_empty());
+
+    let unsigned_outcome = execute_remotely(&keypair, &peer_id, vec![vec![]]);
+    assert_eq!(
+        unsigned_outcome.ret_code, INTERPRETER_SUCCESS,
+        "{}",
+        unsigned_outcome.error_message
+    );
+    assert!(!unsigned_outcome.signing_payload.is_empty());
+    let unsigned_data = data(&unsigned_outcome);
+    let placeholder = unsigned_data.signatures.get(&keypair.public()).unwrap();
+    assert!(placeholder.is_placeholder());
+
+    let signature = sign(&keypair, &unsigned_outcome.signing_payload);
+    let signed_outcome = finalize(&keypair, &peer_id, unsigned_outcome, signature);
+    assert_eq!(
+        signed_outcome.ret_code, INTERPRETER_SUCCESS,
+        "{}",
+        signed_outcome.error_message
+    );
+
+    let local_data = data(&local_outcome);
+    let signed_data = data(&signed_outcome);
+    assert_eq!(signed_data.trace, local_data.trace);
+    let public_key = keypair.public();
+    assert_eq!(
+        signed_data.signatures.get(&public_key),
+        local_data.signatures.get(&public_key)
+    );
+}
+
+#[test]
+fn remote_signing_multi_data() {
+    let (keypair, peer_id) = derive_dummy_keypair("peer");
+
+    let local_data = execute_locally(&keypair, &peer_id, vec![vec![]]).data;
+    let unsigned_outcome = execute_remotely(&keypair, &peer_id, vec![local_data.clone(), local_data]);
+    assert_eq!(
+        unsigned_outcome.ret_code, INTERPRETER_SUCCESS,
+        "{}",
+        unsigned_outcome.error_message
+    );
+
+    let signature = sign(&keypair, &unsigned_outcome.signing_payload);
+    let signed_outcome = finalize(&keypair, &peer_id, unsigned_outcome, signature);
+    assert_eq!(
+        signed_outcome.ret_code, INTERPRETER_SUCCESS,
+        "{}",
+        signed_outcome.error_message
+    );
+}
+
+#[test]
+fn remote_signing_wrong_signature() {
+    let (keypair, peer_id) = derive_dummy_keypair("peer");
+    let (other_keypair, _) = derive_dummy_keypair("other_peer");
+
+    let unsigned_outcome = execute_remotely(&keypair, &peer_id, vec![vec![]]);
+    let unsigned_data = unsigned_outcome.data.clone();
+    let signature = sign(&other_keypair, &unsigned_outcome.signing_payload);
+    let outcome = finalize(&keypair, &peer_id, unsigned_outcome, signature);
+
+    let expected_error_code = RemoteSigningError::NoSignerPublicKey.to_error_code() + 3;
+    assert_eq!(outcome.ret_code, expected_error_code, "{}", outcome.error_message);
+    assert!(outcome.error_message.starts_with("host signature doesn't match"));
+    assert_eq!(outcome.data, unsigned_data);
+}
+
+#[test]
+fn remote_signing_without_public_key() {
+    let (keypair, peer_id) = derive_dummy_keypair("peer");
+
+    let unsigned_outcome = execute_remotely(&keypair, &peer_id, vec![vec![]]);
+    let signature = sign(&keypair, &unsigned_outcome.signing_payload);
+    let params = run_parameters(&peer_id, vec![], vec![]);
+    let outcome = air::finalize_signing(unsigned_outcome.data, params, signature);
+
+    assert_eq!(outcome.ret_code, RemoteSigningError::NoSignerPublicKey.to_error_code());
+}
+
+#[test]
+fn remote_signing_peer_id_mismatch() {
+    let (keypair, _) = derive_dummy_keypair("peer");
+    let (_, other_peer_id) = derive_dummy_keypair("other_peer");
+    let key_peer_id = keypair.public().to_peer_id().unwrap();
+
+    let outcome = execute_remotely(&keypair, &other_peer_id, vec![vec![]]);
+
+    let expected_error = PreparationError::SignerPeerIdMismatch {
+        key_peer_id,
+        current_peer_id: other_peer_id,
+    };
+    assert_eq!(outcome.ret_code, expected_error.to_error_code());
+    assert!(outcome.signing_payload.is_empty());
+}
@@ -42,7 +42,7 @@ async fn test_signature_empty() {
     let res = exec.execute_one(init_peer_name).await.unwrap();
     assert_eq!(res.ret_code, 0, "{:?}", res);
 
-    let data = borsh::to_vec(&(vec![""; 0], "")).unwrap();
+    let data = borsh::to_vec(&(vec![""; 0], test_signature_salt())).unwrap();
     let expected_signature: air_interpreter_signatures::Signature = keypair.sign(&data).unwrap().into();
 
     let data = data_from_result(&res);
@@ -78,7 +78,9 @@ async fn test_signature_call_var() {
 
     let mut expected_tracker = PeerCidTracker::new(init_peer_id.clone());
     expected_tracker.register(&init_peer_id, &expected_cid);
-    let expected_signature = expected_tracker.gen_signature("", &keypair).unwrap();
+    let expected_signature = expected_tracker
+        .gen_signature(&test_signature_salt(), &keypair)
+        .unwrap();
 
     let signature = data.signatures.get(&keypair.public().into());
     assert_eq!(signature, Some(&expected_signature), "{:?}", data.signatures);
@@ -108,7 +110,9 @@ async fn test_signature_call_stream() {
 
     let mut expected_tracker = PeerCidTracker::new(init_peer_id.clone());
     expected_tracker.register(&init_peer_id, &expected_cid);
-    let expected_signature = expected_tracker.gen_signature("", &keypair).unwrap();
+    let expected_signature = expected_tracker
+        .gen_signature(&test_signature_salt(), &keypair)
+        .unwrap();
 
     let signature = data.signatures.get(&keypair.public().into());
     assert_eq!(signature, Some(&expected_signature), "{:?}", data.signatures);
@@ -134,7 +138,9 @@ async fn test_signature_call_unused() {
     let (keypair, init_peer_id) = derive_dummy_keypair(init_peer_name);
 
     let expected_tracker = PeerCidTracker::new(init_peer_id.to_owned());
-    let expected_signature = expected_tracker.gen_signature("", &keypair).unwrap();
+    let expected_signature = expected_tracker
+        .gen_signature(&test_signature_salt(), &keypair)
+        .unwrap();
 
     let signature = data.signatures.get(&keypair.public().into());
     assert_eq!(signature, Some(&expected_signature), "{:?}", data.signatures);
@@ -174,7 +180,9 @@ async fn test_signature_call_merged() {
     let mut expected_tracker = PeerCidTracker::new(init_peer_name.to_owned());
     expected_tracker.register(init_peer_name, &expected_cid0);
     expected_tracker.register(init_peer_name, &expected_cid2);
-    let expected_signature = expected_tracker.gen_signature("", &keypair).unwrap();
+    let expected_signature = expected_tracker
+        .gen_signature(&test_signature_salt(), &keypair)
+        .unwrap();
 
     let signature = data2.signatures.get(&keypair.public().into());
     assert_eq!(signature, Some(&expected_signature), "{:?}", data2.signatures);
@@ -215,12 +223,16 @@ async fn test_signature_call_twice() {
 
     let mut unexpected_tracker = PeerCidTracker::new(init_peer_id.to_owned());
     unexpected_tracker.register(&init_peer_id, &expected_cid);
-    let unexpected_signature = unexpected_tracker.gen_signature("", &keypair).unwrap();
+    let unexpected_signature = unexpected_tracker
+        .gen_signature(&test_signature_salt(), &keypair)
+        .unwrap();
 
     let mut expected_tracker = PeerCidTracker::new(init_peer_id.to_owned());
     expected_tracker.register(&init_peer_id, &expected_cid);
     expected_tracker.register(&init_peer_id, &expected_cid);
-    let expected_signature = expected_tracker.gen_signature("", &keypair).unwrap();
+    let expected_signature = expected_tracker
+        .gen_signature(&test_signature_salt(), &keypair)
+        .unwrap();
 
     assert_ne!(expected_signature, unexpected_signature, "test is incorrect");
 
@@ -297,7 +309,9 @@ async fn test_signature_canon_basic() {
     let mut expected_tracker = PeerCidTracker::new(init_peer_name.to_owned());
     expected_tracker.register(init_peer_name, &expected_canon_cid);
     expected_tracker.register(init_peer_name, &expected_call_result_cid);
-    let expected_signature = expected_tracker.gen_signature("", &keypair).unwrap();
+    let expected_signature = expected_tracker
+        .gen_signature(&test_signature_salt(), &keypair)
+        .unwrap();
 
     let signature = last_data.signatures.get(&keypair.public().into());
     assert_eq!(signature, Some(&expected_signature), "{:?}", last_data);
@@ -380,7 +394,9 @@ async fn test_signature_canon_merge() {
     let mut expected_tracker = PeerCidTracker::new(init_peer_name.to_owned());
     expected_tracker.register(init_peer_name, &expected_canon_cid);
     expected_tracker.register(init_peer_name, &expected_call_result_cid);
-    let expected_signature = expected_tracker.gen_signature("", &keypair).unwrap();
+    let expected_signature = expected_tracker
+        .gen_signature(&test_signature_salt(), &keypair)
+        .unwrap();
 
     let signature = last_data.signatures.get(&keypair.public().into());
     assert_eq!(signature, Some(&expected_signature), "{:?}", last_data);
@@ -477,7 +493,9 @@ async fn test_signature_canon_result() {
     expected_tracker.register(init_peer_name, &expected_call_result_cid1);
     expected_tracker.register(init_peer_name, &expected_call_result_cid2);
     expected_tracker.register(init_peer_name, &expected_canon_cid);
-    let expected_signature = expected_tracker.gen_signature("", &keypair).unwrap();
+    let expected_signature = expected_tracker
+        .gen_signature(&test_signature_salt(), &keypair)
+        .unwrap();
 
     let signature = last_data.signatures.get(&keypair.public().into());
     assert_eq!(signature, Some(&expected_signature), "{:?}", last_data);
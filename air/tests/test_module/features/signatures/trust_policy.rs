@@ -0,0 +1,188 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air::PreparationError;
+use air::TrustPolicyViolation;
+use air_interpreter_interface::TrustPolicy;
+use air_test_utils::key_utils::derive_dummy_keypair;
+use air_test_utils::prelude::*;
+use air_test_utils::test_runner::create_avm_with_key;
+use air_test_utils::test_runner::AirRunner;
+use air_test_utils::test_runner::NativeAirRunner;
+use air_test_utils::test_runner::TestRunner;
+
+fn script(sender_peer_id: &str, receiver_peer_id: &str) -> String {
+    format!(
+        r#"
+        (seq
+            (call "{sender_peer_id}" ("" "") [] x)
+            (call "{receiver_peer_id}" ("" "") [] y))
+        "#
+    )
+}
+
+async fn create_peer(name: &str) -> (TestRunner<NativeAirRunner>, String) {
+    let (keypair, peer_id) = derive_dummy_keypair(name);
+    let avm =
+        create_avm_with_key::<NativeAirRunner>(keypair, set_variable_call_service(json!("ok")), <_>::default()).await;
+    (avm, peer_id)
+}
+
+/// Run the script on the sender, then pass its data to the receiver with the `trust_policy` set.
+async fn run_with_policy(trust_policy: impl FnOnce(&str) -> TrustPolicy) -> (RawAVMOutcome, String) {
+    let (mut sender, sender_peer_id) = create_peer("sender").await;
+    let (mut receiver, receiver_peer_id) = create_peer("receiver").await;
+    receiver.runner.set_trust_policy(trust_policy(&sender_peer_id));
+
+    let air_script = script(&sender_peer_id, &receiver_peer_id);
+    let run_params = TestRunParameters::from_init_peer_id(&sender_peer_id).with_particle_id("particle_id");
+
+    let res1 = sender.call(&air_script, "", "", run_params.clone()).await.unwrap();
+    assert_eq!(res1.ret_code, 0, "{:?}", res1);
+
+    let res2 = receiver.call(&air_script, "", res1.data, run_params).await.unwrap();
+    (res2, sender_peer_id)
+}
+
+#[tokio::test]
+async fn test_empty_policy() {
+    let (res, _) = run_with_policy(|_| <_>::default()).await;
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+}
+
+#[tokio::test]
+async fn test_allowed_peer() {
+    let (res, _) = run_with_policy(|sender_peer_id| TrustPolicy {
+        allowed_peers: vec![sender_peer_id.to_owned()],
+        ..<_>::default()
+    })
+    .await;
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+}
+
+#[tokio::test]
+async fn test_peer_not_allowed() {
+    let (res, sender_peer_id) = run_with_policy(|_| TrustPolicy {
+        allowed_peers: vec!["other_peer_id".to_owned()],
+        ..<_>::default()
+    })
+    .await;
+    assert_error_eq!(
+        &res,
+        PreparationError::TrustPolicyViolation(TrustPolicyViolation::PeerNotAllowed {
+            peer_id: sender_peer_id
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_peer_denied() {
+    let (res, sender_peer_id) = run_with_policy(|sender_peer_id| TrustPolicy {
+        denied_peers: vec![sender_peer_id.to_owned()],
+        ..<_>::default()
+    })
+    .await;
+    assert_error_eq!(
+        &res,
+        PreparationError::TrustPolicyViolation(TrustPolicyViolation::PeerDenied {
+            peer_id: sender_peer_id
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_known_key() {
+    let (sender_keypair, _) = derive_dummy_keypair("sender");
+    let (res, _) = run_with_policy(|sender_peer_id| TrustPolicy {
+        known_key_peers: vec![sender_peer_id.to_owned()],
+        known_keys: vec![sender_keypair.as_inner().public().encode()],
+        ..<_>::default()
+    })
+    .await;
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+}
+
+#[tokio::test]
+async fn test_unknown_key() {
+    let (sender_keypair, _) = derive_dummy_keypair("sender");
+    let (other_keypair, _) = derive_dummy_keypair("other_peer");
+    let (res, sender_peer_id) = run_with_policy(|sender_peer_id| TrustPolicy {
+        known_key_peers: vec![sender_peer_id.to_owned()],
+        known_keys: vec![other_keypair.as_inner().public().encode()],
+        ..<_>::default()
+    })
+    .await;
+    assert_error_eq!(
+        &res,
+        PreparationError::TrustPolicyViolation(TrustPolicyViolation::UnknownKey {
+            peer_id: sender_peer_id,
+            key: sender_keypair.public().to_string(),
+        })
+    );
+}
+
+/// The current peer's own data is always trusted.
+#[tokio::test]
+async fn test_current_peer_exempt() {
+    let (mut peer, peer_id) = create_peer("receiver").await;
+    peer.runner.set_trust_policy(TrustPolicy {
+        allowed_peers: vec!["other_peer_id".to_owned()],
+        ..<_>::default()
+    });
+
+    let air_script = script(&peer_id, &peer_id);
+    let run_params = TestRunParameters::from_init_peer_id(&peer_id).with_particle_id("particle_id");
+
+    let res1 = peer.call(&air_script, "", "", run_params.clone()).await.unwrap();
+    assert_eq!(res1.ret_code, 0, "{:?}", res1);
+    let res2 = peer.call(&air_script, "", res1.data, run_params).await.unwrap();
+    assert_eq!(res2.ret_code, 0, "{:?}", res2);
+}
+
+#[tokio::test]
+async fn test_particle_signature_salt_with_particle_id_salt_denied() {
+    let (res, _) = run_with_policy(|_| TrustPolicy {
+        particle_id_salt_denied: true,
+        ..<_>::default()
+    })
+    .await;
+    assert_eq!(res.ret_code, 0, "{:?}", res);
+}
+
+#[tokio::test]
+async fn test_particle_id_salt_denied() {
+    let (_, sender_peer_id) = derive_dummy_keypair("alice_peer");
+    let (mut receiver, receiver_peer_id) = create_peer("receiver").await;
+    receiver.runner.set_trust_policy(TrustPolicy {
+        particle_id_salt_denied: true,
+        ..<_>::default()
+    });
+    // produced by the previous release, it salted the sender's signature with the particle id
+    let data = include_bytes!("../misc/data/previous_release_data.bin").to_vec();
+
+    let air_script = script(&sender_peer_id, &receiver_peer_id);
+    let run_params = TestRunParameters::from_init_peer_id(&sender_peer_id).with_particle_id("particle_id");
+    let res = receiver.call(&air_script, "", data, run_params).await.unwrap();
+    assert_error_eq!(
+        &res,
+        PreparationError::TrustPolicyViolation(TrustPolicyViolation::ParticleIdSaltDenied {
+            peer_id: sender_peer_id
+        })
+    );
+}
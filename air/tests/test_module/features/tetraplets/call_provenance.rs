@@ -0,0 +1,212 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air_test_utils::prelude::*;
+use futures::FutureExt;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type ArgProvenance = Option<CallProvenance>;
+
+/// Record provenance of the `("check" "func")` call arguments and return `"ok"` for other calls.
+fn provenance_host_function() -> (CallServiceClosure<'static>, Rc<RefCell<ArgProvenance>>) {
+    let arg_provenance = Rc::new(RefCell::new(None));
+    let arg_provenance_inner = arg_provenance.clone();
+
+    let host_function: CallServiceClosure<'static> = Box::new(move |params| {
+        if params.service_id == "check" {
+            *arg_provenance_inner.borrow_mut() = params.provenance.clone();
+        }
+        async { CallServiceResult::ok(json!("ok")) }.boxed_local()
+    });
+
+    (host_function, arg_provenance)
+}
+
+#[tokio::test]
+async fn call_provenance_disabled_by_default() {
+    let peer_id = "peer_id";
+    let (host_function, arg_provenance) = provenance_host_function();
+    let mut vm = create_avm(host_function, peer_id).await;
+
+    let air_script = format!(
+        r#"
+        (seq
+            (call "{peer_id}" ("service" "func") [] x)
+            (call "{peer_id}" ("check" "func") [x]))
+        "#
+    );
+    let test_params = TestRunParameters::from_init_peer_id("init_peer_id");
+    let result = checked_call_vm!(vm, test_params.clone(), &air_script, "", "");
+    assert!(result.next_peer_pks.is_empty());
+    assert_eq!(*arg_provenance.borrow(), None);
+}
+
+#[tokio::test]
+async fn call_provenance_literal_and_service_result() {
+    let peer_id = "peer_id";
+    let (host_function, arg_provenance) = provenance_host_function();
+    let mut vm = create_avm(host_function, peer_id).await;
+    vm.runner.set_call_provenance_enabled(true);
+
+    let air_script = format!(
+        r#"
+        (seq
+            (call "{peer_id}" ("service" "func") [] x)
+            (call "{peer_id}" ("check" "func") ["literal" x]))
+        "#
+    );
+    let test_params = TestRunParameters::from_init_peer_id("init_peer_id");
+    let result = checked_call_vm!(vm, test_params.clone(), &air_script, "", "");
+
+    let data = data_from_result(&result);
+    let service_result_cid = extract_service_result_cid(&data.trace[0.into()]);
+
+    let service_result_cid = service_result_cid.get_inner().to_string();
+    let expected_provenance = CallProvenance {
+        arguments: vec![
+            ArgumentProvenance::Literal,
+            ArgumentProvenance::ServiceResult {
+                cid: service_result_cid.clone(),
+            },
+        ],
+        service_results: maplit::btreemap! {
+            service_result_cid => SecurityTetraplet::new(peer_id, "service", "func", ""),
+        },
+        canons: <_>::default(),
+    };
+    assert_eq!(*arg_provenance.borrow(), Some(expected_provenance));
+}
+
+#[tokio::test]
+async fn call_provenance_through_canon() {
+    let producer_peer_id = "producer_peer_id";
+    let canon_peer_id = "canon_peer_id";
+    let (host_function, arg_provenance) = provenance_host_function();
+    let mut producer_vm = create_avm(unit_call_service(), producer_peer_id).await;
+    let mut canon_vm = create_avm(host_function, canon_peer_id).await;
+    canon_vm.runner.set_call_provenance_enabled(true);
+
+    let air_script = format!(
+        r#"
+        (seq
+            (call "{producer_peer_id}" ("service" "func") [] $stream)
+            (seq
+                (canon "{canon_peer_id}" $stream #canon)
+                (call "{canon_peer_id}" ("check" "func") [#canon #canon.$.[0]])))
+        "#
+    );
+    let test_params = TestRunParameters::from_init_peer_id("init_peer_id");
+    let producer_result = checked_call_vm!(producer_vm, test_params.clone(), &air_script, "", "");
+    let canon_result = checked_call_vm!(canon_vm, test_params.clone(), &air_script, "", producer_result.data);
+
+    let data = data_from_result(&canon_result);
+    let service_result_cid = extract_service_result_cid(&data.trace[0.into()]);
+    let canon_result_cid = extract_canon_result_cid(&data.trace[1.into()]);
+
+    let service_result_cid = service_result_cid.get_inner().to_string();
+    let canon_result_cid = canon_result_cid.get_inner().to_string();
+    let service_result_provenance = ArgumentProvenance::ServiceResult {
+        cid: service_result_cid.clone(),
+    };
+    let expected_provenance = CallProvenance {
+        // a stream element keeps its own provenance
+        arguments: vec![
+            ArgumentProvenance::Canon {
+                cid: canon_result_cid.clone(),
+            },
+            service_result_provenance.clone(),
+        ],
+        service_results: maplit::btreemap! {
+            service_result_cid => SecurityTetraplet::new(producer_peer_id, "service", "func", ""),
+        },
+        canons: maplit::btreemap! {
+            canon_result_cid => CanonProvenance {
+                tetraplet: SecurityTetraplet::new(canon_peer_id, "", "", ""),
+                values: Some(vec![service_result_provenance]),
+            },
+        },
+    };
+    assert_eq!(*arg_provenance.borrow(), Some(expected_provenance));
+}
+
+#[tokio::test]
+async fn call_provenance_of_nested_canons_is_bounded() {
+    // every canon has two elements that are the same previous canon, so inlined chains
+    // would grow exponentially with nesting
+    const NESTING: usize = 2 * MAX_PROVENANCE_DEPTH;
+
+    let peer_id = "peer_id";
+    let (host_function, arg_provenance) = provenance_host_function();
+    let mut vm = create_avm(host_function, peer_id).await;
+    vm.runner.set_call_provenance_enabled(true);
+
+    let mut air_script = format!(r#"(call "{peer_id}" ("check" "func") [#canon_{NESTING}])"#);
+    for level in (1..=NESTING).rev() {
+        let prev_level = level - 1;
+        air_script = format!(
+            r#"
+            (seq
+                (seq
+                    (seq
+                        (ap #canon_{prev_level} $stream_{level})
+                        (ap #canon_{prev_level} $stream_{level}))
+                    (canon "{peer_id}" $stream_{level} #canon_{level}))
+                {air_script})
+            "#
+        );
+    }
+    let air_script = format!(
+        r#"
+        (seq
+            (seq
+                (call "{peer_id}" ("service" "func") [] $stream_0)
+                (canon "{peer_id}" $stream_0 #canon_0))
+            {air_script})
+        "#
+    );
+
+    let test_params = TestRunParameters::from_init_peer_id("init_peer_id");
+    checked_call_vm!(vm, test_params, &air_script, "", "");
+
+    let call_provenance = arg_provenance.borrow().clone().expect("provenance is enabled");
+    let [ArgumentProvenance::Canon { cid: top_canon_cid }] = call_provenance.arguments.as_slice() else {
+        panic!("a single canon argument is expected: {call_provenance:?}");
+    };
+
+    // each canon is listed once down to the depth limit
+    assert_eq!(call_provenance.canons.len(), MAX_PROVENANCE_DEPTH + 1);
+    assert!(call_provenance.service_results.is_empty());
+
+    let mut canon_cid = top_canon_cid.clone();
+    for _ in 0..MAX_PROVENANCE_DEPTH {
+        let values = call_provenance.canons[&canon_cid].values.as_ref().unwrap();
+        let [ArgumentProvenance::Canon { cid: first }, ArgumentProvenance::Canon { cid: second }] = values.as_slice()
+        else {
+            panic!("two nested canons are expected: {values:?}");
+        };
+        assert_eq!(first, second);
+        canon_cid = first.clone();
+    }
+    assert_eq!(call_provenance.canons[&canon_cid].values, None);
+
+    let serialized_size = serde_json::to_vec(&call_provenance).unwrap().len();
+    assert!(serialized_size < 10_000, "provenance size is {serialized_size}");
+}
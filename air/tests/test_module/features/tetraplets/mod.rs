@@ -17,5 +17,6 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+mod call_provenance;
 mod provenance;
 mod security_tetraplets;
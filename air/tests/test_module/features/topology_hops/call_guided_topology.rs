@@ -123,6 +123,7 @@ async fn call_with_join_behaviour() {
             <_>::default(),
             &keypair,
             "".to_string(),
+            TEST_PARTICLE_SIGNATURE.to_vec(),
         )
         .await
         .unwrap();
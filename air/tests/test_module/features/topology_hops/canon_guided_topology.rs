@@ -147,6 +147,7 @@ async fn canon_with_join_behaviour() {
             <_>::default(),
             &keypair,
             "".to_string(),
+            TEST_PARTICLE_SIGNATURE.to_vec(),
         )
         .await
         .unwrap();
@@ -60,6 +60,7 @@ async fn issue_310() {
                     call_results,
                     key_pair,
                     particle_id,
+                    TEST_PARTICLE_SIGNATURE.to_vec(),
                 )
                 .await
                 .unwrap()
@@ -85,7 +85,7 @@ async fn invalid_data_with_versions() {
     let expected_serde_error = InterpreterDataEnvelope::try_from_slice(&invalid_data).unwrap_err();
     let expected_error = PreparationError::EnvelopeDeFailedWithVersions {
         error: expected_serde_error,
-        versions,
+        versions: Box::new(versions),
     };
     assert!(check_error(&result, expected_error));
 }
@@ -119,6 +119,13 @@ async fn invalid_callresults() {
         particle_size_limit,
         call_result_size_limit,
         hard_limit_enable,
+        vec![],
+        <_>::default(),
+        <_>::default(),
+        vec![],
+        false,
+        false,
+        vec![],
     );
 
     let result = air::execute_air(air, prev_data, data, run_parameters, wrong_call_results.clone().into());
@@ -154,6 +161,13 @@ fn air_size_hard_limit() {
         particle_size_limit,
         call_result_size_limit,
         hard_limit_enable,
+        vec![],
+        <_>::default(),
+        <_>::default(),
+        vec![],
+        false,
+        false,
+        <_>::default(),
     );
 
     let result = air::execute_air(script, vec![], vec![], run_parameters, <_>::default());
@@ -187,6 +201,13 @@ fn particle_size_hard_limit() {
         particle_size_limit,
         call_result_size_limit,
         hard_limit_enable,
+        vec![],
+        <_>::default(),
+        <_>::default(),
+        vec![],
+        false,
+        false,
+        <_>::default(),
     );
 
     let result = air::execute_air(script, vec![], cur_data, run_parameters, <_>::default());
@@ -232,6 +253,13 @@ fn call_result_size_hard_limit() {
         particle_size_limit,
         call_result_size_limit,
         hard_limit_enable,
+        vec![],
+        <_>::default(),
+        <_>::default(),
+        vec![],
+        false,
+        false,
+        <_>::default(),
     );
 
     let result = air::execute_air(script, vec![], vec![], run_parameters, raw_call_results);
@@ -241,3 +269,46 @@ fn call_result_size_hard_limit() {
 
     assert_eq!(result.ret_code, expected_error.to_error_code());
 }
+
+#[test]
+fn malformed_call_policy() {
+    use air_interpreter_interface::CallPolicy;
+    use air_interpreter_interface::CallPolicyRepr;
+    use air_interpreter_sede::ToSerialized;
+
+    let air = r#"(null)"#.to_string();
+    let client_peer_id = "some_peer_id".to_string();
+    let keypair = fluence_keypair::KeyPair::generate_ed25519();
+    let call_policy = vec![0xc1];
+
+    let run_parameters = RunParameters::new(
+        client_peer_id.clone(),
+        client_peer_id,
+        0,
+        0,
+        keypair.key_format().into(),
+        keypair.secret().unwrap(),
+        "".to_owned(),
+        MAX_AIR_SIZE,
+        MAX_PARTICLE_SIZE,
+        MAX_CALL_RESULT_SIZE,
+        false,
+        vec![],
+        <_>::default(),
+        call_policy.clone().into(),
+        vec![],
+        false,
+        false,
+        vec![],
+    );
+
+    let call_results = CallResultsRepr.serialize(&<_>::default()).unwrap();
+    let result = air::execute_air(air, vec![], vec![], run_parameters, call_results);
+    let result = RawAVMOutcome::from_interpreter_outcome(result).unwrap();
+
+    let expected_serde_error = FromSerialized::<CallPolicy>::deserialize(&CallPolicyRepr, &call_policy).unwrap_err();
+    let expected_error = PreparationError::MalformedCallPolicy {
+        error: expected_serde_error,
+    };
+    assert!(check_error(&result, expected_error));
+}
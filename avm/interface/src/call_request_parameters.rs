@@ -18,13 +18,16 @@
  */
 
 use super::JValue;
+use crate::CallResults;
 use crate::CallSeDeErrors;
 
+use air_interpreter_interface::CallProvenance;
 use air_interpreter_interface::SerializedCallRequests;
 use polyplets::SecurityTetraplet;
 use serde::Deserialize;
 use serde::Serialize;
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 
 pub type CallRequests = HashMap<u32, CallRequestParams>;
@@ -44,6 +47,15 @@ pub struct CallRequestParams {
 
     /// Tetraplets that should be passed to the service.
     pub tetraplets: Vec<Vec<SecurityTetraplet>>,
+
+    /// Provenance chains of the arguments, if the host has requested them.
+    #[serde(default)]
+    pub provenance: Option<CallProvenance>,
+
+    /// Ids of identical calls made in the same run, the result of this request should be
+    /// supplied to them as well, see `fan_out_call_results`.
+    #[serde(default)]
+    pub fan_out_call_ids: Vec<u32>,
 }
 
 impl CallRequestParams {
@@ -58,13 +70,22 @@ impl CallRequestParams {
             function_name: function_name.into(),
             arguments,
             tetraplets,
+            provenance: None,
+            fan_out_call_ids: vec![],
         }
     }
 
+    /// Ids of all calls the result of this request is for, starting with `call_id` of the
+    /// request itself.
+    pub fn call_ids(&self, call_id: u32) -> impl Iterator<Item = u32> + '_ {
+        std::iter::once(call_id).chain(self.fan_out_call_ids.iter().copied())
+    }
+
     pub(crate) fn from_raw(
         call_params: air_interpreter_interface::CallRequestParams,
     ) -> Result<Self, CallSeDeErrors> {
         use air_interpreter_interface::CallArgumentsRepr;
+        use air_interpreter_interface::ProvenanceRepr;
         use air_interpreter_interface::TetrapletsRepr;
         use air_interpreter_sede::FromSerialized;
 
@@ -83,17 +104,67 @@ impl CallRequestParams {
                 de_error,
             })?;
 
+        let provenance = if call_params.provenance.is_empty() {
+            None
+        } else {
+            let provenance = ProvenanceRepr
+                .deserialize(&call_params.provenance)
+                .map_err(|de_error| CallSeDeErrors::CallParamsProvenanceDeFailed {
+                    call_params: call_params.clone(),
+                    de_error,
+                })?;
+            Some(provenance)
+        };
+
         let call_params = Self {
             service_id: call_params.service_id,
             function_name: call_params.function_name,
             arguments,
             tetraplets,
+            provenance,
+            fan_out_call_ids: call_params.fan_out_call_ids,
         };
 
         Ok(call_params)
     }
 }
 
+/// Requests grouped by their service ids with call ids in ascending order, so a host could
+/// execute calls to the same service in a batch.
+pub fn group_by_service(
+    call_requests: &CallRequests,
+) -> BTreeMap<&str, Vec<(u32, &CallRequestParams)>> {
+    let mut groups = BTreeMap::<_, Vec<_>>::new();
+    for (&call_id, call_params) in call_requests {
+        groups
+            .entry(call_params.service_id.as_str())
+            .or_default()
+            .push((call_id, call_params));
+    }
+
+    for group in groups.values_mut() {
+        group.sort_unstable_by_key(|&(call_id, _)| call_id);
+    }
+
+    groups
+}
+
+/// Supply results of requests to their fan-out call ids as well, results already present for
+/// fan-out call ids are kept.
+pub fn fan_out_call_results(call_requests: &CallRequests, call_results: &mut CallResults) {
+    for (call_id, call_params) in call_requests {
+        let Some(call_result) = call_results.get(call_id).cloned() else {
+            continue;
+        };
+
+        for &fan_out_call_id in &call_params.fan_out_call_ids {
+            call_results
+                .entry(fan_out_call_id)
+                .or_insert_with(|| call_result.clone());
+        }
+    }
+}
+
 pub(crate) fn from_raw_call_requests(
     raw_call_params: SerializedCallRequests,
 ) -> Result<CallRequests, CallSeDeErrors> {
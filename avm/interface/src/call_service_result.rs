@@ -26,6 +26,17 @@ use std::collections::HashMap;
 pub type CallResults = HashMap<u32, CallServiceResult>;
 pub const CALL_SERVICE_SUCCESS: i32 = 0;
 
+/// Defines how a service result is passed to the interpreter.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CallResultEncoding {
+    /// A JSON string, understood by every interpreter version.
+    #[default]
+    Json,
+    /// A binary value serialized with `CallResultValueRepr`, which spares JSON parsing.
+    /// Hosted interpreters released before binary call results are passed JSON instead.
+    Binary,
+}
+
 /// Represents an executed host function result.
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct CallServiceResult {
@@ -34,6 +45,10 @@ pub struct CallServiceResult {
 
     /// Resulted JValue returned by a service string.
     pub result: JValue,
+
+    /// Encoding used to pass a successful result to the interpreter.
+    #[serde(default)]
+    pub encoding: CallResultEncoding,
 }
 
 impl CallServiceResult {
@@ -41,6 +56,15 @@ impl CallServiceResult {
         Self {
             ret_code: CALL_SERVICE_SUCCESS,
             result,
+            encoding: CallResultEncoding::Json,
+        }
+    }
+
+    pub fn ok_binary(result: JValue) -> Self {
+        Self {
+            ret_code: CALL_SERVICE_SUCCESS,
+            result,
+            encoding: CallResultEncoding::Binary,
         }
     }
 
@@ -48,16 +72,30 @@ impl CallServiceResult {
         Self {
             ret_code: err_code,
             result,
+            encoding: CallResultEncoding::Json,
         }
     }
 
     pub fn into_raw(self) -> air_interpreter_interface::CallServiceResult {
-        let CallServiceResult { ret_code, result } = self;
+        use air_interpreter_interface::CALL_SERVICE_SUCCESS;
 
-        air_interpreter_interface::CallServiceResult {
+        let CallServiceResult {
             ret_code,
-            // TODO serializer
-            result: result.to_string(),
+            result,
+            encoding,
+        } = self;
+
+        match encoding {
+            // error messages are always passed as strings
+            CallResultEncoding::Binary if ret_code == CALL_SERVICE_SUCCESS => {
+                air_interpreter_interface::CallServiceResult::ok_binary(&result)
+            }
+            _ => air_interpreter_interface::CallServiceResult {
+                ret_code,
+                // TODO serializer
+                result: result.to_string(),
+                binary_result: None,
+            },
         }
     }
 }
@@ -38,6 +38,7 @@ pub mod raw_outcome;
 use air_interpreter_interface::CallArgumentsDeserializeError;
 use air_interpreter_interface::CallRequestsDeserializeError;
 use air_interpreter_interface::CallResultsSerializeError;
+use air_interpreter_interface::ProvenanceDeserializeError;
 use air_interpreter_interface::SerializedCallRequests;
 use air_interpreter_interface::TetrapletDeserializeError;
 use thiserror::Error as ThisError;
@@ -76,11 +77,27 @@ pub enum CallSeDeErrors {
         call_params: air_interpreter_interface::CallRequestParams,
         de_error: TetrapletDeserializeError,
     },
+
+    /// Errors encountered while trying to deserialize argument provenance from call parameters
+    /// returned by the interpreter.
+    #[error("error occurred while deserialization of provenance from call params `{call_params:?}`: {de_error}")]
+    CallParamsProvenanceDeFailed {
+        call_params: air_interpreter_interface::CallRequestParams,
+        de_error: ProvenanceDeserializeError,
+    },
 }
 
 type JValue = serde_json::Value;
 
+pub use air_interpreter_interface::ArgumentProvenance;
+pub use air_interpreter_interface::CallPolicy;
+pub use air_interpreter_interface::CallProvenance;
+pub use air_interpreter_interface::CallRule;
+pub use air_interpreter_interface::CanonProvenance;
 pub use air_interpreter_interface::SoftLimitsTriggering;
+pub use air_interpreter_interface::TrustPolicy;
+pub use air_interpreter_interface::CALL_NOT_AUTHORIZED;
+pub use air_interpreter_interface::MAX_PROVENANCE_DEPTH;
 pub use call_request_parameters::*;
 pub use call_service_result::*;
 pub use outcome::*;
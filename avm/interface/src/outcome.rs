@@ -90,6 +90,7 @@ impl AVMOutcome {
             call_requests,
             next_peer_pks,
             soft_limits_triggering,
+            signing_payload: _,
         } = raw_outcome;
 
         let avm_outcome = AVMOutcome::new(
@@ -26,6 +26,9 @@ use std::borrow::Cow;
 pub struct ParticleParameters<'ctx> {
     pub init_peer_id: Cow<'ctx, str>,
     pub particle_id: Cow<'ctx, str>,
+    /// Signature of the particle made by its init peer, produced data is salted with it.
+    #[serde(default)]
+    pub particle_signature: Cow<'ctx, [u8]>,
     pub timestamp: u64,
     pub ttl: u32,
     pub current_peer_id: Cow<'ctx, str>,
@@ -35,6 +38,7 @@ impl<'ctx> ParticleParameters<'ctx> {
     pub fn new(
         init_peer_id: Cow<'ctx, str>,
         particle_id: Cow<'ctx, str>,
+        particle_signature: Cow<'ctx, [u8]>,
         timestamp: u64,
         ttl: u32,
         current_peer_id: Cow<'ctx, str>,
@@ -42,6 +46,7 @@ impl<'ctx> ParticleParameters<'ctx> {
         Self {
             init_peer_id,
             particle_id,
+            particle_signature,
             timestamp,
             ttl,
             current_peer_id,
@@ -36,6 +36,8 @@ pub struct RawAVMOutcome {
     pub call_requests: CallRequests,
     pub next_peer_pks: Vec<String>,
     pub soft_limits_triggering: SoftLimitsTriggering,
+    /// A payload the host should sign if remote signing was requested, it's empty otherwise.
+    pub signing_payload: Vec<u8>,
 }
 
 impl RawAVMOutcome {
@@ -49,6 +51,7 @@ impl RawAVMOutcome {
             air_size_limit_exceeded,
             particle_size_limit_exceeded,
             call_result_size_limit_exceeded,
+            signing_payload,
         } = outcome;
 
         let call_requests = crate::from_raw_call_requests(call_requests.into())?;
@@ -65,8 +68,22 @@ impl RawAVMOutcome {
             call_requests,
             next_peer_pks,
             soft_limits_triggering,
+            signing_payload,
         };
 
         Ok(raw_avm_outcome)
     }
+
+    /// An outcome with the given return code and data, without call requests and next peers.
+    pub fn with_data(ret_code: i64, data: Vec<u8>) -> Self {
+        Self {
+            ret_code,
+            error_message: String::new(),
+            data,
+            call_requests: <_>::default(),
+            next_peer_pks: vec![],
+            soft_limits_triggering: <_>::default(),
+            signing_payload: vec![],
+        }
+    }
 }
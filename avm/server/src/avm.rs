@@ -21,31 +21,64 @@ use super::avm_runner::AVMRunner;
 use super::AVMDataStore;
 use super::AVMError;
 use super::AVMMemoryStats;
+use super::AVMRuntimeLimits;
 use crate::config::AVMConfig;
+use crate::config::InterpreterBackend;
+use crate::config::NewParticlePolicy;
+use crate::interpreter_router::supported_call_results;
+use crate::interpreter_router::InterpreterRouter;
+use crate::interpreter_router::VersionedRunner;
+use crate::interpreter_runner::BackendRunner;
+use crate::interpreter_runner::CallSigner;
+use crate::interpreter_runner::RunnerSettings;
+use crate::AVMClock;
 use crate::AVMResult;
 
+use air_interpreter_interface::INTERPRETER_SUCCESS;
 use avm_data_store::AnomalyData;
 use avm_interface::raw_outcome::RawAVMOutcome;
 use avm_interface::AVMOutcome;
 use avm_interface::CallResults;
 use avm_interface::ParticleParameters;
 use fluence_keypair::KeyPair;
+use fluence_keypair::PublicKey;
 
 use marine_wasm_backend_traits::WasmBackend;
 
+use std::collections::BTreeMap;
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::time::Duration;
 use std::time::Instant;
 
+/// An outcome of `AVM::call_remote_signing` that is waiting for the host signature.
+#[derive(Debug)]
+pub struct UnsignedOutcome {
+    outcome: RawAVMOutcome,
+    particle_id: String,
+    particle_signature: Vec<u8>,
+    current_peer_id: String,
+    public_key: PublicKey,
+    interpreter_version: Option<semver::Version>,
+    memory_delta: usize,
+    execution_time: Duration,
+}
+
+impl UnsignedOutcome {
+    /// A payload to sign with the secret key of the signer public key.
+    pub fn signing_payload(&self) -> &[u8] {
+        &self.outcome.signing_payload
+    }
+}
+
 /// A newtype needed to mark it as `unsafe impl Send`
-struct SendSafeRunner<WB: WasmBackend>(AVMRunner<WB>);
+struct SendSafeRunner<WB: WasmBackend>(InterpreterRouter<WB>);
 
 /// Mark runtime as Send, so libp2p on the node (use-site) is happy
 unsafe impl<WB: WasmBackend> Send for SendSafeRunner<WB> {}
 
 impl<WB: WasmBackend> Deref for SendSafeRunner<WB> {
-    type Target = AVMRunner<WB>;
+    type Target = InterpreterRouter<WB>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -60,32 +93,113 @@ impl<WB: WasmBackend> DerefMut for SendSafeRunner<WB> {
 pub struct AVM<E, WB: WasmBackend> {
     runner: SendSafeRunner<WB>,
     data_store: AVMDataStore<E>,
+    clock: Option<AVMClock>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<crate::AVMMetrics>,
 }
 
 impl<E, WB: WasmBackend> AVM<E, WB> {
     /// Create AVM with provided config.
     #[allow(clippy::result_large_err)]
-    pub async fn new(config: AVMConfig<E>, wasm_backend: WB) -> AVMResult<Self, E> {
+    pub async fn new(mut config: AVMConfig<E>, wasm_backend: WB) -> AVMResult<Self, E> {
+        config.data_store.initialize().await?;
+        Self::with_initialized_store(config, wasm_backend).await
+    }
+
+    /// Create AVM with a data store which is already initialized, e.g. by a pool.
+    #[allow(clippy::result_large_err)]
+    pub(crate) async fn with_initialized_store(
+        config: AVMConfig<E>,
+        wasm_backend: WB,
+    ) -> AVMResult<Self, E> {
         let AVMConfig {
             air_wasm_path,
+            hosted_interpreters,
             max_heap_size,
             logging_mask,
-            mut data_store,
+            execution_timeout,
+            allowed_key_formats,
+            trust_policy,
+            call_provenance_enabled,
+            call_deduplication_enabled,
+            call_policy,
+            interpreter_backend,
+            clock,
+            #[cfg(feature = "metrics")]
+            metrics,
+            data_store,
         } = config;
 
-        data_store.initialize()?;
+        let runtime_limits = AVMRuntimeLimits {
+            execution_timeout,
+            ..<_>::default()
+        };
+        let mut router = match interpreter_backend {
+            InterpreterBackend::Wasm if hosted_interpreters.modules.is_empty() => {
+                let runner = AVMRunner::new(
+                    air_wasm_path,
+                    max_heap_size,
+                    runtime_limits,
+                    logging_mask,
+                    wasm_backend,
+                )
+                .await
+                .map_err(AVMError::RunnerError)?;
+                InterpreterRouter::Single(BackendRunner::Wasm(runner))
+            }
+            InterpreterBackend::Wasm => {
+                if let NewParticlePolicy::Pinned(version) = &hosted_interpreters.new_particle_policy
+                {
+                    if !hosted_interpreters.modules.contains_key(version) {
+                        let version = version.clone();
+                        return Err(AVMError::UnknownInterpreterVersion { version });
+                    }
+                }
 
-        let runner = AVMRunner::new(
-            air_wasm_path,
-            max_heap_size,
-            <_>::default(),
-            logging_mask,
-            wasm_backend,
-        )
-        .await
-        .map_err(AVMError::RunnerError)?;
-        let runner = SendSafeRunner(runner);
-        let avm = Self { runner, data_store };
+                let mut runners = BTreeMap::new();
+                for (version, module) in hosted_interpreters.modules {
+                    let runner = AVMRunner::new(
+                        module.air_wasm_path,
+                        max_heap_size,
+                        runtime_limits.clone(),
+                        logging_mask,
+                        wasm_backend.clone(),
+                    )
+                    .await
+                    .map_err(AVMError::RunnerError)?;
+                    let runner = VersionedRunner {
+                        min_supported_version: module.min_supported_version,
+                        runner: BackendRunner::Wasm(runner),
+                    };
+                    runners.insert(version, runner);
+                }
+                InterpreterRouter::versioned(runners, hosted_interpreters.new_particle_policy)
+            }
+            #[cfg(feature = "native")]
+            InterpreterBackend::Native => {
+                let runner =
+                    crate::native_runner::NativeAVMRunner::new(max_heap_size, runtime_limits)
+                        .map_err(AVMError::RunnerError)?;
+                InterpreterRouter::Single(BackendRunner::Native(runner))
+            }
+        };
+        let mut settings = RunnerSettings::default();
+        settings.set_allowed_key_formats(allowed_key_formats);
+        settings.set_trust_policy(trust_policy);
+        settings.set_call_provenance_enabled(call_provenance_enabled);
+        settings.set_call_deduplication_enabled(call_deduplication_enabled);
+        settings.set_call_policy(&call_policy);
+        for runner in router.runners_mut() {
+            *runner.settings_mut() = settings.clone();
+        }
+        let runner = SendSafeRunner(router);
+        let avm = Self {
+            runner,
+            data_store,
+            clock,
+            #[cfg(feature = "metrics")]
+            metrics,
+        };
 
         Ok(avm)
     }
@@ -99,104 +213,331 @@ impl<E, WB: WasmBackend> AVM<E, WB> {
         call_results: CallResults,
         keypair: &KeyPair,
     ) -> AVMResult<AVMOutcome, E> {
-        let air = air.into();
-        let prev_data = self.data_store.read_data(
-            &particle_parameters.particle_id,
-            &particle_parameters.current_peer_id,
-        )?;
-        let current_data = data.into();
+        self.call_impl(
+            air.into(),
+            vec![data.into()],
+            particle_parameters,
+            call_results,
+            keypair,
+        )
+        .await
+    }
 
-        let execution_start_time = Instant::now();
-        let memory_size_before = self.memory_stats().memory_size;
-        let outcome = self
+    /// Execute AIR with several current data of the same particle at once.
+    ///
+    /// The outcome is the same as of sequential calls with each data in order, where call
+    /// results are supplied to the first one, but each data is deserialized and verified only
+    /// once, and the resulted data is stored once.
+    #[allow(clippy::result_large_err)]
+    pub async fn call_multi(
+        &mut self,
+        air: impl Into<String>,
+        data: Vec<Vec<u8>>,
+        particle_parameters: ParticleParameters<'_>,
+        call_results: CallResults,
+        keypair: &KeyPair,
+    ) -> AVMResult<AVMOutcome, E> {
+        self.call_impl(air.into(), data, particle_parameters, call_results, keypair)
+            .await
+    }
+
+    /// Execute AIR without signing the produced data, the host signs it itself.
+    ///
+    /// The resulted data is stored only after the signature of the
+    /// `UnsignedOutcome::signing_payload` is passed to `finalize_signing`. It's so even for a
+    /// catchable error: its data has to be signed too, and `finalize_signing` returns the error
+    /// after storing it.
+    #[allow(clippy::result_large_err)]
+    pub async fn call_remote_signing(
+        &mut self,
+        air: impl Into<String>,
+        data: Vec<Vec<u8>>,
+        particle_parameters: ParticleParameters<'_>,
+        call_results: CallResults,
+        public_key: &PublicKey,
+    ) -> AVMResult<UnsignedOutcome, E> {
+        let (outcome, interpreter_version, memory_delta, execution_time) = self
+            .execute(
+                air.into(),
+                data,
+                &particle_parameters,
+                call_results,
+                CallSigner::Host(public_key),
+            )
+            .await?;
+
+        // nothing to sign means an uncatchable error, its data is the previous one and already stored
+        if outcome.ret_code != INTERPRETER_SUCCESS && outcome.signing_payload.is_empty() {
+            let error = AVMOutcome::from_raw_outcome(outcome, memory_delta, execution_time)
+                .expect_err("outcome has an error code");
+            return Err(AVMError::InterpreterFailed(error));
+        }
+
+        Ok(UnsignedOutcome {
+            outcome,
+            particle_id: particle_parameters.particle_id.into_owned(),
+            particle_signature: particle_parameters.particle_signature.into_owned(),
+            current_peer_id: particle_parameters.current_peer_id.into_owned(),
+            public_key: public_key.clone(),
+            interpreter_version,
+            memory_delta,
+            execution_time,
+        })
+    }
+
+    /// Put the host signature of the `UnsignedOutcome::signing_payload` into the data and
+    /// store it.
+    ///
+    /// If the signature doesn't match, nothing is stored.
+    #[allow(clippy::result_large_err)]
+    pub async fn finalize_signing(
+        &mut self,
+        unsigned: UnsignedOutcome,
+        signature: Vec<u8>,
+    ) -> AVMResult<AVMOutcome, E> {
+        let UnsignedOutcome {
+            mut outcome,
+            particle_id,
+            particle_signature,
+            current_peer_id,
+            public_key,
+            interpreter_version,
+            memory_delta,
+            execution_time,
+        } = unsigned;
+
+        let signed = self
             .runner
-            .call(
+            .runner_mut(interpreter_version.as_ref())
+            .finalize_signing(
+                std::mem::take(&mut outcome.data),
+                current_peer_id.clone(),
+                &public_key,
+                signature,
+                particle_id.clone(),
+                particle_signature,
+            )
+            .await
+            .map_err(AVMError::RunnerError)?;
+
+        outcome.data = signed.data;
+        if signed.ret_code != INTERPRETER_SUCCESS {
+            outcome.ret_code = signed.ret_code;
+            outcome.error_message = signed.error_message;
+        } else {
+            self.data_store
+                .store_data(&outcome.data, &particle_id, &current_peer_id)
+                .await?;
+        }
+
+        AVMOutcome::from_raw_outcome(outcome, memory_delta, execution_time)
+            .map_err(AVMError::InterpreterFailed)
+    }
+
+    #[allow(clippy::result_large_err)]
+    async fn call_impl(
+        &mut self,
+        air: String,
+        current_data: Vec<Vec<u8>>,
+        particle_parameters: ParticleParameters<'_>,
+        call_results: CallResults,
+        keypair: &KeyPair,
+    ) -> AVMResult<AVMOutcome, E> {
+        let (outcome, _, memory_delta, execution_time) = self
+            .execute(
+                air,
+                current_data,
+                &particle_parameters,
+                call_results,
+                CallSigner::KeyPair(keypair),
+            )
+            .await?;
+
+        // persist resulted data
+        self.data_store
+            .store_data(
+                &outcome.data,
+                &particle_parameters.particle_id,
+                &particle_parameters.current_peer_id,
+            )
+            .await?;
+        let outcome = AVMOutcome::from_raw_outcome(outcome, memory_delta, execution_time)
+            .map_err(AVMError::InterpreterFailed)?;
+
+        Ok(outcome)
+    }
+
+    #[allow(clippy::result_large_err)]
+    async fn execute(
+        &mut self,
+        air: String,
+        current_data: Vec<Vec<u8>>,
+        particle_parameters: &ParticleParameters<'_>,
+        call_results: CallResults,
+        signer: CallSigner<'_>,
+    ) -> AVMResult<(RawAVMOutcome, Option<semver::Version>, usize, Duration), E> {
+        self.reject_expired(particle_parameters).await?;
+        self.data_store
+            .track_expiration(
+                &particle_parameters.particle_id,
+                &particle_parameters.current_peer_id,
+                particle_parameters.timestamp,
+                particle_parameters.ttl,
+            )
+            .await?;
+        let prev_data = self
+            .data_store
+            .read_data(
+                &particle_parameters.particle_id,
+                &particle_parameters.current_peer_id,
+            )
+            .await?;
+
+        let interpreter_version = self.runner.select_version(&prev_data, &current_data);
+        let call_results = supported_call_results(interpreter_version.as_ref(), call_results);
+        #[cfg(feature = "metrics")]
+        let input_data_size = prev_data.len() + current_data.iter().map(Vec::len).sum::<usize>();
+        let runner = self.runner.runner_mut(interpreter_version.as_ref());
+
+        let execution_start_time = Instant::now();
+        let memory_size_before = runner.memory_stats().memory_size;
+        let outcome = runner
+            .execute(
                 air.clone(),
                 prev_data,
                 current_data.clone(),
                 particle_parameters.init_peer_id.clone().into_owned(),
                 particle_parameters.timestamp,
                 particle_parameters.ttl,
-                particle_parameters.current_peer_id.clone(),
+                particle_parameters.current_peer_id.to_string(),
                 call_results.clone(),
-                keypair,
+                signer,
                 particle_parameters.particle_id.to_string(),
+                particle_parameters.particle_signature.to_vec(),
             )
             .await
             .map_err(AVMError::RunnerError)?;
 
         let execution_time = execution_start_time.elapsed();
-        let memory_delta = self.memory_stats().memory_size - memory_size_before;
+        let memory_delta = runner.memory_stats().memory_size - memory_size_before;
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_execution(input_data_size, &outcome, memory_delta, execution_time);
+        }
         if self
             .data_store
             .detect_anomaly(execution_time, memory_delta, &outcome)
+            .await
         {
             self.save_anomaly_data(
                 &air,
                 &current_data,
                 &call_results,
-                &particle_parameters,
+                particle_parameters,
                 &outcome,
                 execution_time,
                 memory_delta,
-            )?;
+            )
+            .await?;
         }
 
-        // persist resulted data
-        self.data_store.store_data(
-            &outcome.data,
+        Ok((outcome, interpreter_version, memory_delta, execution_time))
+    }
+
+    /// Fail with `AVMError::ParticleExpired` if the particle TTL is over according to the clock,
+    /// the stored data of such a particle is removed.
+    #[allow(clippy::result_large_err)]
+    async fn reject_expired(
+        &mut self,
+        particle_parameters: &ParticleParameters<'_>,
+    ) -> AVMResult<(), E> {
+        let Some(clock) = &self.clock else {
+            return Ok(());
+        };
+
+        let result = crate::reject_expired(
+            clock.as_ref(),
             &particle_parameters.particle_id,
-            &particle_parameters.current_peer_id,
-        )?;
-        let outcome = AVMOutcome::from_raw_outcome(outcome, memory_delta, execution_time)
-            .map_err(AVMError::InterpreterFailed)?;
+            particle_parameters.timestamp,
+            particle_parameters.ttl,
+        );
+        if result.is_err() {
+            self.cleanup_data(
+                &particle_parameters.particle_id,
+                &particle_parameters.current_peer_id,
+            )
+            .await?;
+        }
 
-        Ok(outcome)
+        result
     }
 
     /// Cleanup data that become obsolete.
     #[allow(clippy::result_large_err)]
-    pub fn cleanup_data(&mut self, particle_id: &str, current_peer_id: &str) -> AVMResult<(), E> {
-        self.data_store.cleanup_data(particle_id, current_peer_id)?;
+    pub async fn cleanup_data(
+        &mut self,
+        particle_id: &str,
+        current_peer_id: &str,
+    ) -> AVMResult<(), E> {
+        self.data_store
+            .cleanup_data(particle_id, current_peer_id)
+            .await?;
         Ok(())
     }
 
-    /// Return memory stat of an interpreter heap.
-    pub fn memory_stats(&self) -> AVMMemoryStats {
+    /// Remove data of particles expired by `now` in milliseconds since the Unix epoch,
+    /// see `DataStore::collect_expired`. Returns the number of removed data.
+    #[allow(clippy::result_large_err)]
+    pub async fn collect_expired_data(&mut self, now: u64) -> AVMResult<usize, E> {
+        let collected = self.data_store.collect_expired(now).await?;
+        Ok(collected)
+    }
+
+    /// Return memory stats of every hosted interpreter heap.
+    pub fn memory_stats(&self) -> Vec<AVMMemoryStats> {
         self.runner.memory_stats()
     }
 
     #[allow(clippy::result_large_err, clippy::too_many_arguments)]
-    fn save_anomaly_data(
+    async fn save_anomaly_data(
         &mut self,
         air_script: &str,
-        current_data: &[u8],
+        current_data: &[Vec<u8>],
         call_result: &CallResults,
         particle_parameters: &ParticleParameters<'_>,
         avm_outcome: &RawAVMOutcome,
         execution_time: Duration,
         memory_delta: usize,
     ) -> AVMResult<(), E> {
-        let prev_data = self.data_store.read_data(
-            &particle_parameters.particle_id,
-            &particle_parameters.current_peer_id,
-        )?;
+        let prev_data = self
+            .data_store
+            .read_data(
+                &particle_parameters.particle_id,
+                &particle_parameters.current_peer_id,
+            )
+            .await?;
         let call_results = serde_json::to_vec(call_result).map_err(AVMError::AnomalyDataSeError)?;
         let ser_particle =
             serde_json::to_vec(particle_parameters).map_err(AVMError::AnomalyDataSeError)?;
         let ser_avm_outcome =
             serde_json::to_vec(avm_outcome).map_err(AVMError::AnomalyDataSeError)?;
 
+        let (first_current_data, extra_current_data) = match current_data.split_first() {
+            Some((first, extra)) => (first.as_slice(), extra),
+            None => (&[][..], &[][..]),
+        };
+
         let anomaly_data = AnomalyData::new(
             air_script,
             &ser_particle,
             &prev_data,
-            current_data,
+            first_current_data,
             &call_results,
             &ser_avm_outcome,
             execution_time,
             memory_delta,
-        );
+        )
+        .with_extra_current_data(extra_current_data);
 
         self.data_store
             .collect_anomaly_data(
@@ -204,6 +545,7 @@ impl<E, WB: WasmBackend> AVM<E, WB> {
                 &particle_parameters.current_peer_id,
                 anomaly_data,
             )
+            .await
             .map_err(Into::into)
     }
 }
@@ -0,0 +1,146 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::AVMError;
+use crate::AVMResult;
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// A source of the current time particle TTLs are checked against.
+pub trait Clock: Send + Sync {
+    /// Current time in milliseconds since the Unix epoch, the unit of particle timestamps.
+    fn now(&self) -> u64;
+
+    /// Whether a particle with the given timestamp and ttl in milliseconds is expired,
+    /// i.e. its `timestamp + ttl` is not after the current time.
+    fn is_expired(&self, timestamp: u64, ttl: u32) -> bool {
+        particle_deadline(timestamp, ttl) <= self.now()
+    }
+}
+
+/// A clock shared between AVMs and their users.
+pub type AVMClock = Arc<dyn Clock>;
+
+/// Time after which a particle is expired, in milliseconds since the Unix epoch.
+pub fn particle_deadline(timestamp: u64, ttl: u32) -> u64 {
+    timestamp.saturating_add(ttl as u64)
+}
+
+/// Fail with `AVMError::ParticleExpired` if the particle TTL is over according to the clock.
+#[allow(clippy::result_large_err)]
+pub fn reject_expired<E>(
+    clock: &dyn Clock,
+    particle_id: &str,
+    timestamp: u64,
+    ttl: u32,
+) -> AVMResult<(), E> {
+    let now = clock.now();
+    let deadline = particle_deadline(timestamp, ttl);
+    if deadline > now {
+        return Ok(());
+    }
+
+    Err(AVMError::ParticleExpired {
+        particle_id: particle_id.to_string(),
+        deadline,
+        now,
+    })
+}
+
+/// The wall clock of the host.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64
+    }
+}
+
+/// A clock moved only explicitly, it's meant for tests. Clones share the same time.
+#[derive(Clone, Debug, Default)]
+pub struct VirtualClock(Arc<AtomicU64>);
+
+impl VirtualClock {
+    pub fn new(now: u64) -> Self {
+        Self(Arc::new(AtomicU64::new(now)))
+    }
+
+    pub fn set(&self, now: u64) {
+        self.0.store(now, Ordering::Relaxed);
+    }
+
+    /// Move the clock forward by `millis` milliseconds.
+    pub fn advance(&self, millis: u64) {
+        self.0.fetch_add(millis, Ordering::Relaxed);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particle_expires_at_deadline() {
+        let clock = VirtualClock::new(1099);
+        assert!(!clock.is_expired(1000, 100));
+
+        clock.advance(1);
+        assert!(clock.is_expired(1000, 100));
+    }
+
+    #[test]
+    fn expired_particle_is_rejected() {
+        let clock = VirtualClock::new(1099);
+        reject_expired::<()>(&clock, "particle", 1000, 100).unwrap();
+
+        clock.advance(1);
+        let error = reject_expired::<()>(&clock, "particle", 1000, 100).unwrap_err();
+        assert!(matches!(
+            error,
+            AVMError::ParticleExpired { particle_id, deadline: 1100, now: 1100 } if particle_id == "particle"
+        ));
+    }
+
+    #[test]
+    fn virtual_clock_clones_share_time() {
+        let clock = VirtualClock::default();
+        let shared: AVMClock = Arc::new(clock.clone());
+
+        clock.set(42);
+        assert_eq!(shared.now(), 42);
+    }
+
+    #[test]
+    fn deadline_saturates() {
+        assert_eq!(particle_deadline(u64::MAX - 1, 100), u64::MAX);
+    }
+}
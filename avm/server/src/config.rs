@@ -18,18 +18,124 @@
  */
 
 use super::AVMDataStore;
+use air_interpreter_interface::CallPolicy;
+use air_interpreter_interface::TrustPolicy;
+use fluence_keypair::KeyFormat;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Describes behaviour of the AVM.
 pub struct AVMConfig<E> {
-    /// Path to a AIR interpreter Wasm file.
+    /// Path to a AIR interpreter Wasm file, it's not used if `hosted_interpreters` are set.
     pub air_wasm_path: PathBuf,
 
+    /// Interpreters of several versions particles are routed between.
+    pub hosted_interpreters: HostedInterpreters,
+
     /// Maximum heap size in bytes available for the interpreter.
     pub max_heap_size: Option<u64>,
 
     /// Mask used to filter logs, for details see `log_utf8_string` in fluence-faas.
     pub logging_mask: i32,
 
+    /// Wall-clock time budget of a single interpreter call, see
+    /// `AVMRuntimeLimits::execution_timeout`: it fires only if the host increments
+    /// the epoch of the Wasm backend.
+    pub execution_timeout: Option<Duration>,
+
+    /// Key algorithms allowed for the current peer key and in other peers' signatures,
+    /// if it's empty, only Ed25519 is allowed.
+    pub allowed_key_formats: Vec<KeyFormat>,
+
+    /// Peers and keys the host accepts data from, it's enforced during data verification.
+    pub trust_policy: TrustPolicy,
+
+    /// If set, call requests contain provenance chains of their arguments.
+    pub call_provenance_enabled: bool,
+
+    /// If set, identical call requests of a run are emitted once with a fan-out list of their
+    /// call ids, a host should supply the result to all of them, see
+    /// `avm_interface::fan_out_call_results`. It suits hosts whose services are deterministic.
+    pub call_deduplication_enabled: bool,
+
+    /// Local services the scripts are allowed to call.
+    pub call_policy: CallPolicy,
+
+    /// Whether the interpreter is executed by Marine or natively in the host process.
+    pub interpreter_backend: InterpreterBackend,
+
+    /// Time source particle TTLs are checked against, if it's set, expired particles are
+    /// rejected with `AVMError::ParticleExpired`, otherwise TTLs aren't enforced.
+    pub clock: Option<crate::AVMClock>,
+
+    /// Metrics executions are recorded to.
+    #[cfg(feature = "metrics")]
+    pub metrics: Option<crate::AVMMetrics>,
+
     pub data_store: AVMDataStore<E>,
 }
+
+/// An interpreter backend used by the AVM.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InterpreterBackend {
+    /// The interpreter is loaded from `AVMConfig::air_wasm_path` to Marine.
+    #[default]
+    Wasm,
+
+    /// The interpreter is linked to the host, Wasm related settings are ignored and
+    /// `AVMConfig::max_heap_size` isn't supported, see `NativeAVMRunner` for details.
+    #[cfg(feature = "native")]
+    Native,
+}
+
+/// Interpreter Wasm modules of several versions hosted by the AVM, a particle is routed to
+/// the one accepting its data, so particles created under an older interpreter keep working
+/// during a rollout of an incompatible one.
+///
+/// It's used only with the Wasm interpreter backend. Interpreters preceding some of the run
+/// parameters get their prefix, so they're hosted as long as the omitted parameters don't
+/// restrict the execution, e.g. with a call policy.
+#[derive(Clone, Debug, Default)]
+pub struct HostedInterpreters {
+    /// Interpreter modules keyed by their versions, if it's empty, only the interpreter
+    /// from `AVMConfig::air_wasm_path` is used.
+    pub modules: BTreeMap<semver::Version, InterpreterModule>,
+
+    /// Interpreter version particles without data start on.
+    pub new_particle_policy: NewParticlePolicy,
+}
+
+/// An interpreter Wasm module of a particular version.
+#[derive(Clone, Debug)]
+pub struct InterpreterModule {
+    /// Path to the interpreter Wasm file.
+    pub air_wasm_path: PathBuf,
+
+    /// The oldest version of interpreters whose data the interpreter accepts,
+    /// i.e. its `air::min_supported_version`.
+    pub min_supported_version: semver::Version,
+}
+
+/// Selects an interpreter version for particles without data.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum NewParticlePolicy {
+    /// New particles start on the latest hosted version.
+    #[default]
+    Latest,
+
+    /// New particles start on the given hosted version, e.g. until the whole network
+    /// supports a newer one.
+    Pinned(semver::Version),
+}
+
+/// Describes behaviour of the AVM pool.
+#[derive(Clone, Copy, Debug)]
+pub struct AVMPoolConfig {
+    /// Number of AVM instances executing particles in parallel.
+    pub pool_size: usize,
+
+    /// Maximum number of calls running or waiting for an instance, further calls are
+    /// rejected, if it's not set, calls are never rejected.
+    pub max_pending_calls: Option<usize>,
+}
@@ -27,6 +27,7 @@ use thiserror::Error as ThisError;
 
 use std::io::Error as IOError;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, ThisError)]
 pub enum AVMError<E> {
@@ -46,6 +47,23 @@ pub enum AVMError<E> {
     /// This errors are encountered from serialization of data tracked during an anomaly.
     #[error(transparent)]
     AnomalyDataSeError(SerdeError),
+
+    /// Too many calls are running or waiting for an instance of an AVM pool.
+    #[error("AVM pool is overloaded: more than {max_pending_calls} calls are pending")]
+    PoolOverloaded { max_pending_calls: usize },
+
+    /// New particles are pinned to an interpreter version which isn't hosted.
+    #[error("interpreter version {version} new particles are pinned to isn't hosted")]
+    UnknownInterpreterVersion { version: semver::Version },
+
+    /// The particle TTL is over, it's rejected before its data is loaded, and the stored
+    /// data is removed.
+    #[error("particle {particle_id} expired at {deadline}, the current time is {now}")]
+    ParticleExpired {
+        particle_id: String,
+        deadline: u64,
+        now: u64,
+    },
 }
 
 #[derive(Debug, ThisError)]
@@ -83,4 +101,21 @@ pub enum RunnerError {
     /// Errors from auxiliary calls.
     #[error("{0}")]
     Aux(String),
+
+    /// The interpreter call was interrupted after exceeding the execution timeout.
+    #[error("interpreter execution exceeded the timeout of {timeout:?}")]
+    ExecutionTimeout { timeout: Duration },
+
+    /// The interpreter interface doesn't match the run parameters record or the passed
+    /// parameters aren't supported by it.
+    #[error("incompatible interpreter: {0}")]
+    IncompatibleInterpreter(String),
+
+    /// The native interpreter heap can't be bounded, so a memory limit isn't supported.
+    #[error("the native interpreter doesn't support a memory limit of {max_heap_size} bytes")]
+    UnsupportedMemoryLimit { max_heap_size: u64 },
+
+    /// Too many native executions abandoned after the timeout still occupy blocking threads.
+    #[error("{limit} abandoned native interpreter executions are still running")]
+    AbandonedExecutionsLimit { limit: usize },
 }
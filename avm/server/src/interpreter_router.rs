@@ -0,0 +1,318 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::config::NewParticlePolicy;
+use crate::interpreter_runner::BackendRunner;
+use crate::AVMMemoryStats;
+
+use air_interpreter_data::InterpreterDataEnvelope;
+use avm_interface::CallResultEncoding;
+use avm_interface::CallResults;
+use marine_wasm_backend_traits::WasmBackend;
+
+use std::collections::BTreeMap;
+
+/// The interpreter release binary call results were added in, older ones ignore them.
+pub(crate) const BINARY_CALL_RESULTS_VERSION: semver::Version = semver::Version::new(0, 65, 0);
+
+/// Interpreters particles are executed with.
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum InterpreterRouter<WB: WasmBackend> {
+    /// A single interpreter executing all particles.
+    Single(BackendRunner<WB>),
+
+    /// Interpreters of several versions, a particle is routed by versions of its data.
+    Versioned {
+        runners: BTreeMap<semver::Version, BackendRunner<WB>>,
+        routing: VersionRouting,
+    },
+}
+
+pub(crate) struct VersionedRunner<WB: WasmBackend> {
+    pub(crate) min_supported_version: semver::Version,
+    pub(crate) runner: BackendRunner<WB>,
+}
+
+/// Versions of hosted interpreters with the oldest data versions they accept.
+#[derive(Debug)]
+pub(crate) struct VersionRouting {
+    min_supported_versions: BTreeMap<semver::Version, semver::Version>,
+    new_particle_version: semver::Version,
+}
+
+impl<WB: WasmBackend> InterpreterRouter<WB> {
+    /// Create a router of hosted interpreters, a pinned version must be among them.
+    pub(crate) fn versioned(
+        runners: BTreeMap<semver::Version, VersionedRunner<WB>>,
+        new_particle_policy: NewParticlePolicy,
+    ) -> Self {
+        let mut min_supported_versions = BTreeMap::new();
+        let runners = runners
+            .into_iter()
+            .map(|(version, versioned)| {
+                min_supported_versions.insert(version.clone(), versioned.min_supported_version);
+                (version, versioned.runner)
+            })
+            .collect();
+
+        Self::Versioned {
+            runners,
+            routing: VersionRouting::new(min_supported_versions, new_particle_policy),
+        }
+    }
+
+    /// Select an interpreter version for a particle with the given data, see
+    /// `VersionRouting::select_version`.
+    pub(crate) fn select_version(
+        &self,
+        prev_data: &[u8],
+        current_data: &[Vec<u8>],
+    ) -> Option<semver::Version> {
+        match self {
+            Self::Single(_) => None,
+            Self::Versioned { routing, .. } => {
+                Some(routing.select_version(prev_data, current_data))
+            }
+        }
+    }
+
+    /// Return the interpreter of the version returned by `select_version`.
+    pub(crate) fn runner_mut(
+        &mut self,
+        version: Option<&semver::Version>,
+    ) -> &mut BackendRunner<WB> {
+        match (self, version) {
+            (Self::Single(runner), _) => runner,
+            (Self::Versioned { runners, .. }, Some(version)) => runners
+                .get_mut(version)
+                .expect("version is selected among the hosted ones"),
+            (Self::Versioned { .. }, None) => {
+                unreachable!("a version is always selected for hosted interpreters")
+            }
+        }
+    }
+
+    pub(crate) fn runners_mut(&mut self) -> Box<dyn Iterator<Item = &mut BackendRunner<WB>> + '_> {
+        match self {
+            Self::Single(runner) => Box::new(std::iter::once(runner)),
+            Self::Versioned { runners, .. } => Box::new(runners.values_mut()),
+        }
+    }
+
+    /// Return memory stats of every interpreter.
+    pub(crate) fn memory_stats(&self) -> Vec<AVMMemoryStats> {
+        match self {
+            Self::Single(runner) => vec![runner.memory_stats()],
+            Self::Versioned { runners, .. } => runners
+                .iter()
+                .map(|(version, runner)| AVMMemoryStats {
+                    interpreter_version: Some(version.clone()),
+                    ..runner.memory_stats()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Pass binary call results as JSON to an interpreter of the given version
+/// if it doesn't accept them; a single interpreter is considered to be up to date.
+pub(crate) fn supported_call_results(
+    version: Option<&semver::Version>,
+    mut call_results: CallResults,
+) -> CallResults {
+    if matches!(version, Some(version) if *version < BINARY_CALL_RESULTS_VERSION) {
+        for call_result in call_results.values_mut() {
+            call_result.encoding = CallResultEncoding::Json;
+        }
+    }
+    call_results
+}
+
+impl VersionRouting {
+    pub(crate) fn new(
+        min_supported_versions: BTreeMap<semver::Version, semver::Version>,
+        new_particle_policy: NewParticlePolicy,
+    ) -> Self {
+        let new_particle_version = match new_particle_policy {
+            NewParticlePolicy::Latest => min_supported_versions
+                .keys()
+                .next_back()
+                .cloned()
+                .expect("versioned router is created only for hosted interpreters"),
+            NewParticlePolicy::Pinned(version) => version,
+        };
+
+        Self {
+            min_supported_versions,
+            new_particle_version,
+        }
+    }
+
+    /// Select an interpreter version for a particle with the given data.
+    ///
+    /// Particles without data start on the version chosen by the policy, others are executed
+    /// with the latest interpreter accepting data of the oldest interpreter produced them.
+    /// If none of them does, the oldest hosted one is used to report the incompatibility.
+    pub(crate) fn select_version(
+        &self,
+        prev_data: &[u8],
+        current_data: &[Vec<u8>],
+    ) -> semver::Version {
+        let oldest_data_version = std::iter::once(prev_data)
+            .chain(current_data.iter().map(Vec::as_slice))
+            .filter(|data| !data.is_empty())
+            // malformed data is reported by the selected interpreter
+            .filter_map(|data| InterpreterDataEnvelope::try_get_versions(data).ok())
+            .map(|versions| versions.interpreter_version)
+            .min();
+
+        let Some(oldest_data_version) = oldest_data_version else {
+            return self.new_particle_version.clone();
+        };
+
+        self.min_supported_versions
+            .iter()
+            .rev()
+            .find(|(_, min_supported_version)| **min_supported_version <= oldest_data_version)
+            .or_else(|| self.min_supported_versions.iter().next())
+            .map(|(version, _)| version.clone())
+            .expect("versioned router is created only for hosted interpreters")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> semver::Version {
+        semver::Version::parse(version).unwrap()
+    }
+
+    fn data_of(interpreter_version: &str) -> Vec<u8> {
+        InterpreterDataEnvelope::new(version(interpreter_version))
+            .serialize()
+            .unwrap()
+    }
+
+    // a pre-series interpreter 0.63 accepting data of 0.40 and the latest one not accepting it
+    fn routing(new_particle_policy: NewParticlePolicy) -> VersionRouting {
+        let min_supported_versions = BTreeMap::from([
+            (version("0.63.0"), version("0.40.0")),
+            (version("0.65.0"), version("0.64.0")),
+        ]);
+        VersionRouting::new(min_supported_versions, new_particle_policy)
+    }
+
+    #[test]
+    fn new_particle_starts_on_latest() {
+        let routing = routing(NewParticlePolicy::Latest);
+
+        assert_eq!(routing.select_version(&[], &[]), version("0.65.0"));
+        assert_eq!(routing.select_version(&[], &[vec![]]), version("0.65.0"));
+    }
+
+    #[test]
+    fn new_particle_starts_on_pinned() {
+        let routing = routing(NewParticlePolicy::Pinned(version("0.63.0")));
+
+        assert_eq!(routing.select_version(&[], &[]), version("0.63.0"));
+        // the pinned version affects only new particles
+        let data = data_of("0.65.0");
+        assert_eq!(routing.select_version(&data, &[]), version("0.65.0"));
+    }
+
+    #[test]
+    fn pre_series_data_routed_to_pre_series_interpreter() {
+        let routing = routing(NewParticlePolicy::Latest);
+        let legacy_data = data_of("0.63.0");
+        let data = data_of("0.65.0");
+
+        assert_eq!(routing.select_version(&legacy_data, &[]), version("0.63.0"));
+        // the oldest data decides
+        assert_eq!(
+            routing.select_version(&data, &[legacy_data.clone()]),
+            version("0.63.0")
+        );
+        assert_eq!(
+            routing.select_version(&data, &[data.clone()]),
+            version("0.65.0")
+        );
+    }
+
+    #[test]
+    fn unsupported_data_routed_to_oldest_interpreter() {
+        let routing = routing(NewParticlePolicy::Latest);
+        let data = data_of("0.30.0");
+
+        assert_eq!(routing.select_version(&data, &[]), version("0.63.0"));
+    }
+
+    #[test]
+    fn binary_call_results_passed_as_json_to_older_interpreters() {
+        use avm_interface::CallServiceResult;
+        use serde_json::json;
+
+        let call_results = CallResults::from([
+            (1, CallServiceResult::ok_binary(json!([1, 2]))),
+            (2, CallServiceResult::ok(json!("json"))),
+        ]);
+        let encodings = |call_results: CallResults| {
+            let mut encodings = call_results
+                .into_iter()
+                .map(|(id, call_result)| (id, call_result.encoding))
+                .collect::<Vec<_>>();
+            encodings.sort_by_key(|(id, _)| *id);
+            encodings
+        };
+
+        let older = supported_call_results(Some(&version("0.64.1")), call_results.clone());
+        assert_eq!(
+            encodings(older),
+            [(1, CallResultEncoding::Json), (2, CallResultEncoding::Json)]
+        );
+
+        let binary = [
+            (1, CallResultEncoding::Binary),
+            (2, CallResultEncoding::Json),
+        ];
+        let latest = supported_call_results(Some(&version("0.65.0")), call_results.clone());
+        assert_eq!(encodings(latest), binary);
+        let single = supported_call_results(None, call_results);
+        assert_eq!(encodings(single), binary);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn binary_call_results_version_is_released() {
+        // the interpreter built with this server accepts binary call results
+        assert!(*air::interpreter_version() >= BINARY_CALL_RESULTS_VERSION);
+    }
+
+    #[test]
+    fn malformed_data_ignored() {
+        let routing = routing(NewParticlePolicy::Pinned(version("0.63.0")));
+        let data = data_of("0.65.0");
+
+        assert_eq!(routing.select_version(b"garbage", &[]), version("0.63.0"));
+        assert_eq!(
+            routing.select_version(b"garbage", &[data]),
+            version("0.65.0")
+        );
+    }
+}
@@ -0,0 +1,242 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::avm_runner::AVMRunner;
+#[cfg(feature = "native")]
+use crate::native_runner::NativeAVMRunner;
+use crate::runner::KeyArgs;
+use crate::AVMMemoryStats;
+use crate::RunnerError;
+use crate::RunnerResult;
+
+use air_interpreter_interface::CallPolicy;
+use air_interpreter_interface::CallPolicyRepr;
+use air_interpreter_interface::SerializedCallPolicy;
+use air_interpreter_interface::TrustPolicy;
+use air_interpreter_sede::ToSerialized;
+use avm_interface::raw_outcome::RawAVMOutcome;
+use avm_interface::CallResults;
+use fluence_keypair::KeyFormat;
+use fluence_keypair::KeyPair;
+use fluence_keypair::PublicKey;
+use marine_wasm_backend_traits::WasmBackend;
+
+use std::future::Future;
+use std::ops::Deref;
+use std::ops::DerefMut;
+use std::pin::Pin;
+
+/// A future returned by `InterpreterRunner` calls.
+pub type RunnerFuture<'runner> =
+    Pin<Box<dyn Future<Output = RunnerResult<RawAVMOutcome>> + 'runner>>;
+
+/// An interpreter backend, `AVM` dispatches particles to the one selected in `AVMConfig`.
+///
+/// `AVMRunner` executes the interpreter in Marine and `NativeAVMRunner` in the host process.
+pub trait InterpreterRunner {
+    /// Settings passed to the interpreter with every call.
+    fn settings_mut(&mut self) -> &mut RunnerSettings;
+
+    /// Execute AIR with the current data, several ones are executed at once and the result
+    /// is the same as of sequential calls with each of them.
+    ///
+    /// Data produced with `CallSigner::Host` isn't signed, the outcome contains a signing
+    /// payload the host should sign and pass to `finalize_signing` instead.
+    #[allow(clippy::too_many_arguments)]
+    fn execute<'runner>(
+        &'runner mut self,
+        air: String,
+        prev_data: Vec<u8>,
+        data: Vec<Vec<u8>>,
+        init_peer_id: String,
+        timestamp: u64,
+        ttl: u32,
+        current_peer_id: String,
+        call_results: CallResults,
+        signer: CallSigner<'runner>,
+        particle_id: String,
+        particle_signature: Vec<u8>,
+    ) -> RunnerFuture<'runner>;
+
+    /// Put the host signature of a signing payload returned by `execute` into the data.
+    fn finalize_signing<'runner>(
+        &'runner mut self,
+        data: Vec<u8>,
+        current_peer_id: String,
+        public_key: &'runner PublicKey,
+        signature: Vec<u8>,
+        particle_id: String,
+        particle_signature: Vec<u8>,
+    ) -> RunnerFuture<'runner>;
+
+    fn memory_stats(&self) -> AVMMemoryStats;
+
+    /// Execute AIR with a single current data signing the produced data with the key pair.
+    #[allow(clippy::too_many_arguments)]
+    fn call<'runner>(
+        &'runner mut self,
+        air: impl Into<String>,
+        prev_data: impl Into<Vec<u8>>,
+        data: impl Into<Vec<u8>>,
+        init_peer_id: impl Into<String>,
+        timestamp: u64,
+        ttl: u32,
+        current_peer_id: impl Into<String>,
+        call_results: CallResults,
+        keypair: &'runner KeyPair,
+        particle_id: String,
+        particle_signature: Vec<u8>,
+    ) -> RunnerFuture<'runner>
+    where
+        Self: Sized,
+    {
+        self.execute(
+            air.into(),
+            prev_data.into(),
+            vec![data.into()],
+            init_peer_id.into(),
+            timestamp,
+            ttl,
+            current_peer_id.into(),
+            call_results,
+            CallSigner::KeyPair(keypair),
+            particle_id,
+            particle_signature,
+        )
+    }
+}
+
+/// Signs data produced by the interpreter.
+#[derive(Clone, Copy)]
+pub enum CallSigner<'key> {
+    /// The interpreter signs the data with the key pair.
+    KeyPair(&'key KeyPair),
+    /// The host signs the data with the secret key of the public key.
+    Host(&'key PublicKey),
+}
+
+impl CallSigner<'_> {
+    /// Key material passed to the interpreter.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn key_args(self) -> RunnerResult<KeyArgs> {
+        match self {
+            // we use secret() for compatibility with JS client that doesn't have keypair type,
+            // it can serialize a secret key only
+            Self::KeyPair(keypair) => Ok(KeyArgs {
+                key_format: keypair.key_format().into(),
+                secret_key_bytes: keypair.secret().map_err(RunnerError::KeyError)?,
+                signer_public_key: vec![],
+            }),
+            Self::Host(public_key) => Ok(KeyArgs {
+                key_format: public_key.get_key_format().into(),
+                secret_key_bytes: vec![],
+                signer_public_key: public_key.encode(),
+            }),
+        }
+    }
+}
+
+/// Settings a host passes to the interpreter with every call.
+#[derive(Debug, Clone, Default)]
+pub struct RunnerSettings {
+    /// Key formats passed to the interpreter, empty for its default whitelist
+    pub(crate) allowed_key_formats: Vec<u8>,
+    /// Peers and keys the host accepts data from
+    pub(crate) trust_policy: TrustPolicy,
+    /// Whether call requests should contain provenance chains of their arguments
+    pub(crate) call_provenance_enabled: bool,
+    /// Whether identical call requests of a run should be emitted once
+    pub(crate) call_deduplication_enabled: bool,
+    /// Serialized policy of local service calls
+    pub(crate) call_policy: SerializedCallPolicy,
+}
+
+impl RunnerSettings {
+    /// Set key algorithms allowed for the current peer key and in other peers' signatures.
+    ///
+    /// An empty set stands for the interpreter default, that is Ed25519 only.
+    pub fn set_allowed_key_formats(&mut self, key_formats: impl IntoIterator<Item = KeyFormat>) {
+        self.allowed_key_formats = key_formats.into_iter().map(Into::into).collect();
+    }
+
+    /// Set peers and keys the host accepts data from.
+    pub fn set_trust_policy(&mut self, trust_policy: TrustPolicy) {
+        self.trust_policy = trust_policy;
+    }
+
+    /// Make call requests contain provenance chains of their arguments.
+    pub fn set_call_provenance_enabled(&mut self, enabled: bool) {
+        self.call_provenance_enabled = enabled;
+    }
+
+    /// Make identical call requests of a run be emitted once with a fan-out list of their
+    /// call ids, see `avm_interface::fan_out_call_results`.
+    pub fn set_call_deduplication_enabled(&mut self, enabled: bool) {
+        self.call_deduplication_enabled = enabled;
+    }
+
+    /// Set local services the scripts are allowed to call.
+    pub fn set_call_policy(&mut self, call_policy: &CallPolicy) {
+        self.call_policy = if call_policy.is_empty() {
+            <_>::default()
+        } else {
+            CallPolicyRepr
+                .serialize(call_policy)
+                .expect("the default serializer shouldn't fail")
+        };
+    }
+
+    /// Settings of `finalize_signing`, it doesn't execute the script, so it gets no call settings.
+    pub(crate) fn for_signing(&self) -> Self {
+        Self {
+            allowed_key_formats: self.allowed_key_formats.clone(),
+            trust_policy: self.trust_policy.clone(),
+            ..<_>::default()
+        }
+    }
+}
+
+/// A runner of the interpreter backend selected in `AVMConfig`.
+pub(crate) enum BackendRunner<WB: WasmBackend> {
+    Wasm(AVMRunner<WB>),
+    #[cfg(feature = "native")]
+    Native(NativeAVMRunner),
+}
+
+impl<WB: WasmBackend> Deref for BackendRunner<WB> {
+    type Target = dyn InterpreterRunner;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Wasm(runner) => runner,
+            #[cfg(feature = "native")]
+            Self::Native(runner) => runner,
+        }
+    }
+}
+
+impl<WB: WasmBackend> DerefMut for BackendRunner<WB> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Self::Wasm(runner) => runner,
+            #[cfg(feature = "native")]
+            Self::Native(runner) => runner,
+        }
+    }
+}
@@ -28,13 +28,46 @@
 )]
 
 mod avm;
+mod clock;
 mod config;
 mod errors;
+mod interpreter_router;
+mod interpreter_runner;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "native")]
+mod native_allocator;
+#[cfg(feature = "native")]
+mod native_runner;
+mod pool;
 mod runner;
 
+pub use avm::UnsignedOutcome;
 pub use avm::AVM;
+pub use clock::particle_deadline;
+pub use clock::reject_expired;
+pub use clock::AVMClock;
+pub use clock::Clock;
+pub use clock::SystemClock;
+pub use clock::VirtualClock;
 pub use config::AVMConfig;
+pub use config::AVMPoolConfig;
+pub use config::HostedInterpreters;
+pub use config::InterpreterBackend;
+pub use config::InterpreterModule;
+pub use config::NewParticlePolicy;
 pub use errors::AVMError;
+pub use interpreter_runner::CallSigner;
+pub use interpreter_runner::InterpreterRunner;
+pub use interpreter_runner::RunnerFuture;
+pub use interpreter_runner::RunnerSettings;
+#[cfg(feature = "metrics")]
+pub use metrics::AVMMetrics;
+#[cfg(feature = "native")]
+pub use native_allocator::NativeAllocator;
+#[cfg(feature = "native")]
+pub use native_runner::NativeAVMRunner;
+pub use pool::AVMPool;
 pub use runner::AVMMemoryStats;
 pub use runner::AVMRuntimeLimits;
 pub use runner::AquaVMRuntimeLimits;
@@ -42,6 +75,9 @@ pub use runner::AquaVMRuntimeLimits;
 pub use avm_interface::*;
 
 pub mod avm_runner {
+    pub use crate::interpreter_runner::CallSigner;
+    pub use crate::interpreter_runner::InterpreterRunner;
+    pub use crate::interpreter_runner::RunnerSettings;
     pub use crate::runner::AVMRunner;
     pub use avm_interface::raw_outcome::RawAVMOutcome;
 }
@@ -57,9 +93,13 @@ pub use marine::IValue;
 pub use polyplets::SecurityTetraplet;
 
 pub use avm_data_store::AnomalyData;
+pub use avm_data_store::AnomalyDetectionFuture;
+pub use avm_data_store::AsyncDataStore;
 pub use avm_data_store::DataStore;
+pub use avm_data_store::DataStoreFuture;
 
-pub type AVMDataStore<E> = Box<dyn DataStore<Error = E> + Send + Sync + 'static>;
+/// A data store of the AVM, both `DataStore` and `AsyncDataStore` implementations fit it.
+pub type AVMDataStore<E> = Box<dyn AsyncDataStore<Error = E> + Send + Sync + 'static>;
 
 pub type AVMResult<T, E> = std::result::Result<T, AVMError<E>>;
 
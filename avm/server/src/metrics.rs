@@ -0,0 +1,279 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use avm_interface::raw_outcome::RawAVMOutcome;
+use prometheus_client::encoding::EncodeLabelSet;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::histogram::exponential_buckets;
+use prometheus_client::metrics::histogram::Histogram;
+use prometheus_client::registry::Registry;
+use prometheus_client::registry::Unit;
+
+use std::time::Duration;
+
+/// Metrics of particle executions by the AVM.
+///
+/// They're registered in a host registry, which could be rendered in the OpenMetrics text
+/// format with `prometheus_client::encoding::text::encode`. Clones share the same metrics,
+/// so several AVMs could report to them.
+#[derive(Clone)]
+pub struct AVMMetrics {
+    executions: Counter,
+    execution_time: Histogram,
+    memory_delta: Histogram,
+    input_data_size: Histogram,
+    output_data_size: Histogram,
+    call_requests: Counter,
+    soft_limits_triggered: Family<SoftLimitLabels, Counter>,
+    interpreter_errors: Family<RetCodeLabels, Counter>,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct SoftLimitLabels {
+    limit: &'static str,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct RetCodeLabels {
+    ret_code: i64,
+}
+
+impl AVMMetrics {
+    /// Create metrics and register them with the `avm` prefix.
+    pub fn new(registry: &mut Registry) -> Self {
+        let registry = registry.sub_registry_with_prefix("avm");
+
+        let executions = Counter::default();
+        registry.register(
+            "executions",
+            "Number of particle executions",
+            executions.clone(),
+        );
+
+        let execution_time = Histogram::new(exponential_buckets(0.001, 2.0, 15));
+        registry.register_with_unit(
+            "execution_time",
+            "Time of interpreter execution",
+            Unit::Seconds,
+            execution_time.clone(),
+        );
+
+        let memory_delta = Histogram::new(exponential_buckets(1024.0, 4.0, 10));
+        registry.register_with_unit(
+            "memory_delta",
+            "Growth of the interpreter memory during execution",
+            Unit::Bytes,
+            memory_delta.clone(),
+        );
+
+        let input_data_size = Histogram::new(exponential_buckets(1024.0, 4.0, 10));
+        registry.register_with_unit(
+            "input_data_size",
+            "Total size of previous and current data passed to the interpreter",
+            Unit::Bytes,
+            input_data_size.clone(),
+        );
+
+        let output_data_size = Histogram::new(exponential_buckets(1024.0, 4.0, 10));
+        registry.register_with_unit(
+            "output_data_size",
+            "Size of data produced by the interpreter",
+            Unit::Bytes,
+            output_data_size.clone(),
+        );
+
+        let call_requests = Counter::default();
+        registry.register(
+            "call_requests",
+            "Number of call requests emitted by the interpreter",
+            call_requests.clone(),
+        );
+
+        let soft_limits_triggered = Family::default();
+        registry.register(
+            "soft_limits_triggered",
+            "Number of executions exceeded a soft limit, by the limit",
+            soft_limits_triggered.clone(),
+        );
+
+        let interpreter_errors = Family::default();
+        registry.register(
+            "interpreter_errors",
+            "Number of failed executions, by the interpreter ret_code",
+            interpreter_errors.clone(),
+        );
+
+        Self {
+            executions,
+            execution_time,
+            memory_delta,
+            input_data_size,
+            output_data_size,
+            call_requests,
+            soft_limits_triggered,
+            interpreter_errors,
+        }
+    }
+
+    pub(crate) fn observe_execution(
+        &self,
+        input_data_size: usize,
+        outcome: &RawAVMOutcome,
+        memory_delta: usize,
+        execution_time: Duration,
+    ) {
+        self.executions.inc();
+        self.execution_time.observe(execution_time.as_secs_f64());
+        self.memory_delta.observe(memory_delta as f64);
+        self.input_data_size.observe(input_data_size as f64);
+        self.output_data_size.observe(outcome.data.len() as f64);
+        self.call_requests
+            .inc_by(outcome.call_requests.len() as u64);
+
+        let soft_limits = &outcome.soft_limits_triggering;
+        let triggered = [
+            ("air_size", soft_limits.air_size_limit_exceeded),
+            ("particle_size", soft_limits.particle_size_limit_exceeded),
+            (
+                "call_result_size",
+                soft_limits.call_result_size_limit_exceeded,
+            ),
+        ];
+        for (limit, _) in triggered.into_iter().filter(|(_, exceeded)| *exceeded) {
+            self.soft_limits_triggered
+                .get_or_create(&SoftLimitLabels { limit })
+                .inc();
+        }
+
+        if outcome.ret_code != air_interpreter_interface::INTERPRETER_SUCCESS {
+            self.interpreter_errors
+                .get_or_create(&RetCodeLabels {
+                    ret_code: outcome.ret_code,
+                })
+                .inc();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use air_interpreter_interface::SoftLimitsTriggering;
+    use avm_interface::CallRequestParams;
+    use prometheus_client::encoding::text::encode;
+
+    fn outcome(ret_code: i64, data_size: usize) -> RawAVMOutcome {
+        RawAVMOutcome::with_data(ret_code, vec![0; data_size])
+    }
+
+    fn render(registry: &Registry) -> String {
+        let mut text = String::new();
+        encode(&mut text, registry).unwrap();
+        text
+    }
+
+    #[test]
+    fn executions_are_recorded() {
+        let mut registry = Registry::default();
+        let metrics = AVMMetrics::new(&mut registry);
+
+        metrics.observe_execution(2000, &outcome(0, 3000), 5000, Duration::from_millis(3));
+        metrics.observe_execution(100, &outcome(0, 200), 0, Duration::from_millis(1));
+
+        let text = render(&registry);
+        assert!(text.contains("avm_executions_total 2\n"), "{text}");
+        assert!(
+            text.contains("avm_execution_time_seconds_count 2\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("avm_memory_delta_bytes_sum 5000.0\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("avm_input_data_size_bytes_sum 2100.0\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("avm_output_data_size_bytes_sum 3200.0\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("avm_output_data_size_bytes_bucket{le=\"4096.0\"} 2\n"),
+            "{text}"
+        );
+        assert!(text.contains("avm_call_requests_total 0\n"), "{text}");
+        assert!(text.ends_with("# EOF\n"), "{text}");
+    }
+
+    #[test]
+    fn errors_are_grouped_by_ret_code() {
+        let mut registry = Registry::default();
+        let metrics = AVMMetrics::new(&mut registry);
+
+        metrics.observe_execution(0, &outcome(10001, 0), 0, Duration::ZERO);
+        metrics.observe_execution(0, &outcome(10001, 0), 0, Duration::ZERO);
+        metrics.observe_execution(0, &outcome(20000, 0), 0, Duration::ZERO);
+        metrics.observe_execution(0, &outcome(0, 0), 0, Duration::ZERO);
+
+        let text = render(&registry);
+        assert!(
+            text.contains("avm_interpreter_errors_total{ret_code=\"10001\"} 2\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("avm_interpreter_errors_total{ret_code=\"20000\"} 1\n"),
+            "{text}"
+        );
+        assert!(!text.contains("ret_code=\"0\""), "{text}");
+    }
+
+    #[test]
+    fn soft_limits_and_call_requests_are_counted() {
+        let mut registry = Registry::default();
+        let metrics = AVMMetrics::new(&mut registry);
+
+        let mut outcome = outcome(0, 0);
+        outcome.soft_limits_triggering = SoftLimitsTriggering {
+            air_size_limit_exceeded: true,
+            particle_size_limit_exceeded: false,
+            call_result_size_limit_exceeded: true,
+        };
+        let call_request = CallRequestParams::new("service", "function", vec![], vec![]);
+        outcome.call_requests = maplit::hashmap! {
+            1 => call_request.clone(),
+            2 => call_request,
+        };
+        metrics.observe_execution(0, &outcome, 0, Duration::ZERO);
+
+        let text = render(&registry);
+        assert!(
+            text.contains("avm_soft_limits_triggered_total{limit=\"air_size\"} 1\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("avm_soft_limits_triggered_total{limit=\"call_result_size\"} 1\n"),
+            "{text}"
+        );
+        assert!(!text.contains("limit=\"particle_size\""), "{text}");
+        assert!(text.contains("avm_call_requests_total 2\n"), "{text}");
+    }
+}
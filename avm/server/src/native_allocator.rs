@@ -0,0 +1,154 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::cell::Cell;
+
+thread_local! {
+    /// Bytes allocated by the current thread, deallocations of memory allocated by
+    /// other threads make it wrap around, so only differences of it are meaningful
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+    /// The highest value of `ALLOCATED` since the last `track_peak_allocation`
+    static PEAK_ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A global allocator counting allocated bytes for memory stats of the native AVM runner.
+///
+/// The runner executes the interpreter in the host process, so it can't measure the
+/// interpreter heap by itself, a host should install this allocator to get the stats:
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: avm_server::NativeAllocator = avm_server::NativeAllocator::system();
+/// ```
+/// Allocations are counted per thread, so executions of other threads aren't
+/// attributed to the interpreter.
+pub struct NativeAllocator<A = System> {
+    inner: A,
+}
+
+impl NativeAllocator<System> {
+    pub const fn system() -> Self {
+        Self::new(System)
+    }
+}
+
+impl<A> NativeAllocator<A> {
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for NativeAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            on_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        on_dealloc(layout.size());
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            on_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            on_dealloc(layout.size());
+            on_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+// thread locals without destructors are accessible even while a thread exits,
+// but a failed access is ignored anyway, an allocator must not panic
+fn on_alloc(size: usize) {
+    let _ = ALLOCATED.try_with(|allocated| {
+        let new_allocated = allocated.get().wrapping_add(size);
+        allocated.set(new_allocated);
+
+        let _ = PEAK_ALLOCATED.try_with(|peak_allocated| {
+            if new_allocated.wrapping_sub(peak_allocated.get()) as isize > 0 {
+                peak_allocated.set(new_allocated);
+            }
+        });
+    });
+}
+
+fn on_dealloc(size: usize) {
+    let _ = ALLOCATED.try_with(|allocated| allocated.set(allocated.get().wrapping_sub(size)));
+}
+
+/// Execute the closure and return the highest number of bytes it had allocated at once on
+/// the current thread, it's zero if `NativeAllocator` isn't installed.
+pub(crate) fn track_peak_allocation<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let start = ALLOCATED.with(Cell::get);
+    PEAK_ALLOCATED.with(|peak_allocated| peak_allocated.set(start));
+
+    let result = f();
+
+    let peak = PEAK_ALLOCATED.with(Cell::get);
+    (result, peak.wrapping_sub(start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::hint::black_box;
+
+    #[global_allocator]
+    static ALLOCATOR: NativeAllocator = NativeAllocator::system();
+
+    const MB: usize = 1 << 20;
+
+    #[test]
+    fn peak_allocation() {
+        let ((), peak) = track_peak_allocation(|| {
+            drop(black_box(vec![0u8; MB]));
+            black_box(vec![0u8; 1024]);
+        });
+
+        assert!((MB..2 * MB).contains(&peak), "{peak}");
+    }
+
+    #[test]
+    fn allocations_of_other_threads_are_not_tracked() {
+        let (data, peak) = track_peak_allocation(|| {
+            std::thread::spawn(|| black_box(vec![0u8; MB]))
+                .join()
+                .unwrap()
+        });
+        drop(data);
+
+        assert!(peak < MB, "{peak}");
+    }
+}
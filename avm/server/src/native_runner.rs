@@ -0,0 +1,383 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::interpreter_runner::CallSigner;
+use crate::interpreter_runner::InterpreterRunner;
+use crate::interpreter_runner::RunnerFuture;
+use crate::interpreter_runner::RunnerSettings;
+use crate::runner::prepare_run_parameters;
+use crate::runner::serialize_call_results;
+use crate::AVMMemoryStats;
+use crate::AVMRuntimeLimits;
+use crate::AquaVMRuntimeLimits;
+use crate::RunnerError;
+use crate::RunnerResult;
+
+use avm_interface::raw_outcome::RawAVMOutcome;
+use avm_interface::CallResults;
+use fluence_keypair::PublicKey;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The default number of abandoned executions a runner tolerates, each of them occupies
+/// a blocking thread of the Tokio runtime until it finishes.
+const MAX_ABANDONED_EXECUTIONS: usize = 16;
+
+/// Executes the interpreter in the host process instead of Marine, it follows the
+/// `AVMRunner` call contract.
+///
+/// The interpreter is executed on a blocking thread of the Tokio runtime, so the runner has
+/// to be used within it. Memory stats are available only if `NativeAllocator` is installed
+/// as the global allocator.
+pub struct NativeAVMRunner {
+    /// The highest heap size of executions, like a Wasm memory size, it never decreases
+    heap_size: usize,
+    /// This struct contains runtime RAM allowance.
+    aquavm_runtime_limits: AquaVMRuntimeLimits,
+    /// Wall-clock time budget of a single interpreter call
+    execution_timeout: Option<Duration>,
+    /// Settings passed to the interpreter with every call
+    settings: RunnerSettings,
+    /// Held by each running execution, the extra references are of abandoned ones
+    executions: Arc<()>,
+    /// Number of abandoned executions after which the runner refuses new ones
+    abandoned_executions_limit: usize,
+}
+
+impl NativeAVMRunner {
+    /// Create a native runner with the provided limits.
+    ///
+    /// The interpreter heap can't be bounded in the host process, so a memory limit is
+    /// rejected with `RunnerError::UnsupportedMemoryLimit`.
+    #[allow(clippy::result_large_err)]
+    pub fn new(
+        total_memory_limit: Option<u64>,
+        avm_runtime_limits: AVMRuntimeLimits,
+    ) -> RunnerResult<Self> {
+        if let Some(max_heap_size) = total_memory_limit {
+            return Err(RunnerError::UnsupportedMemoryLimit { max_heap_size });
+        }
+
+        let execution_timeout = avm_runtime_limits.execution_timeout;
+        let runner = Self {
+            heap_size: 0,
+            aquavm_runtime_limits: avm_runtime_limits.into(),
+            execution_timeout,
+            settings: <_>::default(),
+            executions: <_>::default(),
+            abandoned_executions_limit: MAX_ABANDONED_EXECUTIONS,
+        };
+
+        Ok(runner)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(skip_all)]
+    async fn call_impl(
+        &mut self,
+        air: String,
+        prev_data: Vec<u8>,
+        data: Vec<Vec<u8>>,
+        init_peer_id: String,
+        timestamp: u64,
+        ttl: u32,
+        current_peer_id: String,
+        call_results: CallResults,
+        signer: CallSigner<'_>,
+        particle_id: String,
+        particle_signature: Vec<u8>,
+    ) -> RunnerResult<RawAVMOutcome> {
+        let run_parameters = prepare_run_parameters(
+            current_peer_id,
+            init_peer_id,
+            timestamp,
+            ttl,
+            self.aquavm_runtime_limits,
+            signer.key_args()?,
+            &self.settings,
+            particle_id,
+            particle_signature,
+        );
+        let call_results = serialize_call_results(call_results);
+
+        let outcome = self
+            .execute_blocking(move || match <[Vec<u8>; 1]>::try_from(data) {
+                Ok([data]) => air::execute_air(air, prev_data, data, run_parameters, call_results),
+                Err(data) => {
+                    air::execute_air_multi(air, prev_data, data, run_parameters, call_results)
+                }
+            })
+            .await?;
+        let outcome = RawAVMOutcome::from_interpreter_outcome(outcome)?;
+
+        Ok(outcome)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn finalize_signing_impl(
+        &mut self,
+        data: Vec<u8>,
+        current_peer_id: String,
+        public_key: &PublicKey,
+        signature: Vec<u8>,
+        particle_id: String,
+        particle_signature: Vec<u8>,
+    ) -> RunnerResult<RawAVMOutcome> {
+        let run_parameters = prepare_run_parameters(
+            current_peer_id,
+            String::new(),
+            0,
+            0,
+            self.aquavm_runtime_limits,
+            CallSigner::Host(public_key).key_args()?,
+            &self.settings.for_signing(),
+            particle_id,
+            particle_signature,
+        );
+
+        let outcome = self
+            .execute_blocking(move || air::finalize_signing(data, run_parameters, signature))
+            .await?;
+        let outcome = RawAVMOutcome::from_interpreter_outcome(outcome)?;
+
+        Ok(outcome)
+    }
+
+    /// Execute the interpreter on a blocking thread within the execution timeout.
+    ///
+    /// A native execution can't be interrupted, so after the timeout it's left to finish
+    /// in the background and its result is dropped. New executions are refused while
+    /// the limit of abandoned ones is reached, so they can't exhaust the blocking threads.
+    async fn execute_blocking<T: Send + 'static>(
+        &mut self,
+        execute: impl FnOnce() -> T + Send + 'static,
+    ) -> RunnerResult<T> {
+        let limit = self.abandoned_executions_limit;
+        if self.abandoned_executions() >= limit {
+            return Err(RunnerError::AbandonedExecutionsLimit { limit });
+        }
+
+        let execution_guard = self.executions.clone();
+        let execution = tokio::task::spawn_blocking(move || {
+            let _execution_guard = execution_guard;
+            crate::native_allocator::track_peak_allocation(execute)
+        });
+
+        let result = match self.execution_timeout {
+            None => execution.await,
+            Some(timeout) => match tokio::time::timeout(timeout, execution).await {
+                Ok(result) => result,
+                Err(_) => {
+                    log::warn!("native interpreter execution is abandoned after {timeout:?}");
+                    return Err(RunnerError::ExecutionTimeout { timeout });
+                }
+            },
+        };
+        // the task is never aborted, so it fails only if the interpreter panics
+        let (output, peak_allocation) =
+            result.unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic()));
+        self.heap_size = self.heap_size.max(peak_allocation);
+
+        Ok(output)
+    }
+
+    /// Number of abandoned executions which are still running.
+    fn abandoned_executions(&self) -> usize {
+        Arc::strong_count(&self.executions) - 1
+    }
+}
+
+impl InterpreterRunner for NativeAVMRunner {
+    fn settings_mut(&mut self) -> &mut RunnerSettings {
+        &mut self.settings
+    }
+
+    fn execute<'runner>(
+        &'runner mut self,
+        air: String,
+        prev_data: Vec<u8>,
+        data: Vec<Vec<u8>>,
+        init_peer_id: String,
+        timestamp: u64,
+        ttl: u32,
+        current_peer_id: String,
+        call_results: CallResults,
+        signer: CallSigner<'runner>,
+        particle_id: String,
+        particle_signature: Vec<u8>,
+    ) -> RunnerFuture<'runner> {
+        Box::pin(self.call_impl(
+            air,
+            prev_data,
+            data,
+            init_peer_id,
+            timestamp,
+            ttl,
+            current_peer_id,
+            call_results,
+            signer,
+            particle_id,
+            particle_signature,
+        ))
+    }
+
+    fn finalize_signing<'runner>(
+        &'runner mut self,
+        data: Vec<u8>,
+        current_peer_id: String,
+        public_key: &'runner PublicKey,
+        signature: Vec<u8>,
+        particle_id: String,
+        particle_signature: Vec<u8>,
+    ) -> RunnerFuture<'runner> {
+        Box::pin(self.finalize_signing_impl(
+            data,
+            current_peer_id,
+            public_key,
+            signature,
+            particle_id,
+            particle_signature,
+        ))
+    }
+
+    fn memory_stats(&self) -> AVMMemoryStats {
+        AVMMemoryStats {
+            memory_size: self.heap_size,
+            total_memory_limit: None,
+            allocation_rejects: None,
+            interpreter_version: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::tests::long_running_air;
+
+    use fluence_keypair::KeyPair;
+
+    async fn call(runner: &mut NativeAVMRunner, air: &str) -> RunnerResult<RawAVMOutcome> {
+        let keypair = KeyPair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id().to_string();
+
+        runner
+            .call(
+                air,
+                vec![],
+                vec![],
+                peer_id.clone(),
+                0,
+                u32::MAX,
+                peer_id,
+                <_>::default(),
+                &keypair,
+                "particle".to_string(),
+                b"particle_signature".to_vec(),
+            )
+            .await
+    }
+
+    #[test]
+    fn memory_limit_is_rejected() {
+        let result = NativeAVMRunner::new(Some(1 << 20), <_>::default());
+
+        assert!(matches!(
+            result,
+            Err(RunnerError::UnsupportedMemoryLimit {
+                max_heap_size: 1048576
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn runtime_limits_are_applied() {
+        let limits = AVMRuntimeLimits {
+            air_size_limit: Some(1),
+            ..<_>::default()
+        };
+        let mut runner = NativeAVMRunner::new(None, limits).unwrap();
+
+        let outcome = call(&mut runner, "(null)").await.unwrap();
+
+        assert!(outcome.soft_limits_triggering.air_size_limit_exceeded);
+    }
+
+    #[tokio::test]
+    async fn memory_stats_of_executions() {
+        let mut runner = NativeAVMRunner::new(None, <_>::default()).unwrap();
+        assert_eq!(runner.memory_stats().memory_size, 0);
+
+        call(&mut runner, "(null)").await.unwrap();
+        let memory_size = runner.memory_stats().memory_size;
+        // the test binary installs `NativeAllocator`
+        assert!(memory_size > 0);
+
+        call(&mut runner, "(null)").await.unwrap();
+        assert!(runner.memory_stats().memory_size >= memory_size);
+    }
+
+    #[tokio::test]
+    async fn execution_timeout() {
+        let timeout = Duration::from_millis(50);
+        let limits = AVMRuntimeLimits::default().with_execution_timeout(timeout);
+        let mut runner = NativeAVMRunner::new(None, limits).unwrap();
+
+        let result = call(&mut runner, &long_running_air()).await;
+
+        assert!(
+            matches!(result, Err(RunnerError::ExecutionTimeout { timeout: t }) if t == timeout),
+            "{result:?}"
+        );
+        // the runner keeps working after an abandoned execution
+        assert_eq!(call(&mut runner, "(null)").await.unwrap().ret_code, 0);
+    }
+
+    #[tokio::test]
+    async fn abandoned_executions_are_limited() {
+        let timeout = Duration::from_millis(50);
+        let limits = AVMRuntimeLimits::default().with_execution_timeout(timeout);
+        let mut runner = NativeAVMRunner::new(None, limits).unwrap();
+        runner.abandoned_executions_limit = 1;
+
+        let (sender, receiver) = std::sync::mpsc::channel::<()>();
+        let result = runner.execute_blocking(move || receiver.recv()).await;
+        assert!(
+            matches!(result, Err(RunnerError::ExecutionTimeout { .. })),
+            "{result:?}"
+        );
+
+        let result = call(&mut runner, "(null)").await;
+        assert!(
+            matches!(
+                result,
+                Err(RunnerError::AbandonedExecutionsLimit { limit: 1 })
+            ),
+            "{result:?}"
+        );
+
+        // the runner accepts executions again once the abandoned one finishes
+        sender.send(()).unwrap();
+        while runner.abandoned_executions() > 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(call(&mut runner, "(null)").await.unwrap().ret_code, 0);
+    }
+}
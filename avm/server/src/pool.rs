@@ -0,0 +1,791 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::config::AVMConfig;
+use crate::config::AVMPoolConfig;
+use crate::AVMDataStore;
+use crate::AVMError;
+use crate::AVMMemoryStats;
+use crate::AVMResult;
+use crate::AVM;
+
+use avm_data_store::AnomalyData;
+use avm_data_store::AnomalyDetectionFuture;
+use avm_data_store::AsyncDataStore;
+use avm_data_store::DataStoreFuture;
+use avm_interface::raw_outcome::RawAVMOutcome;
+use avm_interface::AVMOutcome;
+use avm_interface::CallResults;
+use avm_interface::ParticleParameters;
+use fluence_keypair::KeyPair;
+use marine_wasm_backend_traits::WasmBackend;
+use parking_lot::Mutex;
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::sync::MutexGuard as AsyncMutexGuard;
+use tokio::sync::OwnedMutexGuard;
+use tokio::sync::Semaphore;
+
+use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+type ParticleLocks = Mutex<HashMap<String, ParticleLock>>;
+
+/// A pool of AVM instances executing different particles in parallel.
+///
+/// All instances share one data store, and calls of the same particle are executed one by one
+/// in the order they were made. The instances access the store concurrently if it can be
+/// shared, see `DataStore::share`, otherwise its operations are executed one by one.
+pub struct AVMPool<E, WB: WasmBackend> {
+    instances: Vec<PoolInstance<E, WB>>,
+    idle_instances: Mutex<Vec<usize>>,
+    idle_permits: Semaphore,
+    particle_locks: ParticleLocks,
+    pending_calls: AtomicUsize,
+    max_pending_calls: Option<usize>,
+    data_store: Arc<AsyncMutex<AVMDataStore<E>>>,
+}
+
+struct PoolInstance<E, WB: WasmBackend> {
+    avm: AsyncMutex<AVM<E, WB>>,
+    /// Memory stats after the last call, it's kept to report stats of busy instances.
+    memory_stats: Mutex<Vec<AVMMemoryStats>>,
+}
+
+impl<E: 'static, WB: WasmBackend> AVMPool<E, WB> {
+    /// Create a pool of `pool_config.pool_size` AVMs with the provided config.
+    #[allow(clippy::result_large_err)]
+    pub async fn new(
+        config: AVMConfig<E>,
+        pool_config: AVMPoolConfig,
+        wasm_backend: WB,
+    ) -> AVMResult<Self, E> {
+        let AVMConfig {
+            air_wasm_path,
+            hosted_interpreters,
+            max_heap_size,
+            logging_mask,
+            execution_timeout,
+            allowed_key_formats,
+            trust_policy,
+            call_provenance_enabled,
+            call_deduplication_enabled,
+            call_policy,
+            interpreter_backend,
+            clock,
+            #[cfg(feature = "metrics")]
+            metrics,
+            mut data_store,
+        } = config;
+
+        data_store.initialize().await?;
+
+        let pool_size = pool_config.pool_size.max(1);
+        let instance_data_stores = (0..pool_size)
+            .map(|_| data_store.share())
+            .collect::<Vec<_>>();
+        let data_store = Arc::new(AsyncMutex::new(data_store));
+
+        let mut instances = Vec::with_capacity(pool_size);
+        for instance_data_store in instance_data_stores {
+            let instance_data_store = instance_data_store
+                .unwrap_or_else(|| Box::new(SharedDataStore(data_store.clone())));
+            let instance_config = AVMConfig {
+                air_wasm_path: air_wasm_path.clone(),
+                hosted_interpreters: hosted_interpreters.clone(),
+                max_heap_size,
+                logging_mask,
+                execution_timeout,
+                allowed_key_formats: allowed_key_formats.clone(),
+                trust_policy: trust_policy.clone(),
+                call_provenance_enabled,
+                call_deduplication_enabled,
+                call_policy: call_policy.clone(),
+                interpreter_backend,
+                clock: clock.clone(),
+                #[cfg(feature = "metrics")]
+                metrics: metrics.clone(),
+                data_store: instance_data_store,
+            };
+            let avm = AVM::with_initialized_store(instance_config, wasm_backend.clone()).await?;
+            let memory_stats = Mutex::new(avm.memory_stats());
+            instances.push(PoolInstance {
+                avm: AsyncMutex::new(avm),
+                memory_stats,
+            });
+        }
+
+        let pool = Self {
+            instances,
+            idle_instances: Mutex::new((0..pool_size).rev().collect()),
+            idle_permits: Semaphore::new(pool_size),
+            particle_locks: <_>::default(),
+            pending_calls: AtomicUsize::new(0),
+            max_pending_calls: pool_config.max_pending_calls,
+            data_store,
+        };
+
+        Ok(pool)
+    }
+
+    /// Execute AIR on an idle instance, see `AVM::call`.
+    ///
+    /// It waits for the previous calls of the same particle and for an idle instance,
+    /// and fails with `AVMError::PoolOverloaded` if too many calls are already waiting.
+    #[allow(clippy::result_large_err)]
+    pub async fn call(
+        &self,
+        air: impl Into<String>,
+        data: impl Into<Vec<u8>>,
+        particle_parameters: ParticleParameters<'_>,
+        call_results: CallResults,
+        keypair: &KeyPair,
+    ) -> AVMResult<AVMOutcome, E> {
+        let mut slot = self.acquire(&particle_parameters.particle_id).await?;
+        slot.avm()
+            .call(air, data, particle_parameters, call_results, keypair)
+            .await
+    }
+
+    /// Execute AIR with several current data of the same particle on an idle instance,
+    /// see `AVM::call_multi`.
+    #[allow(clippy::result_large_err)]
+    pub async fn call_multi(
+        &self,
+        air: impl Into<String>,
+        data: Vec<Vec<u8>>,
+        particle_parameters: ParticleParameters<'_>,
+        call_results: CallResults,
+        keypair: &KeyPair,
+    ) -> AVMResult<AVMOutcome, E> {
+        let mut slot = self.acquire(&particle_parameters.particle_id).await?;
+        slot.avm()
+            .call_multi(air, data, particle_parameters, call_results, keypair)
+            .await
+    }
+
+    /// Cleanup data that become obsolete, it waits for the running calls of the particle.
+    #[allow(clippy::result_large_err)]
+    pub async fn cleanup_data(&self, particle_id: &str, current_peer_id: &str) -> AVMResult<(), E> {
+        let _particle_guard = self.lock_particle(particle_id).await;
+        self.data_store
+            .lock()
+            .await
+            .cleanup_data(particle_id, current_peer_id)
+            .await?;
+        Ok(())
+    }
+
+    /// Remove data of particles expired by `now` in milliseconds since the Unix epoch,
+    /// see `DataStore::collect_expired`. Returns the number of removed data.
+    #[allow(clippy::result_large_err)]
+    pub async fn collect_expired_data(&self, now: u64) -> AVMResult<usize, E> {
+        let collected = self.data_store.lock().await.collect_expired(now).await?;
+        Ok(collected)
+    }
+
+    /// Return memory stats of every instance interpreter heaps, busy instances report
+    /// their stats after the last finished call.
+    pub fn memory_stats(&self) -> Vec<Vec<AVMMemoryStats>> {
+        self.instances
+            .iter()
+            .map(|instance| instance.memory_stats.lock().clone())
+            .collect()
+    }
+
+    /// Number of AVM instances in the pool.
+    pub fn pool_size(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// Number of calls running or waiting for an instance.
+    pub fn pending_calls(&self) -> usize {
+        self.pending_calls.load(Ordering::Acquire)
+    }
+
+    #[allow(clippy::result_large_err)]
+    async fn acquire(&self, particle_id: &str) -> AVMResult<PoolSlot<'_, E, WB>, E> {
+        let pending_guard = self.enter()?;
+        let particle_guard = self.lock_particle(particle_id).await;
+        let permit = self
+            .idle_permits
+            .acquire()
+            .await
+            .expect("the semaphore is never closed");
+        permit.forget();
+
+        let instance_id = self
+            .idle_instances
+            .lock()
+            .pop()
+            .expect("a permit guarantees an idle instance");
+        let avm = self.instances[instance_id]
+            .avm
+            .try_lock()
+            .expect("idle instance isn't locked");
+
+        Ok(PoolSlot {
+            pool: self,
+            instance_id,
+            avm: Some(avm),
+            _particle_guard: particle_guard,
+            _pending_guard: pending_guard,
+        })
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn enter(&self) -> AVMResult<PendingGuard<'_>, E> {
+        let pending_calls = self.pending_calls.fetch_add(1, Ordering::AcqRel) + 1;
+        let guard = PendingGuard(&self.pending_calls);
+
+        match self.max_pending_calls {
+            Some(max_pending_calls) if pending_calls > max_pending_calls => {
+                Err(AVMError::PoolOverloaded { max_pending_calls })
+            }
+            _ => Ok(guard),
+        }
+    }
+
+    async fn lock_particle(&self, particle_id: &str) -> ParticleGuard<'_> {
+        let lock = {
+            let mut particle_locks = self.particle_locks.lock();
+            let particle_lock = particle_locks.entry(particle_id.to_string()).or_default();
+            particle_lock.users += 1;
+            particle_lock.lock.clone()
+        };
+
+        // the guard is created before waiting, so a cancelled call releases the lock as well
+        let mut particle_guard = ParticleGuard {
+            particle_locks: &self.particle_locks,
+            particle_id: particle_id.to_string(),
+            guard: None,
+        };
+        particle_guard.guard = Some(lock.lock_owned().await);
+        particle_guard
+    }
+}
+
+/// An instance taken from the pool for a call, it's returned back on drop.
+struct PoolSlot<'pool, E, WB: WasmBackend> {
+    pool: &'pool AVMPool<E, WB>,
+    instance_id: usize,
+    avm: Option<AsyncMutexGuard<'pool, AVM<E, WB>>>,
+    _particle_guard: ParticleGuard<'pool>,
+    _pending_guard: PendingGuard<'pool>,
+}
+
+impl<E, WB: WasmBackend> PoolSlot<'_, E, WB> {
+    fn avm(&mut self) -> &mut AVM<E, WB> {
+        self.avm.as_mut().expect("avm is taken only on drop")
+    }
+}
+
+impl<E, WB: WasmBackend> Drop for PoolSlot<'_, E, WB> {
+    fn drop(&mut self) {
+        let instance = &self.pool.instances[self.instance_id];
+        if let Some(avm) = self.avm.take() {
+            *instance.memory_stats.lock() = avm.memory_stats();
+        }
+
+        self.pool.idle_instances.lock().push(self.instance_id);
+        self.pool.idle_permits.add_permits(1);
+    }
+}
+
+#[derive(Default)]
+struct ParticleLock {
+    lock: Arc<AsyncMutex<()>>,
+    /// Number of calls holding or waiting for the lock
+    users: usize,
+}
+
+/// Keeps other calls of the same particle waiting, the lock is removed with the last user.
+struct ParticleGuard<'pool> {
+    particle_locks: &'pool ParticleLocks,
+    particle_id: String,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for ParticleGuard<'_> {
+    fn drop(&mut self) {
+        self.guard.take();
+
+        let mut particle_locks = self.particle_locks.lock();
+        if let Some(particle_lock) = particle_locks.get_mut(&self.particle_id) {
+            particle_lock.users -= 1;
+            if particle_lock.users == 0 {
+                particle_locks.remove(&self.particle_id);
+            }
+        }
+    }
+}
+
+struct PendingGuard<'pool>(&'pool AtomicUsize);
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// A data store shared by the pool instances, it's initialized once by the pool itself.
+struct SharedDataStore<E>(Arc<AsyncMutex<AVMDataStore<E>>>);
+
+impl<E> AsyncDataStore for SharedDataStore<E> {
+    type Error = E;
+
+    fn initialize(&mut self) -> DataStoreFuture<'_, (), Self::Error> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn store_data<'store>(
+        &'store mut self,
+        data: &'store [u8],
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+    ) -> DataStoreFuture<'store, (), Self::Error> {
+        Box::pin(async move {
+            let mut data_store = self.0.lock().await;
+            data_store
+                .store_data(data, particle_id, current_peer_id)
+                .await
+        })
+    }
+
+    fn read_data<'store>(
+        &'store mut self,
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+    ) -> DataStoreFuture<'store, Vec<u8>, Self::Error> {
+        Box::pin(async move {
+            let mut data_store = self.0.lock().await;
+            data_store.read_data(particle_id, current_peer_id).await
+        })
+    }
+
+    fn cleanup_data<'store>(
+        &'store mut self,
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+    ) -> DataStoreFuture<'store, (), Self::Error> {
+        Box::pin(async move {
+            let mut data_store = self.0.lock().await;
+            data_store.cleanup_data(particle_id, current_peer_id).await
+        })
+    }
+
+    fn track_expiration<'store>(
+        &'store mut self,
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+        timestamp: u64,
+        ttl: u32,
+    ) -> DataStoreFuture<'store, (), Self::Error> {
+        Box::pin(async move {
+            let mut data_store = self.0.lock().await;
+            data_store
+                .track_expiration(particle_id, current_peer_id, timestamp, ttl)
+                .await
+        })
+    }
+
+    fn collect_expired(&mut self, now: u64) -> DataStoreFuture<'_, usize, Self::Error> {
+        Box::pin(async move {
+            let mut data_store = self.0.lock().await;
+            data_store.collect_expired(now).await
+        })
+    }
+
+    fn detect_anomaly<'store>(
+        &'store self,
+        execution_time: Duration,
+        memory_delta: usize,
+        outcome: &'store RawAVMOutcome,
+    ) -> AnomalyDetectionFuture<'store> {
+        Box::pin(async move {
+            let data_store = self.0.lock().await;
+            data_store
+                .detect_anomaly(execution_time, memory_delta, outcome)
+                .await
+        })
+    }
+
+    fn collect_anomaly_data<'store>(
+        &'store mut self,
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+        anomaly_data: AnomalyData<'store>,
+    ) -> DataStoreFuture<'store, (), Self::Error> {
+        Box::pin(async move {
+            let mut data_store = self.0.lock().await;
+            data_store
+                .collect_anomaly_data(particle_id, current_peer_id, anomaly_data)
+                .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use marine_wasmtime_backend::WasmtimeWasmBackend;
+
+    #[test]
+    fn pool_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<AVMPool<std::convert::Infallible, WasmtimeWasmBackend>>();
+    }
+
+    #[cfg(feature = "native")]
+    mod native {
+        use super::*;
+        use crate::config::InterpreterBackend;
+        use crate::AVMClock;
+        use crate::VirtualClock;
+
+        use avm_fs_data_store::FsDataStore;
+        use avm_fs_data_store::FsDataStoreConfig;
+        use std::borrow::Cow;
+        use std::convert::Infallible;
+        use tokio::sync::Barrier;
+
+        const TIMEOUT: Duration = Duration::from_secs(10);
+
+        /// Keeps data in memory, reads wait for the barrier if it's set. Shared handles
+        /// record the most calls of a particle running at once.
+        #[derive(Clone, Default)]
+        struct TestStore {
+            data: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+            read_barrier: Option<Arc<Barrier>>,
+            running_calls: Arc<Mutex<HashMap<String, usize>>>,
+            max_running_calls: Arc<AtomicUsize>,
+        }
+
+        impl TestStore {
+            fn with_read_barrier(parties: usize) -> Self {
+                Self {
+                    read_barrier: Some(Arc::new(Barrier::new(parties))),
+                    ..<_>::default()
+                }
+            }
+        }
+
+        impl AsyncDataStore for TestStore {
+            type Error = Infallible;
+
+            fn initialize(&mut self) -> DataStoreFuture<'_, (), Self::Error> {
+                Box::pin(async { Ok(()) })
+            }
+
+            fn store_data<'store>(
+                &'store mut self,
+                data: &'store [u8],
+                particle_id: &'store str,
+                _current_peer_id: &'store str,
+            ) -> DataStoreFuture<'store, (), Self::Error> {
+                self.data
+                    .lock()
+                    .insert(particle_id.to_string(), data.to_vec());
+                *self
+                    .running_calls
+                    .lock()
+                    .get_mut(particle_id)
+                    .expect("data is stored at the end of a call") -= 1;
+                Box::pin(async { Ok(()) })
+            }
+
+            fn read_data<'store>(
+                &'store mut self,
+                particle_id: &'store str,
+                _current_peer_id: &'store str,
+            ) -> DataStoreFuture<'store, Vec<u8>, Self::Error> {
+                Box::pin(async move {
+                    match &self.read_barrier {
+                        Some(read_barrier) => {
+                            read_barrier.wait().await;
+                        }
+                        None => tokio::task::yield_now().await,
+                    }
+                    let data = self.data.lock().get(particle_id).cloned();
+                    Ok(data.unwrap_or_default())
+                })
+            }
+
+            fn cleanup_data<'store>(
+                &'store mut self,
+                particle_id: &'store str,
+                _current_peer_id: &'store str,
+            ) -> DataStoreFuture<'store, (), Self::Error> {
+                self.data.lock().remove(particle_id);
+                Box::pin(async { Ok(()) })
+            }
+
+            fn track_expiration<'store>(
+                &'store mut self,
+                particle_id: &'store str,
+                _current_peer_id: &'store str,
+                _timestamp: u64,
+                _ttl: u32,
+            ) -> DataStoreFuture<'store, (), Self::Error> {
+                // it's the first store operation of a call
+                let mut running_calls = self.running_calls.lock();
+                let particle_calls = running_calls.entry(particle_id.to_string()).or_default();
+                *particle_calls += 1;
+                self.max_running_calls
+                    .fetch_max(*particle_calls, Ordering::AcqRel);
+                Box::pin(async { Ok(()) })
+            }
+
+            fn detect_anomaly<'store>(
+                &'store self,
+                _execution_time: Duration,
+                _memory_delta: usize,
+                _outcome: &'store RawAVMOutcome,
+            ) -> AnomalyDetectionFuture<'store> {
+                Box::pin(async { false })
+            }
+
+            fn collect_anomaly_data<'store>(
+                &'store mut self,
+                _particle_id: &'store str,
+                _current_peer_id: &'store str,
+                _anomaly_data: AnomalyData<'store>,
+            ) -> DataStoreFuture<'store, (), Self::Error> {
+                Box::pin(async { Ok(()) })
+            }
+
+            fn share(&self) -> Option<Box<dyn AsyncDataStore<Error = Self::Error> + Send + Sync>> {
+                Some(Box::new(self.clone()))
+            }
+        }
+
+        async fn make_pool<E: std::fmt::Debug + 'static>(
+            data_store: impl AsyncDataStore<Error = E> + Send + Sync + 'static,
+            pool_size: usize,
+            max_pending_calls: Option<usize>,
+        ) -> AVMPool<E, WasmtimeWasmBackend> {
+            make_pool_with_clock(data_store, pool_size, max_pending_calls, None).await
+        }
+
+        async fn make_pool_with_clock<E: std::fmt::Debug + 'static>(
+            data_store: impl AsyncDataStore<Error = E> + Send + Sync + 'static,
+            pool_size: usize,
+            max_pending_calls: Option<usize>,
+            clock: Option<AVMClock>,
+        ) -> AVMPool<E, WasmtimeWasmBackend> {
+            let config = AVMConfig {
+                air_wasm_path: <_>::default(),
+                hosted_interpreters: <_>::default(),
+                max_heap_size: None,
+                logging_mask: 0,
+                execution_timeout: None,
+                allowed_key_formats: vec![],
+                trust_policy: <_>::default(),
+                call_provenance_enabled: false,
+                call_deduplication_enabled: false,
+                call_policy: <_>::default(),
+                interpreter_backend: InterpreterBackend::Native,
+                clock,
+                #[cfg(feature = "metrics")]
+                metrics: None,
+                data_store: Box::new(data_store),
+            };
+            let pool_config = AVMPoolConfig {
+                pool_size,
+                max_pending_calls,
+            };
+
+            AVMPool::new(
+                config,
+                pool_config,
+                WasmtimeWasmBackend::new_async().unwrap(),
+            )
+            .await
+            .unwrap()
+        }
+
+        async fn call<E: 'static>(
+            pool: &AVMPool<E, WasmtimeWasmBackend>,
+            keypair: &KeyPair,
+            particle_id: &str,
+        ) -> AVMResult<AVMOutcome, E> {
+            let peer_id = keypair.public().to_peer_id().to_string();
+            let particle_parameters = ParticleParameters::new(
+                Cow::Borrowed(&peer_id),
+                Cow::Borrowed(particle_id),
+                Cow::Borrowed(b"particle_signature"),
+                0,
+                u32::MAX,
+                Cow::Borrowed(&peer_id),
+            );
+
+            pool.call(
+                "(null)",
+                vec![],
+                particle_parameters,
+                <_>::default(),
+                keypair,
+            )
+            .await
+        }
+
+        #[tokio::test]
+        async fn different_particles_run_in_parallel() {
+            let keypair = KeyPair::generate_ed25519();
+            // reads of both calls have to meet, so the calls can't be executed one by one
+            let pool = make_pool(TestStore::with_read_barrier(2), 2, None).await;
+
+            let calls = async {
+                tokio::join!(
+                    call(&pool, &keypair, "particle_1"),
+                    call(&pool, &keypair, "particle_2"),
+                )
+            };
+            let (result_1, result_2) = tokio::time::timeout(TIMEOUT, calls)
+                .await
+                .expect("calls are executed in parallel");
+
+            result_1.unwrap();
+            result_2.unwrap();
+            assert_eq!(pool.pending_calls(), 0);
+            assert!(pool.particle_locks.lock().is_empty());
+        }
+
+        #[tokio::test]
+        async fn same_particle_calls_are_serialized() {
+            let keypair = KeyPair::generate_ed25519();
+            let data_store = TestStore::default();
+            let pool = make_pool(data_store.clone(), 2, None).await;
+
+            let (result_1, result_2, result_3) = tokio::join!(
+                call(&pool, &keypair, "particle"),
+                call(&pool, &keypair, "particle"),
+                call(&pool, &keypair, "particle"),
+            );
+
+            result_1.unwrap();
+            result_2.unwrap();
+            result_3.unwrap();
+            assert_eq!(data_store.max_running_calls.load(Ordering::Acquire), 1);
+            assert!(pool.particle_locks.lock().is_empty());
+        }
+
+        #[tokio::test]
+        async fn overloaded_pool_rejects_calls() {
+            let keypair = KeyPair::generate_ed25519();
+            let data_store = TestStore::with_read_barrier(2);
+            let read_barrier = data_store.read_barrier.clone().unwrap();
+            let pool = make_pool(data_store, 1, Some(1)).await;
+
+            let rejected_call = async {
+                // the first call waits for the barrier in its read
+                let result = call(&pool, &keypair, "particle_2").await;
+                read_barrier.wait().await;
+                result
+            };
+            let (result_1, result_2) =
+                tokio::join!(call(&pool, &keypair, "particle_1"), rejected_call);
+
+            result_1.unwrap();
+            assert!(matches!(
+                result_2,
+                Err(AVMError::PoolOverloaded {
+                    max_pending_calls: 1
+                })
+            ));
+            assert_eq!(pool.pending_calls(), 0);
+        }
+
+        #[tokio::test]
+        async fn cancelled_call_releases_particle_lock() {
+            let keypair = KeyPair::generate_ed25519();
+            let data_store = TestStore::with_read_barrier(2);
+            let read_barrier = data_store.read_barrier.clone().unwrap();
+            let pool = make_pool(data_store, 2, None).await;
+
+            let cancelled_call = async {
+                // the first call holds the particle lock while it waits for the barrier
+                let waiting_call = call(&pool, &keypair, "particle");
+                tokio::select! {
+                    biased;
+                    _ = waiting_call => panic!("the call should wait for the first one"),
+                    _ = std::future::ready(()) => {}
+                }
+                read_barrier.wait().await;
+            };
+            let (result, _) = tokio::join!(call(&pool, &keypair, "particle"), cancelled_call);
+
+            result.unwrap();
+            assert_eq!(pool.pending_calls(), 0);
+            assert!(pool.particle_locks.lock().is_empty());
+        }
+
+        #[tokio::test]
+        async fn expired_data_is_collected() {
+            let root_dir = tempfile::tempdir().unwrap();
+            let data_store = FsDataStore::new(FsDataStoreConfig::new(root_dir.path()));
+            let keypair = KeyPair::generate_ed25519();
+            let pool = make_pool(data_store, 2, None).await;
+
+            let (result_1, result_2) = tokio::join!(
+                call(&pool, &keypair, "particle_1"),
+                call(&pool, &keypair, "particle_2"),
+            );
+            result_1.unwrap();
+            result_2.unwrap();
+
+            // the calls expire at `u32::MAX`
+            assert_eq!(
+                pool.collect_expired_data(u32::MAX as u64 - 1)
+                    .await
+                    .unwrap(),
+                0
+            );
+            assert_eq!(pool.collect_expired_data(u32::MAX as u64).await.unwrap(), 2);
+        }
+
+        #[tokio::test]
+        async fn expired_particle_is_rejected_and_its_data_is_removed() {
+            let keypair = KeyPair::generate_ed25519();
+            let data_store = TestStore::default();
+            let clock = VirtualClock::new(u32::MAX as u64 - 1);
+            let pool =
+                make_pool_with_clock(data_store.clone(), 1, None, Some(Arc::new(clock.clone())))
+                    .await;
+
+            call(&pool, &keypair, "particle").await.unwrap();
+            assert!(data_store.data.lock().contains_key("particle"));
+
+            // the call expires at `u32::MAX`
+            clock.advance(1);
+            let result = call(&pool, &keypair, "particle").await;
+            assert!(matches!(
+                result,
+                Err(AVMError::ParticleExpired { deadline, now, .. })
+                    if deadline == u32::MAX as u64 && now == deadline
+            ));
+            assert!(!data_store.data.lock().contains_key("particle"));
+            assert_eq!(pool.pending_calls(), 0);
+        }
+    }
+}
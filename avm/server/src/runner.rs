@@ -17,24 +17,38 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use crate::interpreter_runner::CallSigner;
+use crate::interpreter_runner::InterpreterRunner;
+use crate::interpreter_runner::RunnerFuture;
+use crate::interpreter_runner::RunnerSettings;
 use crate::RunnerError;
 use crate::RunnerResult;
 
 use air_interpreter_interface::try_as_string;
 use air_interpreter_interface::CallResultsRepr;
 use air_interpreter_interface::InterpreterOutcome;
+use air_interpreter_interface::RunParameters;
+use air_interpreter_interface::SerializedCallResults;
 use air_interpreter_sede::ToSerialized;
 use air_utils::measure;
 use avm_interface::raw_outcome::RawAVMOutcome;
 use avm_interface::CallResults;
-use fluence_keypair::KeyPair;
+use fluence_keypair::PublicKey;
 use marine::generic::Marine;
 use marine::generic::MarineConfig;
 use marine::generic::ModuleDescriptor;
+use marine::IType;
 use marine::IValue;
+use marine::MarineModuleInterface;
 use marine_wasm_backend_traits::WasmBackend;
 
+use std::future::poll_fn;
+use std::future::Future;
 use std::path::PathBuf;
+use std::pin::pin;
+use std::task::Poll;
+use std::time::Duration;
+use std::time::Instant;
 
 #[derive(Clone, Copy, Debug)]
 pub struct AquaVMRuntimeLimits {
@@ -47,7 +61,7 @@ pub struct AquaVMRuntimeLimits {
     pub hard_limit_enabled: bool,
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct AVMRuntimeLimits {
     // The AIR script size limit.
     pub air_size_limit: Option<u64>,
@@ -57,19 +71,40 @@ pub struct AVMRuntimeLimits {
     pub call_result_size_limit: Option<u64>,
     /// This knob controls hard RAM limits behavior for AVMRunner.
     pub hard_limit_enabled: bool,
+    /// Wall-clock time budget of a single interpreter call.
+    ///
+    /// It's checked only when the Wasm backend interrupts the execution on an epoch increment,
+    /// so the host must increment the epoch periodically, e.g. with
+    /// `WasmtimeWasmBackend::increment_epoch`, otherwise the timeout never fires.
+    /// The native runner can't interrupt the interpreter, so it stops waiting for the result
+    /// instead.
+    pub execution_timeout: Option<Duration>,
 }
 
 pub struct AVMRunner<WB: WasmBackend> {
     marine: Marine<WB>,
+    /// Backend used to re-instantiate the interpreter after an interrupted call
+    wasm_backend: WB,
+    /// directory of the AIR interpreter .wasm
+    wasm_dir: PathBuf,
     /// file name of the AIR interpreter .wasm
     wasm_filename: String,
+    /// Mask used to filter logs of the interpreter
+    logging_mask: i32,
+    /// Number of run parameters fields the interpreter accepts, older ones accept a prefix
+    run_parameters_fields_count: usize,
     /// The memory limit provided by constructor
     total_memory_limit: Option<u64>,
     /// This struct contains runtime RAM allowance.
     aquavm_runtime_limits: AquaVMRuntimeLimits,
+    /// Wall-clock time budget of a single interpreter call
+    execution_timeout: Option<Duration>,
+    /// Settings passed to the interpreter with every call
+    settings: RunnerSettings,
 }
 
 /// Return statistic of AVM server Wasm module heap footprint.
+#[derive(Clone, Debug)]
 pub struct AVMMemoryStats {
     /// Size of currently used linear memory in bytes.
     /// Please note that linear memory contains not only heap, but globals, shadow stack and so on.
@@ -79,6 +114,8 @@ pub struct AVMMemoryStats {
     /// Number of allocations rejected due to memory limit.
     /// May be not recorded by some backends in Marine.
     pub allocation_rejects: Option<u32>,
+    /// Version of a hosted interpreter the stats are of, it's `None` for a single interpreter.
+    pub interpreter_version: Option<semver::Version>,
 }
 
 impl<WB: WasmBackend> AVMRunner<WB> {
@@ -92,16 +129,29 @@ impl<WB: WasmBackend> AVMRunner<WB> {
     ) -> RunnerResult<Self> {
         let (wasm_dir, wasm_filename) = split_dirname(air_wasm_path)?;
 
-        let marine_config =
-            make_marine_config(wasm_dir, &wasm_filename, total_memory_limit, logging_mask);
-        let marine = Marine::with_raw_config(wasm_backend, marine_config).await?;
+        let marine_config = make_marine_config(
+            wasm_dir.clone(),
+            &wasm_filename,
+            total_memory_limit,
+            logging_mask,
+        );
+        let marine = Marine::with_raw_config(wasm_backend.clone(), marine_config).await?;
+        let run_parameters_fields_count =
+            interpreter_run_parameters_fields_count(&marine, &wasm_filename)?;
+        let execution_timeout = avm_runtime_limits.execution_timeout;
         let aquavm_runtime_limits = avm_runtime_limits.into();
 
         let avm = Self {
             marine,
+            wasm_backend,
+            wasm_dir,
             wasm_filename,
+            logging_mask,
+            run_parameters_fields_count,
             total_memory_limit,
             aquavm_runtime_limits,
+            execution_timeout,
+            settings: <_>::default(),
         };
 
         Ok(avm)
@@ -109,48 +159,83 @@ impl<WB: WasmBackend> AVMRunner<WB> {
 
     #[allow(clippy::too_many_arguments)]
     #[tracing::instrument(skip_all)]
-    pub async fn call(
+    async fn call_impl(
         &mut self,
-        air: impl Into<String>,
-        prev_data: impl Into<Vec<u8>>,
-        data: impl Into<Vec<u8>>,
-        init_peer_id: impl Into<String>,
+        air: String,
+        prev_data: Vec<u8>,
+        data: Vec<Vec<u8>>,
+        init_peer_id: String,
         timestamp: u64,
         ttl: u32,
-        current_peer_id: impl Into<String>,
+        current_peer_id: String,
         call_results: CallResults,
-        keypair: &KeyPair,
+        signer: CallSigner<'_>,
         particle_id: String,
+        particle_signature: Vec<u8>,
     ) -> RunnerResult<RawAVMOutcome> {
-        let key_format = keypair.key_format();
-        // we use secret() for compatibility with JS client that doesn't have keypair type,
-        // it can serialize a secret key only
-        let secret_key_bytes: Vec<u8> = keypair.secret().map_err(RunnerError::KeyError)?;
-
+        let (method, data) = match <[Vec<u8>; 1]>::try_from(data) {
+            Ok([data]) => ("invoke", IValue::ByteArray(data)),
+            Err(data) => (
+                "invoke_multi",
+                IValue::Array(data.into_iter().map(IValue::ByteArray).collect()),
+            ),
+        };
         let args = prepare_args(
             air,
             prev_data,
             data,
-            current_peer_id.into(),
-            init_peer_id.into(),
+            current_peer_id,
+            init_peer_id,
             timestamp,
             ttl,
             self.aquavm_runtime_limits,
             call_results,
-            key_format.into(),
-            secret_key_bytes,
+            signer.key_args()?,
+            &self.settings,
             particle_id,
-        );
+            particle_signature,
+            self.run_parameters_fields_count,
+        )?;
 
-        let result = measure!(
-            self.marine
-                .call_with_ivalues_async(&self.wasm_filename, "invoke", &args, <_>::default())
-                .await?,
-            tracing::Level::INFO,
-            "marine.call_with_ivalues",
-            method = "invoke",
-        );
+        let result = self.call_interpreter(method, &args).await?;
+        let result = try_as_one_value_vec(result)?;
+        let outcome = InterpreterOutcome::from_ivalue(result)
+            .map_err(RunnerError::InterpreterResultDeError)?;
+        let outcome = RawAVMOutcome::from_interpreter_outcome(outcome)?;
 
+        Ok(outcome)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn finalize_signing_impl(
+        &mut self,
+        data: Vec<u8>,
+        current_peer_id: String,
+        public_key: &PublicKey,
+        signature: Vec<u8>,
+        particle_id: String,
+        particle_signature: Vec<u8>,
+    ) -> RunnerResult<RawAVMOutcome> {
+        let run_parameters = prepare_run_parameters(
+            current_peer_id,
+            String::new(),
+            0,
+            0,
+            self.aquavm_runtime_limits,
+            CallSigner::Host(public_key).key_args()?,
+            &self.settings.for_signing(),
+            particle_id,
+            particle_signature,
+        )
+        .into_ivalue_prefix(self.run_parameters_fields_count)
+        .map_err(RunnerError::IncompatibleInterpreter)?;
+        let args = vec![
+            IValue::ByteArray(data),
+            run_parameters,
+            IValue::ByteArray(signature),
+        ];
+
+        let result = self.call_interpreter("finalize_signing", &args).await?;
         let result = try_as_one_value_vec(result)?;
         let outcome = InterpreterOutcome::from_ivalue(result)
             .map_err(RunnerError::InterpreterResultDeError)?;
@@ -176,38 +261,32 @@ impl<WB: WasmBackend> AVMRunner<WB> {
         key_format: u8,
         secret_key_bytes: Vec<u8>,
         particle_id: String,
+        particle_signature: Vec<u8>,
     ) -> RunnerResult<RawAVMOutcome> {
         let mut args = prepare_args(
-            air,
-            prev_data,
-            data,
+            air.into(),
+            prev_data.into(),
+            IValue::ByteArray(data.into()),
             current_peer_id.into(),
             init_peer_id.into(),
             timestamp,
             ttl,
             self.aquavm_runtime_limits,
             call_results,
-            key_format,
-            secret_key_bytes,
+            KeyArgs {
+                key_format,
+                secret_key_bytes,
+                signer_public_key: vec![],
+            },
+            &self.settings,
             particle_id,
-        );
+            particle_signature,
+            self.run_parameters_fields_count,
+        )?;
         args.push(IValue::String(tracing_params));
         args.push(IValue::U8(tracing_output_mode));
 
-        let result = measure!(
-            self.marine
-                .call_with_ivalues_async(
-                    &self.wasm_filename,
-                    "invoke_tracing",
-                    &args,
-                    <_>::default(),
-                )
-                .await?,
-            tracing::Level::INFO,
-            "marine.call_with_ivalues",
-            method = "invoke_tracing",
-        );
-
+        let result = self.call_interpreter("invoke_tracing", &args).await?;
         let result = try_as_one_value_vec(result)?;
         let outcome = InterpreterOutcome::from_ivalue(result)
             .map_err(RunnerError::InterpreterResultDeError)?;
@@ -216,6 +295,53 @@ impl<WB: WasmBackend> AVMRunner<WB> {
         Ok(outcome)
     }
 
+    /// Call an interpreter export within the execution timeout.
+    ///
+    /// An interrupted call leaves the interpreter in an inconsistent state, so it's
+    /// re-instantiated before the timeout error is returned.
+    async fn call_interpreter(
+        &mut self,
+        method: &str,
+        args: &[IValue],
+    ) -> RunnerResult<Vec<IValue>> {
+        let call =
+            self.marine
+                .call_with_ivalues_async(&self.wasm_filename, method, args, <_>::default());
+
+        let result = measure!(
+            match self.execution_timeout {
+                None => Some(call.await),
+                Some(timeout) => with_timeout(call, timeout).await,
+            },
+            tracing::Level::INFO,
+            "marine.call_with_ivalues",
+            method = method,
+        );
+
+        match result {
+            Some(result) => Ok(result?),
+            None => {
+                let timeout = self.execution_timeout.unwrap_or_default();
+                log::warn!("interpreter call {method} is interrupted after {timeout:?}");
+
+                self.reinstantiate().await?;
+                Err(RunnerError::ExecutionTimeout { timeout })
+            }
+        }
+    }
+
+    async fn reinstantiate(&mut self) -> RunnerResult<()> {
+        let marine_config = make_marine_config(
+            self.wasm_dir.clone(),
+            &self.wasm_filename,
+            self.total_memory_limit,
+            self.logging_mask,
+        );
+        self.marine = Marine::with_raw_config(self.wasm_backend.clone(), marine_config).await?;
+
+        Ok(())
+    }
+
     pub async fn to_human_readable_data<'this>(
         &'this mut self,
         data: Vec<u8>,
@@ -235,8 +361,62 @@ impl<WB: WasmBackend> AVMRunner<WB> {
         let outcome = try_as_string(result, "result").map_err(RunnerError::Aux)?;
         Ok(outcome)
     }
+}
 
-    pub fn memory_stats(&self) -> AVMMemoryStats {
+impl<WB: WasmBackend> InterpreterRunner for AVMRunner<WB> {
+    fn settings_mut(&mut self) -> &mut RunnerSettings {
+        &mut self.settings
+    }
+
+    fn execute<'runner>(
+        &'runner mut self,
+        air: String,
+        prev_data: Vec<u8>,
+        data: Vec<Vec<u8>>,
+        init_peer_id: String,
+        timestamp: u64,
+        ttl: u32,
+        current_peer_id: String,
+        call_results: CallResults,
+        signer: CallSigner<'runner>,
+        particle_id: String,
+        particle_signature: Vec<u8>,
+    ) -> RunnerFuture<'runner> {
+        Box::pin(self.call_impl(
+            air,
+            prev_data,
+            data,
+            init_peer_id,
+            timestamp,
+            ttl,
+            current_peer_id,
+            call_results,
+            signer,
+            particle_id,
+            particle_signature,
+        ))
+    }
+
+    fn finalize_signing<'runner>(
+        &'runner mut self,
+        data: Vec<u8>,
+        current_peer_id: String,
+        public_key: &'runner PublicKey,
+        signature: Vec<u8>,
+        particle_id: String,
+        particle_signature: Vec<u8>,
+    ) -> RunnerFuture<'runner> {
+        Box::pin(self.finalize_signing_impl(
+            data,
+            current_peer_id,
+            public_key,
+            signature,
+            particle_id,
+            particle_signature,
+        ))
+    }
+
+    fn memory_stats(&self) -> AVMMemoryStats {
         let stats = self.marine.module_memory_stats();
 
         // only the interpreters must be loaded in Marine
@@ -246,26 +426,70 @@ impl<WB: WasmBackend> AVMRunner<WB> {
             memory_size: stats.modules[0].memory_size,
             total_memory_limit: self.total_memory_limit,
             allocation_rejects: stats.allocation_stats.map(|stats| stats.allocation_rejects),
+            interpreter_version: None,
         }
     }
 }
 
 #[allow(clippy::too_many_arguments)]
-#[tracing::instrument(skip(air, prev_data, data, call_results, secret_key_bytes))]
+#[tracing::instrument(skip(air, prev_data, data, call_results, key_args))]
 fn prepare_args(
-    air: impl Into<String>,
-    prev_data: impl Into<Vec<u8>>,
-    data: impl Into<Vec<u8>>,
+    air: String,
+    prev_data: Vec<u8>,
+    data: IValue,
     current_peer_id: String,
     init_peer_id: String,
     timestamp: u64,
     ttl: u32,
     aquavm_runtime_limits: AquaVMRuntimeLimits,
     call_results: CallResults,
-    key_format: u8,
-    secret_key_bytes: Vec<u8>,
+    key_args: KeyArgs,
+    settings: &RunnerSettings,
+    particle_id: String,
+    particle_signature: Vec<u8>,
+    run_parameters_fields_count: usize,
+) -> RunnerResult<Vec<IValue>> {
+    let run_parameters = prepare_run_parameters(
+        current_peer_id,
+        init_peer_id,
+        timestamp,
+        ttl,
+        aquavm_runtime_limits,
+        key_args,
+        settings,
+        particle_id,
+        particle_signature,
+    )
+    .into_ivalue_prefix(run_parameters_fields_count)
+    .map_err(RunnerError::IncompatibleInterpreter)?;
+    let call_results = serialize_call_results(call_results);
+
+    Ok(vec![
+        IValue::String(air),
+        IValue::ByteArray(prev_data),
+        data,
+        run_parameters,
+        IValue::ByteArray(call_results.to_vec()),
+    ])
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn prepare_run_parameters(
+    current_peer_id: String,
+    init_peer_id: String,
+    timestamp: u64,
+    ttl: u32,
+    aquavm_runtime_limits: AquaVMRuntimeLimits,
+    key_args: KeyArgs,
+    settings: &RunnerSettings,
     particle_id: String,
-) -> Vec<IValue> {
+    particle_signature: Vec<u8>,
+) -> RunParameters {
+    let KeyArgs {
+        key_format,
+        secret_key_bytes,
+        signer_public_key,
+    } = key_args;
     let AquaVMRuntimeLimits {
         air_size_limit,
         particle_size_limit,
@@ -273,7 +497,7 @@ fn prepare_args(
         hard_limit_enabled,
     } = aquavm_runtime_limits;
 
-    let run_parameters = air_interpreter_interface::RunParameters::new(
+    RunParameters::new(
         init_peer_id,
         current_peer_id,
         timestamp,
@@ -285,25 +509,32 @@ fn prepare_args(
         particle_size_limit,
         call_result_size_limit,
         hard_limit_enabled,
+        settings.allowed_key_formats.clone(),
+        settings.trust_policy.clone(),
+        settings.call_policy.clone(),
+        particle_signature,
+        settings.call_provenance_enabled,
+        settings.call_deduplication_enabled,
+        signer_public_key,
     )
-    .into_ivalue();
+}
 
+pub(crate) fn serialize_call_results(call_results: CallResults) -> SerializedCallResults {
     let call_results = avm_interface::into_raw_result(call_results);
-    let call_results = measure!(
+    measure!(
         CallResultsRepr
             .serialize(&call_results)
             .expect("the default serializer shouldn't fail"),
         tracing::Level::INFO,
         "CallResultsRepr.serialize"
-    );
+    )
+}
 
-    vec![
-        IValue::String(air.into()),
-        IValue::ByteArray(prev_data.into()),
-        IValue::ByteArray(data.into()),
-        run_parameters,
-        IValue::ByteArray(call_results.to_vec()),
-    ]
+/// Key material passed to the interpreter.
+pub(crate) struct KeyArgs {
+    pub(crate) key_format: u8,
+    pub(crate) secret_key_bytes: Vec<u8>,
+    pub(crate) signer_public_key: Vec<u8>,
 }
 
 /// Splits given path into its directory and file name
@@ -365,6 +596,67 @@ fn make_marine_config<WB: WasmBackend>(
     }
 }
 
+#[allow(clippy::result_large_err)]
+fn interpreter_run_parameters_fields_count<WB: WasmBackend>(
+    marine: &Marine<WB>,
+    module_name: &str,
+) -> RunnerResult<usize> {
+    let interface = marine.get_interface();
+    let module_interface = interface.modules.get(module_name).ok_or_else(|| {
+        RunnerError::IncompatibleInterpreter(format!("module {module_name} isn't loaded"))
+    })?;
+
+    run_parameters_fields_count(module_interface)
+}
+
+/// Number of fields of the run parameters record accepted by the `invoke` export.
+#[allow(clippy::result_large_err)]
+fn run_parameters_fields_count(interface: &MarineModuleInterface<'_>) -> RunnerResult<usize> {
+    use RunnerError::IncompatibleInterpreter;
+
+    // air, prev_data, data, run_parameters, call_results
+    const RUN_PARAMETERS_POSITION: usize = 3;
+
+    let invoke = interface
+        .function_signatures
+        .iter()
+        .find(|signature| signature.name.as_str() == "invoke")
+        .ok_or_else(|| IncompatibleInterpreter("interpreter has no invoke export".to_owned()))?;
+
+    let record_type = match invoke.arguments.get(RUN_PARAMETERS_POSITION) {
+        Some(argument) => match &argument.ty {
+            IType::Record(record_id) => interface.record_types.get(record_id),
+            _ => None,
+        },
+        None => None,
+    };
+
+    record_type
+        .map(|record_type| record_type.fields.len())
+        .ok_or_else(|| {
+            IncompatibleInterpreter(format!(
+                "invoke export doesn't accept run parameters record: {:?}",
+                invoke.arguments
+            ))
+        })
+}
+
+/// Polls the future until it's ready or the timeout elapses.
+///
+/// The elapsed time is checked only when the future yields, a Wasm call yields on each epoch
+/// increment of the backend.
+async fn with_timeout<F: Future>(future: F, timeout: Duration) -> Option<F::Output> {
+    let deadline = Instant::now() + timeout;
+    let mut future = pin!(future);
+
+    poll_fn(|cx| match future.as_mut().poll(cx) {
+        Poll::Ready(output) => Poll::Ready(Some(output)),
+        Poll::Pending if Instant::now() >= deadline => Poll::Ready(None),
+        Poll::Pending => Poll::Pending,
+    })
+    .await
+}
+
 fn try_as_one_value_vec(mut ivalues: Vec<IValue>) -> RunnerResult<IValue> {
     use RunnerError::IncorrectInterpreterResult;
 
@@ -403,8 +695,15 @@ impl AVMRuntimeLimits {
             particle_size_limit,
             call_result_size_limit,
             hard_limit_enabled,
+            execution_timeout: None,
         }
     }
+
+    /// Set the wall-clock time budget of a single interpreter call, see `execution_timeout`.
+    pub fn with_execution_timeout(mut self, execution_timeout: Duration) -> Self {
+        self.execution_timeout = Some(execution_timeout);
+        self
+    }
 }
 
 impl From<AVMRuntimeLimits> for AquaVMRuntimeLimits {
@@ -421,3 +720,266 @@ impl From<AVMRuntimeLimits> for AquaVMRuntimeLimits {
         )
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use air_interpreter_interface::CallPolicy;
+    use air_interpreter_interface::CallPolicyRepr;
+    use air_interpreter_interface::CallRule;
+    use air_interpreter_interface::SerializedCallPolicy;
+    use air_interpreter_interface::LEGACY_RUN_PARAMETERS_FIELDS_COUNT;
+    use fluence_it_types::IRecordFieldType;
+    use fluence_keypair::KeyPair;
+    use marine::ne_vec::NEVec;
+    use marine::IFunctionArg;
+    use marine::IRecordType;
+    use marine::MRecordTypes;
+    use marine::MarineFunctionSignature;
+
+    use marine_wasmtime_backend::WasmtimeWasmBackend;
+
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    const RUN_PARAMETERS_RECORD_ID: u64 = 1;
+    const OUTCOME_RECORD_ID: u64 = 2;
+    const AIR_WASM_PATH: &str = "../../target/wasm32-wasi/debug/air_interpreter_server.wasm";
+
+    /// A script busy for a while: a thousand iterations of nested folds over a canon stream
+    /// of 32 values repeated several times; folds are recursive, so they aren't nested deeper.
+    pub(crate) fn long_running_air() -> String {
+        let fill = (0..32).fold("(null)".to_string(), |air, value| {
+            format!("(seq (ap {value} $stream) {air})")
+        });
+        let iterate = (0..2).fold("(null)".to_string(), |air, depth| {
+            format!("(fold #canon i{depth} (seq {air} (next i{depth})))")
+        });
+        let repeat = (0..64).fold("(null)".to_string(), |air, _| {
+            format!("(seq {iterate} {air})")
+        });
+        format!("(new $stream (seq {fill} (seq (canon %init_peer_id% $stream #canon) {repeat})))")
+    }
+
+    /// Increments the epoch of the backend until dropped, as the host is supposed to do.
+    struct EpochTicker {
+        stop: Arc<AtomicBool>,
+        thread: Option<std::thread::JoinHandle<()>>,
+    }
+
+    impl EpochTicker {
+        fn start(wasm_backend: WasmtimeWasmBackend, period: Duration) -> Self {
+            let stop = Arc::new(AtomicBool::new(false));
+            let thread_stop = stop.clone();
+            let thread = std::thread::spawn(move || {
+                while !thread_stop.load(Ordering::Relaxed) {
+                    std::thread::sleep(period);
+                    wasm_backend.increment_epoch();
+                }
+            });
+
+            Self {
+                stop,
+                thread: Some(thread),
+            }
+        }
+    }
+
+    impl Drop for EpochTicker {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+            if let Some(thread) = self.thread.take() {
+                thread.join().unwrap();
+            }
+        }
+    }
+
+    async fn wasm_runner(
+        limits: AVMRuntimeLimits,
+        wasm_backend: WasmtimeWasmBackend,
+    ) -> AVMRunner<WasmtimeWasmBackend> {
+        AVMRunner::new(PathBuf::from(AIR_WASM_PATH), None, limits, 0, wasm_backend)
+            .await
+            .unwrap()
+    }
+
+    #[allow(clippy::result_large_err)]
+    async fn call(
+        runner: &mut AVMRunner<WasmtimeWasmBackend>,
+        air: &str,
+    ) -> RunnerResult<RawAVMOutcome> {
+        let keypair = KeyPair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id().to_string();
+
+        runner
+            .call(
+                air,
+                vec![],
+                vec![],
+                peer_id.clone(),
+                0,
+                u32::MAX,
+                peer_id,
+                <_>::default(),
+                &keypair,
+                "particle".to_string(),
+                b"particle_signature".to_vec(),
+            )
+            .await
+    }
+
+    fn record_types(run_parameters_fields_count: usize) -> MRecordTypes {
+        let fields = (0..run_parameters_fields_count)
+            .map(|id| IRecordFieldType {
+                name: format!("field_{id}"),
+                ty: IType::U8,
+            })
+            .collect();
+        let record_type = IRecordType {
+            name: "RunParameters".to_owned(),
+            fields: NEVec::new(fields).unwrap(),
+        };
+        MRecordTypes::from([(RUN_PARAMETERS_RECORD_ID, Arc::new(record_type))])
+    }
+
+    fn invoke_signature() -> MarineFunctionSignature {
+        let argument = |name: &str, ty| IFunctionArg {
+            name: name.to_owned(),
+            ty,
+        };
+        MarineFunctionSignature {
+            name: Arc::new("invoke".to_owned()),
+            arguments: Arc::new(vec![
+                argument("air", IType::String),
+                argument("prev_data", IType::ByteArray),
+                argument("data", IType::ByteArray),
+                argument("run_parameters", IType::Record(RUN_PARAMETERS_RECORD_ID)),
+                argument("call_results", IType::ByteArray),
+            ]),
+            outputs: Arc::new(vec![IType::Record(OUTCOME_RECORD_ID)]),
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn args(
+        call_policy: SerializedCallPolicy,
+        run_parameters_fields_count: usize,
+    ) -> RunnerResult<Vec<IValue>> {
+        let keypair = KeyPair::generate_ed25519();
+        let settings = RunnerSettings {
+            call_policy,
+            call_provenance_enabled: true,
+            call_deduplication_enabled: true,
+            ..<_>::default()
+        };
+        prepare_args(
+            "(null)".to_owned(),
+            vec![],
+            IValue::ByteArray(vec![]),
+            "current_peer_id".to_owned(),
+            "init_peer_id".to_owned(),
+            42,
+            1000,
+            AVMRuntimeLimits::default().into(),
+            <_>::default(),
+            CallSigner::KeyPair(&keypair).key_args().unwrap(),
+            &settings,
+            "particle_id".to_owned(),
+            b"particle_signature".to_vec(),
+            run_parameters_fields_count,
+        )
+    }
+
+    #[test]
+    fn legacy_interpreter_gets_run_parameters_prefix() {
+        let record_types = record_types(LEGACY_RUN_PARAMETERS_FIELDS_COUNT);
+        let interface = MarineModuleInterface {
+            record_types: &record_types,
+            function_signatures: vec![invoke_signature()],
+        };
+        let fields_count = run_parameters_fields_count(&interface).unwrap();
+        assert_eq!(fields_count, LEGACY_RUN_PARAMETERS_FIELDS_COUNT);
+
+        let args = args(<_>::default(), fields_count).unwrap();
+        let IValue::Record(run_parameters) = &args[3] else {
+            panic!("run parameters should be a record: {:?}", args[3]);
+        };
+        assert_eq!(run_parameters.len(), LEGACY_RUN_PARAMETERS_FIELDS_COUNT);
+        assert_eq!(run_parameters[6], IValue::String("particle_id".to_owned()));
+    }
+
+    #[test]
+    fn legacy_interpreter_rejects_call_policy() {
+        let call_policy = CallPolicy {
+            rules: vec![CallRule {
+                service_id: "*".to_owned(),
+                ..<_>::default()
+            }],
+        };
+        let call_policy = CallPolicyRepr.serialize(&call_policy).unwrap();
+
+        let result = args(call_policy, LEGACY_RUN_PARAMETERS_FIELDS_COUNT);
+        assert!(
+            matches!(result, Err(RunnerError::IncompatibleInterpreter(_))),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    fn legacy_interpreter_outcome_has_no_signing_payload() {
+        let outcome = IValue::Record(
+            NEVec::new(vec![
+                IValue::S64(0),
+                IValue::String(String::new()),
+                IValue::ByteArray(b"data".to_vec()),
+                IValue::Array(vec![IValue::String("next_peer".to_owned())]),
+                IValue::ByteArray(vec![]),
+                IValue::Boolean(false),
+                IValue::Boolean(false),
+                IValue::Boolean(false),
+            ])
+            .unwrap(),
+        );
+
+        let outcome = InterpreterOutcome::from_ivalue(outcome).unwrap();
+        assert_eq!(outcome.data, b"data");
+        assert_eq!(outcome.next_peer_pks, ["next_peer"]);
+        assert!(outcome.signing_payload.is_empty());
+    }
+
+    #[test]
+    fn interpreter_without_invoke_rejected() {
+        let record_types = record_types(LEGACY_RUN_PARAMETERS_FIELDS_COUNT);
+        let interface = MarineModuleInterface {
+            record_types: &record_types,
+            function_signatures: vec![],
+        };
+
+        let result = run_parameters_fields_count(&interface);
+        assert!(
+            matches!(result, Err(RunnerError::IncompatibleInterpreter(_))),
+            "{result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn execution_timeout() {
+        let wasm_backend = WasmtimeWasmBackend::new_async().unwrap();
+        let timeout = Duration::from_millis(50);
+        let limits = AVMRuntimeLimits::default().with_execution_timeout(timeout);
+        let mut runner = wasm_runner(limits, wasm_backend.clone()).await;
+        let _ticker = EpochTicker::start(wasm_backend, Duration::from_millis(5));
+
+        let result = call(&mut runner, &long_running_air()).await;
+
+        assert!(
+            matches!(result, Err(RunnerError::ExecutionTimeout { timeout: t }) if t == timeout),
+            "{result:?}"
+        );
+        // the interrupted interpreter is re-instantiated
+        let outcome = call(&mut runner, "(null)").await.unwrap();
+        assert_eq!(outcome.ret_code, 0, "{}", outcome.error_message);
+    }
+}
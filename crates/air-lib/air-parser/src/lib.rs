@@ -33,6 +33,7 @@ mod parser;
 
 pub use parser::lexer::AirPos;
 pub use parser::parse;
+pub use parser::parse_quiet;
 pub use parser::AIRLexer;
 pub use parser::AIRParser;
 pub use parser::VariableValidator;
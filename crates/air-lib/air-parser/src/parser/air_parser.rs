@@ -38,6 +38,16 @@ thread_local!(static PARSER: AIRParser = AIRParser::new());
 /// Parse AIR `source_code` to `Box<Instruction>`
 #[tracing::instrument(skip_all)]
 pub fn parse(air_script: &str) -> Result<Instruction<'_>, String> {
+    parse_and_report(air_script, true)
+}
+
+/// Parse AIR `source_code` like [`parse`] does, but without writing errors to stderr,
+/// for callers that check many scripts and only need the result.
+pub fn parse_quiet(air_script: &str) -> Result<Instruction<'_>, String> {
+    parse_and_report(air_script, false)
+}
+
+fn parse_and_report(air_script: &str, to_stderr: bool) -> Result<Instruction<'_>, String> {
     let mut files = SimpleFiles::new();
     let file_id = files.add("script.air", air_script);
 
@@ -52,7 +62,7 @@ pub fn parse(air_script: &str) -> Result<Instruction<'_>, String> {
 
         match result {
             Ok(r) if errors.is_empty() => Ok(r),
-            Ok(_) => Err(report_errors(file_id, files, errors)),
+            Ok(_) => Err(report_errors(file_id, files, errors, to_stderr)),
             Err(error) => Err(report_errors(
                 file_id,
                 files,
@@ -60,6 +70,7 @@ pub fn parse(air_script: &str) -> Result<Instruction<'_>, String> {
                     error,
                     dropped_tokens: vec![],
                 }],
+                to_stderr,
             )),
         }
     })
@@ -69,14 +80,17 @@ fn report_errors(
     file_id: usize,
     files: SimpleFiles<&str, &str>,
     errors: Vec<ErrorRecovery<AirPos, Token<'_>, ParserError>>,
+    to_stderr: bool,
 ) -> String {
     let labels = errors_to_labels(file_id, errors);
     let diagnostic = Diagnostic::error().with_labels(labels);
+    let config = codespan_reporting::term::Config::default();
 
     // Write to stderr
-    let writer = StandardStream::stderr(ColorChoice::Auto);
-    let config = codespan_reporting::term::Config::default();
-    term::emit(&mut writer.lock(), &config, &files, &diagnostic).expect("term emit to stderr");
+    if to_stderr {
+        let writer = StandardStream::stderr(ColorChoice::Auto);
+        term::emit(&mut writer.lock(), &config, &files, &diagnostic).expect("term emit to stderr");
+    }
 
     // Return as a string
     let mut buffer = Buffer::no_color();
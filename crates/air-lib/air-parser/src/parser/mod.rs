@@ -34,6 +34,7 @@ mod validator;
 pub mod tests;
 
 pub use self::air_parser::parse;
+pub use self::air_parser::parse_quiet;
 pub use air::AIRParser;
 pub use lexer::AIRLexer;
 pub(crate) use lexer::ERROR;
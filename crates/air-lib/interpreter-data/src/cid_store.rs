@@ -90,7 +90,7 @@ impl<Val: Serialize> CidStore<Val> {
 impl CidStore<RawValue> {
     pub fn verify_raw_value(&self) -> Result<(), CidStoreVerificationError> {
         for (cid, value) in &self.0 {
-            verify_raw_value(cid, value.as_inner())?;
+            verify_raw_value(cid, value.as_inner().as_bytes())?;
         }
         Ok(())
     }
@@ -154,7 +154,7 @@ impl<Val: Serialize> CidTracker<Val> {
 impl CidTracker<RawValue> {
     pub fn track_raw_value(&mut self, value: impl Into<Rc<RawValue>>) -> CID<RawValue> {
         let value = value.into();
-        let cid = raw_value_to_json_cid(value.as_inner());
+        let cid = raw_value_to_json_cid(value.as_inner().as_bytes());
         self.cids.insert(cid.clone(), value);
         cid
     }
@@ -139,11 +139,27 @@ pub struct CallServiceFailed {
     pub ret_code: i32,
     /// This field contains a JSON-serialized value, not a plain error message.
     pub message: Rc<String>,
+    /// The call was rejected by the host call policy, so it wasn't made at all.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub not_authorized: bool,
 }
 
 impl CallServiceFailed {
     pub fn new(ret_code: i32, message: Rc<String>) -> Self {
-        Self { ret_code, message }
+        Self {
+            ret_code,
+            message,
+            not_authorized: false,
+        }
+    }
+
+    /// A call rejected by the host call policy, it has no ret code of a service.
+    pub fn not_authorized(message: Rc<String>) -> Self {
+        Self {
+            ret_code: i32::MAX,
+            message,
+            not_authorized: true,
+        }
     }
 
     pub fn to_value(&self) -> JValue {
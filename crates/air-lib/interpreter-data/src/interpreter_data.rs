@@ -18,7 +18,10 @@
  */
 
 pub(crate) mod errors;
+pub mod inclusion_proof;
 pub(crate) mod repr;
+pub mod size_report;
+pub mod validation;
 pub mod verification;
 
 pub use self::repr::InterpreterDataEnvelopeFormat;
@@ -110,6 +113,13 @@ pub struct Versions {
 
     /// Version of an interpreter produced this data.
     pub interpreter_version: semver::Version,
+
+    /// Peers whose signatures in this data are still salted with the particle id.
+    ///
+    /// Data merged from legacy and current data contains signatures of both schemes until
+    /// the legacy peers sign it again.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub particle_id_salted_peers: Vec<String>,
 }
 
 impl InterpreterDataEnvelope<'_> {
@@ -144,6 +154,11 @@ impl InterpreterDataEnvelope<'_> {
             signatures,
         };
 
+        Self::from_interpreter_data(&inner_data, versions)
+    }
+
+    /// Wraps already built interpreter data into an envelope.
+    pub fn from_interpreter_data(inner_data: &InterpreterData, versions: Versions) -> Self {
         let inner_data = inner_data
             .serialize()
             .expect("shouldn't fail on valid data")
@@ -180,9 +195,31 @@ impl<'data> InterpreterDataEnvelope<'data> {
 
 impl Versions {
     pub fn new(interpreter_version: semver::Version) -> Self {
+        Self::with_data_version(crate::data_version().clone(), interpreter_version)
+    }
+
+    pub fn with_data_version(
+        data_version: semver::Version,
+        interpreter_version: semver::Version,
+    ) -> Self {
         Self {
-            data_version: crate::data_version().clone(),
+            data_version,
             interpreter_version,
+            particle_id_salted_peers: vec![],
         }
     }
+
+    /// Returns true if all signatures of this data are salted with the particle id.
+    pub fn has_particle_id_salt(&self) -> bool {
+        self.data_version < *crate::particle_signature_salt_data_version()
+    }
+
+    /// Returns true if the signature of the peer in this data is salted with the particle id.
+    pub fn peer_has_particle_id_salt(&self, peer_id: &str) -> bool {
+        self.has_particle_id_salt()
+            || self
+                .particle_id_salted_peers
+                .iter()
+                .any(|salted_peer_id| salted_peer_id == peer_id)
+    }
 }
@@ -0,0 +1,312 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Compact proofs that a single call or canon result was produced by a particular peer.
+//!
+//! A proof contains the result itself and the producing peer's sorted CID list with its
+//! signature, so it can be checked against the peer's public key and the particle salt without
+//! the rest of the particle data.
+
+use super::verification::group_peers_cids;
+use super::verification::DataVerifierError;
+use crate::CanonCidAggregate;
+use crate::CanonResultCidAggregate;
+use crate::CidInfo;
+use crate::CidStore;
+use crate::CidStoreVerificationError;
+use crate::InterpreterData;
+use crate::Provenance;
+use crate::RawValue;
+use crate::ServiceResultCidAggregate;
+
+use air_interpreter_cid::raw_value_to_json_cid;
+use air_interpreter_cid::value_to_json_cid;
+use air_interpreter_cid::CidCalculationError;
+use air_interpreter_cid::CidRef;
+use air_interpreter_cid::CID;
+use air_interpreter_signatures::KeyError;
+use air_interpreter_signatures::KeyFormatWhitelist;
+use air_interpreter_signatures::PublicKey;
+use air_interpreter_signatures::Signature;
+use air_interpreter_signatures::VerificationError;
+use polyplets::SecurityTetraplet;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error as ThisError;
+
+use std::rc::Rc;
+
+/// A proof that a result was produced and signed by a peer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof<Payload> {
+    /// The result itself.
+    pub payload: Payload,
+
+    /// Sorted CIDs of all values the producing peer has signed.
+    pub peer_cids: Vec<Rc<CidRef>>,
+
+    /// The producing peer's signature of `peer_cids`.
+    pub signature: Signature,
+}
+
+/// A proof of a single call result.
+pub type ServiceResultProof = InclusionProof<ServiceResultPayload>;
+
+/// A proof of a single canon result.
+pub type CanonResultProof = InclusionProof<CanonResultPayload>;
+
+/// Expanded form of the `ServiceResultCidAggregate`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceResultPayload {
+    pub value: Rc<RawValue>,
+    pub tetraplet: Rc<SecurityTetraplet>,
+    pub argument_hash: Rc<str>,
+}
+
+/// Expanded form of the `CanonResultCidAggregate`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanonResultPayload {
+    pub tetraplet: Rc<SecurityTetraplet>,
+    pub values: Vec<CanonElementPayload>,
+}
+
+/// Expanded form of the `CanonCidAggregate`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanonElementPayload {
+    pub value: Rc<RawValue>,
+    pub tetraplet: Rc<SecurityTetraplet>,
+    pub provenance: Provenance,
+}
+
+#[derive(Debug, ThisError)]
+pub enum InclusionProofError {
+    #[error(transparent)]
+    CidStore(#[from] CidStoreVerificationError),
+
+    #[error(transparent)]
+    DataVerifier(#[from] DataVerifierError),
+
+    #[error(transparent)]
+    CidCalculation(#[from] CidCalculationError),
+
+    #[error("{type_name} with CID {cid:?} was not found in the data")]
+    MissingValue {
+        type_name: &'static str,
+        cid: Rc<CidRef>,
+    },
+
+    #[error("CID {0:?} is not signed by its producing peer")]
+    NotSigned(Rc<CidRef>),
+
+    #[error("malformed key: {0}")]
+    MalformedKey(#[from] KeyError),
+
+    #[error(
+        "result was produced by {tetraplet_peer_pk:?}, but the key belongs to {key_peer_id:?}"
+    )]
+    PeerIdMismatch {
+        tetraplet_peer_pk: String,
+        key_peer_id: String,
+    },
+
+    #[error("signature mismatch: {0}")]
+    SignatureMismatch(#[from] VerificationError),
+}
+
+impl ServiceResultProof {
+    /// Extract a proof of the service result with the `cid` from the data.
+    pub fn from_data(
+        data: &InterpreterData,
+        cid: &CID<ServiceResultCidAggregate>,
+    ) -> Result<Self, InclusionProofError> {
+        data.cid_info.verify()?;
+
+        let payload = ServiceResultPayload::from_cid_info(&data.cid_info, cid)?;
+        Self::from_payload(data, payload, cid.get_inner())
+    }
+}
+
+impl CanonResultProof {
+    /// Extract a proof of the canon result with the `cid` from the data.
+    pub fn from_data(
+        data: &InterpreterData,
+        cid: &CID<CanonResultCidAggregate>,
+    ) -> Result<Self, InclusionProofError> {
+        data.cid_info.verify()?;
+
+        let payload = CanonResultPayload::from_cid_info(&data.cid_info, cid)?;
+        Self::from_payload(data, payload, cid.get_inner())
+    }
+}
+
+impl<Payload: ProofPayload> InclusionProof<Payload> {
+    fn from_payload(
+        data: &InterpreterData,
+        payload: Payload,
+        cid: Rc<CidRef>,
+    ) -> Result<Self, InclusionProofError> {
+        let grouped_cids = group_peers_cids(data, KeyFormatWhitelist::all())?;
+        let peer_info = grouped_cids
+            .get(payload.tetraplet().peer_pk.as_str())
+            .filter(|peer_info| peer_info.cids.binary_search(&cid).is_ok())
+            .ok_or(InclusionProofError::NotSigned(cid))?;
+
+        Ok(Self {
+            payload,
+            peer_cids: peer_info.cids.clone(),
+            signature: peer_info.signature.clone(),
+        })
+    }
+
+    /// Verify the proof with the producing peer's public key and the particle salt.
+    ///
+    /// Keys of any supported algorithm are accepted; check `PublicKey::validate` beforehand
+    /// to apply a narrower whitelist.
+    pub fn verify(&self, public_key: &PublicKey, salt: &str) -> Result<(), InclusionProofError> {
+        public_key.validate(KeyFormatWhitelist::all())?;
+
+        let key_peer_id = public_key.to_peer_id()?;
+        let tetraplet_peer_pk = &self.payload.tetraplet().peer_pk;
+        if *tetraplet_peer_pk != key_peer_id {
+            return Err(InclusionProofError::PeerIdMismatch {
+                tetraplet_peer_pk: tetraplet_peer_pk.clone(),
+                key_peer_id,
+            });
+        }
+
+        let cid = self.payload.calculate_cid()?;
+        if self.peer_cids.binary_search(&cid).is_err() {
+            return Err(InclusionProofError::NotSigned(cid));
+        }
+
+        public_key.verify(&self.peer_cids, salt, &self.signature)?;
+        Ok(())
+    }
+}
+
+/// A result that may be proven with an `InclusionProof`.
+pub trait ProofPayload {
+    /// The tetraplet that defines the peer that produced the result.
+    fn tetraplet(&self) -> &SecurityTetraplet;
+
+    /// Calculate the CID the producing peer has signed.
+    fn calculate_cid(&self) -> Result<Rc<CidRef>, CidCalculationError>;
+}
+
+impl ProofPayload for ServiceResultPayload {
+    fn tetraplet(&self) -> &SecurityTetraplet {
+        &self.tetraplet
+    }
+
+    fn calculate_cid(&self) -> Result<Rc<CidRef>, CidCalculationError> {
+        let aggregate = ServiceResultCidAggregate {
+            value_cid: raw_value_to_json_cid(self.value.as_inner().as_bytes()),
+            argument_hash: self.argument_hash.clone(),
+            tetraplet_cid: value_to_json_cid(&*self.tetraplet)?,
+        };
+        value_to_json_cid(&aggregate).map(|cid| cid.get_inner())
+    }
+}
+
+impl ProofPayload for CanonResultPayload {
+    fn tetraplet(&self) -> &SecurityTetraplet {
+        &self.tetraplet
+    }
+
+    fn calculate_cid(&self) -> Result<Rc<CidRef>, CidCalculationError> {
+        let values = self
+            .values
+            .iter()
+            .map(CanonElementPayload::calculate_cid)
+            .collect::<Result<_, _>>()?;
+        let aggregate = CanonResultCidAggregate {
+            tetraplet: value_to_json_cid(&*self.tetraplet)?,
+            values,
+        };
+        value_to_json_cid(&aggregate).map(|cid| cid.get_inner())
+    }
+}
+
+impl ServiceResultPayload {
+    fn from_cid_info(
+        cid_info: &CidInfo,
+        cid: &CID<ServiceResultCidAggregate>,
+    ) -> Result<Self, InclusionProofError> {
+        let aggregate = get_value(&cid_info.service_result_store, cid)?;
+
+        Ok(Self {
+            value: get_value(&cid_info.value_store, &aggregate.value_cid)?,
+            tetraplet: get_value(&cid_info.tetraplet_store, &aggregate.tetraplet_cid)?,
+            argument_hash: aggregate.argument_hash.clone(),
+        })
+    }
+}
+
+impl CanonResultPayload {
+    fn from_cid_info(
+        cid_info: &CidInfo,
+        cid: &CID<CanonResultCidAggregate>,
+    ) -> Result<Self, InclusionProofError> {
+        let aggregate = get_value(&cid_info.canon_result_store, cid)?;
+
+        let values = aggregate
+            .values
+            .iter()
+            .map(|element_cid| CanonElementPayload::from_cid_info(cid_info, element_cid))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            tetraplet: get_value(&cid_info.tetraplet_store, &aggregate.tetraplet)?,
+            values,
+        })
+    }
+}
+
+impl CanonElementPayload {
+    fn from_cid_info(
+        cid_info: &CidInfo,
+        cid: &CID<CanonCidAggregate>,
+    ) -> Result<Self, InclusionProofError> {
+        let element = get_value(&cid_info.canon_element_store, cid)?;
+
+        Ok(Self {
+            value: get_value(&cid_info.value_store, &element.value)?,
+            tetraplet: get_value(&cid_info.tetraplet_store, &element.tetraplet)?,
+            provenance: element.provenance.clone(),
+        })
+    }
+
+    fn calculate_cid(&self) -> Result<CID<CanonCidAggregate>, CidCalculationError> {
+        let element = CanonCidAggregate {
+            value: raw_value_to_json_cid(self.value.as_inner().as_bytes()),
+            tetraplet: value_to_json_cid(&*self.tetraplet)?,
+            provenance: self.provenance.clone(),
+        };
+        value_to_json_cid(&element)
+    }
+}
+
+fn get_value<Val>(store: &CidStore<Val>, cid: &CID<Val>) -> Result<Rc<Val>, InclusionProofError> {
+    store
+        .get(cid)
+        .ok_or_else(|| InclusionProofError::MissingValue {
+            type_name: std::any::type_name::<Val>(),
+            cid: cid.get_inner(),
+        })
+}
@@ -0,0 +1,466 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Attribution of the serialized data size to the trace states.
+//!
+//! Every CID store entry and peer signature is attributed to the first trace state that
+//! references it, so values shared by several states are counted once.  The states and the
+//! entries are serialized one by one in the attribution order with a single serializer, so
+//! shared parts are serialized only once like in the whole data; the rest of the serialized data,
+//! like the containers, is reported as the format overhead.
+
+use crate::rkyv::RkyvSerializeError;
+use crate::rkyv::RkyvSerializer;
+use crate::CanonCidAggregate;
+use crate::CanonResult;
+use crate::CanonResultCidAggregate;
+use crate::CidStore;
+use crate::ExecutedState;
+use crate::InterpreterData;
+use crate::Provenance;
+use crate::ServiceResultCidAggregate;
+use crate::TracePos;
+
+use air_interpreter_cid::CidRef;
+use air_interpreter_cid::CID;
+use air_interpreter_signatures::PublicKey;
+use air_interpreter_signatures::Signature;
+use polyplets::SecurityTetraplet;
+use rkyv::ser::Serializer;
+use thiserror::Error as ThisError;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Debug, ThisError)]
+pub enum SizeReportError {
+    #[error("failed to serialize data: {0}")]
+    Serialization(#[from] RkyvSerializeError),
+}
+
+/// Sizes of the serialized data parts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SizeReport {
+    /// Size of the whole serialized data.
+    pub total_size: usize,
+
+    /// Sizes attributed to each trace state, indexed by trace position.
+    pub states: Vec<StateSizes>,
+
+    /// Size of CID store entries and signatures that are not referenced from the trace.
+    pub unreferenced_size: usize,
+
+    /// Size of the rest of the data, i.e. the format overhead.
+    pub overhead_size: usize,
+}
+
+/// Sizes attributed to a single trace state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateSizes {
+    /// The trace state itself.
+    pub state: usize,
+
+    /// Values from the value store.
+    pub values: usize,
+
+    /// Tetraplets from the tetraplet store.
+    pub tetraplets: usize,
+
+    /// Service result aggregates.
+    pub service_results: usize,
+
+    /// Canon result aggregates.
+    pub canon_results: usize,
+
+    /// Canon elements.
+    pub canon_elements: usize,
+
+    /// Public keys and signatures of the peers.
+    pub signatures: usize,
+
+    /// Size of the entries the state refers to, but which are attributed to previous states.
+    pub shared: usize,
+
+    /// The tetraplet of the call or canon result, if any.
+    pub tetraplet: Option<Rc<SecurityTetraplet>>,
+}
+
+impl StateSizes {
+    /// Total size attributed to the state; it doesn't include the shared size.
+    pub fn total(&self) -> usize {
+        self.state
+            + self.values
+            + self.tetraplets
+            + self.service_results
+            + self.canon_results
+            + self.canon_elements
+            + self.signatures
+    }
+}
+
+/// A peer, a service and a function that produced a result.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Producer {
+    pub peer_pk: String,
+    pub service_id: String,
+    pub function_name: String,
+}
+
+impl SizeReport {
+    pub fn from_data(data: &InterpreterData) -> Result<Self, SizeReportError> {
+        let total_size = data.serialize()?.len();
+
+        let mut attributor = SizeAttributor::new(data);
+        let states = attributor.attribute_trace()?;
+        let unreferenced_size = attributor.unreferenced_size()?;
+
+        let attributed_size = states.iter().map(StateSizes::total).sum::<usize>();
+        let overhead_size = total_size.saturating_sub(attributed_size + unreferenced_size);
+
+        Ok(Self {
+            total_size,
+            states,
+            unreferenced_size,
+            overhead_size,
+        })
+    }
+
+    /// Trace positions with attributed sizes, the largest first.
+    pub fn largest_states(&self) -> Vec<(TracePos, &StateSizes)> {
+        let mut states: Vec<_> = self
+            .states
+            .iter()
+            .enumerate()
+            .map(|(pos, sizes)| (to_trace_pos(pos), sizes))
+            .collect();
+        states.sort_by_key(|(_, sizes)| std::cmp::Reverse(sizes.total()));
+        states
+    }
+
+    /// Aggregate attributed sizes by the producers of the results.
+    ///
+    /// States without results, like par or ap, are not included.
+    pub fn by_producer(&self) -> BTreeMap<Producer, usize> {
+        let mut result = BTreeMap::<_, usize>::new();
+        for sizes in &self.states {
+            if let Some(tetraplet) = &sizes.tetraplet {
+                let producer = Producer {
+                    peer_pk: tetraplet.peer_pk.clone(),
+                    service_id: tetraplet.service_id.clone(),
+                    function_name: tetraplet.function_name.clone(),
+                };
+                *result.entry(producer).or_default() += sizes.total();
+            }
+        }
+        result
+    }
+}
+
+struct SizeAttributor<'data> {
+    data: &'data InterpreterData,
+    serializer: RkyvSerializer,
+    // sizes of the attributed entries and signatures
+    seen_cids: HashMap<Rc<CidRef>, usize>,
+    peers: HashMap<String, (&'data PublicKey, &'data Signature)>,
+    seen_peers: HashMap<String, usize>,
+}
+
+impl<'data> SizeAttributor<'data> {
+    fn new(data: &'data InterpreterData) -> Self {
+        // keys that are malformed can't be matched with tetraplets, so they are unreferenced
+        let peers = data
+            .signatures
+            .iter()
+            .filter_map(|(public_key, signature)| {
+                let peer_id = public_key.to_peer_id().ok()?;
+                Some((peer_id, (public_key, signature)))
+            })
+            .collect();
+
+        Self {
+            data,
+            serializer: <_>::default(),
+            seen_cids: <_>::default(),
+            peers,
+            seen_peers: <_>::default(),
+        }
+    }
+
+    fn attribute_trace(&mut self) -> Result<Vec<StateSizes>, SizeReportError> {
+        let trace = &self.data.trace;
+        let mut states = Vec::with_capacity(trace.len());
+
+        for state in trace.iter() {
+            let mut sizes = StateSizes {
+                state: self.measure(state)?,
+                ..<_>::default()
+            };
+
+            match state {
+                ExecutedState::Call(call_result) => {
+                    if let Some(cid) = call_result.get_cid() {
+                        self.attribute_service_result(cid, &mut sizes)?;
+                    }
+                }
+                ExecutedState::Canon(CanonResult::Executed(cid)) => {
+                    self.attribute_canon_result(cid, &mut sizes)?;
+                }
+                _ => {}
+            }
+
+            if let Some(tetraplet) = &sizes.tetraplet {
+                let peer_pk = tetraplet.peer_pk.clone();
+                self.attribute_signature(peer_pk, &mut sizes)?;
+            }
+
+            states.push(sizes);
+        }
+
+        // service results and canons referenced only from canon provenances are attributed
+        // to the canons
+        for (state, sizes) in trace.iter().zip(states.iter_mut()) {
+            if let ExecutedState::Canon(CanonResult::Executed(cid)) = state {
+                self.attribute_provenances(cid, sizes)?;
+            }
+        }
+
+        Ok(states)
+    }
+
+    fn attribute_service_result(
+        &mut self,
+        cid: &CID<ServiceResultCidAggregate>,
+        sizes: &mut StateSizes,
+    ) -> Result<(), SizeReportError> {
+        let cid_info = &self.data.cid_info;
+        let Some(service_result) = cid_info.service_result_store.get(cid) else {
+            return Ok(());
+        };
+        sizes.tetraplet = cid_info.tetraplet_store.get(&service_result.tetraplet_cid);
+
+        self.attribute_entry(&cid_info.service_result_store, cid, sizes, |sizes| {
+            &mut sizes.service_results
+        })?;
+        self.attribute_entry(
+            &cid_info.value_store,
+            &service_result.value_cid,
+            sizes,
+            |sizes| &mut sizes.values,
+        )?;
+        self.attribute_entry(
+            &cid_info.tetraplet_store,
+            &service_result.tetraplet_cid,
+            sizes,
+            |sizes| &mut sizes.tetraplets,
+        )?;
+
+        Ok(())
+    }
+
+    fn attribute_canon_result(
+        &mut self,
+        cid: &CID<CanonResultCidAggregate>,
+        sizes: &mut StateSizes,
+    ) -> Result<(), SizeReportError> {
+        let cid_info = &self.data.cid_info;
+        let Some(canon_result) = cid_info.canon_result_store.get(cid) else {
+            return Ok(());
+        };
+        sizes.tetraplet = cid_info.tetraplet_store.get(&canon_result.tetraplet);
+
+        self.attribute_entry(&cid_info.canon_result_store, cid, sizes, |sizes| {
+            &mut sizes.canon_results
+        })?;
+        self.attribute_entry(
+            &cid_info.tetraplet_store,
+            &canon_result.tetraplet,
+            sizes,
+            |sizes| &mut sizes.tetraplets,
+        )?;
+
+        for element_cid in &canon_result.values {
+            self.attribute_canon_element(element_cid, sizes)?;
+        }
+
+        Ok(())
+    }
+
+    fn attribute_canon_element(
+        &mut self,
+        cid: &CID<CanonCidAggregate>,
+        sizes: &mut StateSizes,
+    ) -> Result<(), SizeReportError> {
+        let cid_info = &self.data.cid_info;
+        let Some(element) = cid_info.canon_element_store.get(cid) else {
+            return Ok(());
+        };
+
+        self.attribute_entry(&cid_info.canon_element_store, cid, sizes, |sizes| {
+            &mut sizes.canon_elements
+        })?;
+        self.attribute_entry(&cid_info.value_store, &element.value, sizes, |sizes| {
+            &mut sizes.values
+        })?;
+        self.attribute_entry(
+            &cid_info.tetraplet_store,
+            &element.tetraplet,
+            sizes,
+            |sizes| &mut sizes.tetraplets,
+        )
+    }
+
+    fn attribute_provenances(
+        &mut self,
+        cid: &CID<CanonResultCidAggregate>,
+        sizes: &mut StateSizes,
+    ) -> Result<(), SizeReportError> {
+        let cid_info = &self.data.cid_info;
+        let Some(canon_result) = cid_info.canon_result_store.get(cid) else {
+            return Ok(());
+        };
+
+        for element_cid in &canon_result.values {
+            let Some(element) = cid_info.canon_element_store.get(element_cid) else {
+                continue;
+            };
+
+            // the tetraplet of the canon state remains unchanged
+            let mut provenance_sizes = StateSizes::default();
+            match &element.provenance {
+                Provenance::Literal => {}
+                Provenance::ServiceResult { cid } => {
+                    if !self.seen_cids.contains_key(&*cid.get_inner()) {
+                        self.attribute_service_result(cid, &mut provenance_sizes)?;
+                    }
+                }
+                Provenance::Canon { cid } => {
+                    if !self.seen_cids.contains_key(&*cid.get_inner()) {
+                        self.attribute_canon_result(cid, &mut provenance_sizes)?;
+                    }
+                }
+            }
+
+            sizes.values += provenance_sizes.values;
+            sizes.tetraplets += provenance_sizes.tetraplets;
+            sizes.service_results += provenance_sizes.service_results;
+            sizes.canon_results += provenance_sizes.canon_results;
+            sizes.canon_elements += provenance_sizes.canon_elements;
+        }
+
+        Ok(())
+    }
+
+    fn attribute_signature(
+        &mut self,
+        peer_pk: String,
+        sizes: &mut StateSizes,
+    ) -> Result<(), SizeReportError> {
+        let Some(&(public_key, signature)) = self.peers.get(&peer_pk) else {
+            return Ok(());
+        };
+
+        match self.seen_peers.get(&peer_pk) {
+            Some(size) => sizes.shared += size,
+            None => {
+                let size = self.measure(public_key)? + self.measure(signature)?;
+                self.seen_peers.insert(peer_pk, size);
+                sizes.signatures += size;
+            }
+        }
+        Ok(())
+    }
+
+    fn attribute_entry<Val>(
+        &mut self,
+        store: &CidStore<Val>,
+        cid: &CID<Val>,
+        sizes: &mut StateSizes,
+        field: impl FnOnce(&mut StateSizes) -> &mut usize,
+    ) -> Result<(), SizeReportError>
+    where
+        Rc<Val>: rkyv::Serialize<RkyvSerializer>,
+    {
+        let Some(value) = store.get(cid) else {
+            return Ok(());
+        };
+
+        match self.seen_cids.get(&*cid.get_inner()) {
+            Some(size) => sizes.shared += size,
+            None => {
+                let size = self.measure(cid)? + self.measure(&value)?;
+                self.seen_cids.insert(cid.get_inner(), size);
+                *field(sizes) += size;
+            }
+        }
+        Ok(())
+    }
+
+    fn unreferenced_size(&mut self) -> Result<usize, SizeReportError> {
+        let cid_info = &self.data.cid_info;
+
+        let mut size = self.unreferenced_entries_size(&cid_info.value_store)?
+            + self.unreferenced_entries_size(&cid_info.tetraplet_store)?
+            + self.unreferenced_entries_size(&cid_info.canon_element_store)?
+            + self.unreferenced_entries_size(&cid_info.canon_result_store)?
+            + self.unreferenced_entries_size(&cid_info.service_result_store)?;
+
+        for (public_key, signature) in self.data.signatures.iter() {
+            let seen = public_key
+                .to_peer_id()
+                .map(|peer_id| self.seen_peers.contains_key(&peer_id))
+                .unwrap_or(false);
+            if !seen {
+                size += self.measure(public_key)? + self.measure(signature)?;
+            }
+        }
+
+        Ok(size)
+    }
+
+    fn unreferenced_entries_size<Val>(
+        &mut self,
+        store: &CidStore<Val>,
+    ) -> Result<usize, SizeReportError>
+    where
+        Rc<Val>: rkyv::Serialize<RkyvSerializer>,
+    {
+        let mut size = 0;
+        for (cid, value) in store.iter() {
+            if !self.seen_cids.contains_key(&*cid.get_inner()) {
+                size += self.measure(cid)? + self.measure(value)?;
+            }
+        }
+        Ok(size)
+    }
+
+    /// Serialize the value and return the count of bytes it added to the serialized data.
+    fn measure<Value>(&mut self, value: &Value) -> Result<usize, SizeReportError>
+    where
+        Value: rkyv::Serialize<RkyvSerializer>,
+    {
+        let start_pos = self.serializer.pos();
+        self.serializer.serialize_value(value)?;
+        Ok(self.serializer.pos() - start_pos)
+    }
+}
+
+fn to_trace_pos(pos: usize) -> TracePos {
+    TracePos::try_from(pos).expect("trace position doesn't fit into TracePos")
+}
@@ -0,0 +1,673 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Structural validation of the interpreter data that doesn't require an AIR script.
+//!
+//! The interpreter checks data consistency only while merging it with a script, so corrupt data
+//! may be noticed late or not at all.  The `DataValidator` checks everything that can be checked
+//! without a script and reports all the problems it finds, with trace positions where applicable.
+
+use super::verification::DataSalts;
+use super::verification::DataVerifier;
+use super::verification::DataVerifierError;
+use crate::ApResult;
+use crate::CallResult;
+use crate::CanonResult;
+use crate::CidStore;
+use crate::CidStoreVerificationError;
+use crate::ExecutedState;
+use crate::FoldResult;
+use crate::GenerationIdx;
+use crate::InterpreterData;
+use crate::ParResult;
+use crate::SubTraceDesc;
+use crate::TracePos;
+use crate::ValueRef;
+use crate::Versions;
+
+use air_interpreter_cid::CidRef;
+use air_interpreter_cid::CID;
+use air_interpreter_signatures::KeyFormatWhitelist;
+use thiserror::Error as ThisError;
+
+use std::collections::HashSet;
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Count of subtrace descriptors each fold lore has to contain.
+const FOLD_SUBTRACES_COUNT: usize = 2;
+
+#[derive(Debug, ThisError)]
+pub enum DataValidationError {
+    #[error(transparent)]
+    CidStore(#[from] CidStoreVerificationError),
+
+    #[error(transparent)]
+    Signatures(#[from] DataVerifierError),
+
+    #[error("state {pos}: {type_name} with CID {cid:?} is not found in the CID store")]
+    MissingCid {
+        pos: TracePos,
+        type_name: &'static str,
+        cid: Rc<CidRef>,
+    },
+
+    #[error("par state {pos}: subtraces of sizes {par_result:?} end after the enclosing subtrace end {subtrace_end}")]
+    ParOutOfBounds {
+        pos: TracePos,
+        par_result: ParResult,
+        subtrace_end: usize,
+    },
+
+    #[error("fold state {pos}: subtrace lengths overflow")]
+    FoldLenOverflow { pos: TracePos },
+
+    #[error("fold state {pos}: {states_count} states end after the enclosing subtrace end {subtrace_end}")]
+    FoldOutOfBounds {
+        pos: TracePos,
+        states_count: u32,
+        subtrace_end: usize,
+    },
+
+    #[error("fold state {pos}: lore {lore_idx} contains {count} subtraces instead of {FOLD_SUBTRACES_COUNT}")]
+    FoldSubtracesCount {
+        pos: TracePos,
+        lore_idx: usize,
+        count: usize,
+    },
+
+    #[error("fold state {pos}: subtrace {desc:?} of lore {lore_idx} is out of the fold states {fold_states:?}")]
+    FoldSubtraceOutOfBounds {
+        pos: TracePos,
+        lore_idx: usize,
+        desc: SubTraceDesc,
+        fold_states: Range<usize>,
+    },
+
+    #[error("fold state {pos}: subtraces {first:?} and {second:?} overlap")]
+    FoldSubtracesOverlap {
+        pos: TracePos,
+        first: SubTraceDesc,
+        second: SubTraceDesc,
+    },
+
+    #[error("fold state {pos}: several lores refer to the same value position {value_pos}")]
+    FoldDuplicateValuePos { pos: TracePos, value_pos: TracePos },
+
+    #[error("fold state {pos}: value position {value_pos} doesn't refer to a stream value state")]
+    FoldInvalidValuePos { pos: TracePos, value_pos: TracePos },
+
+    #[error("ap state {pos}: {ap_result:?} contains {} generations instead of one", ap_result.res_generations.len())]
+    ApGenerationsCount { pos: TracePos, ap_result: ApResult },
+
+    #[error("stream value state {pos}: generation {generation} exceeds the count of stream values {values_count}")]
+    GenerationOutOfBounds {
+        pos: TracePos,
+        generation: GenerationIdx,
+        values_count: usize,
+    },
+
+    #[error("stream value state {pos}: no stream value has generation {missing}, that precedes generation {generation}")]
+    GenerationGap {
+        pos: TracePos,
+        generation: GenerationIdx,
+        missing: GenerationIdx,
+    },
+}
+
+impl DataValidationError {
+    /// A trace position of the state the error relates to, if any.
+    pub fn trace_pos(&self) -> Option<TracePos> {
+        use DataValidationError::*;
+
+        match self {
+            CidStore(_) | Signatures(_) => None,
+            MissingCid { pos, .. }
+            | ParOutOfBounds { pos, .. }
+            | FoldLenOverflow { pos }
+            | FoldOutOfBounds { pos, .. }
+            | FoldSubtracesCount { pos, .. }
+            | FoldSubtraceOutOfBounds { pos, .. }
+            | FoldSubtracesOverlap { pos, .. }
+            | FoldDuplicateValuePos { pos, .. }
+            | FoldInvalidValuePos { pos, .. }
+            | ApGenerationsCount { pos, .. }
+            | GenerationOutOfBounds { pos, .. }
+            | GenerationGap { pos, .. } => Some(*pos),
+        }
+    }
+}
+
+/// An util for checking data consistency without an AIR script.
+///
+/// It checks the CID store, CID references from the trace, par and fold subtrace bounds,
+/// stream generations and, if the salts are known, peers' signatures.
+pub struct DataValidator<'data> {
+    data: &'data InterpreterData,
+    signatures: Option<(&'data Versions, DataSalts<'data>)>,
+    key_formats: KeyFormatWhitelist,
+}
+
+impl<'data> DataValidator<'data> {
+    /// Signatures are not checked by default; keys are checked anyway against the default
+    /// algorithm whitelist.
+    pub fn new(data: &'data InterpreterData) -> Self {
+        Self {
+            data,
+            signatures: None,
+            key_formats: <_>::default(),
+        }
+    }
+
+    /// Check signatures too, each of them is salted according to the data versions.
+    pub fn with_signatures(mut self, versions: &'data Versions, salts: DataSalts<'data>) -> Self {
+        self.signatures = Some((versions, salts));
+        self
+    }
+
+    /// Check keys against the provided algorithm whitelist instead of the default one.
+    pub fn with_key_formats(mut self, key_formats: KeyFormatWhitelist) -> Self {
+        self.key_formats = key_formats;
+        self
+    }
+
+    /// Run all the checks and return all found errors; the data is valid if the result is empty.
+    pub fn validate(&self) -> Vec<DataValidationError> {
+        let mut errors = vec![];
+
+        let cid_store_result = self.data.cid_info.verify();
+        let cid_store_valid = cid_store_result.is_ok();
+        errors.extend(cid_store_result.err().map(Into::into));
+
+        let errors_before_trace = errors.len();
+        let trace_len = self.data.trace.len();
+        self.validate_subtrace(0..trace_len, &mut errors);
+        let references_valid = !errors[errors_before_trace..]
+            .iter()
+            .any(|error| matches!(error, DataValidationError::MissingCid { .. }));
+        self.validate_generations(&mut errors);
+
+        // the data verifier expects all the CIDs to be resolvable
+        if cid_store_valid && references_valid {
+            let verification_result = match self.signatures {
+                Some((versions, salts)) => {
+                    DataVerifier::with_versions(self.data, versions, salts, self.key_formats)
+                        .and_then(|verifier| verifier.verify())
+                }
+                None => DataVerifier::new(self.data, "", self.key_formats).map(drop),
+            };
+            errors.extend(verification_result.err().map(Into::into));
+        }
+
+        errors
+    }
+
+    fn validate_subtrace(&self, range: Range<usize>, errors: &mut Vec<DataValidationError>) {
+        let mut pos = range.start;
+
+        while pos < range.end {
+            let trace_pos = to_trace_pos(pos);
+
+            match &self.data.trace[trace_pos] {
+                ExecutedState::Par(par_result) => {
+                    pos = self.validate_par(pos, par_result, range.end, errors);
+                }
+                ExecutedState::Fold(fold_result) => {
+                    pos = self.validate_fold(pos, fold_result, range.end, errors);
+                }
+                ExecutedState::Call(call_result) => {
+                    self.validate_call(trace_pos, call_result, errors);
+                    pos += 1;
+                }
+                ExecutedState::Canon(CanonResult::Executed(cid)) => {
+                    let store = &self.data.cid_info.canon_result_store;
+                    errors.extend(check_reference(trace_pos, store, cid));
+                    pos += 1;
+                }
+                ExecutedState::Canon(CanonResult::RequestSentBy(_)) => {
+                    pos += 1;
+                }
+                ExecutedState::Ap(ap_result) => {
+                    if ap_result.res_generations.len() != 1 {
+                        errors.push(DataValidationError::ApGenerationsCount {
+                            pos: trace_pos,
+                            ap_result: ap_result.clone(),
+                        });
+                    }
+                    pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the position right after the par subtraces.
+    fn validate_par(
+        &self,
+        pos: usize,
+        par_result: &ParResult,
+        subtrace_end: usize,
+        errors: &mut Vec<DataValidationError>,
+    ) -> usize {
+        let left_begin = pos + 1;
+        let left_end = left_begin.saturating_add(par_result.left_size as usize);
+        let right_end = left_end.saturating_add(par_result.right_size as usize);
+
+        if right_end > subtrace_end {
+            errors.push(DataValidationError::ParOutOfBounds {
+                pos: to_trace_pos(pos),
+                par_result: *par_result,
+                subtrace_end,
+            });
+        }
+
+        // malformed sizes are clamped to find more errors in the subtraces
+        let left_end = left_end.min(subtrace_end);
+        let right_end = right_end.min(subtrace_end);
+        self.validate_subtrace(left_begin..left_end, errors);
+        self.validate_subtrace(left_end..right_end, errors);
+
+        right_end
+    }
+
+    /// Returns the position right after the fold states.
+    ///
+    /// Fold subtraces are laid out right after the fold state.  Their lengths include only
+    /// states of the iteration itself, while a par inside an iteration may include states of
+    /// the next iterations, so the fold states are validated as a single subtrace.
+    fn validate_fold(
+        &self,
+        pos: usize,
+        fold_result: &FoldResult,
+        subtrace_end: usize,
+        errors: &mut Vec<DataValidationError>,
+    ) -> usize {
+        let trace_pos = to_trace_pos(pos);
+        let fold_begin = pos + 1;
+
+        let states_count = fold_result
+            .lore
+            .iter()
+            .flat_map(|lore| &lore.subtraces_desc)
+            .try_fold(0u32, |count, desc| count.checked_add(desc.subtrace_len));
+        let fold_end = match states_count {
+            Some(states_count) => {
+                let fold_end = fold_begin.saturating_add(states_count as usize);
+                if fold_end > subtrace_end {
+                    errors.push(DataValidationError::FoldOutOfBounds {
+                        pos: trace_pos,
+                        states_count,
+                        subtrace_end,
+                    });
+                }
+                fold_end.min(subtrace_end)
+            }
+            None => {
+                errors.push(DataValidationError::FoldLenOverflow { pos: trace_pos });
+                subtrace_end
+            }
+        };
+
+        let mut value_positions = HashSet::new();
+        let mut descs = vec![];
+        for (lore_idx, lore) in fold_result.lore.iter().enumerate() {
+            if lore.subtraces_desc.len() != FOLD_SUBTRACES_COUNT {
+                errors.push(DataValidationError::FoldSubtracesCount {
+                    pos: trace_pos,
+                    lore_idx,
+                    count: lore.subtraces_desc.len(),
+                });
+            }
+
+            if !value_positions.insert(lore.value_pos) {
+                errors.push(DataValidationError::FoldDuplicateValuePos {
+                    pos: trace_pos,
+                    value_pos: lore.value_pos,
+                });
+            }
+            if !self.is_stream_value(lore.value_pos) {
+                errors.push(DataValidationError::FoldInvalidValuePos {
+                    pos: trace_pos,
+                    value_pos: lore.value_pos,
+                });
+            }
+
+            for desc in &lore.subtraces_desc {
+                let desc_begin = usize::from(desc.begin_pos);
+                let desc_end = desc_begin.saturating_add(desc.subtrace_len as usize);
+                if desc_begin < fold_begin || desc_end > fold_end {
+                    errors.push(DataValidationError::FoldSubtraceOutOfBounds {
+                        pos: trace_pos,
+                        lore_idx,
+                        desc: *desc,
+                        fold_states: fold_begin..fold_end,
+                    });
+                } else if desc.subtrace_len != 0 {
+                    descs.push(*desc);
+                }
+            }
+        }
+
+        descs.sort_unstable_by_key(|desc| desc.begin_pos);
+        for pair in descs.windows(2) {
+            let first_end = usize::from(pair[0].begin_pos) + pair[0].subtrace_len as usize;
+            if first_end > usize::from(pair[1].begin_pos) {
+                errors.push(DataValidationError::FoldSubtracesOverlap {
+                    pos: trace_pos,
+                    first: pair[0],
+                    second: pair[1],
+                });
+            }
+        }
+
+        self.validate_subtrace(fold_begin..fold_end, errors);
+        fold_end
+    }
+
+    fn validate_call(
+        &self,
+        pos: TracePos,
+        call_result: &CallResult,
+        errors: &mut Vec<DataValidationError>,
+    ) {
+        let cid = match call_result {
+            CallResult::Executed(ValueRef::Scalar(cid))
+            | CallResult::Executed(ValueRef::Stream { cid, .. })
+            | CallResult::Failed(cid) => cid,
+            // the unused values are not stored
+            CallResult::Executed(ValueRef::Unused(_)) | CallResult::RequestSentBy(_) => return,
+        };
+
+        let store = &self.data.cid_info.service_result_store;
+        errors.extend(check_reference(pos, store, cid));
+    }
+
+    /// Streams are compactified after each execution, so generations of every stream are
+    /// dense: a value of generation `n` means the same stream has values of all generations
+    /// before `n`.  The streams are unknown without a script, so the check is done over
+    /// all of them together.
+    fn validate_generations(&self, errors: &mut Vec<DataValidationError>) {
+        let stream_values = self
+            .data
+            .trace
+            .iter()
+            .enumerate()
+            .flat_map(|(pos, state)| {
+                let generations = match state {
+                    ExecutedState::Call(CallResult::Executed(ValueRef::Stream {
+                        generation,
+                        ..
+                    })) => std::slice::from_ref(generation),
+                    ExecutedState::Ap(ap_result) => &ap_result.res_generations[..],
+                    _ => &[],
+                };
+                generations
+                    .iter()
+                    .map(move |&generation| (to_trace_pos(pos), generation))
+            })
+            .collect::<Vec<_>>();
+
+        let values_count = stream_values.len();
+        let generations = stream_values
+            .iter()
+            .map(|&(_, generation)| generation)
+            .collect::<HashSet<_>>();
+
+        for (pos, generation) in stream_values {
+            if usize::from(generation) >= values_count {
+                errors.push(DataValidationError::GenerationOutOfBounds {
+                    pos,
+                    generation,
+                    values_count,
+                });
+            } else if generation > 0 && !generations.contains(&generation.prev()) {
+                errors.push(DataValidationError::GenerationGap {
+                    pos,
+                    generation,
+                    missing: generation.prev(),
+                });
+            }
+        }
+    }
+
+    /// Fold iterates over stream values that are produced by calls and aps.
+    fn is_stream_value(&self, value_pos: TracePos) -> bool {
+        matches!(
+            self.data.trace.get(value_pos),
+            Some(ExecutedState::Call(CallResult::Executed(
+                ValueRef::Stream { .. }
+            ))) | Some(ExecutedState::Ap(_))
+        )
+    }
+}
+
+fn check_reference<Val>(
+    pos: TracePos,
+    store: &CidStore<Val>,
+    cid: &CID<Val>,
+) -> Option<DataValidationError> {
+    match store.get(cid) {
+        Some(_) => None,
+        None => Some(DataValidationError::MissingCid {
+            pos,
+            type_name: std::any::type_name::<Val>(),
+            cid: cid.get_inner(),
+        }),
+    }
+}
+
+fn to_trace_pos(pos: usize) -> TracePos {
+    TracePos::try_from(pos).expect("trace position doesn't fit into TracePos")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FoldSubTraceLore;
+
+    fn fold_lore(value_pos: u32, subtraces: [(u32, usize); 2]) -> FoldSubTraceLore {
+        FoldSubTraceLore {
+            value_pos: value_pos.into(),
+            subtraces_desc: subtraces
+                .into_iter()
+                .map(|(begin_pos, len)| SubTraceDesc::new(begin_pos.into(), len))
+                .collect(),
+        }
+    }
+
+    fn sent_call() -> ExecutedState {
+        ExecutedState::Call(CallResult::sent_peer_id(Rc::new("peer_id".to_owned())))
+    }
+
+    fn validate(trace: Vec<ExecutedState>) -> Vec<DataValidationError> {
+        let data = InterpreterData {
+            trace: trace.into(),
+            ..<_>::default()
+        };
+        DataValidator::new(&data).validate()
+    }
+
+    // (fold $stream i (par (call ...) (next i))) over two values
+    fn fold_with_par_trace() -> Vec<ExecutedState> {
+        vec![
+            ExecutedState::Ap(ApResult::new(GenerationIdx::from(0))),
+            ExecutedState::Ap(ApResult::new(GenerationIdx::from(1))),
+            ExecutedState::Fold(FoldResult {
+                lore: vec![
+                    fold_lore(0, [(3, 2), (7, 0)]),
+                    fold_lore(1, [(5, 2), (7, 0)]),
+                ],
+            }),
+            ExecutedState::par(1, 2),
+            sent_call(),
+            ExecutedState::par(1, 0),
+            sent_call(),
+        ]
+    }
+
+    #[test]
+    fn test_valid_fold_with_par() {
+        let errors = validate(fold_with_par_trace());
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn test_par_out_of_bounds() {
+        let mut trace = fold_with_par_trace();
+        trace[5] = ExecutedState::par(1, 1);
+
+        let errors = validate(trace);
+        assert!(
+            matches!(
+                &errors[..],
+                [DataValidationError::ParOutOfBounds { pos, subtrace_end: 7, .. }] if *pos == 5.into()
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn test_fold_subtraces_overlap() {
+        let mut trace = fold_with_par_trace();
+        trace[2] = ExecutedState::Fold(FoldResult {
+            lore: vec![
+                fold_lore(0, [(3, 2), (7, 0)]),
+                fold_lore(1, [(4, 2), (7, 0)]),
+            ],
+        });
+
+        let errors = validate(trace);
+        assert!(
+            matches!(
+                &errors[..],
+                [DataValidationError::FoldSubtracesOverlap { pos, .. }] if *pos == 2.into()
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn test_fold_out_of_bounds() {
+        let mut trace = fold_with_par_trace();
+        trace[2] = ExecutedState::Fold(FoldResult {
+            lore: vec![
+                fold_lore(0, [(3, 2), (7, 0)]),
+                fold_lore(1, [(5, 3), (7, 0)]),
+            ],
+        });
+
+        let errors = validate(trace);
+        assert!(
+            matches!(
+                &errors[..],
+                [
+                    DataValidationError::FoldOutOfBounds {
+                        states_count: 5,
+                        subtrace_end: 7,
+                        ..
+                    },
+                    DataValidationError::FoldSubtraceOutOfBounds { lore_idx: 1, .. },
+                ]
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn test_fold_invalid_value_pos() {
+        let mut trace = fold_with_par_trace();
+        trace[1] = sent_call();
+
+        let errors = validate(trace);
+        assert!(
+            matches!(
+                &errors[..],
+                [DataValidationError::FoldInvalidValuePos { value_pos, .. }] if *value_pos == 1.into()
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn test_ap_generations_count() {
+        let trace = vec![ExecutedState::Ap(ApResult {
+            res_generations: vec![],
+        })];
+
+        let errors = validate(trace);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].trace_pos(), Some(0.into()));
+        assert!(matches!(
+            errors[0],
+            DataValidationError::ApGenerationsCount { .. }
+        ));
+    }
+
+    #[test]
+    fn test_ap_generation_gap() {
+        let trace = vec![
+            ExecutedState::Ap(ApResult::new(GenerationIdx::from(0))),
+            ExecutedState::Ap(ApResult::new(GenerationIdx::from(2))),
+            ExecutedState::Ap(ApResult::new(GenerationIdx::from(2))),
+        ];
+
+        let errors = validate(trace);
+        let positions: Vec<_> = errors.iter().map(DataValidationError::trace_pos).collect();
+        assert_eq!(positions, vec![Some(1.into()), Some(2.into())]);
+        assert!(
+            errors
+                .iter()
+                .all(|error| matches!(error, DataValidationError::GenerationGap { missing, .. } if *missing == 1)),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn test_ap_generation_out_of_bounds() {
+        let trace = vec![
+            ExecutedState::Ap(ApResult::new(GenerationIdx::from(0))),
+            ExecutedState::Ap(ApResult::new(GenerationIdx::from(7))),
+        ];
+
+        let errors = validate(trace);
+        assert!(
+            matches!(
+                &errors[..],
+                [DataValidationError::GenerationOutOfBounds { generation, values_count: 2, .. }] if *generation == 7
+            ),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn test_missing_cid() {
+        let cid = "bagaaihrarcyykpv4oj7zwdbepczyfthxya4og7s2rwvrzolm5kg2eu5dz3xa";
+        let trace = vec![
+            sent_call(),
+            ExecutedState::Canon(CanonResult::executed(CID::new(cid))),
+            ExecutedState::Call(CallResult::executed_scalar(CID::new(cid))),
+        ];
+
+        let errors = validate(trace);
+        let positions: Vec<_> = errors.iter().map(DataValidationError::trace_pos).collect();
+        assert_eq!(positions, vec![Some(1.into()), Some(2.into())]);
+        assert!(errors
+            .iter()
+            .all(|error| matches!(error, DataValidationError::MissingCid { .. })));
+    }
+}
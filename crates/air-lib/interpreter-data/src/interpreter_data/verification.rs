@@ -23,8 +23,11 @@ use crate::CidInfo;
 use crate::ExecutedState;
 use crate::ExecutionTrace;
 use crate::InterpreterData;
+use crate::Versions;
 
 use air_interpreter_cid::{CidRef, CID};
+use air_interpreter_signatures::BatchVerifier;
+use air_interpreter_signatures::KeyFormatWhitelist;
 use air_interpreter_signatures::PublicKey;
 use air_interpreter_signatures::Signature;
 use air_interpreter_signatures::SignatureStore;
@@ -34,60 +37,99 @@ use std::rc::Rc;
 
 const CANNOT_HAPPEN_IN_VERIFIED_CID_STORE: &str = "cannot happen in a checked CID store";
 
+/// Salts a peer's signature may be salted with, depending on the data versions.
+#[derive(Debug, Clone, Copy)]
+pub struct DataSalts<'data> {
+    /// The salt of legacy data signatures.
+    pub particle_id: &'data str,
+    /// The salt of current data signatures.
+    pub particle_signature: &'data str,
+}
+
+/// Signatures merged from the prev and the current data.
+#[derive(Debug, Default)]
+pub struct MergedSignatures {
+    pub signature_store: SignatureStore,
+    /// Peers whose merged signatures are salted with the particle id.
+    pub particle_id_salted_peers: Vec<String>,
+}
+
 /// An util for verificating particular data's signatures.
 pub struct DataVerifier<'data> {
-    // a map from peer_id to peer's info (public key, signature, CIDS)
+    // a map from peer_id to peer's info (public key, signature, salt, CIDS)
     grouped_cids: HashMap<Box<str>, PeerInfo<'data>>,
-    salt: &'data str,
 }
 
 impl<'data> DataVerifier<'data> {
+    /// Creates a verifier of data which signatures are all salted with the same salt.
     // it can be further optimized if only required parts are passed;
     // SignatureStore is not used elsewhere
-    pub fn new(data: &'data InterpreterData, salt: &'data str) -> Result<Self, DataVerifierError> {
-        // validate key algoritms
-        for (public_key, _) in data.signatures.iter() {
-            public_key
-                .validate()
-                .map_err(|error| DataVerifierError::MalformedKey {
-                    error,
-                    key: public_key.to_string(),
-                })?;
-        }
-
-        // it contains signature too; if we try to add a value to a peer w/o signature, it is an immediate error
-        let mut grouped_cids: HashMap<Box<str>, PeerInfo<'data>> = data
-            .signatures
-            .iter()
-            .map(|(public_key, signature)| {
-                (
-                    public_key
-                        .to_peer_id()
-                        .expect("cannot happen, was verifeid before")
-                        .to_string()
-                        .into(),
-                    PeerInfo::new(public_key, signature),
-                )
-            })
-            .collect();
-
-        // fill PeerInfo's `cids` field, checking for peer IDs without a key
-        collect_peers_cids_from_trace(&data.trace, &data.cid_info, &mut grouped_cids)?;
-
-        // sort cids for canonicalization
+    pub fn new(
+        data: &'data InterpreterData,
+        salt: &'data str,
+        key_formats: KeyFormatWhitelist,
+    ) -> Result<Self, DataVerifierError> {
+        let mut grouped_cids = group_peers_cids(data, key_formats)?;
         for peer_info in grouped_cids.values_mut() {
-            peer_info.cids.sort_unstable();
+            peer_info.salt = salt;
         }
+        Ok(Self { grouped_cids })
+    }
 
-        Ok(Self { grouped_cids, salt })
+    /// Creates a verifier of data which signatures are salted according to its versions.
+    pub fn with_versions(
+        data: &'data InterpreterData,
+        versions: &Versions,
+        salts: DataSalts<'data>,
+        key_formats: KeyFormatWhitelist,
+    ) -> Result<Self, DataVerifierError> {
+        let mut grouped_cids = group_peers_cids(data, key_formats)?;
+        for (peer_id, peer_info) in grouped_cids.iter_mut() {
+            if versions.peer_has_particle_id_salt(peer_id) {
+                peer_info.salt = salts.particle_id;
+                peer_info.particle_id_salted = true;
+            } else {
+                peer_info.salt = salts.particle_signature;
+            }
+        }
+        Ok(Self { grouped_cids })
     }
 
     /// Verify each peers' signatures.
+    ///
+    /// Ed25519 signatures are checked in a batch; if the batch fails, they are checked one by one
+    /// to find the peer with a wrong signature.
     pub fn verify(&self) -> Result<(), DataVerifierError> {
+        let mut batch = BatchVerifier::new();
+        let mut unbatched = vec![];
         for peer_info in self.grouped_cids.values() {
+            if !batch.push(
+                peer_info.public_key,
+                &peer_info.cids,
+                peer_info.salt,
+                peer_info.signature,
+            ) {
+                unbatched.push(peer_info);
+            }
+        }
+
+        if batch.verify().is_err() {
+            return self.verify_each(self.grouped_cids.values());
+        }
+        self.verify_each(unbatched)
+    }
+
+    fn verify_each<'info>(
+        &self,
+        peer_infos: impl IntoIterator<Item = &'info PeerInfo<'data>>,
+    ) -> Result<(), DataVerifierError>
+    where
+        'data: 'info,
+    {
+        for peer_info in peer_infos {
             peer_info
                 .public_key
-                .verify(&peer_info.cids, self.salt, peer_info.signature)
+                .verify(&peer_info.cids, peer_info.salt, peer_info.signature)
                 .map_err(|error| DataVerifierError::SignatureMismatch {
                     error: error.into(),
                     cids: peer_info.cids.clone(),
@@ -101,6 +143,21 @@ impl<'data> DataVerifier<'data> {
         Ok(())
     }
 
+    /// Peers that produced the data with their public keys.
+    pub fn peers(&self) -> impl Iterator<Item = (&str, &PublicKey)> {
+        self.grouped_cids
+            .iter()
+            .map(|(peer_id, peer_info)| (peer_id.as_ref(), peer_info.public_key))
+    }
+
+    /// Sorted CIDs produced by the peer, i.e. the CIDs its signature has to cover.
+    pub fn peer_cids(&self, peer_id: &str) -> &[Rc<CidRef>] {
+        self.grouped_cids
+            .get(peer_id)
+            .map(|peer_info| peer_info.cids.as_slice())
+            .unwrap_or_default()
+    }
+
     /// For each peer, merge previous and current CID multisets by determining the largest set.
     ///
     /// This code uses an invariant: peer's multiset of produced CIDs is always a superset of
@@ -112,10 +169,13 @@ impl<'data> DataVerifier<'data> {
     /// and a error is returned if it is violated.
     ///
     /// If the multisets are of same size, they have to be equal.
+    ///
+    /// A selected signature keeps the salt it was verified with, so the merged signatures
+    /// may be salted differently.
     // TODO enforce merging only verified sets
     // The result is same regardless argument order, so "prevous/current" terminology
     // is not used deliberately.
-    pub fn merge(mut self, other: Self) -> Result<SignatureStore, DataVerifierError> {
+    pub fn merge(mut self, other: Self) -> Result<MergedSignatures, DataVerifierError> {
         use std::collections::hash_map::Entry::*;
 
         for (other_peer_pk, mut other_info) in other.grouped_cids {
@@ -145,14 +205,65 @@ impl<'data> DataVerifier<'data> {
                 }
             }
         }
-        let mut store = SignatureStore::new();
-        for peer_info in self.grouped_cids.into_values() {
-            store.put(peer_info.public_key.clone(), peer_info.signature.clone())
+        let mut merged = MergedSignatures::default();
+        for (peer_id, peer_info) in self.grouped_cids {
+            merged
+                .signature_store
+                .put(peer_info.public_key.clone(), peer_info.signature.clone());
+            if peer_info.particle_id_salted {
+                merged.particle_id_salted_peers.push(peer_id.into());
+            }
         }
-        Ok(store)
+        // for determinism of the produced data
+        merged.particle_id_salted_peers.sort_unstable();
+        Ok(merged)
     }
 }
 
+/// Group the data's CIDs by the peers that produced them, pairing each peer with its public key and
+/// signature.  The CID store is expected to be verified, and peers' keys have to be of
+/// whitelisted algorithms.
+pub(crate) fn group_peers_cids(
+    data: &InterpreterData,
+    key_formats: KeyFormatWhitelist,
+) -> Result<HashMap<Box<str>, PeerInfo<'_>>, DataVerifierError> {
+    // validate key algoritms
+    for (public_key, _) in data.signatures.iter() {
+        public_key
+            .validate(key_formats)
+            .map_err(|error| DataVerifierError::MalformedKey {
+                error,
+                key: public_key.to_string(),
+            })?;
+    }
+
+    // it contains signature too; if we try to add a value to a peer w/o signature, it is an immediate error
+    let mut grouped_cids: HashMap<Box<str>, PeerInfo<'_>> = data
+        .signatures
+        .iter()
+        .map(|(public_key, signature)| {
+            (
+                public_key
+                    .to_peer_id()
+                    .expect("cannot happen, was verifeid before")
+                    .to_string()
+                    .into(),
+                PeerInfo::new(public_key, signature),
+            )
+        })
+        .collect();
+
+    // fill PeerInfo's `cids` field, checking for peer IDs without a key
+    collect_peers_cids_from_trace(&data.trace, &data.cid_info, &mut grouped_cids)?;
+
+    // sort cids for canonicalization
+    for peer_info in grouped_cids.values_mut() {
+        peer_info.cids.sort_unstable();
+    }
+
+    Ok(grouped_cids)
+}
+
 fn collect_peers_cids_from_trace<'data>(
     trace: &'data ExecutionTrace,
     cid_info: &'data CidInfo,
@@ -263,13 +374,17 @@ fn is_multisubset(
     true
 }
 
-struct PeerInfo<'data> {
+pub(crate) struct PeerInfo<'data> {
     /// A peer's public key.
-    public_key: &'data PublicKey,
+    pub(crate) public_key: &'data PublicKey,
     /// A peer's signature.
-    signature: &'data Signature,
+    pub(crate) signature: &'data Signature,
+    /// A salt of the peer's signature.
+    pub(crate) salt: &'data str,
+    /// Whether the salt is the legacy particle id one.
+    pub(crate) particle_id_salted: bool,
     /// Sorted vector of CIDs that belong to the peer.
-    cids: Vec<Rc<CidRef>>,
+    pub(crate) cids: Vec<Rc<CidRef>>,
 }
 
 impl<'data> PeerInfo<'data> {
@@ -277,6 +392,8 @@ impl<'data> PeerInfo<'data> {
         Self {
             public_key,
             signature,
+            salt: "",
+            particle_id_salted: false,
             cids: vec![],
         }
     }
@@ -63,3 +63,12 @@ static INTERPRETER_DATA_VERSION: Lazy<semver::Version> = Lazy::new(|| {
 pub fn data_version() -> &'static semver::Version {
     Lazy::force(&INTERPRETER_DATA_VERSION)
 }
+
+/// Signatures of data of older versions are salted with the particle id,
+/// and since this version they are salted with the particle signature.
+static PARTICLE_SIGNATURE_SALT_DATA_VERSION: Lazy<semver::Version> =
+    Lazy::new(|| semver::Version::new(0, 18, 0));
+
+pub fn particle_signature_salt_data_version() -> &'static semver::Version {
+    Lazy::force(&PARTICLE_SIGNATURE_SALT_DATA_VERSION)
+}
@@ -22,14 +22,20 @@ use crate::JValue;
 use serde::Deserialize;
 use serde::Serialize;
 
+use std::borrow::Cow;
 use std::cell::RefCell;
 
+/// Starts byte strings kept as is, it never occurs in UTF-8, so JSON values are kept
+/// as their text, and the archived layout is the same as of a string.
+const BYTES_TAG: u8 = 0xFF;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(transparent)]
 #[derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize)]
 #[archive(check_bytes)]
 pub struct RawValue {
-    raw: Box<str>,
+    #[serde(with = "tagged_raw")]
+    raw: Box<[u8]>,
 
     #[serde(skip)]
     #[with(::rkyv::with::Skip)]
@@ -39,23 +45,122 @@ pub struct RawValue {
 impl RawValue {
     pub fn from_value(value: impl Into<JValue>) -> Self {
         let value = value.into();
-        let raw = value.to_string().into();
+        let raw = value.to_string().into_bytes().into();
         Self {
             raw,
             parsed: Some(value).into(),
         }
     }
 
+    /// Keeps a byte string as is; it's seen as an array of numbers by the rest of the interpreter.
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Self {
+        Self {
+            raw: tag_bytes(bytes.as_ref()),
+            parsed: <_>::default(),
+        }
+    }
+
     pub fn get_value(&self) -> JValue {
         let mut parsed_guard = self.parsed.borrow_mut();
 
-        let parsed_value = parsed_guard
-            .get_or_insert_with(|| serde_json::from_str(&self.raw).expect("TODO handle error"));
+        let parsed_value = parsed_guard.get_or_insert_with(|| match RawRepr::from_raw(&self.raw) {
+            RawRepr::Json(raw) => serde_json::from_slice(raw).expect("TODO handle error"),
+            RawRepr::Bytes(bytes) => bytes_to_value(bytes),
+        });
         parsed_value.clone()
     }
 
-    pub(crate) fn as_inner(&self) -> &str {
-        &self.raw
+    /// JSON representation the value CID is calculated from, so bytes have the same CID
+    /// as the corresponding array of numbers.
+    pub(crate) fn as_inner(&self) -> Cow<'_, str> {
+        match RawRepr::from_raw(&self.raw) {
+            RawRepr::Json(raw) => String::from_utf8_lossy(raw),
+            RawRepr::Bytes(bytes) => Cow::Owned(bytes_to_value(bytes).to_string()),
+        }
+    }
+}
+
+fn bytes_to_value(bytes: &[u8]) -> JValue {
+    bytes
+        .iter()
+        .map(|&byte| JValue::from(byte))
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn tag_bytes(bytes: &[u8]) -> Box<[u8]> {
+    let mut raw = Vec::with_capacity(bytes.len() + 1);
+    raw.push(BYTES_TAG);
+    raw.extend_from_slice(bytes);
+    raw.into()
+}
+
+/// A stored value, either JSON text or a byte string.
+enum RawRepr<'raw> {
+    Json(&'raw [u8]),
+    Bytes(&'raw [u8]),
+}
+
+impl<'raw> RawRepr<'raw> {
+    fn from_raw(raw: &'raw [u8]) -> Self {
+        match raw.split_first() {
+            Some((&BYTES_TAG, bytes)) => Self::Bytes(bytes),
+            _ => Self::Json(raw),
+        }
+    }
+}
+
+/// JSON values are (de)serialized as strings and byte strings as bytes.
+mod tagged_raw {
+    use super::tag_bytes;
+    use super::RawRepr;
+
+    use serde::de::SeqAccess;
+    use serde::de::Visitor;
+    use serde::Deserializer;
+    use serde::Serializer;
+
+    use std::fmt;
+
+    pub(super) fn serialize<S: Serializer>(raw: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        match RawRepr::from_raw(raw) {
+            RawRepr::Json(raw) => serializer.serialize_str(&String::from_utf8_lossy(raw)),
+            RawRepr::Bytes(bytes) => serializer.serialize_bytes(bytes),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Box<[u8]>, D::Error> {
+        struct RawVisitor;
+
+        impl<'de> Visitor<'de> for RawVisitor {
+            type Value = Box<[u8]>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a JSON string or a byte string")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Box<[u8]>, E> {
+                Ok(value.as_bytes().into())
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, value: &[u8]) -> Result<Box<[u8]>, E> {
+                Ok(tag_bytes(value))
+            }
+
+            // formats without a bytes type, like JSON, write bytes as an array of numbers
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Box<[u8]>, A::Error> {
+                let mut raw = Vec::with_capacity(seq.size_hint().unwrap_or(0) + 1);
+                raw.push(super::BYTES_TAG);
+                while let Some(byte) = seq.next_element()? {
+                    raw.push(byte);
+                }
+                Ok(raw.into())
+            }
+        }
+
+        deserializer.deserialize_any(RawVisitor)
     }
 }
 
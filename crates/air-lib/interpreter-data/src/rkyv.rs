@@ -49,15 +49,15 @@ pub enum RkyvDeserializeError {
 pub type RkyvSerializeError =
     CompositeSerializerError<std::convert::Infallible, AllocScratchError, SharedSerializeMapError>;
 
+pub(crate) type RkyvSerializer = CompositeSerializer<
+    AlignedSerializer<AlignedVec>,
+    FallbackScratch<HeapScratch<4096>, AllocScratch>,
+    SharedSerializeMap,
+>;
+
 pub(crate) fn to_vec<Value>(value: &Value) -> Result<Vec<u8>, RkyvSerializeError>
 where
-    Value: rkyv::Serialize<
-        CompositeSerializer<
-            AlignedSerializer<AlignedVec>,
-            FallbackScratch<HeapScratch<4096>, AllocScratch>,
-            SharedSerializeMap,
-        >,
-    >,
+    Value: rkyv::Serialize<RkyvSerializer>,
 {
     let mut ser = rkyv::ser::serializers::AllocSerializer::<4096>::default();
     ser.serialize_value(value)?;
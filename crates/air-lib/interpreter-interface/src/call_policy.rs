@@ -0,0 +1,71 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air_interpreter_sede::define_simple_representation;
+use air_interpreter_sede::derive_serialized_type;
+use air_interpreter_sede::MsgPackFormat;
+use air_interpreter_sede::Representation;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The `%last_error%.error_code` of calls rejected by the call policy.
+pub const CALL_NOT_AUTHORIZED: i64 = 10013;
+
+derive_serialized_type!(SerializedCallPolicy);
+
+pub type CallPolicyFormat = MsgPackFormat;
+
+define_simple_representation! {
+    CallPolicyRepr,
+    CallPolicy,
+    CallPolicyFormat,
+    SerializedCallPolicy
+}
+
+pub type CallPolicyDeserializeError = <CallPolicyRepr as Representation>::DeserializeError;
+
+/// Local services a script may call.
+///
+/// An empty policy allows any call; otherwise, a call has to match one of the rules.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallPolicy {
+    pub rules: Vec<CallRule>,
+}
+
+/// Allows calls of matching service functions.
+///
+/// Patterns match the whole string, a `*` in them matches any sequence of characters.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallRule {
+    /// A service id pattern.
+    pub service_id: String,
+
+    /// A function name pattern.
+    pub function_name: String,
+
+    /// If it isn't empty, tetraplets of every argument have to point to one of these peers.
+    #[serde(default)]
+    pub argument_peers: Vec<String>,
+}
+
+impl CallPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
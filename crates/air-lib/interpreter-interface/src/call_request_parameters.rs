@@ -26,6 +26,8 @@ use air_interpreter_sede::MsgPackMultiformat;
 use air_interpreter_sede::Representation;
 use air_interpreter_value::JValue;
 
+use crate::CallProvenance;
+
 use marine_call_parameters::SecurityTetraplet;
 #[cfg(feature = "marine")]
 use marine_rs_sdk::marine;
@@ -40,10 +42,12 @@ pub type CallRequests = HashMap<u32, CallRequestParams>;
 derive_serialized_type!(SerializedCallArguments);
 derive_serialized_type!(SerializedTetraplets);
 derive_serialized_type!(SerializedCallRequests);
+derive_serialized_type!(SerializedProvenance);
 
 pub type CallArgumentsFormat = MsgPackFormat;
 pub type TetrapletsFormat = MsgPackFormat;
 pub type CallRequestsFormat = MsgPackMultiformat;
+pub type ProvenanceFormat = MsgPackFormat;
 
 define_simple_representation! {
     CallArgumentsRepr,
@@ -71,6 +75,15 @@ define_simple_representation! {
 
 pub type TetrapletDeserializeError = <TetrapletsRepr as Representation>::DeserializeError;
 
+define_simple_representation! {
+    ProvenanceRepr,
+    CallProvenance,
+    ProvenanceFormat,
+    SerializedProvenance
+}
+
+pub type ProvenanceDeserializeError = <ProvenanceRepr as Representation>::DeserializeError;
+
 define_simple_representation! {
     CallRequestsRepr,
     CallRequests,
@@ -105,6 +118,17 @@ pub struct CallRequestParams {
 
     /// Serialized to JSON string Vec<Vec<SecurityTetraplet>> that should be passed to a service.
     pub tetraplets: SerializedTetraplets,
+
+    /// Serialized CallProvenance of arguments.
+    ///
+    /// It is empty unless `RunParameters::call_provenance_enabled` is set.
+    #[serde(default)]
+    pub provenance: SerializedProvenance,
+
+    /// Ids of identical calls met in the same run, the result of this request should be
+    /// supplied to them as well.
+    #[serde(default)]
+    pub fan_out_call_ids: Vec<u32>,
 }
 
 impl CallRequestParams {
@@ -113,12 +137,15 @@ impl CallRequestParams {
         function_name: String,
         arguments: SerializedCallArguments,
         tetraplets: SerializedTetraplets,
+        provenance: SerializedProvenance,
     ) -> Self {
         Self {
             service_id,
             function_name,
             arguments,
             tetraplets,
+            provenance,
+            fan_out_call_ids: vec![],
         }
     }
 }
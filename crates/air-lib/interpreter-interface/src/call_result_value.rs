@@ -0,0 +1,125 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air_interpreter_value::JValue;
+use serde::de::IntoDeserializer;
+use serde::de::MapAccess;
+use serde::de::SeqAccess;
+use serde::de::Visitor;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+use std::fmt;
+
+/// A service result passed in the binary form.
+///
+/// Unlike `JValue`, it keeps a top-level byte string as is, so it's stored without being
+/// expanded into an array of numbers; nested byte strings aren't supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallResultValue {
+    Value(JValue),
+    Bytes(Vec<u8>),
+}
+
+impl Serialize for CallResultValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            CallResultValue::Value(value) => value.serialize(serializer),
+            CallResultValue::Bytes(bytes) => serializer.serialize_bytes(bytes),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CallResultValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(CallResultValueVisitor)
+    }
+}
+
+struct CallResultValueVisitor;
+
+impl CallResultValueVisitor {
+    fn value<'de, E: serde::de::Error>(
+        deserializer: impl Deserializer<'de, Error = E>,
+    ) -> Result<CallResultValue, E> {
+        JValue::deserialize(deserializer).map(CallResultValue::Value)
+    }
+}
+
+// everything but byte strings is delegated to JValue
+impl<'de> Visitor<'de> for CallResultValueVisitor {
+    type Value = CallResultValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("any valid JSON value or a byte string")
+    }
+
+    fn visit_bool<E: serde::de::Error>(self, value: bool) -> Result<CallResultValue, E> {
+        Self::value(value.into_deserializer())
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<CallResultValue, E> {
+        Self::value(value.into_deserializer())
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<CallResultValue, E> {
+        Self::value(value.into_deserializer())
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<CallResultValue, E> {
+        Self::value(value.into_deserializer())
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<CallResultValue, E> {
+        Self::value(value.into_deserializer())
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, value: &[u8]) -> Result<CallResultValue, E> {
+        Ok(CallResultValue::Bytes(value.to_vec()))
+    }
+
+    fn visit_byte_buf<E: serde::de::Error>(self, value: Vec<u8>) -> Result<CallResultValue, E> {
+        Ok(CallResultValue::Bytes(value))
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<CallResultValue, E> {
+        Ok(CallResultValue::Value(JValue::Null))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<CallResultValue, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<CallResultValue, E> {
+        Ok(CallResultValue::Value(JValue::Null))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<CallResultValue, A::Error> {
+        Self::value(serde::de::value::SeqAccessDeserializer::new(seq))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<CallResultValue, A::Error> {
+        Self::value(serde::de::value::MapAccessDeserializer::new(map))
+    }
+}
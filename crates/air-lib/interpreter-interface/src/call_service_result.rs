@@ -19,6 +19,7 @@
 
 use air_interpreter_sede::define_simple_representation;
 use air_interpreter_sede::derive_serialized_type;
+use air_interpreter_sede::Format;
 use air_interpreter_sede::MsgPackMultiformat;
 use air_interpreter_sede::Representation;
 use serde::Deserialize;
@@ -45,6 +46,19 @@ define_simple_representation! {
 pub type CallResultsDeserializeError = <CallResultsRepr as Representation>::DeserializeError;
 pub type CallResultsSerializeError = <CallResultsRepr as Representation>::SerializeError;
 
+derive_serialized_type!(SerializedCallResultValue);
+
+// a binary service result is encoded with the same codec as the call results themselves
+define_simple_representation! {
+    CallResultValueRepr,
+    crate::CallResultValue,
+    CallResultsFormat,
+    SerializedCallResultValue
+}
+
+pub type CallResultValueDeserializeError =
+    <CallResultValueRepr as Representation>::DeserializeError;
+
 /// Represents an executed host function result.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct CallServiceResult {
@@ -54,6 +68,13 @@ pub struct CallServiceResult {
     /// Resulted JValue serialized to a string. It's impossible to wrap it with the marine macro,
     /// inasmuch as it's a enum uses HashMap inside.
     pub result: String,
+
+    /// Resulted value serialized with `CallResultValueRepr`, an alternative to `result` that spares
+    /// JSON parsing. A top-level byte string is stored as is and seen by the interpreter as
+    /// an array of numbers.
+    /// If present, it takes precedence over `result`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary_result: Option<SerializedCallResultValue>,
 }
 
 impl CallServiceResult {
@@ -62,13 +83,47 @@ impl CallServiceResult {
             ret_code: CALL_SERVICE_SUCCESS,
             // for compatiblity with JavaScript with binary formats, string IDs are used
             result: result.to_string(),
+            binary_result: None,
         }
     }
 
+    pub fn ok_binary(result: &JValue) -> Self {
+        let binary_result = CallResultsFormat::default()
+            .to_vec(result)
+            .expect("a JSON value should be serializable");
+
+        Self::with_binary_result(binary_result)
+    }
+
+    pub fn ok_bytes(result: Vec<u8>) -> Self {
+        let binary_result = CallResultsFormat::default()
+            .to_vec(&crate::CallResultValue::Bytes(result))
+            .expect("bytes should be serializable");
+
+        Self::with_binary_result(binary_result)
+    }
+
     pub fn err(err_code: i32, result: &JValue) -> Self {
         Self {
             ret_code: err_code,
             result: result.to_string(),
+            binary_result: None,
+        }
+    }
+
+    /// Size of the result in whichever form it was passed.
+    pub fn result_size(&self) -> usize {
+        match &self.binary_result {
+            Some(binary_result) => binary_result.len(),
+            None => self.result.len(),
+        }
+    }
+
+    fn with_binary_result(binary_result: Vec<u8>) -> Self {
+        Self {
+            ret_code: CALL_SERVICE_SUCCESS,
+            result: String::new(),
+            binary_result: Some(binary_result.into()),
         }
     }
 }
@@ -77,6 +132,14 @@ use std::fmt;
 
 impl fmt::Display for CallServiceResult {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "ret_code: {}, result: '{}'", self.ret_code, self.result)
+        match &self.binary_result {
+            Some(binary_result) => write!(
+                f,
+                "ret_code: {}, binary result of {} bytes",
+                self.ret_code,
+                binary_result.len()
+            ),
+            None => write!(f, "ret_code: {}, result: '{}'", self.ret_code, self.result),
+        }
     }
 }
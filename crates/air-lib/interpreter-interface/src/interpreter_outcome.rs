@@ -63,6 +63,9 @@ pub struct InterpreterOutcome {
 
     /// This flag signals that call result size exceeds the limit.
     pub call_result_size_limit_exceeded: bool,
+
+    /// A payload the host should sign if remote signing was requested, it's empty otherwise.
+    pub signing_payload: Vec<u8>,
 }
 
 impl SoftLimitsTriggering {
@@ -93,6 +96,7 @@ impl InterpreterOutcome {
         next_peer_pks: Vec<String>,
         call_requests: SerializedCallRequests,
         soft_limits_triggering: SoftLimitsTriggering,
+        signing_payload: Vec<u8>,
     ) -> Self {
         let call_requests = call_requests.into();
         Self {
@@ -104,22 +108,31 @@ impl InterpreterOutcome {
             air_size_limit_exceeded: soft_limits_triggering.air_size_limit_exceeded,
             particle_size_limit_exceeded: soft_limits_triggering.particle_size_limit_exceeded,
             call_result_size_limit_exceeded: soft_limits_triggering.call_result_size_limit_exceeded,
+            signing_payload,
         }
     }
 }
 
 #[cfg(feature = "marine")]
 impl InterpreterOutcome {
+    /// Convert from a record of the latest layout or of the one of interpreters preceding
+    /// remote signing, they don't return `signing_payload`.
     pub fn from_ivalue(ivalue: IValue) -> Result<Self, String> {
-        const OUTCOME_FIELDS_COUNT: usize = 8;
+        const OUTCOME_FIELDS_COUNT: usize = 9;
+        const LEGACY_OUTCOME_FIELDS_COUNT: usize = 8;
 
         let mut record_values = try_as_record(ivalue)?.into_vec();
-        if record_values.len() != OUTCOME_FIELDS_COUNT {
-            return Err(format!(
-                "expected InterpreterOutcome struct with {OUTCOME_FIELDS_COUNT} fields, got {record_values:?}"
-            ));
-        }
-
+        let signing_payload = match record_values.len() {
+            OUTCOME_FIELDS_COUNT => {
+                try_as_byte_vec(record_values.pop().unwrap(), "signing_payload")?
+            }
+            LEGACY_OUTCOME_FIELDS_COUNT => vec![],
+            _ => {
+                return Err(format!(
+                    "expected InterpreterOutcome struct with {OUTCOME_FIELDS_COUNT} fields, got {record_values:?}"
+                ))
+            }
+        };
         let call_result_size_limit_exceeded = try_as_boolean(
             record_values.pop().unwrap(),
             "call_result_size_limit_exceeded",
@@ -147,6 +160,7 @@ impl InterpreterOutcome {
             next_peer_pks,
             call_requests.into(),
             soft_limits_triggering,
+            signing_payload,
         );
 
         Ok(outcome)
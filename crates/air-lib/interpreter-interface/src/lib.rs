@@ -29,14 +29,22 @@
     unreachable_patterns
 )]
 
+mod call_policy;
 mod call_request_parameters;
+mod call_result_value;
 mod call_service_result;
 mod interpreter_outcome;
+mod provenance;
 mod run_args_memory_limits;
 mod run_parameters;
+mod trust_policy;
 
+pub use call_policy::*;
 pub use call_request_parameters::*;
+pub use call_result_value::*;
 pub use call_service_result::*;
 pub use interpreter_outcome::*;
+pub use provenance::*;
 pub use run_args_memory_limits::*;
 pub use run_parameters::*;
+pub use trust_policy::*;
@@ -0,0 +1,69 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use marine_call_parameters::SecurityTetraplet;
+use serde::Deserialize;
+use serde::Serialize;
+
+use std::collections::BTreeMap;
+
+/// Nesting depth of canons whose elements are listed in `CallProvenance`, the elements of
+/// deeper ones aren't.
+pub const MAX_PROVENANCE_DEPTH: usize = 8;
+
+/// Describes how call arguments were produced.
+///
+/// Service results and canons the argument chains go through are listed once in the tables
+/// keyed by their CIDs in the particle data, the chains refer to them by these CIDs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallProvenance {
+    /// Provenances of the arguments in their order.
+    pub arguments: Vec<ArgumentProvenance>,
+
+    /// Tetraplets of the calls that produced the service results.
+    pub service_results: BTreeMap<String, SecurityTetraplet>,
+
+    /// Canons the chains go through.
+    pub canons: BTreeMap<String, CanonProvenance>,
+}
+
+/// Describes how a value was produced.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ArgumentProvenance {
+    /// The value is a literal or was produced by the interpreter itself.
+    Literal,
+
+    /// The value is a service call result, possibly with a lambda applied.
+    ServiceResult { cid: String },
+
+    /// The value is a canon stream or its part.
+    Canon { cid: String },
+}
+
+/// A canon the provenance chains go through.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanonProvenance {
+    /// A tetraplet of the canon instruction.
+    pub tetraplet: SecurityTetraplet,
+
+    /// Provenances of the stream elements at the moment of canonicalization, they aren't
+    /// listed if the canon is nested deeper than `MAX_PROVENANCE_DEPTH`.
+    pub values: Option<Vec<ArgumentProvenance>>,
+}
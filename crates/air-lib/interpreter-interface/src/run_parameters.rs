@@ -26,7 +26,13 @@ use marine_rs_sdk::marine;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::SerializedCallPolicy;
+use crate::TrustPolicy;
+
 /// Parameters that a host side should pass to an interpreter and that necessary for execution.
+///
+/// Fields are only appended to the end of the record, so interpreters preceding some of them
+/// accept its prefix, see `RunParameters::into_ivalue_prefix`.
 #[cfg_attr(feature = "marine", marine)]
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct RunParameters {
@@ -68,8 +74,69 @@ pub struct RunParameters {
 
     /// This knob controls hard RAM limits behavior for AVMRunner.
     pub hard_limit_enabled: bool,
+
+    /// Key formats allowed for the current peer key and in other peers' signatures.
+    ///
+    /// The values are results of `fluence_keypair::KeyType::into`; if it's empty,
+    /// only Ed25519 is allowed.
+    #[serde(default)]
+    pub allowed_key_formats: Vec<u8>,
+
+    /// `TrustPolicy::allowed_peers`.
+    ///
+    /// The trust policy is enforced only if the interpreter checks signatures.
+    #[serde(default)]
+    pub allowed_peers: Vec<String>,
+
+    /// `TrustPolicy::denied_peers`.
+    #[serde(default)]
+    pub denied_peers: Vec<String>,
+
+    /// `TrustPolicy::known_key_peers`.
+    #[serde(default)]
+    pub known_key_peers: Vec<String>,
+
+    /// `TrustPolicy::known_keys`.
+    #[serde(default)]
+    pub known_keys: Vec<Vec<u8>>,
+
+    /// `CallPolicy` serialized with `CallPolicyRepr`, empty for allowing any call.
+    #[serde(default)]
+    pub call_policy: Vec<u8>,
+
+    /// Signature of the particle made by its init peer.
+    ///
+    /// Produced data is signed with it as a salt instead of the particle id that can be replayed;
+    /// the particle id is used only for signatures of data produced by older interpreters.
+    #[serde(default)]
+    pub particle_signature: Vec<u8>,
+
+    /// If set, call requests contain provenance chains of their arguments.
+    #[serde(default)]
+    pub call_provenance_enabled: bool,
+
+    /// If set, identical call requests of a run are emitted once with a fan-out list of
+    /// their call ids, it suits hosts whose services are deterministic.
+    #[serde(default)]
+    pub call_deduplication_enabled: bool,
+
+    /// A public key of the current peer for remote signing.
+    ///
+    /// If it isn't empty, the interpreter doesn't sign produced data and ignores `secret_key_bytes`;
+    /// instead, the outcome contains a `signing_payload` that the host should sign with the
+    /// corresponding secret key and pass to the `finalize_signing` entry point.
+    /// The value is the result of `fluence_keypair::PublicKey::encode`.
+    #[serde(default)]
+    pub signer_public_key: Vec<u8>,
+
+    /// `TrustPolicy::particle_id_salt_denied`.
+    #[serde(default)]
+    pub particle_id_salt_denied: bool,
 }
 
+/// Number of fields of the record accepted by interpreters preceding the appended fields.
+pub const LEGACY_RUN_PARAMETERS_FIELDS_COUNT: usize = 11;
+
 impl RunParameters {
     #![allow(clippy::too_many_arguments)]
     pub fn new(
@@ -84,6 +151,13 @@ impl RunParameters {
         particle_size_limit: u64,
         call_result_size_limit: u64,
         hard_limit_enabled: bool,
+        allowed_key_formats: Vec<u8>,
+        trust_policy: TrustPolicy,
+        call_policy: SerializedCallPolicy,
+        particle_signature: Vec<u8>,
+        call_provenance_enabled: bool,
+        call_deduplication_enabled: bool,
+        signer_public_key: Vec<u8>,
     ) -> Self {
         Self {
             init_peer_id,
@@ -97,26 +171,167 @@ impl RunParameters {
             particle_size_limit,
             call_result_size_limit,
             hard_limit_enabled,
+            allowed_key_formats,
+            allowed_peers: trust_policy.allowed_peers,
+            denied_peers: trust_policy.denied_peers,
+            known_key_peers: trust_policy.known_key_peers,
+            known_keys: trust_policy.known_keys,
+            call_policy: call_policy.into(),
+            particle_signature,
+            call_provenance_enabled,
+            call_deduplication_enabled,
+            signer_public_key,
+            particle_id_salt_denied: trust_policy.particle_id_salt_denied,
         }
     }
 
     #[cfg(feature = "marine")]
     pub fn into_ivalue(self) -> IValue {
-        let run_parameters = vec![
-            IValue::String(self.init_peer_id),
-            IValue::String(self.current_peer_id),
-            IValue::U64(self.timestamp),
-            IValue::U32(self.ttl),
-            IValue::U8(self.key_format),
-            IValue::ByteArray(self.secret_key_bytes),
-            IValue::String(self.particle_id),
-            IValue::U64(self.air_size_limit),
-            IValue::U64(self.particle_size_limit),
-            IValue::U64(self.call_result_size_limit),
-            IValue::Boolean(self.hard_limit_enabled),
-        ];
+        let run_parameters = self
+            .into_ivalue_fields()
+            .into_iter()
+            .map(|(_, value, _)| value)
+            .collect();
         // unwrap is safe here because run_parameters is non-empty array
         let run_parameters = NEVec::new(run_parameters).unwrap();
         IValue::Record(run_parameters)
     }
+
+    /// Convert to a record of the first `fields_count` fields for an interpreter preceding
+    /// the rest of them.
+    ///
+    /// The dropped fields mustn't restrict the execution, e.g. with a trust or a call policy,
+    /// as the interpreter would silently ignore the restriction.
+    #[cfg(feature = "marine")]
+    pub fn into_ivalue_prefix(self, fields_count: usize) -> Result<IValue, String> {
+        let mut fields = self.into_ivalue_fields();
+        if !(LEGACY_RUN_PARAMETERS_FIELDS_COUNT..=fields.len()).contains(&fields_count) {
+            return Err(format!(
+                "interpreter accepts RunParameters struct with {fields_count} fields, \
+                 expected from {LEGACY_RUN_PARAMETERS_FIELDS_COUNT} to {} fields",
+                fields.len()
+            ));
+        }
+
+        if let Some((field_name, _, _)) = fields[fields_count..]
+            .iter()
+            .find(|(_, _, restricting)| *restricting)
+        {
+            return Err(format!(
+                "interpreter accepting RunParameters struct with {fields_count} fields \
+                 doesn't support {field_name}"
+            ));
+        }
+
+        fields.truncate(fields_count);
+        let run_parameters = fields.into_iter().map(|(_, value, _)| value).collect();
+        // unwrap is safe here because the prefix isn't shorter than the legacy one
+        let run_parameters = NEVec::new(run_parameters).unwrap();
+        Ok(IValue::Record(run_parameters))
+    }
+
+    /// Record fields with their names and whether they restrict the execution.
+    #[cfg(feature = "marine")]
+    fn into_ivalue_fields(self) -> Vec<(&'static str, IValue, bool)> {
+        let restricting_policy = !self.allowed_peers.is_empty()
+            || !self.denied_peers.is_empty()
+            || !self.known_key_peers.is_empty()
+            || !self.known_keys.is_empty();
+        let restricting_calls = !self.call_policy.is_empty();
+        let remote_signing = !self.signer_public_key.is_empty();
+
+        vec![
+            ("init_peer_id", IValue::String(self.init_peer_id), false),
+            (
+                "current_peer_id",
+                IValue::String(self.current_peer_id),
+                false,
+            ),
+            ("timestamp", IValue::U64(self.timestamp), false),
+            ("ttl", IValue::U32(self.ttl), false),
+            ("key_format", IValue::U8(self.key_format), false),
+            (
+                "secret_key_bytes",
+                IValue::ByteArray(self.secret_key_bytes),
+                false,
+            ),
+            ("particle_id", IValue::String(self.particle_id), false),
+            ("air_size_limit", IValue::U64(self.air_size_limit), false),
+            (
+                "particle_size_limit",
+                IValue::U64(self.particle_size_limit),
+                false,
+            ),
+            (
+                "call_result_size_limit",
+                IValue::U64(self.call_result_size_limit),
+                false,
+            ),
+            (
+                "hard_limit_enabled",
+                IValue::Boolean(self.hard_limit_enabled),
+                false,
+            ),
+            (
+                "allowed_key_formats",
+                IValue::ByteArray(self.allowed_key_formats),
+                false,
+            ),
+            (
+                "allowed_peers",
+                string_vec_to_ivalue(self.allowed_peers),
+                restricting_policy,
+            ),
+            (
+                "denied_peers",
+                string_vec_to_ivalue(self.denied_peers),
+                restricting_policy,
+            ),
+            (
+                "known_key_peers",
+                string_vec_to_ivalue(self.known_key_peers),
+                restricting_policy,
+            ),
+            (
+                "known_keys",
+                IValue::Array(self.known_keys.into_iter().map(IValue::ByteArray).collect()),
+                restricting_policy,
+            ),
+            (
+                "call_policy",
+                IValue::ByteArray(self.call_policy),
+                restricting_calls,
+            ),
+            (
+                "particle_signature",
+                IValue::ByteArray(self.particle_signature),
+                false,
+            ),
+            (
+                "call_provenance_enabled",
+                IValue::Boolean(self.call_provenance_enabled),
+                false,
+            ),
+            (
+                "call_deduplication_enabled",
+                IValue::Boolean(self.call_deduplication_enabled),
+                false,
+            ),
+            (
+                "signer_public_key",
+                IValue::ByteArray(self.signer_public_key),
+                remote_signing,
+            ),
+            (
+                "particle_id_salt_denied",
+                IValue::Boolean(self.particle_id_salt_denied),
+                self.particle_id_salt_denied,
+            ),
+        ]
+    }
+}
+
+#[cfg(feature = "marine")]
+fn string_vec_to_ivalue(values: Vec<String>) -> IValue {
+    IValue::Array(values.into_iter().map(IValue::String).collect())
 }
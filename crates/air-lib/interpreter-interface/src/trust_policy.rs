@@ -0,0 +1,57 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Peers and keys a host accepts data from.
+///
+/// It is checked for the current data only, the previous data is trusted.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TrustPolicy {
+    /// If it isn't empty, only data produced by these peers is accepted.
+    pub allowed_peers: Vec<String>,
+
+    /// Data produced by these peers is refused.
+    pub denied_peers: Vec<String>,
+
+    /// Peers whose data has to be signed with one of the `known_keys`.
+    pub known_key_peers: Vec<String>,
+
+    /// Public keys known to the host.
+    ///
+    /// The values are results of `fluence_keypair::PublicKey::encode`.
+    pub known_keys: Vec<Vec<u8>>,
+
+    /// If set, signatures salted with the particle id are refused.
+    ///
+    /// Data lists such signatures in its unsigned versions, so the legacy salt should be denied
+    /// once all peers of the network run interpreters salting with the particle signature.
+    #[serde(default)]
+    pub particle_id_salt_denied: bool,
+}
+
+impl TrustPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.allowed_peers.is_empty()
+            && self.denied_peers.is_empty()
+            && self.known_key_peers.is_empty()
+            && !self.particle_id_salt_denied
+    }
+}
@@ -0,0 +1,115 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::PublicKey;
+use crate::SaltedData;
+use crate::Signature;
+
+use borsh::BorshSerialize;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use fluence_keypair::KeyFormat;
+
+/// Accumulates Ed25519 signatures to check them all at once.
+///
+/// Batch verification is much faster than checking signatures one by one, but it doesn't tell
+/// which signature is wrong: on failure, signatures should be checked individually.
+///
+/// Batch and individual checks agree only on points without a torsion component, so signatures
+/// with a torsioned key or nonce aren't batched.
+#[derive(Default)]
+pub struct BatchVerifier {
+    messages: Vec<Vec<u8>>,
+    signatures: Vec<ed25519_dalek::Signature>,
+    verifying_keys: Vec<ed25519_dalek::VerifyingKey>,
+}
+
+impl BatchVerifier {
+    pub fn new() -> Self {
+        <_>::default()
+    }
+
+    /// Queue a salted value signature for verification.
+    ///
+    /// Returns `false` if the key or the signature is not an Ed25519 one, is malformed or
+    /// has a torsion component: it has to be verified individually then.
+    pub fn push<T: BorshSerialize + ?Sized>(
+        &mut self,
+        public_key: &PublicKey,
+        value: &T,
+        salt: &str,
+        signature: &Signature,
+    ) -> bool {
+        let Some((verifying_key, signature)) = decode_ed25519(public_key, signature) else {
+            return false;
+        };
+
+        self.messages
+            .push(SaltedData::new(&value, salt).serialize());
+        self.signatures.push(signature);
+        self.verifying_keys.push(verifying_key);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// Check all the queued signatures; an empty batch is always valid.
+    pub fn verify(&self) -> Result<(), ed25519_dalek::SignatureError> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let messages: Vec<&[u8]> = self.messages.iter().map(Vec::as_slice).collect();
+        ed25519_dalek::verify_batch(&messages, &self.signatures, &self.verifying_keys)
+    }
+}
+
+fn decode_ed25519(
+    public_key: &PublicKey,
+    signature: &Signature,
+) -> Option<(ed25519_dalek::VerifyingKey, ed25519_dalek::Signature)> {
+    let pk = fluence_keypair::PublicKey::decode(&public_key.0).ok()?;
+    let signature = fluence_keypair::Signature::decode(signature.0.to_vec()).ok()?;
+    if pk.get_key_format() != KeyFormat::Ed25519
+        || signature.get_signature_type() != KeyFormat::Ed25519
+    {
+        return None;
+    }
+
+    let key_bytes = pk.to_vec().as_slice().try_into().ok()?;
+    let verifying_key = ed25519_dalek::VerifyingKey::from_bytes(&key_bytes).ok()?;
+    let signature = ed25519_dalek::Signature::from_slice(signature.to_vec()).ok()?;
+    if !is_torsion_free(&key_bytes) || !is_torsion_free(signature.r_bytes()) {
+        return None;
+    }
+    Some((verifying_key, signature))
+}
+
+// the batch equation is randomized, so a torsion component may be accepted by it
+// while the individual check rejects it
+fn is_torsion_free(point: &[u8; 32]) -> bool {
+    CompressedEdwardsY(*point)
+        .decompress()
+        .is_some_and(|point| point.is_torsion_free())
+}
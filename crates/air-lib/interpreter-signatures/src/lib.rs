@@ -29,10 +29,12 @@
     unreachable_patterns
 )]
 
+mod batch;
 mod sede;
 mod stores;
 mod trackers;
 
+pub use crate::batch::BatchVerifier;
 pub use crate::stores::*;
 pub use crate::trackers::*;
 
@@ -49,6 +51,8 @@ use std::hash::Hash;
 pub enum KeyError {
     #[error("signature algorithm {0:?} not whitelisted")]
     AlgorithmNotWhitelisted(fluence_keypair::KeyFormat),
+    #[error("signature algorithm {0:?} is not supported")]
+    AlgorithmNotSupported(fluence_keypair::KeyFormat),
     #[error("invalid key data: {0}")]
     InvalidKeyData(#[from] DecodingError),
 }
@@ -87,6 +91,12 @@ impl PublicKey {
         Self(inner.encode().into())
     }
 
+    /// Decode a public key from the `fluence_keypair::PublicKey::encode` representation.
+    pub fn try_from_bytes(bytes: &[u8], whitelist: KeyFormatWhitelist) -> Result<Self, KeyError> {
+        let pk = fluence_keypair::PublicKey::decode(bytes)?;
+        validate_with_key_format(Self::new(pk.clone()), pk.get_key_format(), whitelist)
+    }
+
     pub fn verify<T: BorshSerialize + ?Sized>(
         &self,
         value: &T,
@@ -99,7 +109,12 @@ impl PublicKey {
             .map_err(VerificationError::InvalidSignature)?;
 
         let serialized_value = SaltedData::new(&value, salt).serialize();
-        Ok(pk.verify(&serialized_value, &signature)?)
+        match pk {
+            fluence_keypair::PublicKey::Secp256k1(_) => {
+                verify_secp256k1(&pk, &serialized_value, &signature)
+            }
+            _ => Ok(pk.verify(&serialized_value, &signature)?),
+        }
     }
 
     pub fn to_peer_id(&self) -> Result<String, KeyError> {
@@ -108,10 +123,14 @@ impl PublicKey {
         Ok(pk.to_peer_id().to_string())
     }
 
-    pub fn validate(&self) -> Result<(), KeyError> {
+    pub fn validate(&self, whitelist: KeyFormatWhitelist) -> Result<(), KeyError> {
+        let key_format = self.key_format()?;
+        validate_with_key_format((), key_format, whitelist)
+    }
+
+    pub fn key_format(&self) -> Result<KeyFormat, KeyError> {
         let pk = fluence_keypair::PublicKey::decode(&self.0)?;
-        let key_format = pk.get_key_format();
-        validate_with_key_format((), key_format)
+        Ok(pk.get_key_format())
     }
 }
 
@@ -125,9 +144,12 @@ impl ToString for PublicKey {
 pub struct KeyPair(fluence_keypair::KeyPair);
 
 impl KeyPair {
+    /// Wrap a key pair of any supported algorithm; whether it may sign data is up to a whitelist.
     pub fn new(inner: fluence_keypair::KeyPair) -> Result<Self, KeyError> {
         let key_format = inner.key_format();
-        validate_with_key_format((), key_format)?;
+        if !KeyFormatWhitelist::all().contains(key_format) {
+            return Err(KeyError::AlgorithmNotSupported(key_format));
+        }
 
         Ok(Self(inner))
     }
@@ -176,13 +198,118 @@ impl From<KeyPair> for fluence_keypair::KeyPair {
     }
 }
 
-pub(crate) fn validate_with_key_format<V>(inner: V, key_format: KeyFormat) -> Result<V, KeyError> {
-    // this allow is needed in order to support old versions of the fluence_keypair
-    // repos which is used to build it for RISC-0
-    #[allow(unreachable_patterns)]
-    match key_format {
-        fluence_keypair::KeyFormat::Ed25519 => Ok(inner),
-        _ => Err(KeyError::AlgorithmNotWhitelisted(key_format)),
+pub(crate) fn validate_with_key_format<V>(
+    inner: V,
+    key_format: KeyFormat,
+    whitelist: KeyFormatWhitelist,
+) -> Result<V, KeyError> {
+    if whitelist.contains(key_format) {
+        Ok(inner)
+    } else {
+        Err(KeyError::AlgorithmNotWhitelisted(key_format))
+    }
+}
+
+// fluence_keypair ignores the secp256k1 verification result, so the signature is checked here
+fn verify_secp256k1(
+    pk: &fluence_keypair::PublicKey,
+    msg: &[u8],
+    signature: &fluence_keypair::Signature,
+) -> Result<(), VerificationError> {
+    use sha2::Digest as _;
+
+    let to_verification_error = |error| {
+        fluence_keypair::error::VerificationError::Secp256k1(
+            error,
+            bs58::encode(signature.to_vec()).into_string(),
+            bs58::encode(pk.to_vec()).into_string(),
+        )
+    };
+
+    let key_bytes = pk.to_vec();
+    let key = libsecp256k1::PublicKey::parse_slice(
+        &key_bytes,
+        Some(libsecp256k1::PublicKeyFormat::Compressed),
+    )
+    .map_err(to_verification_error)?;
+    let message = libsecp256k1::Message::parse_slice(&sha2::Sha256::digest(msg))
+        .map_err(to_verification_error)?;
+    let signature =
+        libsecp256k1::Signature::parse_der(signature.to_vec()).map_err(to_verification_error)?;
+
+    if libsecp256k1::verify(&message, &signature, &key) {
+        Ok(())
+    } else {
+        Err(to_verification_error(libsecp256k1::Error::InvalidSignature).into())
+    }
+}
+
+/// A set of key algorithms allowed for the current peer key and in other peers' signatures.
+///
+/// By default, only Ed25519 is allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyFormatWhitelist {
+    // a bit per `KeyFormat` code
+    mask: u8,
+}
+
+impl KeyFormatWhitelist {
+    pub fn new(key_formats: impl IntoIterator<Item = KeyFormat>) -> Self {
+        let mask = key_formats
+            .into_iter()
+            .fold(0, |mask, key_format| mask | Self::bit(key_format));
+        Self { mask }
+    }
+
+    /// All the algorithms supported both for signing and verification.
+    pub fn all() -> Self {
+        Self::new([KeyFormat::Ed25519, KeyFormat::Secp256k1])
+    }
+
+    /// Decode a whitelist from `KeyFormat` codes; an empty slice stands for the default whitelist.
+    pub fn from_codes(codes: &[u8]) -> Result<Self, KeyError> {
+        if codes.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let key_formats = codes
+            .iter()
+            .map(|&code| KeyFormat::try_from(code))
+            .collect::<Result<Vec<_>, _>>()?;
+        let whitelist = Self::new(key_formats);
+
+        // whitelisting algorithms that cannot sign data, like RSA, makes no sense
+        let unsupported = whitelist.mask & !Self::all().mask;
+        if unsupported != 0 {
+            let key_format = KeyFormat::try_from(unsupported.trailing_zeros() as u8)?;
+            return Err(KeyError::AlgorithmNotSupported(key_format));
+        }
+
+        Ok(whitelist)
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = KeyFormat> {
+        (0..u8::BITS as u8)
+            .filter(move |code| self.mask & (1 << code) != 0)
+            .filter_map(|code| KeyFormat::try_from(code).ok())
+    }
+
+    pub fn to_codes(self) -> Vec<u8> {
+        self.iter().map(u8::from).collect()
+    }
+
+    pub fn contains(&self, key_format: KeyFormat) -> bool {
+        self.mask & Self::bit(key_format) != 0
+    }
+
+    fn bit(key_format: KeyFormat) -> u8 {
+        1 << u8::from(key_format)
+    }
+}
+
+impl Default for KeyFormatWhitelist {
+    fn default() -> Self {
+        Self::new([KeyFormat::Ed25519])
     }
 }
 
@@ -209,6 +336,22 @@ impl Signature {
     fn new(signature: fluence_keypair::Signature) -> Self {
         Self(signature.encode().into())
     }
+
+    /// Create a signature from raw signature bytes, e.g. produced by an external keystore.
+    pub fn from_raw_bytes(key_format: KeyFormat, bytes: Vec<u8>) -> Self {
+        Self::new(fluence_keypair::Signature::from_bytes(key_format, bytes))
+    }
+
+    /// An empty signature that stands for a signature the host hasn't provided yet.
+    ///
+    /// It never passes verification.
+    pub fn placeholder() -> Self {
+        Self(<_>::default())
+    }
+
+    pub fn is_placeholder(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
 impl From<fluence_keypair::Signature> for Signature {
@@ -232,3 +375,11 @@ impl<'ctx, Data: BorshSerialize> SaltedData<'ctx, Data> {
         borsh::to_vec(&self).expect("borsh serializer shouldn't fail")
     }
 }
+
+/// Salt of signatures bound to a particle signature.
+///
+/// Unlike a particle id, a particle signature can't be reused by another particle, so signatures
+/// of produced data can't be replayed in another particle with the same id.
+pub fn particle_signature_salt(particle_signature: &[u8]) -> String {
+    bs58::encode(particle_signature).into_string()
+}
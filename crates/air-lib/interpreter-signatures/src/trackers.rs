@@ -53,15 +53,25 @@ impl PeerCidTracker {
     ) -> Result<crate::Signature, SigningError> {
         sign_cids(self.cids.clone(), salt, &keypair.0).map(Into::into)
     }
+
+    /// The exact payload `gen_signature` signs, for signing it outside of the interpreter.
+    pub fn signing_payload(&self, salt: &str) -> Vec<u8> {
+        salted_cids_payload(self.cids.clone(), salt)
+    }
 }
 
 pub fn sign_cids(
-    mut cids: Vec<Rc<CidRef>>,
+    cids: Vec<Rc<CidRef>>,
     salt: &str,
     keypair: &fluence_keypair::KeyPair,
 ) -> Result<fluence_keypair::Signature, SigningError> {
+    let serialized_cids = salted_cids_payload(cids, salt);
+    keypair.sign(&serialized_cids)
+}
+
+/// Serialize CIDs in canonical order together with the salt, that is what a peer signs.
+pub fn salted_cids_payload(mut cids: Vec<Rc<CidRef>>, salt: &str) -> Vec<u8> {
     cids.sort_unstable();
 
-    let serialized_cids = SaltedData::new(&cids, salt).serialize();
-    keypair.sign(&serialized_cids)
+    SaltedData::new(&cids, salt).serialize()
 }
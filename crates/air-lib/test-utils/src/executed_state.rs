@@ -252,6 +252,18 @@ macro_rules! failed {
     }}
 }
 
+#[macro_export]
+macro_rules! not_authorized {
+    ($reason:expr) => {{
+        let failed_value = $crate::executed_state::_not_authorized_to_value($reason);
+        _trace_value_body!(failed_value).failed()
+    }};
+    ($reason:expr, $func1:ident = $v1:expr $(, $func:ident = $v:expr)*) => {{
+        let failed_value = $crate::executed_state::_not_authorized_to_value($reason);
+        _trace_value_body!(failed_value, $func1 = $v1 $(, $func = $v)*).failed()
+    }}
+}
+
 #[macro_export]
 macro_rules! stream {
     ($value:expr, $generation:expr) => {
@@ -291,6 +303,11 @@ pub fn _failure_to_value(ret_code: i32, error_message: &str) -> JValue {
     crate::CallServiceFailed::new(ret_code, message_serialized.into()).to_value()
 }
 
+pub fn _not_authorized_to_value(reason: &str) -> JValue {
+    let message_serialized = serde_json::to_string(reason).unwrap();
+    crate::CallServiceFailed::not_authorized(message_serialized.into()).to_value()
+}
+
 pub struct ExecutedCallBuilder {
     result: JValue,
     tetraplet: SecurityTetraplet,
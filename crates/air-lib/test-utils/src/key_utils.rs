@@ -17,7 +17,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use crate::executed_state::extract_service_result_cid;
+use crate::InterpreterData;
+
+use air::ExecutionCidState;
 use air_interpreter_signatures::KeyPair;
+use air_interpreter_signatures::PeerCidTracker;
+use air_interpreter_signatures::SignatureStore;
 use rand_chacha::rand_core::SeedableRng;
 
 ///  Derive fake keypair for testing proposes.
@@ -46,3 +52,51 @@ pub fn derive_dummy_keypair(seed: &str) -> (KeyPair, String) {
 pub fn at(peer_name: &str) -> String {
     derive_dummy_keypair(peer_name).1
 }
+
+/// Deterministic Ed25519 keypairs of peers `peer_0`, `peer_1`, etc.
+pub fn dummy_keypairs(count: usize) -> Vec<KeyPair> {
+    (0..count)
+        .map(|idx| derive_dummy_keypair(&format!("peer_{idx}")).0)
+        .collect()
+}
+
+/// Make data with a call result of each peer signed by it with the salt;
+/// the `wrong_signer` signs with another salt.
+pub fn signed_data(
+    keypairs: &[KeyPair],
+    salt: &str,
+    wrong_signer: Option<usize>,
+) -> InterpreterData {
+    use crate::_trace_value_body;
+    use crate::scalar_tracked;
+
+    let mut cid_state = ExecutionCidState::new();
+    let mut signature_store = SignatureStore::new();
+    let mut trace = vec![];
+
+    for (idx, keypair) in keypairs.iter().enumerate() {
+        let peer_id = keypair.public().to_peer_id().unwrap();
+        let state = scalar_tracked!(idx, &mut cid_state, peer = &peer_id);
+
+        let mut tracker = PeerCidTracker::new(peer_id.clone());
+        tracker.register(&peer_id, &extract_service_result_cid(&state));
+        let salt = if wrong_signer == Some(idx) {
+            "another_particle"
+        } else {
+            salt
+        };
+        signature_store.put(
+            keypair.public(),
+            tracker.gen_signature(salt, keypair).unwrap(),
+        );
+
+        trace.push(state);
+    }
+
+    InterpreterData {
+        trace: trace.into(),
+        last_call_request_id: 0,
+        cid_info: cid_state.into(),
+        signatures: signature_store,
+    }
+}
@@ -19,8 +19,11 @@
 
 use crate::prelude::TestInitParameters;
 use crate::test_runner::AirRunner;
+use air_interpreter_interface::CallPolicy;
+use air_interpreter_interface::CallPolicyRepr;
 use air_interpreter_interface::CallResultsRepr;
 use air_interpreter_interface::RunParameters;
+use air_interpreter_interface::TrustPolicy;
 use air_interpreter_sede::ToSerialized;
 use avm_server::avm_runner::*;
 use avm_server::into_raw_result;
@@ -32,6 +35,10 @@ use futures::FutureExt;
 pub struct NativeAirRunner {
     current_peer_id: String,
     test_init_parameters: TestInitParameters,
+    trust_policy: TrustPolicy,
+    call_provenance_enabled: bool,
+    call_deduplication_enabled: bool,
+    call_policy: Vec<u8>,
 }
 
 impl NativeAirRunner {
@@ -39,6 +46,10 @@ impl NativeAirRunner {
         Self {
             current_peer_id: current_peer_id.into(),
             test_init_parameters,
+            trust_policy: <_>::default(),
+            call_provenance_enabled: false,
+            call_deduplication_enabled: false,
+            call_policy: vec![],
         }
     }
 }
@@ -63,6 +74,7 @@ impl AirRunner for NativeAirRunner {
         call_results: avm_server::CallResults,
         keypair: &KeyPair,
         particle_id: String,
+        particle_signature: Vec<u8>,
     ) -> LocalBoxFuture<'this, Result<RawAVMOutcome, Box<dyn std::error::Error + 'this>>> {
         let air = air.into();
         let prev_data = prev_data.into();
@@ -98,11 +110,22 @@ impl AirRunner for NativeAirRunner {
                     ttl,
                     key_format,
                     secret_key_bytes,
+                    signer_public_key: vec![],
+                    allowed_key_formats: self.test_init_parameters.allowed_key_formats.to_codes(),
+                    allowed_peers: self.trust_policy.allowed_peers.clone(),
+                    denied_peers: self.trust_policy.denied_peers.clone(),
+                    known_key_peers: self.trust_policy.known_key_peers.clone(),
+                    known_keys: self.trust_policy.known_keys.clone(),
+                    particle_id_salt_denied: self.trust_policy.particle_id_salt_denied,
+                    call_policy: self.call_policy.clone(),
                     particle_id,
+                    particle_signature,
                     air_size_limit,
                     particle_size_limit,
                     call_result_size_limit,
                     hard_limit_enabled,
+                    call_provenance_enabled: self.call_provenance_enabled,
+                    call_deduplication_enabled: self.call_deduplication_enabled,
                 },
                 raw_call_results,
             );
@@ -116,4 +139,25 @@ impl AirRunner for NativeAirRunner {
     fn get_current_peer_id(&self) -> &str {
         &self.current_peer_id
     }
+
+    fn set_trust_policy(&mut self, trust_policy: TrustPolicy) {
+        self.trust_policy = trust_policy;
+    }
+
+    fn set_call_provenance_enabled(&mut self, enabled: bool) {
+        self.call_provenance_enabled = enabled;
+    }
+
+    fn set_call_deduplication_enabled(&mut self, enabled: bool) {
+        self.call_deduplication_enabled = enabled;
+    }
+
+    fn set_call_policy(&mut self, call_policy: CallPolicy) {
+        use air_interpreter_sede::ToSerialized;
+
+        self.call_policy = CallPolicyRepr
+            .serialize(&call_policy)
+            .expect("the default serializer shouldn't fail")
+            .into();
+    }
 }
@@ -29,7 +29,14 @@ pub use crate::wasm_test_runner::WasmAirRunner;
 
 use super::CallServiceClosure;
 
+use air_interpreter_interface::CallPolicy;
+use air_interpreter_interface::TrustPolicy;
+use air_interpreter_signatures::particle_signature_salt;
+use air_interpreter_signatures::KeyFormatWhitelist;
 use avm_server::avm_runner::*;
+use avm_server::fan_out_call_results;
+use avm_server::reject_expired;
+use avm_server::AVMClock;
 use avm_server::AVMRuntimeLimits;
 use avm_server::AquaVMRuntimeLimits;
 use fluence_keypair::KeyPair;
@@ -38,6 +45,7 @@ use futures::StreamExt;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::convert::Infallible;
 
 pub trait AirRunner {
     fn new(
@@ -58,24 +66,43 @@ pub trait AirRunner {
         call_results: avm_server::CallResults,
         key_pair: &KeyPair,
         particle_id: String,
+        particle_signature: Vec<u8>,
     ) -> LocalBoxFuture<'this, Result<RawAVMOutcome, Box<dyn std::error::Error + 'this>>>;
 
     fn get_current_peer_id(&self) -> &str;
+
+    /// Set peers and keys the runner accepts data from.
+    fn set_trust_policy(&mut self, trust_policy: TrustPolicy);
+
+    /// Make call requests contain provenance chains of their arguments.
+    fn set_call_provenance_enabled(&mut self, enabled: bool);
+
+    /// Make identical call requests of a run be emitted once with a fan-out list of their
+    /// call ids.
+    fn set_call_deduplication_enabled(&mut self, enabled: bool);
+
+    /// Set local services the scripts are allowed to call.
+    fn set_call_policy(&mut self, call_policy: CallPolicy);
 }
 
 pub struct TestRunner<R = DefaultAirRunner> {
     pub runner: R,
     call_service: CallServiceClosure<'static>,
     pub keypair: KeyPair,
+    clock: Option<AVMClock>,
 }
 
-#[derive(Debug, Default, Clone)]
+/// Particle signature of test runs, the interpreter salts signatures of produced data with it.
+pub const TEST_PARTICLE_SIGNATURE: &[u8] = b"particle_signature";
+
+#[derive(Debug, Clone)]
 pub struct TestRunParameters {
     pub init_peer_id: String,
     pub timestamp: u64,
     pub ttl: u32,
     pub override_current_peer_id: Option<String>,
     pub particle_id: String,
+    pub particle_signature: Vec<u8>,
 }
 
 /// This struct is used to set limits for the test runner creating AVMRunner.
@@ -85,9 +112,16 @@ pub struct TestInitParameters {
     pub particle_size_limit: Option<u64>,
     pub call_result_size_limit: Option<u64>,
     pub hard_limit_enabled: bool,
+    pub allowed_key_formats: KeyFormatWhitelist,
 }
 
 impl<R: AirRunner> TestRunner<R> {
+    /// Reject expired particles according to the clock like `AVM` does, it's usually
+    /// a `VirtualClock` moved by a test.
+    pub fn set_clock(&mut self, clock: AVMClock) {
+        self.clock = Some(clock);
+    }
+
     pub async fn call(
         &mut self,
         air: impl Into<String>,
@@ -105,8 +139,14 @@ impl<R: AirRunner> TestRunner<R> {
             ttl,
             override_current_peer_id,
             particle_id,
+            particle_signature,
         } = test_run_params;
 
+        if let Some(clock) = &self.clock {
+            reject_expired::<Infallible>(clock.as_ref(), &particle_id, timestamp, ttl)
+                .map_err(|e| e.to_string())?;
+        }
+
         let mut call_results = HashMap::new();
         let mut next_peer_pks = HashSet::new();
 
@@ -124,6 +164,7 @@ impl<R: AirRunner> TestRunner<R> {
                     call_results,
                     &self.keypair,
                     particle_id.clone(),
+                    particle_signature.clone(),
                 )
                 .await
                 .map_err(|e| e.to_string())?;
@@ -135,13 +176,15 @@ impl<R: AirRunner> TestRunner<R> {
                 return Ok(outcome);
             }
 
-            call_results = futures::stream::iter(outcome.call_requests.into_iter())
+            let call_requests = outcome.call_requests;
+            call_results = futures::stream::iter(call_requests.clone().into_iter())
                 .then(|(id, call_parameters)| {
                     let service_result = (self.call_service)(call_parameters);
                     async move { (id, service_result.await) }
                 })
                 .collect::<HashMap<_, _>>()
                 .await;
+            fan_out_call_results(&call_requests, &mut call_results);
 
             prev_data = outcome.data;
             data = vec![];
@@ -173,6 +216,7 @@ impl<R: AirRunner> TestRunner<R> {
                 call_results,
                 &self.keypair,
                 particle_id.into(),
+                TEST_PARTICLE_SIGNATURE.to_vec(),
             )
             .await
     }
@@ -197,6 +241,7 @@ pub async fn create_custom_avm<R: AirRunner>(
         runner,
         call_service,
         keypair: keypair.into_inner(),
+        clock: None,
     }
 }
 
@@ -213,6 +258,7 @@ pub async fn create_avm_with_key<R: AirRunner>(
         runner,
         call_service,
         keypair,
+        clock: None,
     }
 }
 
@@ -229,6 +275,7 @@ impl TestRunParameters {
             ttl,
             override_current_peer_id: None,
             particle_id: particle_id.into(),
+            particle_signature: TEST_PARTICLE_SIGNATURE.to_vec(),
         }
     }
 
@@ -257,6 +304,29 @@ impl TestRunParameters {
         self.particle_id = particle_id.into();
         self
     }
+
+    pub fn with_particle_signature(mut self, particle_signature: impl Into<Vec<u8>>) -> Self {
+        self.particle_signature = particle_signature.into();
+        self
+    }
+}
+
+impl Default for TestRunParameters {
+    fn default() -> Self {
+        Self {
+            init_peer_id: <_>::default(),
+            timestamp: <_>::default(),
+            ttl: <_>::default(),
+            override_current_peer_id: None,
+            particle_id: <_>::default(),
+            particle_signature: TEST_PARTICLE_SIGNATURE.to_vec(),
+        }
+    }
+}
+
+/// Salt of signatures of data produced by test runs.
+pub fn test_signature_salt() -> String {
+    particle_signature_salt(TEST_PARTICLE_SIGNATURE)
 }
 
 impl TestInitParameters {
@@ -271,6 +341,7 @@ impl TestInitParameters {
             particle_size_limit: Some(particle_size_limit),
             call_result_size_limit: Some(call_result_size_limit),
             hard_limit_enabled,
+            allowed_key_formats: <_>::default(),
         }
     }
 
@@ -280,8 +351,14 @@ impl TestInitParameters {
             particle_size_limit: Some(u64::MAX),
             call_result_size_limit: Some(u64::MAX),
             hard_limit_enabled: false,
+            allowed_key_formats: <_>::default(),
         }
     }
+
+    pub fn with_allowed_key_formats(mut self, allowed_key_formats: KeyFormatWhitelist) -> Self {
+        self.allowed_key_formats = allowed_key_formats;
+        self
+    }
 }
 
 impl From<TestInitParameters> for AVMRuntimeLimits {
@@ -354,6 +431,7 @@ mod tests {
                 HashMap::new(),
                 &keypair,
                 "".to_owned(),
+                TEST_PARTICLE_SIGNATURE.to_vec(),
             )
             .await
             .expect("call should be success");
@@ -389,6 +467,7 @@ mod tests {
                 HashMap::new(),
                 &keypair2,
                 "".to_owned(),
+                TEST_PARTICLE_SIGNATURE.to_vec(),
             )
             .await
             .expect("call should be success");
@@ -21,6 +21,8 @@ use crate::prelude::TestInitParameters;
 use crate::test_runner::AirRunner;
 
 use avm_server::avm_runner::*;
+use avm_server::CallPolicy;
+use avm_server::TrustPolicy;
 
 use fluence_keypair::KeyPair;
 use futures::future::LocalBoxFuture;
@@ -85,10 +87,14 @@ impl AirRunner for WasmAirRunner {
                 )
             });
 
-            let runner = match pool.try_pull() {
+            let mut runner = match pool.try_pull() {
                 Some(runner) => runner,
                 None => Reusable::new(pool, make_pooled_avm_runner(test_init_parameters).await),
             };
+            // pooled runners may be created with other parameters and keep settings of other tests
+            let settings = runner.settings_mut();
+            *settings = <_>::default();
+            settings.set_allowed_key_formats(test_init_parameters.allowed_key_formats.iter());
 
             Self {
                 current_peer_id: current_peer_id.into(),
@@ -110,6 +116,7 @@ impl AirRunner for WasmAirRunner {
         call_results: avm_server::CallResults,
         keypair: &KeyPair,
         particle_id: String,
+        particle_signature: Vec<u8>,
     ) -> LocalBoxFuture<'this, Result<RawAVMOutcome, Box<dyn std::error::Error + 'this>>> {
         let air = air.into();
         let prev_data = prev_data.into();
@@ -133,6 +140,7 @@ impl AirRunner for WasmAirRunner {
                     call_results,
                     &keypair,
                     particle_id,
+                    particle_signature,
                 )
                 .await?)
         }
@@ -142,6 +150,26 @@ impl AirRunner for WasmAirRunner {
     fn get_current_peer_id(&self) -> &str {
         &self.current_peer_id
     }
+
+    fn set_trust_policy(&mut self, trust_policy: TrustPolicy) {
+        self.runner.settings_mut().set_trust_policy(trust_policy);
+    }
+
+    fn set_call_provenance_enabled(&mut self, enabled: bool) {
+        self.runner
+            .settings_mut()
+            .set_call_provenance_enabled(enabled);
+    }
+
+    fn set_call_deduplication_enabled(&mut self, enabled: bool) {
+        self.runner
+            .settings_mut()
+            .set_call_deduplication_enabled(enabled);
+    }
+
+    fn set_call_policy(&mut self, call_policy: CallPolicy) {
+        self.runner.settings_mut().set_call_policy(&call_policy);
+    }
 }
 
 /// WASM runner that runs release build form benchmarking.
@@ -161,7 +189,7 @@ impl AirRunner for ReleaseWasmAirRunner {
             let logging_mask = i32::MAX;
 
             let wasm_backend = create_wasm_backend();
-            let runner = AVMRunner::new(
+            let mut runner = AVMRunner::new(
                 PathBuf::from(RELEASE_AIR_WASM_PATH),
                 Some(AVM_MAX_HEAP_SIZE),
                 test_init_parameters.into(),
@@ -170,6 +198,9 @@ impl AirRunner for ReleaseWasmAirRunner {
             )
             .await
             .expect("vm should be created");
+            runner
+                .settings_mut()
+                .set_allowed_key_formats(test_init_parameters.allowed_key_formats.iter());
 
             Self {
                 current_peer_id: current_peer_id.into(),
@@ -191,6 +222,7 @@ impl AirRunner for ReleaseWasmAirRunner {
         call_results: avm_server::CallResults,
         keypair: &KeyPair,
         particle_id: String,
+        particle_signature: Vec<u8>,
     ) -> LocalBoxFuture<'this, Result<RawAVMOutcome, Box<dyn std::error::Error + 'this>>> {
         let air = air.into();
         let prev_data = prev_data.into();
@@ -214,6 +246,7 @@ impl AirRunner for ReleaseWasmAirRunner {
                     call_results,
                     &keypair,
                     particle_id,
+                    particle_signature,
                 )
                 .await?)
         }
@@ -223,4 +256,24 @@ impl AirRunner for ReleaseWasmAirRunner {
     fn get_current_peer_id(&self) -> &str {
         &self.current_peer_id
     }
+
+    fn set_trust_policy(&mut self, trust_policy: TrustPolicy) {
+        self.runner.settings_mut().set_trust_policy(trust_policy);
+    }
+
+    fn set_call_provenance_enabled(&mut self, enabled: bool) {
+        self.runner
+            .settings_mut()
+            .set_call_provenance_enabled(enabled);
+    }
+
+    fn set_call_deduplication_enabled(&mut self, enabled: bool) {
+        self.runner
+            .settings_mut()
+            .set_call_deduplication_enabled(enabled);
+    }
+
+    fn set_call_policy(&mut self, call_policy: CallPolicy) {
+        self.runner.settings_mut().set_call_policy(&call_policy);
+    }
 }
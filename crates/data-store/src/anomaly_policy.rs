@@ -0,0 +1,385 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use avm_interface::raw_outcome::RawAVMOutcome;
+
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Describes when `AnomalyPolicy` flags an execution.
+#[derive(Clone, Debug)]
+pub struct AnomalyPolicyConfig {
+    /// Number of the latest executions the statistics are kept for, it's also the period
+    /// of `max_anomalies_per_window`.
+    pub window_size: usize,
+
+    /// Number of executions needed to flag outliers, so the statistics are representative.
+    pub warmup_size: usize,
+
+    /// A value is an outlier if it exceeds the p99 of the window multiplied by the factor.
+    pub outlier_factor: f64,
+
+    /// Executions taking less time are never outliers.
+    pub min_execution_time: Duration,
+
+    /// Executions growing the interpreter heap by less bytes are never outliers.
+    pub min_memory_delta: usize,
+
+    /// Executions producing smaller data are never outliers.
+    pub min_data_size: usize,
+
+    /// Interpreter errors with these codes are always flagged, regardless of the statistics.
+    pub error_ret_codes: HashSet<i64>,
+
+    /// Maximum number of anomalies flagged per `window_size` executions, further ones are
+    /// skipped to keep the storage usage bounded.
+    pub max_anomalies_per_window: usize,
+}
+
+impl Default for AnomalyPolicyConfig {
+    fn default() -> Self {
+        Self {
+            window_size: 1000,
+            warmup_size: 100,
+            outlier_factor: 2.0,
+            min_execution_time: Duration::from_millis(100),
+            min_memory_delta: 1024 * 1024,
+            min_data_size: 1024 * 1024,
+            error_ret_codes: <_>::default(),
+            max_anomalies_per_window: 10,
+        }
+    }
+}
+
+/// A reason an execution is flagged as an anomaly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnomalyKind {
+    /// The interpreter failed with one of the configured codes.
+    InterpreterError { ret_code: i64 },
+
+    /// The execution took much longer than usual.
+    SlowExecution,
+
+    /// The interpreter heap grew much more than usual.
+    MemoryGrowth,
+
+    /// The produced data is much bigger than usual.
+    LargeData,
+}
+
+/// The median and the 99th percentile of a metric over the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Percentiles {
+    pub p50: u64,
+    pub p99: u64,
+}
+
+/// Rolling statistics of executions kept by `AnomalyPolicy`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AnomalyStatistics {
+    /// Number of executions in the window.
+    pub samples: usize,
+    /// Execution time in microseconds.
+    pub execution_time: Percentiles,
+    /// Interpreter heap growth in bytes.
+    pub memory_delta: Percentiles,
+    /// Size of the produced data in bytes.
+    pub data_size: Percentiles,
+}
+
+/// Detects anomalies by rolling statistics of the latest executions, a `DataStore` can
+/// delegate `detect_anomaly` to it instead of fixed thresholds.
+pub struct AnomalyPolicy {
+    config: AnomalyPolicyConfig,
+    state: Mutex<PolicyState>,
+}
+
+struct PolicyState {
+    execution_time: RollingWindow,
+    memory_delta: RollingWindow,
+    data_size: RollingWindow,
+    executions: u64,
+    flagged_in_window: usize,
+}
+
+impl AnomalyPolicy {
+    pub fn new(config: AnomalyPolicyConfig) -> Self {
+        let window_size = config.window_size.max(1);
+        let state = PolicyState {
+            execution_time: RollingWindow::new(window_size),
+            memory_delta: RollingWindow::new(window_size),
+            data_size: RollingWindow::new(window_size),
+            executions: 0,
+            flagged_in_window: 0,
+        };
+
+        Self {
+            config,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Account the execution in the statistics and check whether it should be saved as an
+    /// anomaly, it has the same arguments as `DataStore::detect_anomaly`.
+    ///
+    /// An execution is compared with the previous ones, so a series of similar heavy
+    /// executions is flagged only until they become usual.
+    pub fn detect(
+        &self,
+        execution_time: Duration,
+        memory_delta: usize,
+        outcome: &RawAVMOutcome,
+    ) -> Option<AnomalyKind> {
+        let mut state = self.state.lock().unwrap_or_else(|error| error.into_inner());
+
+        let execution_time = execution_time.as_micros().try_into().unwrap_or(u64::MAX);
+        let memory_delta = memory_delta as u64;
+        let data_size = outcome.data.len() as u64;
+
+        let anomaly = if self.config.error_ret_codes.contains(&outcome.ret_code) {
+            Some(AnomalyKind::InterpreterError {
+                ret_code: outcome.ret_code,
+            })
+        } else if state.execution_time.len() < self.config.warmup_size {
+            None
+        } else if self.is_outlier(
+            &state.execution_time,
+            execution_time,
+            self.config.min_execution_time.as_micros() as u64,
+        ) {
+            Some(AnomalyKind::SlowExecution)
+        } else if self.is_outlier(
+            &state.memory_delta,
+            memory_delta,
+            self.config.min_memory_delta as u64,
+        ) {
+            Some(AnomalyKind::MemoryGrowth)
+        } else if self.is_outlier(
+            &state.data_size,
+            data_size,
+            self.config.min_data_size as u64,
+        ) {
+            Some(AnomalyKind::LargeData)
+        } else {
+            None
+        };
+
+        state.execution_time.push(execution_time);
+        state.memory_delta.push(memory_delta);
+        state.data_size.push(data_size);
+
+        // anomalies above the budget are sampled out
+        let anomaly = match anomaly {
+            Some(_) if state.flagged_in_window < self.config.max_anomalies_per_window => {
+                state.flagged_in_window += 1;
+                anomaly
+            }
+            _ => None,
+        };
+
+        state.executions += 1;
+        if state.executions % self.config.window_size.max(1) as u64 == 0 {
+            state.flagged_in_window = 0;
+        }
+
+        anomaly
+    }
+
+    /// Return the current statistics of the window.
+    pub fn statistics(&self) -> AnomalyStatistics {
+        let state = self.state.lock().unwrap_or_else(|error| error.into_inner());
+
+        AnomalyStatistics {
+            samples: state.execution_time.len(),
+            execution_time: state.execution_time.percentiles(),
+            memory_delta: state.memory_delta.percentiles(),
+            data_size: state.data_size.percentiles(),
+        }
+    }
+
+    fn is_outlier(&self, window: &RollingWindow, value: u64, min_value: u64) -> bool {
+        let threshold = window.percentile(0.99) as f64 * self.config.outlier_factor;
+        value >= min_value && value as f64 > threshold
+    }
+}
+
+/// The latest values of a metric, they are also kept sorted to get percentiles.
+struct RollingWindow {
+    values: VecDeque<u64>,
+    sorted: Vec<u64>,
+    capacity: usize,
+}
+
+impl RollingWindow {
+    fn new(capacity: usize) -> Self {
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            sorted: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn push(&mut self, value: u64) {
+        if self.values.len() == self.capacity {
+            if let Some(oldest) = self.values.pop_front() {
+                let position = self.sorted.partition_point(|&sorted| sorted < oldest);
+                self.sorted.remove(position);
+            }
+        }
+
+        self.values.push_back(value);
+        let position = self.sorted.partition_point(|&sorted| sorted < value);
+        self.sorted.insert(position, value);
+    }
+
+    /// A nearest-rank percentile, it's zero for an empty window.
+    fn percentile(&self, quantile: f64) -> u64 {
+        if self.sorted.is_empty() {
+            return 0;
+        }
+
+        let rank = (quantile * self.sorted.len() as f64).ceil() as usize;
+        self.sorted[rank.clamp(1, self.sorted.len()) - 1]
+    }
+
+    fn percentiles(&self) -> Percentiles {
+        Percentiles {
+            p50: self.percentile(0.5),
+            p99: self.percentile(0.99),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(ret_code: i64, data_size: usize) -> RawAVMOutcome {
+        RawAVMOutcome::with_data(ret_code, vec![0; data_size])
+    }
+
+    fn config() -> AnomalyPolicyConfig {
+        AnomalyPolicyConfig {
+            window_size: 10,
+            warmup_size: 5,
+            outlier_factor: 2.0,
+            min_execution_time: Duration::from_millis(10),
+            min_memory_delta: 100,
+            min_data_size: 100,
+            error_ret_codes: maplit::hashset! {42},
+            max_anomalies_per_window: 2,
+        }
+    }
+
+    fn warm_up(policy: &AnomalyPolicy) {
+        for _ in 0..5 {
+            let anomaly = policy.detect(Duration::from_millis(20), 200, &outcome(0, 200));
+            assert_eq!(anomaly, None);
+        }
+    }
+
+    #[test]
+    fn outliers_are_flagged_after_warmup() {
+        let policy = AnomalyPolicy::new(config());
+
+        warm_up(&policy);
+        let outcome = outcome(0, 200);
+        let anomaly = policy.detect(Duration::from_millis(50), 200, &outcome);
+        assert_eq!(anomaly, Some(AnomalyKind::SlowExecution));
+        let anomaly = policy.detect(Duration::from_millis(20), 1000, &outcome);
+        assert_eq!(anomaly, Some(AnomalyKind::MemoryGrowth));
+    }
+
+    #[test]
+    fn outliers_are_not_flagged_during_warmup() {
+        let policy = AnomalyPolicy::new(config());
+
+        let anomaly = policy.detect(Duration::from_secs(10), 0, &outcome(0, 0));
+        assert_eq!(anomaly, None);
+    }
+
+    #[test]
+    fn large_data_is_flagged() {
+        let policy = AnomalyPolicy::new(config());
+        warm_up(&policy);
+
+        let anomaly = policy.detect(Duration::from_millis(20), 200, &outcome(0, 1000));
+        assert_eq!(anomaly, Some(AnomalyKind::LargeData));
+    }
+
+    #[test]
+    fn small_values_are_not_outliers() {
+        let policy = AnomalyPolicy::new(config());
+        for _ in 0..5 {
+            policy.detect(Duration::ZERO, 0, &outcome(0, 0));
+        }
+
+        let anomaly = policy.detect(Duration::from_millis(5), 50, &outcome(0, 50));
+        assert_eq!(anomaly, None);
+    }
+
+    #[test]
+    fn error_codes_are_always_flagged() {
+        let policy = AnomalyPolicy::new(config());
+
+        let anomaly = policy.detect(Duration::ZERO, 0, &outcome(42, 0));
+        assert_eq!(
+            anomaly,
+            Some(AnomalyKind::InterpreterError { ret_code: 42 })
+        );
+        let anomaly = policy.detect(Duration::ZERO, 0, &outcome(43, 0));
+        assert_eq!(anomaly, None);
+    }
+
+    #[test]
+    fn anomalies_are_sampled() {
+        let policy = AnomalyPolicy::new(config());
+
+        let flagged = (0..10)
+            .filter_map(|_| policy.detect(Duration::ZERO, 0, &outcome(42, 0)))
+            .count();
+        assert_eq!(flagged, 2);
+
+        // the budget is renewed with the next window
+        let anomaly = policy.detect(Duration::ZERO, 0, &outcome(42, 0));
+        assert_eq!(
+            anomaly,
+            Some(AnomalyKind::InterpreterError { ret_code: 42 })
+        );
+    }
+
+    #[test]
+    fn statistics_are_rolling() {
+        let policy = AnomalyPolicy::new(config());
+        for memory_delta in 1..=20 {
+            policy.detect(Duration::from_micros(memory_delta), 0, &outcome(0, 0));
+        }
+
+        let statistics = policy.statistics();
+        assert_eq!(statistics.samples, 10);
+        assert_eq!(statistics.execution_time, Percentiles { p50: 15, p99: 20 });
+        assert_eq!(statistics.memory_delta, Percentiles::default());
+    }
+}
@@ -0,0 +1,394 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::AnomalyData;
+use crate::DataStore;
+
+use avm_interface::raw_outcome::RawAVMOutcome;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+/// A future returned by `AsyncDataStore` methods.
+pub type DataStoreFuture<'store, T, E> =
+    Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'store>>;
+
+/// A future returned by `AsyncDataStore::detect_anomaly`.
+pub type AnomalyDetectionFuture<'store> = Pin<Box<dyn Future<Output = bool> + Send + 'store>>;
+
+/// An async counterpart of `DataStore` for storages that shouldn't block an executor,
+/// every `DataStore` implements it as well.
+pub trait AsyncDataStore {
+    type Error;
+
+    fn initialize(&mut self) -> DataStoreFuture<'_, (), Self::Error>;
+
+    fn store_data<'store>(
+        &'store mut self,
+        data: &'store [u8],
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+    ) -> DataStoreFuture<'store, (), Self::Error>;
+
+    fn read_data<'store>(
+        &'store mut self,
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+    ) -> DataStoreFuture<'store, Vec<u8>, Self::Error>;
+
+    /// Cleanup data that become obsolete.
+    fn cleanup_data<'store>(
+        &'store mut self,
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+    ) -> DataStoreFuture<'store, (), Self::Error>;
+
+    /// See `DataStore::track_expiration`.
+    fn track_expiration<'store>(
+        &'store mut self,
+        _particle_id: &'store str,
+        _current_peer_id: &'store str,
+        _timestamp: u64,
+        _ttl: u32,
+    ) -> DataStoreFuture<'store, (), Self::Error> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// See `DataStore::collect_expired`.
+    fn collect_expired(&mut self, _now: u64) -> DataStoreFuture<'_, usize, Self::Error> {
+        Box::pin(async { Ok(0) })
+    }
+
+    /// See `DataStore::detect_anomaly`.
+    fn detect_anomaly<'store>(
+        &'store self,
+        execution_time: Duration,
+        memory_delta: usize,
+        outcome: &'store RawAVMOutcome,
+    ) -> AnomalyDetectionFuture<'store>;
+
+    fn collect_anomaly_data<'store>(
+        &'store mut self,
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+        anomaly_data: AnomalyData<'store>,
+    ) -> DataStoreFuture<'store, (), Self::Error>;
+
+    /// See `DataStore::share`.
+    fn share(&self) -> Option<Box<dyn AsyncDataStore<Error = Self::Error> + Send + Sync>> {
+        None
+    }
+}
+
+/// A synchronous store completes its futures on the first poll.
+impl<T: DataStore + Send + Sync + 'static> AsyncDataStore for T {
+    type Error = T::Error;
+
+    fn initialize(&mut self) -> DataStoreFuture<'_, (), Self::Error> {
+        Box::pin(async move { DataStore::initialize(self) })
+    }
+
+    fn store_data<'store>(
+        &'store mut self,
+        data: &'store [u8],
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+    ) -> DataStoreFuture<'store, (), Self::Error> {
+        Box::pin(async move { DataStore::store_data(self, data, particle_id, current_peer_id) })
+    }
+
+    fn read_data<'store>(
+        &'store mut self,
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+    ) -> DataStoreFuture<'store, Vec<u8>, Self::Error> {
+        Box::pin(async move { DataStore::read_data(self, particle_id, current_peer_id) })
+    }
+
+    fn cleanup_data<'store>(
+        &'store mut self,
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+    ) -> DataStoreFuture<'store, (), Self::Error> {
+        Box::pin(async move { DataStore::cleanup_data(self, particle_id, current_peer_id) })
+    }
+
+    fn track_expiration<'store>(
+        &'store mut self,
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+        timestamp: u64,
+        ttl: u32,
+    ) -> DataStoreFuture<'store, (), Self::Error> {
+        Box::pin(async move {
+            DataStore::track_expiration(self, particle_id, current_peer_id, timestamp, ttl)
+        })
+    }
+
+    fn collect_expired(&mut self, now: u64) -> DataStoreFuture<'_, usize, Self::Error> {
+        Box::pin(async move { DataStore::collect_expired(self, now) })
+    }
+
+    fn detect_anomaly<'store>(
+        &'store self,
+        execution_time: Duration,
+        memory_delta: usize,
+        outcome: &'store RawAVMOutcome,
+    ) -> AnomalyDetectionFuture<'store> {
+        let is_anomaly = DataStore::detect_anomaly(self, execution_time, memory_delta, outcome);
+        Box::pin(std::future::ready(is_anomaly))
+    }
+
+    fn collect_anomaly_data<'store>(
+        &'store mut self,
+        particle_id: &'store str,
+        current_peer_id: &'store str,
+        anomaly_data: AnomalyData<'store>,
+    ) -> DataStoreFuture<'store, (), Self::Error> {
+        Box::pin(async move {
+            DataStore::collect_anomaly_data(self, particle_id, current_peer_id, anomaly_data)
+        })
+    }
+
+    fn share(&self) -> Option<Box<dyn AsyncDataStore<Error = Self::Error> + Send + Sync>> {
+        DataStore::share(self).map(|store| Box::new(store) as _)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::block_on;
+
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+    use std::task::Poll;
+
+    type Storage = HashMap<(String, String), Vec<u8>>;
+
+    /// Keeps data in memory, but yields before every operation like a real async backend.
+    #[derive(Default)]
+    struct AsyncMemoryStore {
+        data: Storage,
+        anomalies: Vec<String>,
+    }
+
+    #[derive(Default)]
+    struct MemoryStore {
+        data: Storage,
+        anomalies: Vec<String>,
+    }
+
+    async fn yield_now() {
+        let mut yielded = false;
+        std::future::poll_fn(|cx| {
+            if yielded {
+                return Poll::Ready(());
+            }
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        })
+        .await
+    }
+
+    fn key(particle_id: &str, current_peer_id: &str) -> (String, String) {
+        (particle_id.to_string(), current_peer_id.to_string())
+    }
+
+    impl AsyncDataStore for AsyncMemoryStore {
+        type Error = Infallible;
+
+        fn initialize(&mut self) -> DataStoreFuture<'_, (), Self::Error> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn store_data<'store>(
+            &'store mut self,
+            data: &'store [u8],
+            particle_id: &'store str,
+            current_peer_id: &'store str,
+        ) -> DataStoreFuture<'store, (), Self::Error> {
+            Box::pin(async move {
+                yield_now().await;
+                self.data
+                    .insert(key(particle_id, current_peer_id), data.to_vec());
+                Ok(())
+            })
+        }
+
+        fn read_data<'store>(
+            &'store mut self,
+            particle_id: &'store str,
+            current_peer_id: &'store str,
+        ) -> DataStoreFuture<'store, Vec<u8>, Self::Error> {
+            Box::pin(async move {
+                yield_now().await;
+                let data = self.data.get(&key(particle_id, current_peer_id));
+                Ok(data.cloned().unwrap_or_default())
+            })
+        }
+
+        fn cleanup_data<'store>(
+            &'store mut self,
+            particle_id: &'store str,
+            current_peer_id: &'store str,
+        ) -> DataStoreFuture<'store, (), Self::Error> {
+            Box::pin(async move {
+                yield_now().await;
+                self.data.remove(&key(particle_id, current_peer_id));
+                Ok(())
+            })
+        }
+
+        fn detect_anomaly<'store>(
+            &'store self,
+            _execution_time: Duration,
+            memory_delta: usize,
+            _outcome: &'store RawAVMOutcome,
+        ) -> AnomalyDetectionFuture<'store> {
+            Box::pin(async move {
+                yield_now().await;
+                memory_delta > 0
+            })
+        }
+
+        fn collect_anomaly_data<'store>(
+            &'store mut self,
+            _particle_id: &'store str,
+            _current_peer_id: &'store str,
+            anomaly_data: AnomalyData<'store>,
+        ) -> DataStoreFuture<'store, (), Self::Error> {
+            Box::pin(async move {
+                yield_now().await;
+                self.anomalies.push(anomaly_data.air_script.into_owned());
+                Ok(())
+            })
+        }
+    }
+
+    impl DataStore for MemoryStore {
+        type Error = Infallible;
+
+        fn initialize(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn store_data(
+            &mut self,
+            data: &[u8],
+            particle_id: &str,
+            current_peer_id: &str,
+        ) -> Result<(), Self::Error> {
+            self.data
+                .insert(key(particle_id, current_peer_id), data.to_vec());
+            Ok(())
+        }
+
+        fn read_data(
+            &mut self,
+            particle_id: &str,
+            current_peer_id: &str,
+        ) -> Result<Vec<u8>, Self::Error> {
+            let data = self.data.get(&key(particle_id, current_peer_id));
+            Ok(data.cloned().unwrap_or_default())
+        }
+
+        fn cleanup_data(
+            &mut self,
+            particle_id: &str,
+            current_peer_id: &str,
+        ) -> Result<(), Self::Error> {
+            self.data.remove(&key(particle_id, current_peer_id));
+            Ok(())
+        }
+
+        fn detect_anomaly(
+            &self,
+            _execution_time: Duration,
+            memory_delta: usize,
+            _outcome: &RawAVMOutcome,
+        ) -> bool {
+            memory_delta > 0
+        }
+
+        fn collect_anomaly_data(
+            &mut self,
+            _particle_id: &str,
+            _current_peer_id: &str,
+            anomaly_data: AnomalyData<'_>,
+        ) -> Result<(), Self::Error> {
+            self.anomalies.push(anomaly_data.air_script.into_owned());
+            Ok(())
+        }
+    }
+
+    /// Uses the store as the AVM does, that is as a trait object.
+    async fn check_store(store: &mut (dyn AsyncDataStore<Error = Infallible> + Send + Sync)) {
+        store.initialize().await.unwrap();
+        store
+            .track_expiration("particle", "peer", 1000, 100)
+            .await
+            .unwrap();
+
+        assert_eq!(store.read_data("particle", "peer").await.unwrap(), b"");
+        store.store_data(b"data", "particle", "peer").await.unwrap();
+        assert_eq!(store.read_data("particle", "peer").await.unwrap(), b"data");
+        store.cleanup_data("particle", "peer").await.unwrap();
+        assert_eq!(store.read_data("particle", "peer").await.unwrap(), b"");
+
+        let outcome = RawAVMOutcome::with_data(0, vec![]);
+        assert!(
+            !store
+                .detect_anomaly(Duration::from_secs(1), 0, &outcome)
+                .await
+        );
+        assert!(
+            store
+                .detect_anomaly(Duration::from_secs(1), 42, &outcome)
+                .await
+        );
+
+        let anomaly = AnomalyData::new("(null)", b"", b"", b"", b"", b"", Duration::ZERO, 42);
+        store
+            .collect_anomaly_data("particle", "peer", anomaly)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn async_store() {
+        let mut store = AsyncMemoryStore::default();
+        block_on(check_store(&mut store));
+
+        assert!(store.data.is_empty());
+        assert_eq!(store.anomalies, ["(null)"]);
+    }
+
+    #[test]
+    fn sync_store_is_async_store() {
+        let mut store = MemoryStore::default();
+        block_on(check_store(&mut store));
+
+        assert!(store.data.is_empty());
+        assert_eq!(store.anomalies, ["(null)"]);
+    }
+}
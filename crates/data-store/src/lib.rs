@@ -29,6 +29,18 @@
     unreachable_patterns
 )]
 
+mod anomaly_policy;
+mod async_data_store;
+
+pub use anomaly_policy::AnomalyKind;
+pub use anomaly_policy::AnomalyPolicy;
+pub use anomaly_policy::AnomalyPolicyConfig;
+pub use anomaly_policy::AnomalyStatistics;
+pub use anomaly_policy::Percentiles;
+pub use async_data_store::AnomalyDetectionFuture;
+pub use async_data_store::AsyncDataStore;
+pub use async_data_store::DataStoreFuture;
+
 use avm_interface::raw_outcome::RawAVMOutcome;
 
 use serde::Deserialize;
@@ -61,6 +73,29 @@ pub trait DataStore {
     fn cleanup_data(&mut self, particle_id: &str, current_peer_id: &str)
         -> Result<(), Self::Error>;
 
+    /// Remember when data of a particle becomes obsolete, it's called before the particle is
+    /// executed with its timestamp and ttl in milliseconds.
+    ///
+    /// Stores without expiration of data can ignore it.
+    fn track_expiration(
+        &mut self,
+        _particle_id: &str,
+        _current_peer_id: &str,
+        _timestamp: u64,
+        _ttl: u32,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Remove data of particles whose `timestamp + ttl` tracked by `track_expiration` is not
+    /// after `now`, all values are in milliseconds since the Unix epoch. Returns the number
+    /// of removed data.
+    ///
+    /// Stores without expiration of data collect nothing.
+    fn collect_expired(&mut self, _now: u64) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+
     /// Returns true if an anomaly happened and it's necessary to save execution data
     /// for debugging purposes.
     ///  execution_time - time taken by the interpreter to execute provided script
@@ -80,6 +115,15 @@ pub trait DataStore {
         current_peer_id: &str,
         anomaly_data: AnomalyData<'_>,
     ) -> Result<(), Self::Error>;
+
+    /// Returns another handle to the same storage which can be used concurrently with this one,
+    /// e.g. by AVMs of a pool. Stores that can't be shared return `None`.
+    fn share(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -98,6 +142,9 @@ pub struct AnomalyData<'data> {
     pub avm_outcome: Cow<'data, [u8]>,
     pub execution_time: Duration,
     pub memory_delta: usize,
+    /// Current data that followed `current_data` in a call with several current data.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_current_data: Vec<serde_bytes::ByteBuf>,
 }
 
 impl<'data> AnomalyData<'data> {
@@ -121,8 +168,19 @@ impl<'data> AnomalyData<'data> {
             avm_outcome: avm_outcome.into(),
             execution_time,
             memory_delta,
+            extra_current_data: vec![],
         }
     }
+
+    /// Set current data that followed `current_data` in a call with several current data.
+    pub fn with_extra_current_data(mut self, extra_current_data: &[Vec<u8>]) -> Self {
+        self.extra_current_data = extra_current_data
+            .iter()
+            .cloned()
+            .map(serde_bytes::ByteBuf::from)
+            .collect();
+        self
+    }
 }
 
 #[cfg(test)]
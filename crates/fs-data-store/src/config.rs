@@ -0,0 +1,53 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use avm_data_store::AnomalyPolicyConfig;
+
+use std::path::PathBuf;
+
+const DEFAULT_MAX_ANOMALY_COUNT: usize = 100;
+const DEFAULT_MAX_ANOMALY_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Describes behaviour of the filesystem data store.
+#[derive(Clone, Debug)]
+pub struct FsDataStoreConfig {
+    /// A directory where particle data and anomalies are stored.
+    pub root_dir: PathBuf,
+
+    /// Decides which executions are saved as anomalies.
+    pub anomaly_policy: AnomalyPolicyConfig,
+
+    /// Maximum number of stored anomalies, the oldest ones are removed first.
+    pub max_anomaly_count: usize,
+
+    /// Maximum total size of stored anomalies in bytes, the oldest ones are removed first.
+    pub max_anomaly_bytes: u64,
+}
+
+impl FsDataStoreConfig {
+    /// Create a config with the default anomaly policy and retention.
+    pub fn new(root_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            root_dir: root_dir.into(),
+            anomaly_policy: <_>::default(),
+            max_anomaly_count: DEFAULT_MAX_ANOMALY_COUNT,
+            max_anomaly_bytes: DEFAULT_MAX_ANOMALY_BYTES,
+        }
+    }
+}
@@ -0,0 +1,45 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use serde_json::Error as SerdeError;
+use thiserror::Error as ThisError;
+
+use std::io::Error as IOError;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, ThisError)]
+pub enum FsDataStoreError {
+    /// A file or directory of the store can't be accessed.
+    #[error("failed to access {path:?}: {io_error}")]
+    Io { path: PathBuf, io_error: IOError },
+
+    /// An anomaly data can't be serialized.
+    #[error("failed to serialize anomaly data: {0}")]
+    AnomalyDataSeError(#[from] SerdeError),
+}
+
+impl FsDataStoreError {
+    pub(crate) fn io(path: &Path) -> impl FnOnce(IOError) -> Self + '_ {
+        move |io_error| Self::Io {
+            path: path.to_path_buf(),
+            io_error,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::FsDataStoreError;
+use crate::FsDataStoreResult;
+
+use std::ffi::OsString;
+use std::fs;
+use std::io::ErrorKind;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// A suffix of files being written, such files are left only after a crash.
+pub(crate) const TMP_SUFFIX: &str = ".tmp";
+
+/// Writes the content to a temporary file and renames it to the path, so the path has
+/// either the previous or the new content even after a crash.
+pub(crate) fn write_atomically(path: &Path, content: &[u8]) -> FsDataStoreResult<()> {
+    let tmp_path = tmp_path(path);
+
+    let mut file = fs::File::create(&tmp_path).map_err(FsDataStoreError::io(&tmp_path))?;
+    file.write_all(content)
+        .and_then(|_| file.sync_all())
+        .map_err(FsDataStoreError::io(&tmp_path))?;
+    drop(file);
+
+    fs::rename(&tmp_path, path).map_err(FsDataStoreError::io(path))?;
+    match path.parent() {
+        Some(dir) => sync_dir(dir),
+        None => Ok(()),
+    }
+}
+
+/// Reads the file, a missing file is the same as an empty one.
+pub(crate) fn read_or_default(path: &Path) -> FsDataStoreResult<Vec<u8>> {
+    match fs::read(path) {
+        Ok(content) => Ok(content),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(error) => Err(FsDataStoreError::io(path)(error)),
+    }
+}
+
+pub(crate) fn remove_file_if_exists(path: &Path) -> FsDataStoreResult<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => Err(FsDataStoreError::io(path)(error)),
+        _ => Ok(()),
+    }
+}
+
+/// Removes the directory if it's empty, other directories are left as is.
+pub(crate) fn remove_dir_if_empty(path: &Path) {
+    // an error means that the directory isn't empty or is already removed
+    let _ = fs::remove_dir(path);
+}
+
+/// Returns directory entries, a missing directory is the same as an empty one.
+pub(crate) fn read_dir_or_default(path: &Path) -> FsDataStoreResult<Vec<fs::DirEntry>> {
+    match fs::read_dir(path) {
+        Ok(entries) => entries
+            .collect::<Result<_, _>>()
+            .map_err(FsDataStoreError::io(path)),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(error) => Err(FsDataStoreError::io(path)(error)),
+    }
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(TMP_SUFFIX);
+    path.with_file_name(file_name)
+}
+
+/// Makes a rename in the directory durable.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> FsDataStoreResult<()> {
+    fs::File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(FsDataStoreError::io(dir))
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> FsDataStoreResult<()> {
+    Ok(())
+}
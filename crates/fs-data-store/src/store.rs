@@ -0,0 +1,591 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::fs_utils::read_dir_or_default;
+use crate::fs_utils::read_or_default;
+use crate::fs_utils::remove_dir_if_empty;
+use crate::fs_utils::remove_file_if_exists;
+use crate::fs_utils::write_atomically;
+use crate::fs_utils::TMP_SUFFIX;
+use crate::FsDataStoreConfig;
+use crate::FsDataStoreError;
+use crate::FsDataStoreResult;
+
+use avm_data_store::AnomalyData;
+use avm_data_store::AnomalyPolicy;
+use avm_data_store::DataStore;
+use avm_interface::raw_outcome::RawAVMOutcome;
+use sha2::Digest;
+use sha2::Sha256;
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+
+const PARTICLES_DIR: &str = "particles";
+const ANOMALIES_DIR: &str = "anomalies";
+const SHARD_COUNT: u64 = 256;
+
+/// Longer ids are hashed, so that hex-encoded ids with extensions and the temporary suffix
+/// fit into the common file name limit of 255 bytes.
+const MAX_ENCODED_ID_LEN: usize = 100;
+
+const DATA_EXTENSION: &str = "data";
+const EXPIRATION_EXTENSION: &str = "expires";
+const ANOMALY_EXTENSION: &str = "json";
+
+/// A `DataStore` keeping particle data and anomalies in files.
+///
+/// Data of a particle is kept in `particles/<shard>/<particle id>/<peer id>.data` with
+/// hex-encoded ids, or their SHA-256 for too long ones, along with the `.expires` file
+/// holding the particle deadline.
+/// Every file is written to a temporary one and renamed, so a crash leaves either
+/// the previous or the new content.
+///
+/// Clones share the anomaly policy and can be used concurrently for different particles.
+#[derive(Clone)]
+pub struct FsDataStore {
+    config: FsDataStoreConfig,
+    anomaly_policy: Arc<AnomalyPolicy>,
+    /// Distinguishes anomalies collected within the same nanosecond
+    anomaly_counter: Arc<AtomicU64>,
+}
+
+impl FsDataStore {
+    pub fn new(config: FsDataStoreConfig) -> Self {
+        let anomaly_policy = AnomalyPolicy::new(config.anomaly_policy.clone());
+        Self {
+            config,
+            anomaly_policy: Arc::new(anomaly_policy),
+            anomaly_counter: <_>::default(),
+        }
+    }
+
+    fn particles_dir(&self) -> PathBuf {
+        self.config.root_dir.join(PARTICLES_DIR)
+    }
+
+    fn anomalies_dir(&self) -> PathBuf {
+        self.config.root_dir.join(ANOMALIES_DIR)
+    }
+
+    fn particle_dir(&self, particle_id: &str) -> PathBuf {
+        self.particles_dir()
+            .join(shard_name(particle_id))
+            .join(id_file_name(particle_id))
+    }
+
+    fn peer_file(&self, particle_id: &str, current_peer_id: &str, extension: &str) -> PathBuf {
+        let file_name = format!("{}.{extension}", id_file_name(current_peer_id));
+        self.particle_dir(particle_id).join(file_name)
+    }
+
+    fn create_particle_dir(&self, particle_id: &str) -> FsDataStoreResult<()> {
+        let particle_dir = self.particle_dir(particle_id);
+        fs::create_dir_all(&particle_dir).map_err(FsDataStoreError::io(&particle_dir))
+    }
+
+    /// Remove the oldest anomalies until the retention limits are met.
+    fn enforce_anomaly_retention(&self) -> FsDataStoreResult<()> {
+        let anomalies_dir = self.anomalies_dir();
+
+        let mut anomalies = vec![];
+        for entry in read_dir_or_default(&anomalies_dir)? {
+            let path = entry.path();
+            if !has_extension(&path, ANOMALY_EXTENSION) {
+                continue;
+            }
+            match entry.metadata() {
+                Ok(metadata) => anomalies.push((path, metadata.len())),
+                // removed by a concurrent clone of the store
+                Err(error) if error.kind() == ErrorKind::NotFound => {}
+                Err(error) => return Err(FsDataStoreError::io(&path)(error)),
+            }
+        }
+        // names start with the collection time, so they are sorted from the oldest
+        anomalies.sort();
+
+        let mut count = anomalies.len();
+        let mut bytes = anomalies.iter().map(|(_, size)| size).sum::<u64>();
+        for (path, size) in anomalies {
+            if count <= self.config.max_anomaly_count && bytes <= self.config.max_anomaly_bytes {
+                break;
+            }
+
+            remove_file_if_exists(&path)?;
+            count -= 1;
+            bytes -= size;
+        }
+
+        Ok(())
+    }
+
+    /// Remove temporary files left by writes interrupted by a crash.
+    fn remove_tmp_files(&self) -> FsDataStoreResult<()> {
+        let mut dirs = vec![self.anomalies_dir()];
+        for shard in read_dir_or_default(&self.particles_dir())? {
+            for particle_dir in read_dir_or_default(&shard.path())? {
+                dirs.push(particle_dir.path());
+            }
+        }
+
+        for dir in dirs {
+            for entry in read_dir_or_default(&dir)? {
+                if entry.file_name().to_string_lossy().ends_with(TMP_SUFFIX) {
+                    remove_file_if_exists(&entry.path())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl DataStore for FsDataStore {
+    type Error = FsDataStoreError;
+
+    fn initialize(&mut self) -> Result<(), Self::Error> {
+        for dir in [self.particles_dir(), self.anomalies_dir()] {
+            fs::create_dir_all(&dir).map_err(FsDataStoreError::io(&dir))?;
+        }
+
+        self.remove_tmp_files()
+    }
+
+    fn store_data(
+        &mut self,
+        data: &[u8],
+        particle_id: &str,
+        current_peer_id: &str,
+    ) -> Result<(), Self::Error> {
+        self.create_particle_dir(particle_id)?;
+
+        let data_path = self.peer_file(particle_id, current_peer_id, DATA_EXTENSION);
+        write_atomically(&data_path, data)
+    }
+
+    fn read_data(
+        &mut self,
+        particle_id: &str,
+        current_peer_id: &str,
+    ) -> Result<Vec<u8>, Self::Error> {
+        let data_path = self.peer_file(particle_id, current_peer_id, DATA_EXTENSION);
+        read_or_default(&data_path)
+    }
+
+    fn cleanup_data(
+        &mut self,
+        particle_id: &str,
+        current_peer_id: &str,
+    ) -> Result<(), Self::Error> {
+        remove_file_if_exists(&self.peer_file(particle_id, current_peer_id, DATA_EXTENSION))?;
+        remove_file_if_exists(&self.peer_file(particle_id, current_peer_id, EXPIRATION_EXTENSION))?;
+        remove_dir_if_empty(&self.particle_dir(particle_id));
+
+        Ok(())
+    }
+
+    fn track_expiration(
+        &mut self,
+        particle_id: &str,
+        current_peer_id: &str,
+        timestamp: u64,
+        ttl: u32,
+    ) -> Result<(), Self::Error> {
+        let deadline = timestamp.saturating_add(ttl as u64);
+        let expiration_path = self.peer_file(particle_id, current_peer_id, EXPIRATION_EXTENSION);
+        // the deadline of a particle doesn't change, so it's written once
+        if read_expiration(&expiration_path)? == Some(deadline) {
+            return Ok(());
+        }
+
+        self.create_particle_dir(particle_id)?;
+        write_atomically(&expiration_path, deadline.to_string().as_bytes())
+    }
+
+    fn collect_expired(&mut self, now: u64) -> Result<usize, Self::Error> {
+        let mut collected = 0;
+
+        for shard in read_dir_or_default(&self.particles_dir())? {
+            for particle_dir in read_dir_or_default(&shard.path())? {
+                let particle_dir = particle_dir.path();
+
+                for entry in read_dir_or_default(&particle_dir)? {
+                    let expiration_path = entry.path();
+                    if !has_extension(&expiration_path, EXPIRATION_EXTENSION) {
+                        continue;
+                    }
+
+                    if matches!(read_expiration(&expiration_path)?, Some(deadline) if deadline <= now)
+                    {
+                        remove_file_if_exists(&expiration_path.with_extension(DATA_EXTENSION))?;
+                        remove_file_if_exists(&expiration_path)?;
+                        collected += 1;
+                    }
+                }
+
+                remove_dir_if_empty(&particle_dir);
+            }
+        }
+
+        Ok(collected)
+    }
+
+    fn detect_anomaly(
+        &self,
+        execution_time: Duration,
+        memory_delta: usize,
+        outcome: &RawAVMOutcome,
+    ) -> bool {
+        self.anomaly_policy
+            .detect(execution_time, memory_delta, outcome)
+            .is_some()
+    }
+
+    fn collect_anomaly_data(
+        &mut self,
+        particle_id: &str,
+        current_peer_id: &str,
+        anomaly_data: AnomalyData<'_>,
+    ) -> Result<(), Self::Error> {
+        let anomaly = serde_json::to_vec(&anomaly_data)?;
+
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let anomaly_counter = self.anomaly_counter.fetch_add(1, Ordering::Relaxed) + 1;
+        let file_name = format!("{now:039}-{anomaly_counter:020}.{ANOMALY_EXTENSION}");
+
+        let anomaly_path = self.anomalies_dir().join(file_name);
+        write_atomically(&anomaly_path, &anomaly)?;
+        log::warn!(
+            "anomaly of particle {particle_id} on {current_peer_id} is saved to {anomaly_path:?}"
+        );
+
+        self.enforce_anomaly_retention()
+    }
+
+    fn share(&self) -> Option<Self> {
+        Some(self.clone())
+    }
+}
+
+/// Read a tracked deadline, a malformed file can appear only on external changes, so it's
+/// removed and the data is considered untracked until the next `track_expiration`.
+fn read_expiration(path: &Path) -> FsDataStoreResult<Option<u64>> {
+    let content = read_or_default(path)?;
+    if content.is_empty() {
+        return Ok(None);
+    }
+
+    let deadline = std::str::from_utf8(&content)
+        .ok()
+        .and_then(|deadline| deadline.parse().ok());
+    if deadline.is_none() {
+        log::warn!("malformed expiration file {path:?} is removed");
+        remove_file_if_exists(path)?;
+    }
+
+    Ok(deadline)
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension() == Some(extension.as_ref())
+}
+
+/// Ids are hex-encoded to be valid file names, too long ones are replaced with their hash.
+fn id_file_name(id: &str) -> String {
+    if id.len() <= MAX_ENCODED_ID_LEN {
+        return hex::encode(id);
+    }
+
+    // a hex-encoded id has no dashes, so the names don't collide
+    format!("sha256-{}", hex::encode(Sha256::digest(id)))
+}
+
+/// A shard of a particle is stable between runs, so it's computed with FNV-1a
+/// instead of the std hasher.
+fn shard_name(particle_id: &str) -> String {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let hash = particle_id.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    });
+
+    format!("{:02x}", hash % SHARD_COUNT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::block_on;
+
+    fn make_store(root_dir: &Path) -> FsDataStore {
+        let mut store = FsDataStore::new(FsDataStoreConfig::new(root_dir));
+        store.initialize().unwrap();
+        store
+    }
+
+    fn anomaly(air_script: &str) -> AnomalyData<'_> {
+        AnomalyData::new(
+            air_script,
+            b"",
+            b"",
+            b"",
+            b"",
+            b"",
+            Duration::from_secs(42),
+            0,
+        )
+    }
+
+    fn stored_anomalies(store: &FsDataStore) -> Vec<String> {
+        let mut anomalies = read_dir_or_default(&store.anomalies_dir())
+            .unwrap()
+            .into_iter()
+            .map(|entry| fs::read_to_string(entry.path()).unwrap())
+            .collect::<Vec<_>>();
+        anomalies.sort();
+        anomalies
+    }
+
+    #[test]
+    fn store_read_cleanup() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let mut store = make_store(root_dir.path());
+
+        assert_eq!(store.read_data("particle", "peer_1").unwrap(), b"");
+
+        store.store_data(b"data_1", "particle", "peer_1").unwrap();
+        store.store_data(b"data_2", "particle", "peer_2").unwrap();
+        store.store_data(b"data_3", "particle", "peer_1").unwrap();
+        assert_eq!(store.read_data("particle", "peer_1").unwrap(), b"data_3");
+        assert_eq!(store.read_data("particle", "peer_2").unwrap(), b"data_2");
+
+        let particle_dir = store.particle_dir("particle");
+        assert!(particle_dir.starts_with(root_dir.path().join(PARTICLES_DIR)));
+        assert_eq!(read_dir_or_default(&particle_dir).unwrap().len(), 2);
+
+        store.cleanup_data("particle", "peer_1").unwrap();
+        assert_eq!(store.read_data("particle", "peer_1").unwrap(), b"");
+        assert_eq!(store.read_data("particle", "peer_2").unwrap(), b"data_2");
+
+        store.cleanup_data("particle", "peer_2").unwrap();
+        assert!(!particle_dir.exists());
+    }
+
+    #[test]
+    fn data_survives_restart() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let mut store = make_store(root_dir.path());
+        store.store_data(b"data", "particle", "peer").unwrap();
+
+        // a write interrupted by a crash
+        let data_path = store.peer_file("particle", "peer", DATA_EXTENSION);
+        let tmp_path = data_path.with_extension(format!("{DATA_EXTENSION}{TMP_SUFFIX}"));
+        fs::write(&tmp_path, b"partial data").unwrap();
+
+        let mut store = make_store(root_dir.path());
+        assert_eq!(store.read_data("particle", "peer").unwrap(), b"data");
+        assert!(!tmp_path.exists());
+    }
+
+    #[test]
+    fn expired_data_is_collected() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let mut store = make_store(root_dir.path());
+
+        store
+            .track_expiration("particle_1", "peer", 1000, 100)
+            .unwrap();
+        store.store_data(b"data_1", "particle_1", "peer").unwrap();
+        store
+            .track_expiration("particle_2", "peer", 1000, 500)
+            .unwrap();
+        store.store_data(b"data_2", "particle_2", "peer").unwrap();
+        // data without expiration is never collected
+        store.store_data(b"data_3", "particle_3", "peer").unwrap();
+
+        assert_eq!(store.collect_expired(1099).unwrap(), 0);
+        assert_eq!(store.collect_expired(1100).unwrap(), 1);
+        assert_eq!(store.read_data("particle_1", "peer").unwrap(), b"");
+        assert!(!store.particle_dir("particle_1").exists());
+        assert_eq!(store.read_data("particle_2", "peer").unwrap(), b"data_2");
+
+        assert_eq!(store.collect_expired(u64::MAX).unwrap(), 1);
+        assert_eq!(store.read_data("particle_2", "peer").unwrap(), b"");
+        assert_eq!(store.read_data("particle_3", "peer").unwrap(), b"data_3");
+    }
+
+    #[test]
+    fn expired_data_is_collected_through_boxed_store() {
+        let root_dir = tempfile::tempdir().unwrap();
+        // the way the AVM keeps its store
+        let mut store: Box<
+            dyn avm_data_store::AsyncDataStore<Error = FsDataStoreError> + Send + Sync,
+        > = Box::new(FsDataStore::new(FsDataStoreConfig::new(root_dir.path())));
+        block_on(store.initialize()).unwrap();
+
+        block_on(store.track_expiration("particle", "peer", 1000, 100)).unwrap();
+        block_on(store.store_data(b"data", "particle", "peer")).unwrap();
+
+        assert_eq!(block_on(store.collect_expired(1099)).unwrap(), 0);
+        assert_eq!(block_on(store.collect_expired(1100)).unwrap(), 1);
+        assert_eq!(block_on(store.read_data("particle", "peer")).unwrap(), b"");
+    }
+
+    #[test]
+    fn malformed_expiration() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let mut store = make_store(root_dir.path());
+        store
+            .track_expiration("particle", "peer", 1000, 100)
+            .unwrap();
+
+        store.store_data(b"data", "particle", "peer").unwrap();
+        store
+            .track_expiration("other_particle", "peer", 1000, 100)
+            .unwrap();
+
+        let expiration_path = store.peer_file("particle", "peer", EXPIRATION_EXTENSION);
+        fs::write(&expiration_path, b"not a deadline").unwrap();
+
+        // the malformed file is removed, other particles are still collected
+        assert_eq!(store.collect_expired(u64::MAX).unwrap(), 1);
+        assert!(!expiration_path.exists());
+        assert_eq!(store.read_data("particle", "peer").unwrap(), b"data");
+
+        // the next execution tracks the deadline again
+        fs::write(&expiration_path, b"not a deadline").unwrap();
+        store
+            .track_expiration("particle", "peer", 1000, 100)
+            .unwrap();
+        assert_eq!(store.collect_expired(u64::MAX).unwrap(), 1);
+        assert_eq!(store.read_data("particle", "peer").unwrap(), b"");
+    }
+
+    #[test]
+    fn anomalies_are_bounded_by_count() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let mut config = FsDataStoreConfig::new(root_dir.path());
+        config.max_anomaly_count = 2;
+        let mut store = FsDataStore::new(config);
+        store.initialize().unwrap();
+
+        for air_script in ["(null)", "(never)", "(seq (null) (null))"] {
+            store
+                .collect_anomaly_data("particle", "peer", anomaly(air_script))
+                .unwrap();
+        }
+
+        let expected = ["(never)", "(seq (null) (null))"]
+            .map(|air_script| serde_json::to_string(&anomaly(air_script)).unwrap());
+        assert_eq!(stored_anomalies(&store), expected);
+    }
+
+    #[test]
+    fn anomalies_are_bounded_by_size() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let anomaly_size = serde_json::to_vec(&anomaly("(null)")).unwrap().len() as u64;
+        let mut config = FsDataStoreConfig::new(root_dir.path());
+        config.max_anomaly_bytes = 2 * anomaly_size + 1;
+        let mut store = FsDataStore::new(config);
+        store.initialize().unwrap();
+
+        for _ in 0..3 {
+            store
+                .collect_anomaly_data("particle", "peer", anomaly("(null)"))
+                .unwrap();
+        }
+
+        assert_eq!(stored_anomalies(&store).len(), 2);
+    }
+
+    #[test]
+    fn shared_store_uses_same_files() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let mut store = make_store(root_dir.path());
+        let mut shared =
+            avm_data_store::AsyncDataStore::share(&store).expect("fs store is shareable");
+
+        block_on(shared.store_data(b"data", "particle", "peer")).unwrap();
+        assert_eq!(store.read_data("particle", "peer").unwrap(), b"data");
+
+        block_on(shared.collect_anomaly_data("particle", "peer", anomaly("(null)"))).unwrap();
+        store
+            .collect_anomaly_data("particle", "peer", anomaly("(null)"))
+            .unwrap();
+        assert_eq!(stored_anomalies(&store).len(), 2);
+    }
+
+    #[test]
+    fn long_ids_are_hashed() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let mut store = make_store(root_dir.path());
+        let particle_id = "p".repeat(1000);
+        let peer_id = "q".repeat(1000);
+
+        store
+            .track_expiration(&particle_id, &peer_id, 1000, 100)
+            .unwrap();
+        store.store_data(b"data", &particle_id, &peer_id).unwrap();
+        assert_eq!(store.read_data(&particle_id, &peer_id).unwrap(), b"data");
+        // another id of the same length has its own data
+        assert_eq!(
+            store.read_data(&particle_id, &"r".repeat(1000)).unwrap(),
+            b""
+        );
+
+        let expiration_path = store.peer_file(&particle_id, &peer_id, EXPIRATION_EXTENSION);
+        for name in [
+            expiration_path.file_name(),
+            expiration_path.parent().unwrap().file_name(),
+        ] {
+            assert!(name.unwrap().len() < 255);
+        }
+
+        assert_eq!(store.collect_expired(u64::MAX).unwrap(), 1);
+        assert!(!store.particle_dir(&particle_id).exists());
+    }
+
+    #[test]
+    fn short_ids_are_hex_encoded() {
+        assert_eq!(id_file_name("id"), "6964");
+        assert_eq!(
+            id_file_name(&"i".repeat(MAX_ENCODED_ID_LEN)),
+            "69".repeat(MAX_ENCODED_ID_LEN)
+        );
+        assert!(id_file_name(&"i".repeat(MAX_ENCODED_ID_LEN + 1)).starts_with("sha256-"));
+    }
+
+    #[test]
+    fn shards_are_stable() {
+        assert_eq!(shard_name(""), "25");
+        assert_eq!(shard_name("particle"), shard_name("particle"));
+    }
+}
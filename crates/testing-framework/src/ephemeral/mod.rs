@@ -31,7 +31,7 @@ use air_test_utils::{
         create_avm_with_key, AirRunner, DefaultAirRunner, TestInitParameters, TestRunParameters,
         TestRunner,
     },
-    RawAVMOutcome,
+    AVMClock, RawAVMOutcome,
 };
 use fluence_keypair::KeyPair;
 
@@ -128,6 +128,11 @@ impl<R: AirRunner> Peer<R> {
     pub fn get_keypair(&self) -> &KeyPair {
         &self.runner.keypair
     }
+
+    /// Reject expired particles according to the clock, see `TestRunner::set_clock`.
+    pub fn set_clock(&mut self, clock: AVMClock) {
+        self.runner.set_clock(clock);
+    }
 }
 
 impl<R: AirRunner> std::fmt::Debug for Peer<R> {
@@ -147,6 +152,9 @@ pub struct Network<R = DefaultAirRunner> {
 
     // Resolves human-readable peer names to real peer IDs.
     resolver: RefCell<HashMap<PeerId, PeerId>>,
+
+    // Peers reject expired particles according to it, if it's set.
+    clock: RefCell<Option<AVMClock>>,
 }
 
 // it is implemented only for the default runner for compatibility reasons
@@ -168,6 +176,7 @@ impl<R: AirRunner> Network<R> {
             peers: Default::default(),
             services: NetworkServices::new(common_services).into(),
             resolver: Default::default(),
+            clock: Default::default(),
         });
         for peer_name in named_peers {
             network.ensure_named_peer(peer_name, test_init_params).await;
@@ -184,6 +193,20 @@ impl<R: AirRunner> Network<R> {
         network
     }
 
+    /// Make all peers, including ones added later, reject expired particles according to
+    /// the clock, e.g. a `VirtualClock` moved by a test. An expired particle execution fails
+    /// and the peer drops the particle data.
+    pub fn set_clock(&self, clock: AVMClock) {
+        for peer_env in self.peers.borrow().values() {
+            peer_env.borrow_mut().peer.set_clock(clock.clone());
+        }
+        *self.clock.borrow_mut() = Some(clock);
+    }
+
+    pub(crate) fn get_clock(&self) -> Option<AVMClock> {
+        self.clock.borrow().clone()
+    }
+
     pub fn add_peer_env(
         self: &Rc<Self>,
         peer: Peer<R>,
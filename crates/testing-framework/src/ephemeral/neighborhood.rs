@@ -136,7 +136,11 @@ pub struct PeerEnv<R> {
 }
 
 impl<R: AirRunner> PeerEnv<R> {
-    pub fn new(peer: Peer<R>, network: &Rc<Network<R>>) -> Self {
+    pub fn new(mut peer: Peer<R>, network: &Rc<Network<R>>) -> Self {
+        if let Some(clock) = network.get_clock() {
+            peer.set_clock(clock);
+        }
+
         Self {
             peer,
             failed: false,
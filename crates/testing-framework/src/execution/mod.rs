@@ -209,6 +209,8 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use std::cell::RefCell;
+    use std::future::Future;
+    use std::sync::Arc;
 
     #[tokio::test]
     async fn test_execution() {
@@ -260,6 +262,47 @@ mod tests {
         assert_eq!(outcome2.ret_code, 0);
     }
 
+    #[tokio::test]
+    async fn test_expired_particle_is_rejected() {
+        let exec = AirScriptExecutor::<NativeAirRunner>::new(
+            TestRunParameters::new("init_peer_id", 0, 1000, "particle"),
+            vec![],
+            std::iter::empty(),
+            r#"(seq
+(call "init_peer_id" ("service" "func") [] arg) ; ok = 42
+(call "peer1" ("service" "func") [arg]) ; ok = 43
+)
+"#,
+        )
+        .await
+        .unwrap();
+
+        let clock = VirtualClock::new(999);
+        exec.get_network().set_clock(Arc::new(clock.clone()));
+
+        let outcome = exec.execute_one("init_peer_id").await.unwrap();
+        assert_eq!(outcome.ret_code, 0, "{:?}", outcome);
+
+        clock.advance(1);
+        // `execute_one` panics on errors, so the peer is executed directly
+        let network = exec.get_network();
+        let peer_env = network.get_named_peer_env("peer1").unwrap();
+        let result = futures::future::poll_fn(|ctx| {
+            std::pin::pin!(peer_env.borrow_mut().execute_once(
+                &*exec.transformed_air_script,
+                &network,
+                &exec.queue,
+                &exec.test_parameters,
+            ))
+            .poll(ctx)
+        })
+        .await;
+        assert_eq!(
+            result.unwrap().unwrap_err(),
+            "particle particle expired at 1000, the current time is 1000"
+        );
+    }
+
     #[tokio::test]
     async fn test_call_result_success() {
         let exec = AirScriptExecutor::<NativeAirRunner>::new(
@@ -17,6 +17,15 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+mod graph;
+mod script_instructions;
+mod size_report;
+mod trace_tree;
+mod verify;
+
+use air_interpreter_data::InterpreterData;
+use air_interpreter_data::InterpreterDataEnvelope;
+use air_interpreter_data::Versions;
 use clap::Parser;
 use eyre::Context;
 use std::path::Path;
@@ -61,7 +70,24 @@ impl From<ModeArgs> for Option<Mode> {
 
 #[derive(Parser)]
 #[clap(about = "Print human-readable AquaVM data")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub(crate) struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    #[clap(flatten)]
+    readable: ReadableArgs,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    Graph(self::graph::Args),
+    SizeReport(self::size_report::Args),
+    Verify(self::verify::Args),
+}
+
+#[derive(clap::Args)]
+struct ReadableArgs {
     #[clap(
         long = "interpreter",
         env = "AIR_INTERPRETER_WASM_PATH",
@@ -72,17 +98,28 @@ pub(crate) struct Args {
     #[clap(flatten)]
     mode: ModeArgs,
     // TODO be able to read from stdin
-    #[arg(help = "Input path")]
-    input: PathBuf,
+    // it is optional only to make the subcommands work
+    #[arg(help = "Input path", required = true)]
+    input: Option<PathBuf>,
 }
 
-pub(crate) async fn to_human_readable_data(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+pub(crate) async fn data(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    match args.command {
+        None => to_human_readable_data(args.readable).await,
+        Some(Command::Graph(args)) => Ok(self::graph::graph(args)?),
+        Some(Command::SizeReport(args)) => Ok(self::size_report::size_report(args)?),
+        Some(Command::Verify(args)) => Ok(self::verify::verify(args)?),
+    }
+}
+
+async fn to_human_readable_data(args: ReadableArgs) -> Result<(), Box<dyn std::error::Error>> {
     init_tracing("warn");
 
-    let data: Vec<u8> = load_data(&args.input)?;
+    let input = args.input.expect("clap should require the input");
+    let data: Vec<u8> = load_data(&input)?;
 
     if data.is_empty() {
-        Err(eyre::eyre!("empty input data: {:?}", args.input))?;
+        Err(eyre::eyre!("empty input data: {:?}", input))?;
     }
 
     let mut runner =
@@ -93,6 +130,19 @@ pub(crate) async fn to_human_readable_data(args: Args) -> Result<(), Box<dyn std
     Ok(())
 }
 
+fn load_interpreter_data(path: &Path) -> eyre::Result<InterpreterData> {
+    load_interpreter_data_with_versions(path).map(|(data, _)| data)
+}
+
+fn load_interpreter_data_with_versions(path: &Path) -> eyre::Result<(InterpreterData, Versions)> {
+    let raw_data = load_data(path)?;
+    let envelope = InterpreterDataEnvelope::try_from_slice(&raw_data)
+        .map_err(|e| eyre::eyre!("failed to parse data envelope: {e}"))?;
+    let data = InterpreterData::try_from_slice(&envelope.inner_data)
+        .map_err(|e| eyre::eyre!("failed to parse data: {e}"))?;
+    Ok((data, envelope.versions))
+}
+
 fn init_tracing(tracing_params: &str) {
     let builder = tracing_subscriber::fmt()
         .with_env_filter(tracing_params)
@@ -0,0 +1,536 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::load_interpreter_data;
+use super::script_instructions;
+use super::script_instructions::ScriptInstructions;
+use super::trace_tree;
+use super::trace_tree::FoldIteration;
+use super::trace_tree::TraceNode;
+
+use air::SecurityTetraplet;
+use air_interpreter_cid::CID;
+use air_interpreter_data::CallResult;
+use air_interpreter_data::CanonResult;
+use air_interpreter_data::CidInfo;
+use air_interpreter_data::ExecutedState;
+use air_interpreter_data::InterpreterData;
+use air_interpreter_data::Sender;
+use air_interpreter_data::ServiceResultCidAggregate;
+use air_interpreter_data::TracePos;
+use clap::Parser;
+use eyre::Context as _;
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+#[derive(Parser)]
+#[clap(about = "Render an execution trace structure as a DOT or Mermaid graph")]
+pub(crate) struct Args {
+    #[clap(long, value_enum, default_value_t = Format::Dot)]
+    format: Format,
+
+    #[clap(
+        long,
+        help = "AIR script that produced the data, to link states to instructions"
+    )]
+    script: Option<PathBuf>,
+
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+
+    #[arg(help = "Input path")]
+    input: PathBuf,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    Dot,
+    Mermaid,
+}
+
+pub(crate) fn graph(args: Args) -> eyre::Result<()> {
+    let data = load_interpreter_data(&args.input)?;
+
+    let script = args
+        .script
+        .as_ref()
+        .map(|path| {
+            std::fs::read_to_string(path).with_context(|| path.to_string_lossy().into_owned())
+        })
+        .transpose()?;
+
+    let graph = render_graph(&data, script.as_deref(), args.format)?;
+
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    output.write_all(graph.as_bytes())?;
+    Ok(())
+}
+
+fn render_graph(
+    data: &InterpreterData,
+    script: Option<&str>,
+    format: Format,
+) -> eyre::Result<String> {
+    let tree = trace_tree::build_tree(&data.trace);
+
+    let instructions = match script {
+        Some(script) => script_instructions::map_instructions(script, &data.trace, &tree)
+            .context("failed to parse the script")?,
+        None => <_>::default(),
+    };
+
+    let mut renderer: Box<dyn Renderer> = match format {
+        Format::Dot => Box::new(Dot::default()),
+        Format::Mermaid => Box::new(Mermaid::default()),
+    };
+
+    let mut graph = GraphBuilder {
+        data,
+        instructions: &instructions,
+        renderer: &mut *renderer,
+        peers: <_>::default(),
+    };
+    graph.render(&tree);
+
+    Ok(renderer.finish())
+}
+
+/// Output format-specific primitives.
+trait Renderer {
+    fn begin_cluster(&mut self, id: &str, label: &str);
+    fn end_cluster(&mut self);
+    fn node(&mut self, id: &str, label: &str, tooltip: Option<&str>);
+    fn peer(&mut self, id: &str, label: &str);
+    fn edge(&mut self, from: &str, to: &str, label: &str);
+    fn finish(&mut self) -> String;
+}
+
+struct GraphBuilder<'data, 'rend> {
+    data: &'data InterpreterData,
+    instructions: &'data ScriptInstructions,
+    renderer: &'rend mut dyn Renderer,
+    // peer id to graph node id, with edges to be rendered outside clusters
+    peers: BTreeMap<String, Vec<(String, &'static str)>>,
+}
+
+impl GraphBuilder<'_, '_> {
+    fn render(&mut self, tree: &[TraceNode]) {
+        self.render_nodes(tree);
+
+        let peers = std::mem::take(&mut self.peers);
+        for (peer_idx, (peer_id, edges)) in peers.iter().enumerate() {
+            let peer_node_id = format!("peer{peer_idx}");
+            self.renderer.peer(&peer_node_id, peer_id);
+            for (state_node_id, label) in edges {
+                self.renderer.edge(&peer_node_id, state_node_id, label);
+            }
+        }
+    }
+
+    fn render_nodes(&mut self, nodes: &[TraceNode]) {
+        for node in nodes {
+            match node {
+                TraceNode::State(pos) => self.render_state(*pos),
+                TraceNode::Par { pos, left, right } => {
+                    self.begin_cluster(&format!("par{pos}"), *pos, "par");
+                    self.render_subtrace(&format!("par{pos}_left"), "left", left);
+                    self.render_subtrace(&format!("par{pos}_right"), "right", right);
+                    self.renderer.end_cluster();
+                }
+                TraceNode::Fold { pos, iterations } => {
+                    self.begin_cluster(&format!("fold{pos}"), *pos, "fold");
+                    for (iteration_idx, iteration) in iterations.iter().enumerate() {
+                        self.render_iteration(*pos, iteration_idx, iteration);
+                    }
+                    self.renderer.end_cluster();
+                }
+            }
+        }
+    }
+
+    fn render_iteration(
+        &mut self,
+        fold_pos: usize,
+        iteration_idx: usize,
+        iteration: &FoldIteration,
+    ) {
+        let iteration_id = format!("fold{fold_pos}_{iteration_idx}");
+        self.renderer.begin_cluster(
+            &iteration_id,
+            &format!(
+                "iteration {iteration_idx}, value at {}",
+                iteration.value_pos
+            ),
+        );
+        for (subtrace_idx, subtrace) in iteration.subtraces.iter().enumerate() {
+            let label = match subtrace_idx {
+                0 => "before next".to_owned(),
+                1 => "after next".to_owned(),
+                _ => format!("subtrace {subtrace_idx}"),
+            };
+            self.render_subtrace(&format!("{iteration_id}_{subtrace_idx}"), &label, subtrace);
+        }
+        self.renderer.end_cluster();
+    }
+
+    fn render_subtrace(&mut self, id: &str, label: &str, nodes: &[TraceNode]) {
+        self.renderer.begin_cluster(id, label);
+        self.render_nodes(nodes);
+        self.renderer.end_cluster();
+    }
+
+    fn begin_cluster(&mut self, id: &str, pos: usize, name: &str) {
+        let label = match self.instructions.get(pos) {
+            Some((_, instruction)) => format!("{pos}: {}", instruction.header),
+            None => format!("{pos}: {name}"),
+        };
+        self.renderer.begin_cluster(id, &label);
+    }
+
+    fn render_state(&mut self, pos: usize) {
+        let node_id = format!("s{pos}");
+        let cid_info = &self.data.cid_info;
+
+        let mut lines = match &self.data.trace[TracePos::from(pos as u32)] {
+            ExecutedState::Call(CallResult::RequestSentBy(sender)) => {
+                let peer_id = match sender {
+                    Sender::PeerId(peer_id) => peer_id,
+                    Sender::PeerIdWithCallId { peer_id, .. } => peer_id,
+                };
+                self.add_peer_edge(peer_id, &node_id, "sent_by");
+                vec!["call: request sent".to_owned()]
+            }
+            ExecutedState::Call(call @ CallResult::Executed(_)) => match call.get_cid() {
+                Some(cid) => self.call_lines("call", cid, &node_id),
+                None => vec!["call: unused result".to_owned()],
+            },
+            ExecutedState::Call(CallResult::Failed(cid)) => {
+                self.call_lines("call: failed", cid, &node_id)
+            }
+            ExecutedState::Canon(CanonResult::RequestSentBy(peer_id)) => {
+                self.add_peer_edge(peer_id, &node_id, "sent_by");
+                vec!["canon: request sent".to_owned()]
+            }
+            ExecutedState::Canon(CanonResult::Executed(cid)) => {
+                let mut lines = vec!["canon".to_owned()];
+                let tetraplet = cid_info
+                    .canon_result_store
+                    .get(cid)
+                    .and_then(|canon| cid_info.tetraplet_store.get(&canon.tetraplet));
+                if let Some(tetraplet) = tetraplet {
+                    self.add_peer_edge(&tetraplet.peer_pk, &node_id, "produced");
+                    lines.push(tetraplet.peer_pk.clone());
+                }
+                lines
+            }
+            ExecutedState::Ap(ap) => vec![format!("ap: generations {:?}", ap.res_generations)],
+            ExecutedState::Par(_) | ExecutedState::Fold(_) => {
+                unreachable!("par and fold are rendered as clusters")
+            }
+        };
+        lines[0] = format!("{pos}: {}", lines[0]);
+
+        let tooltip = self.instructions.get(pos).map(|(_, instruction)| {
+            lines.push(instruction.header.clone());
+            instruction.text.as_str()
+        });
+        self.renderer.node(&node_id, &lines.join("\n"), tooltip);
+    }
+
+    fn call_lines(
+        &mut self,
+        title: &str,
+        cid: &CID<ServiceResultCidAggregate>,
+        node_id: &str,
+    ) -> Vec<String> {
+        let mut lines = vec![title.to_owned()];
+        if let Some(tetraplet) = service_result_tetraplet(&self.data.cid_info, cid) {
+            self.add_peer_edge(&tetraplet.peer_pk, node_id, "produced");
+            lines.push(tetraplet.peer_pk.clone());
+            lines.push(format!(
+                "{}.{}",
+                tetraplet.service_id, tetraplet.function_name
+            ));
+        }
+        lines
+    }
+
+    fn add_peer_edge(&mut self, peer_id: &str, node_id: &str, label: &'static str) {
+        self.peers
+            .entry(peer_id.to_owned())
+            .or_default()
+            .push((node_id.to_owned(), label));
+    }
+}
+
+fn service_result_tetraplet(
+    cid_info: &CidInfo,
+    cid: &CID<ServiceResultCidAggregate>,
+) -> Option<Rc<SecurityTetraplet>> {
+    let service_result = cid_info.service_result_store.get(cid)?;
+    cid_info.tetraplet_store.get(&service_result.tetraplet_cid)
+}
+
+#[derive(Default)]
+struct Dot {
+    out: String,
+    depth: usize,
+}
+
+impl Dot {
+    fn line(&mut self, line: &str) {
+        self.out.push_str(&"    ".repeat(self.depth + 1));
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+}
+
+impl Renderer for Dot {
+    fn begin_cluster(&mut self, id: &str, label: &str) {
+        self.line(&format!("subgraph cluster_{id} {{"));
+        self.depth += 1;
+        self.line(&format!("label={};", dot_string(label)));
+    }
+
+    fn end_cluster(&mut self) {
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn node(&mut self, id: &str, label: &str, tooltip: Option<&str>) {
+        let tooltip = tooltip
+            .map(|tooltip| format!(", tooltip={}", dot_string(tooltip)))
+            .unwrap_or_default();
+        self.line(&format!("{id} [label={}{tooltip}];", dot_string(label)));
+    }
+
+    fn peer(&mut self, id: &str, label: &str) {
+        self.line(&format!(
+            "{id} [label={}, shape=ellipse];",
+            dot_string(label)
+        ));
+    }
+
+    fn edge(&mut self, from: &str, to: &str, label: &str) {
+        self.line(&format!(
+            "{from} -> {to} [label={}, style=dashed];",
+            dot_string(label)
+        ));
+    }
+
+    fn finish(&mut self) -> String {
+        format!("digraph trace {{\n    node [shape=box];\n{}}}\n", self.out)
+    }
+}
+
+fn dot_string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+#[derive(Default)]
+struct Mermaid {
+    out: String,
+    depth: usize,
+}
+
+impl Mermaid {
+    fn line(&mut self, line: &str) {
+        self.out.push_str(&"    ".repeat(self.depth + 1));
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+}
+
+impl Renderer for Mermaid {
+    fn begin_cluster(&mut self, id: &str, label: &str) {
+        self.line(&format!("subgraph {id} [{}]", mermaid_string(label)));
+        self.depth += 1;
+    }
+
+    fn end_cluster(&mut self) {
+        self.depth -= 1;
+        self.line("end");
+    }
+
+    fn node(&mut self, id: &str, label: &str, tooltip: Option<&str>) {
+        self.line(&format!("{id}[{}]", mermaid_string(label)));
+        // Mermaid has tooltips only for interactive nodes, so the node gets a no-op callback
+        if let Some(tooltip) = tooltip {
+            self.line(&format!(
+                "click {id} callback {}",
+                mermaid_tooltip_string(tooltip)
+            ));
+        }
+    }
+
+    fn peer(&mut self, id: &str, label: &str) {
+        self.line(&format!("{id}([{}])", mermaid_string(label)));
+    }
+
+    fn edge(&mut self, from: &str, to: &str, label: &str) {
+        self.line(&format!("{from} -. {label} .-> {to}"));
+    }
+
+    fn finish(&mut self) -> String {
+        format!("flowchart TD\n{}", self.out)
+    }
+}
+
+fn mermaid_string(s: &str) -> String {
+    let escaped = s.replace('"', "#quot;").replace('\n', "<br/>");
+    format!("\"{escaped}\"")
+}
+
+fn mermaid_tooltip_string(s: &str) -> String {
+    // tooltips are not parsed as markdown, so line breaks are collapsed
+    let escaped = s
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('"', "#quot;");
+    format!("\"{escaped}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use air::ExecutionCidState;
+    use air_interpreter_data::InterpreterDataEnvelope;
+    use air_test_utils::prelude::*;
+
+    const SCRIPT: &str = r#"
+        (seq
+            (par
+                (call "peer_a" ("svc" "a") [] $s)
+                (call "peer_b" ("svc" "b") [] $s))
+            (seq
+                (ap "x" $t)
+                (canon "peer_a" $s #c)))
+    "#;
+
+    fn test_data() -> InterpreterData {
+        let mut cid_state = ExecutionCidState::new();
+        let trace = vec![
+            executed_state::par(1, 1),
+            stream_tracked!(
+                "a",
+                0,
+                cid_state,
+                peer = "peer_a",
+                service = "svc",
+                function = "a"
+            ),
+            executed_state::request_sent_by("peer_a"),
+            executed_state::ap(0),
+            executed_state::canon_request("peer_a"),
+        ];
+        let raw_data = raw_data_from_trace_with_canon(trace, cid_state);
+        let envelope = InterpreterDataEnvelope::try_from_slice(&raw_data).unwrap();
+        InterpreterData::try_from_slice(&envelope.inner_data).unwrap()
+    }
+
+    #[test]
+    fn dot_snapshot() {
+        let graph = render_graph(&test_data(), Some(SCRIPT), Format::Dot).unwrap();
+
+        let expected = r#"digraph trace {
+    node [shape=box];
+    subgraph cluster_par0 {
+        label="0: par";
+        subgraph cluster_par0_left {
+            label="left";
+            s1 [label="1: call\npeer_a\nsvc.a\ncall \"peer_a\" (\"svc\" \"a\") [] $s", tooltip="$s <- call \"peer_a\" (\"svc\", \"a\") []"];
+        }
+        subgraph cluster_par0_right {
+            label="right";
+            s2 [label="2: call: request sent\ncall \"peer_b\" (\"svc\" \"b\") [] $s", tooltip="$s <- call \"peer_b\" (\"svc\", \"b\") []"];
+        }
+    }
+    s3 [label="3: ap: generations [0]\nap \"x\" $t", tooltip="ap \"x\" $t"];
+    s4 [label="4: canon: request sent\ncanon \"peer_a\" $s #c", tooltip="canon \"peer_a\" $s #c"];
+    peer0 [label="peer_a", shape=ellipse];
+    peer0 -> s1 [label="produced", style=dashed];
+    peer0 -> s2 [label="sent_by", style=dashed];
+    peer0 -> s4 [label="sent_by", style=dashed];
+}
+"#;
+        assert_eq!(graph, expected, "{graph}");
+    }
+
+    #[test]
+    fn mermaid_snapshot() {
+        let graph = render_graph(&test_data(), Some(SCRIPT), Format::Mermaid).unwrap();
+
+        let expected = r#"flowchart TD
+    subgraph par0 ["0: par"]
+        subgraph par0_left ["left"]
+            s1["1: call<br/>peer_a<br/>svc.a<br/>call #quot;peer_a#quot; (#quot;svc#quot; #quot;a#quot;) [] $s"]
+            click s1 callback "$s <- call #quot;peer_a#quot; (#quot;svc#quot;, #quot;a#quot;) []"
+        end
+        subgraph par0_right ["right"]
+            s2["2: call: request sent<br/>call #quot;peer_b#quot; (#quot;svc#quot; #quot;b#quot;) [] $s"]
+            click s2 callback "$s <- call #quot;peer_b#quot; (#quot;svc#quot;, #quot;b#quot;) []"
+        end
+    end
+    s3["3: ap: generations [0]<br/>ap #quot;x#quot; $t"]
+    click s3 callback "ap #quot;x#quot; $t"
+    s4["4: canon: request sent<br/>canon #quot;peer_a#quot; $s #c"]
+    click s4 callback "canon #quot;peer_a#quot; $s #c"
+    peer0(["peer_a"])
+    peer0 -. produced .-> s1
+    peer0 -. sent_by .-> s2
+    peer0 -. sent_by .-> s4
+"#;
+        assert_eq!(graph, expected, "{graph}");
+    }
+
+    #[test]
+    fn graph_without_script() {
+        let graph = render_graph(&test_data(), None, Format::Mermaid).unwrap();
+
+        let expected = r#"flowchart TD
+    subgraph par0 ["0: par"]
+        subgraph par0_left ["left"]
+            s1["1: call<br/>peer_a<br/>svc.a"]
+        end
+        subgraph par0_right ["right"]
+            s2["2: call: request sent"]
+        end
+    end
+    s3["3: ap: generations [0]"]
+    s4["4: canon: request sent"]
+    peer0(["peer_a"])
+    peer0 -. produced .-> s1
+    peer0 -. sent_by .-> s2
+    peer0 -. sent_by .-> s4
+"#;
+        assert_eq!(graph, expected, "{graph}");
+    }
+}
@@ -0,0 +1,387 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Best-effort mapping of trace states to AIR instructions that produced them.
+//!
+//! The script is parsed with `air-parser`, and its AST is walked along the trace tree,
+//! matching state-producing instructions to states of the corresponding kind.
+
+use super::trace_tree::TraceNode;
+
+use air_beautifier::Beautifier;
+use air_interpreter_data::ExecutedState;
+use air_parser::ast;
+
+use std::collections::HashMap;
+
+/// An instruction of the script that produced some states.
+pub(super) struct ScriptInstruction {
+    /// Short one-line description, like `call "peer" ("service" "function") [] result`.
+    pub(super) header: String,
+    /// The beautified instruction with its subtree.
+    pub(super) text: String,
+}
+
+/// Trace states mapped to the script instructions.
+#[derive(Default)]
+pub(super) struct ScriptInstructions {
+    instructions: Vec<ScriptInstruction>,
+    by_pos: HashMap<usize, usize>,
+}
+
+impl ScriptInstructions {
+    /// Index and description of the instruction that produced the state at the position.
+    pub(super) fn get(&self, pos: usize) -> Option<(usize, &ScriptInstruction)> {
+        let idx = *self.by_pos.get(&pos)?;
+        Some((idx, &self.instructions[idx]))
+    }
+
+    pub(super) fn instruction(&self, idx: usize) -> &ScriptInstruction {
+        &self.instructions[idx]
+    }
+}
+
+/// Map trace positions to the script instructions.
+pub(super) fn map_instructions(
+    script: &str,
+    trace: &[ExecutedState],
+    tree: &[TraceNode],
+) -> eyre::Result<ScriptInstructions> {
+    let root = air_parser::parse(script).map_err(|e| eyre::eyre!(e))?;
+
+    let mut walker = Walker {
+        trace,
+        result: <_>::default(),
+        ids: <_>::default(),
+    };
+    let mut cursor = Cursor::new(tree);
+    walker.walk(&root, &mut cursor, &mut Phase::Normal);
+
+    Ok(walker.result)
+}
+
+/// Position among trace tree nodes of a subtrace.
+struct Cursor<'tree> {
+    nodes: &'tree [TraceNode],
+    idx: usize,
+}
+
+impl<'tree> Cursor<'tree> {
+    fn new(nodes: &'tree [TraceNode]) -> Self {
+        Self { nodes, idx: 0 }
+    }
+
+    fn peek(&self) -> Option<&'tree TraceNode> {
+        self.nodes.get(self.idx)
+    }
+}
+
+/// Which part of a fold body is being walked.
+enum Phase<'i> {
+    Normal,
+    /// Stop at the `next` of the iterator.
+    BeforeNext(&'i str),
+    /// The `next` of the iterator has been met.
+    Stopped,
+    /// Skip everything until the `next` of the iterator.
+    AfterNext(&'i str),
+}
+
+impl Phase<'_> {
+    fn consumes_states(&self) -> bool {
+        matches!(self, Phase::Normal | Phase::BeforeNext(_))
+    }
+}
+
+struct Walker<'trace> {
+    trace: &'trace [ExecutedState],
+    result: ScriptInstructions,
+    // AST nodes are identified by address, as the same fold body is walked on every iteration
+    ids: HashMap<*const (), usize>,
+}
+
+impl<'trace> Walker<'trace> {
+    fn walk<'i>(
+        &mut self,
+        instruction: &ast::Instruction<'i>,
+        cursor: &mut Cursor<'_>,
+        phase: &mut Phase<'i>,
+    ) {
+        use ast::Instruction;
+
+        if matches!(phase, Phase::Stopped) {
+            return;
+        }
+
+        match instruction {
+            Instruction::Seq(seq) => {
+                self.walk(&seq.0, cursor, phase);
+                self.walk(&seq.1, cursor, phase);
+            }
+            Instruction::Xor(xor) => {
+                self.walk(&xor.0, cursor, phase);
+                self.walk(&xor.1, cursor, phase);
+            }
+            Instruction::Match(match_) => self.walk(&match_.instruction, cursor, phase),
+            Instruction::MisMatch(mismatch) => self.walk(&mismatch.instruction, cursor, phase),
+            Instruction::New(new) => self.walk(&new.instruction, cursor, phase),
+            Instruction::Next(next) => match phase {
+                Phase::BeforeNext(iterator) if next.iterator.name == *iterator => {
+                    *phase = Phase::Stopped;
+                }
+                Phase::AfterNext(iterator) if next.iterator.name == *iterator => {
+                    *phase = Phase::Normal;
+                }
+                _ => {}
+            },
+            Instruction::Par(par) => self.walk_par(instruction, par, cursor, phase),
+            Instruction::FoldScalar(fold) => {
+                if !phase.consumes_states() {
+                    return;
+                }
+                // a scalar fold has no state of its own, so its iterations are walked
+                // while they consume anything
+                loop {
+                    let idx = cursor.idx;
+                    self.walk(&fold.instruction, cursor, &mut Phase::Normal);
+                    if cursor.idx == idx {
+                        break;
+                    }
+                }
+                if let Some(last_instruction) = &fold.last_instruction {
+                    self.walk(last_instruction, cursor, phase);
+                }
+            }
+            Instruction::FoldStream(fold) => self.walk_stream_fold(
+                instruction,
+                fold.iterator.name,
+                &fold.instruction,
+                fold.last_instruction.as_deref(),
+                cursor,
+                phase,
+            ),
+            Instruction::FoldStreamMap(fold) => self.walk_stream_fold(
+                instruction,
+                fold.iterator.name,
+                &fold.instruction,
+                fold.last_instruction.as_deref(),
+                cursor,
+                phase,
+            ),
+            Instruction::Call(_) => self.consume_state(instruction, cursor, phase, |state| {
+                matches!(state, ExecutedState::Call(_))
+            }),
+            Instruction::Canon(_)
+            | Instruction::CanonMap(_)
+            | Instruction::CanonStreamMapScalar(_) => {
+                self.consume_state(instruction, cursor, phase, |state| {
+                    matches!(state, ExecutedState::Canon(_))
+                })
+            }
+            Instruction::Ap(ap) if matches!(ap.result, ast::ApResult::Stream(_)) => self
+                .consume_state(instruction, cursor, phase, |state| {
+                    matches!(state, ExecutedState::Ap(_))
+                }),
+            Instruction::ApMap(_) => self.consume_state(instruction, cursor, phase, |state| {
+                matches!(state, ExecutedState::Ap(_))
+            }),
+            Instruction::Ap(_)
+            | Instruction::Fail(_)
+            | Instruction::Never(_)
+            | Instruction::Null(_)
+            | Instruction::Error => {}
+        }
+    }
+
+    fn walk_par<'i>(
+        &mut self,
+        instruction: &ast::Instruction<'i>,
+        par: &ast::Par<'i>,
+        cursor: &mut Cursor<'_>,
+        phase: &mut Phase<'i>,
+    ) {
+        match cursor.peek() {
+            Some(TraceNode::Par { pos, left, right }) if phase.consumes_states() => {
+                self.map_state(*pos, instruction);
+                cursor.idx += 1;
+
+                self.walk(&par.0, &mut Cursor::new(left), phase);
+                self.walk(&par.1, &mut Cursor::new(right), phase);
+            }
+            _ if !phase.consumes_states() => {
+                // look for the `next` inside
+                self.walk(&par.0, cursor, phase);
+                self.walk(&par.1, cursor, phase);
+            }
+            _ => {}
+        }
+    }
+
+    fn walk_stream_fold<'i>(
+        &mut self,
+        instruction: &ast::Instruction<'i>,
+        iterator: &'i str,
+        body: &ast::Instruction<'i>,
+        last_instruction: Option<&ast::Instruction<'i>>,
+        cursor: &mut Cursor<'_>,
+        phase: &mut Phase<'i>,
+    ) {
+        if !phase.consumes_states() {
+            return;
+        }
+        let Some(TraceNode::Fold { pos, iterations }) = cursor.peek() else {
+            return;
+        };
+        self.map_state(*pos, instruction);
+        cursor.idx += 1;
+
+        for iteration in iterations {
+            for (subtrace_idx, subtrace) in iteration.subtraces.iter().enumerate() {
+                let mut iteration_phase = match subtrace_idx {
+                    0 => Phase::BeforeNext(iterator),
+                    _ => Phase::AfterNext(iterator),
+                };
+                self.walk(body, &mut Cursor::new(subtrace), &mut iteration_phase);
+            }
+        }
+
+        if let Some(last_instruction) = last_instruction {
+            self.walk(last_instruction, cursor, phase);
+        }
+    }
+
+    fn consume_state(
+        &mut self,
+        instruction: &ast::Instruction<'_>,
+        cursor: &mut Cursor<'_>,
+        phase: &Phase<'_>,
+        is_expected_state: impl Fn(&ExecutedState) -> bool,
+    ) {
+        if !phase.consumes_states() {
+            return;
+        }
+        if let Some(TraceNode::State(pos)) = cursor.peek() {
+            if is_expected_state(&self.trace[*pos]) {
+                self.map_state(*pos, instruction);
+                cursor.idx += 1;
+            }
+        }
+    }
+
+    fn map_state(&mut self, pos: usize, instruction: &ast::Instruction<'_>) {
+        let key = instruction as *const ast::Instruction<'_> as *const ();
+        let instructions = &mut self.result.instructions;
+        let idx = *self.ids.entry(key).or_insert_with(|| {
+            instructions.push(ScriptInstruction {
+                header: instruction.to_string(),
+                text: beautify(instruction),
+            });
+            instructions.len() - 1
+        });
+        self.result.by_pos.insert(pos, idx);
+    }
+}
+
+fn beautify(instruction: &ast::Instruction<'_>) -> String {
+    let mut beautifier = Beautifier::new(vec![]);
+    match beautifier.beautify_ast(instruction) {
+        Ok(()) => String::from_utf8_lossy(&beautifier.into_inner())
+            .trim_end()
+            .to_owned(),
+        Err(_) => instruction.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::trace_tree::build_tree;
+
+    use air_test_utils::prelude::*;
+
+    #[test]
+    fn fold_iterations_map_to_same_instructions() {
+        let script = r#"
+            (seq
+                (seq
+                    (ap 1 $s)
+                    (ap 2 $s))
+                (seq
+                    (fold $s i
+                        (seq
+                            (par
+                                (call "peer" ("svc" "f") [i] $r)
+                                (call "peer" ("svc" "h") [i] $r))
+                            (next i)))
+                    (call "peer" ("svc" "g") [] x)))
+        "#;
+        let trace = vec![
+            executed_state::ap(0),
+            executed_state::ap(0),
+            executed_state::fold(vec![
+                executed_state::subtrace_lore(
+                    0,
+                    executed_state::subtrace_desc(3, 3),
+                    executed_state::subtrace_desc(9, 0),
+                ),
+                executed_state::subtrace_lore(
+                    1,
+                    executed_state::subtrace_desc(6, 3),
+                    executed_state::subtrace_desc(9, 0),
+                ),
+            ]),
+            executed_state::par(1, 1),
+            executed_state::request_sent_by("peer"),
+            executed_state::request_sent_by("peer"),
+            executed_state::par(1, 1),
+            executed_state::request_sent_by("peer"),
+            executed_state::request_sent_by("peer"),
+            executed_state::request_sent_by("peer"),
+        ];
+
+        let instructions = map_instructions(script, &trace, &build_tree(&trace)).unwrap();
+
+        let headers: Vec<_> = (0..trace.len())
+            .map(|pos| {
+                instructions
+                    .get(pos)
+                    .map(|(_, instruction)| instruction.header.as_str())
+            })
+            .collect();
+        assert_eq!(
+            headers,
+            vec![
+                Some("ap 1 $s"),
+                Some("ap 2 $s"),
+                Some("fold $s i"),
+                Some("par"),
+                Some(r#"call "peer" ("svc" "f") [i] $r"#),
+                Some(r#"call "peer" ("svc" "h") [i] $r"#),
+                Some("par"),
+                Some(r#"call "peer" ("svc" "f") [i] $r"#),
+                Some(r#"call "peer" ("svc" "h") [i] $r"#),
+                Some(r#"call "peer" ("svc" "g") [] x"#),
+            ]
+        );
+        assert_eq!(
+            instructions.get(4).unwrap().0,
+            instructions.get(7).unwrap().0
+        );
+    }
+}
@@ -0,0 +1,157 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::script_instructions;
+use super::trace_tree;
+
+use air_interpreter_data::size_report::SizeReport;
+use air_interpreter_data::size_report::StateSizes;
+use air_interpreter_data::ExecutedState;
+use air_interpreter_data::InterpreterData;
+use air_interpreter_data::InterpreterDataEnvelope;
+use clap::Parser;
+use eyre::Context as _;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+const SNIPPET_MAX_LEN: usize = 60;
+
+#[derive(Parser)]
+#[clap(about = "Attribute the serialized data size to trace states and instructions")]
+pub(crate) struct Args {
+    #[clap(
+        long,
+        help = "AIR script that produced the data, to aggregate sizes by instructions"
+    )]
+    script: Option<PathBuf>,
+
+    #[clap(
+        long,
+        default_value_t = 20,
+        help = "Count of the largest entries to print"
+    )]
+    top: usize,
+
+    #[arg(help = "Input path")]
+    input: PathBuf,
+}
+
+pub(crate) fn size_report(args: Args) -> eyre::Result<()> {
+    let raw_data = crate::trace::run::load_data(&args.input)?;
+    let envelope = InterpreterDataEnvelope::try_from_slice(&raw_data)
+        .map_err(|e| eyre::eyre!("failed to parse data envelope: {e}"))?;
+    let envelope_size = raw_data.len() - envelope.inner_data.len();
+    let data = InterpreterData::try_from_slice(&envelope.inner_data)
+        .map_err(|e| eyre::eyre!("failed to parse data: {e}"))?;
+
+    let report = SizeReport::from_data(&data)?;
+    let attributed_size: usize = report.states.iter().map(StateSizes::total).sum();
+
+    println!("total size:        {:>10}", raw_data.len());
+    println!("  envelope:        {:>10}", envelope_size);
+    println!("  trace states:    {:>10}", attributed_size);
+    println!("  unreferenced:    {:>10}", report.unreferenced_size);
+    println!("  format overhead: {:>10}", report.overhead_size);
+
+    println!();
+    println!("largest states:");
+    println!(
+        "{:>6} {:>10} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}  kind",
+        "pos", "total", "state", "values", "tetraplets", "results", "canon", "signatures", "shared"
+    );
+    for (pos, sizes) in report.largest_states().into_iter().take(args.top) {
+        println!(
+            "{:>6} {:>10} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}  {}",
+            pos,
+            sizes.total(),
+            sizes.state,
+            sizes.values,
+            sizes.tetraplets,
+            sizes.service_results + sizes.canon_results,
+            sizes.canon_elements,
+            sizes.signatures,
+            sizes.shared,
+            state_kind(&data.trace[pos]),
+        );
+    }
+
+    println!();
+    println!("by producer:");
+    let mut by_producer: Vec<_> = report.by_producer().into_iter().collect();
+    by_producer.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    for (producer, size) in by_producer.into_iter().take(args.top) {
+        println!(
+            "{size:>10}  {} ({} {})",
+            producer.peer_pk, producer.service_id, producer.function_name
+        );
+    }
+
+    if let Some(path) = &args.script {
+        let script =
+            std::fs::read_to_string(path).with_context(|| path.to_string_lossy().into_owned())?;
+        let tree = trace_tree::build_tree(&data.trace);
+        let instructions = script_instructions::map_instructions(&script, &data.trace, &tree)
+            .context("failed to parse the script")?;
+
+        let mut by_instruction = BTreeMap::<usize, usize>::new();
+        let mut unmapped_size = 0;
+        for (pos, sizes) in report.states.iter().enumerate() {
+            match instructions.get(pos) {
+                Some((idx, _)) => *by_instruction.entry(idx).or_default() += sizes.total(),
+                None => unmapped_size += sizes.total(),
+            }
+        }
+
+        let mut by_instruction: Vec<_> = by_instruction.into_iter().collect();
+        by_instruction.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+
+        println!();
+        println!("by instruction:");
+        for (idx, size) in by_instruction.into_iter().take(args.top) {
+            println!(
+                "{size:>10}  {}",
+                snippet(&instructions.instruction(idx).header)
+            );
+        }
+        if unmapped_size != 0 {
+            println!("{unmapped_size:>10}  not mapped to the script");
+        }
+    }
+
+    Ok(())
+}
+
+fn state_kind(state: &ExecutedState) -> &'static str {
+    match state {
+        ExecutedState::Par(_) => "par",
+        ExecutedState::Call(_) => "call",
+        ExecutedState::Fold(_) => "fold",
+        ExecutedState::Ap(_) => "ap",
+        ExecutedState::Canon(_) => "canon",
+    }
+}
+
+fn snippet(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(SNIPPET_MAX_LEN) {
+        Some((idx, _)) => format!("{}...", &text[..idx]),
+        None => text,
+    }
+}
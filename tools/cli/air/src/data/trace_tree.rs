@@ -0,0 +1,214 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use air_interpreter_data::ExecutedState;
+use air_interpreter_data::TracePos;
+
+use std::ops::Range;
+
+/// A trace state with the subtraces it owns.
+pub(super) enum TraceNode {
+    State(usize),
+    Par {
+        pos: usize,
+        left: Vec<TraceNode>,
+        right: Vec<TraceNode>,
+    },
+    Fold {
+        pos: usize,
+        iterations: Vec<FoldIteration>,
+    },
+}
+
+pub(super) struct FoldIteration {
+    pub(super) value_pos: TracePos,
+    pub(super) subtraces: Vec<Vec<TraceNode>>,
+}
+
+/// Restore the tree structure of a flat trace from par sizes and fold lores.
+///
+/// Malformed sizes and positions are clamped to the enclosing subtrace, so any data
+/// produces some tree.
+pub(super) fn build_tree(trace: &[ExecutedState]) -> Vec<TraceNode> {
+    build_subtree(trace, 0..trace.len())
+}
+
+fn build_subtree(trace: &[ExecutedState], range: Range<usize>) -> Vec<TraceNode> {
+    let mut nodes = vec![];
+    let mut pos = range.start;
+
+    while pos < range.end {
+        match &trace[pos] {
+            ExecutedState::Par(par) => {
+                let left_start = pos + 1;
+                let left_end = clamp(left_start, par.left_size, range.end);
+                let right_end = clamp(left_end, par.right_size, range.end);
+
+                nodes.push(TraceNode::Par {
+                    pos,
+                    left: build_subtree(trace, left_start..left_end),
+                    right: build_subtree(trace, left_end..right_end),
+                });
+                pos = right_end;
+            }
+            ExecutedState::Fold(fold) => {
+                let mut fold_end = pos + 1;
+                let iterations = fold
+                    .lore
+                    .iter()
+                    .map(|lore| {
+                        let subtraces = lore
+                            .subtraces_desc
+                            .iter()
+                            .map(|desc| {
+                                let begin = usize::from(desc.begin_pos).clamp(pos + 1, range.end);
+                                let end = clamp(begin, desc.subtrace_len, range.end);
+                                fold_end = fold_end.max(end);
+                                build_subtree(trace, begin..end)
+                            })
+                            .collect();
+                        FoldIteration {
+                            value_pos: lore.value_pos,
+                            subtraces,
+                        }
+                    })
+                    .collect();
+
+                nodes.push(TraceNode::Fold { pos, iterations });
+                pos = fold_end;
+            }
+            _ => {
+                nodes.push(TraceNode::State(pos));
+                pos += 1;
+            }
+        }
+    }
+
+    nodes
+}
+
+fn clamp(start: usize, len: u32, end: usize) -> usize {
+    start.saturating_add(len as usize).min(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use air_test_utils::prelude::*;
+
+    use std::fmt::Write as _;
+
+    fn dump(nodes: &[TraceNode], depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        for node in nodes {
+            match node {
+                TraceNode::State(pos) => writeln!(out, "{indent}{pos}").unwrap(),
+                TraceNode::Par { pos, left, right } => {
+                    writeln!(out, "{indent}{pos}: par").unwrap();
+                    dump(left, depth + 1, out);
+                    writeln!(out, "{indent}  --").unwrap();
+                    dump(right, depth + 1, out);
+                }
+                TraceNode::Fold { pos, iterations } => {
+                    writeln!(out, "{indent}{pos}: fold").unwrap();
+                    for iteration in iterations {
+                        writeln!(out, "{indent}  value {}", iteration.value_pos).unwrap();
+                        for subtrace in &iteration.subtraces {
+                            writeln!(out, "{indent}    subtrace").unwrap();
+                            dump(subtrace, depth + 3, out);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn render(trace: &[ExecutedState]) -> String {
+        let mut out = String::new();
+        dump(&build_tree(trace), 0, &mut out);
+        out
+    }
+
+    #[test]
+    fn par_and_fold_snapshot() {
+        let trace = vec![
+            executed_state::ap(0),
+            executed_state::ap(0),
+            executed_state::fold(vec![
+                executed_state::subtrace_lore(
+                    0,
+                    executed_state::subtrace_desc(3, 3),
+                    executed_state::subtrace_desc(7, 0),
+                ),
+                executed_state::subtrace_lore(
+                    1,
+                    executed_state::subtrace_desc(6, 1),
+                    executed_state::subtrace_desc(7, 0),
+                ),
+            ]),
+            executed_state::par(1, 1),
+            executed_state::request_sent_by("peer"),
+            executed_state::request_sent_by("peer"),
+            executed_state::request_sent_by("peer"),
+            executed_state::request_sent_by("peer"),
+        ];
+
+        let expected = r#"0
+1
+2: fold
+  value 0
+    subtrace
+      3: par
+        4
+        --
+        5
+    subtrace
+  value 1
+    subtrace
+      6
+    subtrace
+7
+"#;
+        assert_eq!(render(&trace), expected);
+    }
+
+    #[test]
+    fn malformed_sizes_are_clamped() {
+        let trace = vec![
+            executed_state::par(5, 7),
+            executed_state::request_sent_by("peer"),
+            executed_state::fold(vec![executed_state::subtrace_lore(
+                0,
+                executed_state::subtrace_desc(100, 2),
+                executed_state::subtrace_desc(0, 1),
+            )]),
+        ];
+
+        let expected = r#"0: par
+  1
+  2: fold
+    value 0
+      subtrace
+      subtrace
+  --
+"#;
+        assert_eq!(render(&trace), expected);
+    }
+}
@@ -0,0 +1,92 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::load_interpreter_data_with_versions;
+
+use air_interpreter_data::validation::DataValidator;
+use air_interpreter_data::verification::DataSalts;
+use air_interpreter_signatures::particle_signature_salt;
+use clap::Parser;
+use eyre::Context as _;
+
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[clap(about = "Check AquaVM data consistency without running a script")]
+pub(crate) struct Args {
+    #[clap(
+        long,
+        help = "Particle ID the data belongs to; signatures are not checked without it"
+    )]
+    particle_id: Option<String>,
+
+    #[clap(
+        long,
+        requires = "particle_id",
+        help = "base58-encoded particle signature, the salt of data since version 0.18"
+    )]
+    particle_signature: Option<String>,
+
+    #[arg(help = "Input path")]
+    input: PathBuf,
+}
+
+pub(crate) fn verify(args: Args) -> eyre::Result<()> {
+    let (data, versions) = load_interpreter_data_with_versions(&args.input)?;
+
+    let particle_signature = match &args.particle_signature {
+        Some(particle_signature) => bs58::decode(particle_signature)
+            .into_vec()
+            .context("failed to decode the base58 particle signature")?,
+        None => vec![],
+    };
+    let particle_signature_salt = particle_signature_salt(&particle_signature);
+
+    let mut validator = DataValidator::new(&data);
+    match &args.particle_id {
+        Some(particle_id) => {
+            let salts = DataSalts {
+                particle_id,
+                particle_signature: &particle_signature_salt,
+            };
+            validator = validator.with_signatures(&versions, salts);
+
+            let current_salted = !versions.has_particle_id_salt();
+            if current_salted && args.particle_signature.is_none() {
+                eprintln!(
+                    "warning: particle signature is not provided, data of version {} is salted with it",
+                    versions.data_version
+                );
+            }
+        }
+        None => eprintln!("warning: particle ID is not provided, signatures are not checked"),
+    }
+
+    let errors = validator.validate();
+    for error in &errors {
+        println!("{error}");
+    }
+
+    if errors.is_empty() {
+        println!("data is valid");
+        Ok(())
+    } else {
+        Err(eyre::eyre!("{} problem(s) found", errors.len()))
+    }
+}
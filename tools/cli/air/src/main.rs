@@ -33,6 +33,7 @@
 
 mod beautify;
 mod data;
+mod minimize;
 mod trace;
 
 use clap::Parser;
@@ -50,6 +51,8 @@ enum Subcommand {
     Beautify(self::beautify::Args),
     #[clap(alias = "d")]
     Data(self::data::Args),
+    #[clap(alias = "m")]
+    Minimize(self::minimize::Args),
     #[clap(alias = "r")]
     Run(self::trace::run::Args),
     #[clap(alias = "s")]
@@ -61,7 +64,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Cli::parse();
     match args.subcommand {
         Subcommand::Beautify(args) => self::beautify::beautify(args)?,
-        Subcommand::Data(args) => self::data::to_human_readable_data(args).await?,
+        Subcommand::Data(args) => self::data::data(args).await?,
+        Subcommand::Minimize(args) => self::minimize::minimize(args).await?,
         Subcommand::Run(args) => self::trace::run::run(args).await?,
         Subcommand::Stats(args) => self::trace::stats::stats(args)?,
     }
@@ -0,0 +1,581 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+mod air_tree;
+
+use self::air_tree::AirTree;
+use crate::trace::run::create_runner;
+use crate::trace::run::data::anomaly::AnomalyDataArgs;
+use crate::trace::run::init_tracing;
+use crate::trace::run::runner::AirRunner;
+use crate::trace::run::Keys;
+use crate::trace::run::Mode;
+use crate::trace::run::ModeArgs;
+
+use air_interpreter_data::InterpreterData;
+use air_interpreter_data::InterpreterDataEnvelope;
+use avm_data_store::AnomalyData;
+use avm_interface::raw_outcome::RawAVMOutcome;
+use avm_interface::CallResults;
+use avm_interface::ParticleParameters;
+use clap::Parser;
+use eyre::Context as _;
+use fluence_keypair::KeyPair;
+
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+#[derive(Parser, Debug)]
+#[clap(about = "Shrink anomaly data to the smallest reproducer keeping a predicate")]
+pub(crate) struct Args {
+    #[clap(long, default_value = "error")]
+    tracing_params: String,
+
+    #[clap(flatten)]
+    mode: ModeArgs,
+
+    #[clap(
+        long = "interpreter",
+        env = "AIR_INTERPRETER_WASM_PATH",
+        default_value = "target/wasm32-wasi/release/air_interpreter_server.wasm"
+    )]
+    air_interpreter_path: PathBuf,
+
+    #[clap(
+        long = "near-contract",
+        env = "AIR_NEAR_CONTRACT_PATH",
+        default_value = "tools/wasm/air-near-contract/target/wasm32-unknown-unknown/release/air-near-contract.wasm"
+    )]
+    air_near_contract_path: PathBuf,
+
+    #[clap(long)]
+    max_heap_size: Option<u64>,
+
+    #[command(flatten)]
+    keys: Keys,
+
+    #[command(flatten)]
+    predicate: PredicateArgs,
+
+    #[clap(long, short, help = "Write the reproducer to a file instead of stdout")]
+    output: Option<PathBuf>,
+
+    #[clap(flatten)]
+    anomaly: AnomalyDataArgs,
+}
+
+/// Properties of the original execution a reproducer has to keep, all of the chosen ones
+/// should hold. The same ret_code is checked if nothing is chosen.
+#[derive(clap::Args, Debug)]
+struct PredicateArgs {
+    #[arg(long, help = "The reproducer returns the same ret_code")]
+    same_ret_code: bool,
+
+    #[arg(long, help = "The reproducer returns the same error message")]
+    same_error: bool,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "The reproducer executes at least this time"
+    )]
+    min_time: Option<u64>,
+}
+
+struct Predicate {
+    ret_code: Option<i64>,
+    error_message: Option<String>,
+    min_time: Option<Duration>,
+}
+
+impl Predicate {
+    fn new(args: &PredicateArgs, outcome: &RawAVMOutcome) -> Self {
+        let same_ret_code = args.same_ret_code || (!args.same_error && args.min_time.is_none());
+
+        Self {
+            ret_code: same_ret_code.then_some(outcome.ret_code),
+            error_message: args.same_error.then(|| outcome.error_message.clone()),
+            min_time: args.min_time.map(Duration::from_millis),
+        }
+    }
+
+    fn holds(&self, outcome: &RawAVMOutcome, execution_time: Duration) -> bool {
+        self.ret_code
+            .map_or(true, |ret_code| ret_code == outcome.ret_code)
+            && self
+                .error_message
+                .as_ref()
+                .map_or(true, |message| message == &outcome.error_message)
+            && self
+                .min_time
+                .map_or(true, |min_time| execution_time >= min_time)
+    }
+}
+
+#[derive(Clone)]
+struct TestCase {
+    air_script: String,
+    prev_data: Vec<u8>,
+    /// Current data of the call, several ones are executed at once.
+    current_data: Vec<Vec<u8>>,
+    call_results: CallResults,
+}
+
+#[derive(Clone, Copy)]
+enum DataKind {
+    Prev,
+    Current(usize),
+}
+
+impl TestCase {
+    fn data(&self, kind: DataKind) -> &[u8] {
+        match kind {
+            DataKind::Prev => &self.prev_data,
+            DataKind::Current(idx) => &self.current_data[idx],
+        }
+    }
+
+    fn with_data(&self, kind: DataKind, data: Vec<u8>) -> Self {
+        let mut case = self.clone();
+        match kind {
+            DataKind::Prev => case.prev_data = data,
+            DataKind::Current(idx) => case.current_data[idx] = data,
+        }
+        case
+    }
+
+    /// Drop the data: a call is removed at all unless it is the only one.
+    fn without_data(&self, kind: DataKind) -> Self {
+        match kind {
+            DataKind::Current(idx) if self.current_data.len() > 1 => {
+                let mut case = self.clone();
+                case.current_data.remove(idx);
+                case
+            }
+            _ => self.with_data(kind, vec![]),
+        }
+    }
+
+    fn current_data_size(&self) -> usize {
+        self.current_data.iter().map(Vec::len).sum()
+    }
+
+    fn size(&self) -> (usize, usize, usize, usize, usize) {
+        (
+            self.air_script.len(),
+            self.call_results.len(),
+            self.prev_data.len(),
+            self.current_data.len(),
+            self.current_data_size(),
+        )
+    }
+}
+
+pub(crate) async fn minimize(args: Args) -> eyre::Result<()> {
+    init_tracing(args.tracing_params.clone(), 1);
+
+    let execution_data = crate::trace::run::data::anomaly::load(&args.anomaly)?;
+    let call_results = crate::trace::run::data::anomaly::load_call_results(&args.anomaly)?;
+
+    let mode: Option<Mode> = args.mode.into();
+    // a truncated trace doesn't match the signatures of its peers, and they can't be re-signed
+    // without the peers' keys, so traces are truncated only if signatures are not checked
+    let truncate_traces = matches!(mode, Some(Mode::Native)) && !cfg!(feature = "check_signatures");
+    if !truncate_traces {
+        eprintln!(
+            "traces are not truncated as the runner checks signatures, \
+             use --native with a build without the check_signatures feature"
+        );
+    }
+
+    let runner = create_runner(
+        mode,
+        &args.air_interpreter_path,
+        &args.air_near_contract_path,
+        args.max_heap_size,
+        execution_data.test_init_parameters,
+    )
+    .await?;
+    let key_pair = args
+        .keys
+        .get_keypair()
+        .context("failed to get the keypair")?;
+
+    let mut minimizer = Minimizer {
+        runner,
+        particle: execution_data.particle,
+        key_pair,
+        tracing_params: args.tracing_params,
+        truncate_traces,
+        runs: 0,
+    };
+    let case = TestCase {
+        air_script: execution_data.air_script,
+        prev_data: execution_data.prev_data,
+        current_data: std::iter::once(execution_data.current_data)
+            .chain(execution_data.extra_current_data)
+            .collect(),
+        call_results,
+    };
+
+    let (outcome, execution_time) = minimizer
+        .execute(&case)
+        .await
+        .context("Failed to execute the anomaly")?;
+    let predicate = Predicate::new(&args.predicate, &outcome);
+    eyre::ensure!(
+        predicate.holds(&outcome, execution_time),
+        "The predicate doesn't hold for the anomaly itself"
+    );
+
+    let original_size = case.size();
+    let case = minimizer.minimize(&predicate, case).await?;
+    let (outcome, execution_time) = minimizer.execute(&case).await?;
+    eprintln!(
+        "minimized in {} runs: script {} -> {} bytes, call results {} -> {}, \
+         prev_data {} -> {} bytes, current_data {} -> {} blobs, {} -> {} bytes",
+        minimizer.runs,
+        original_size.0,
+        case.air_script.len(),
+        original_size.1,
+        case.call_results.len(),
+        original_size.2,
+        case.prev_data.len(),
+        original_size.3,
+        case.current_data.len(),
+        original_size.4,
+        case.current_data_size(),
+    );
+    if let Ok(tree) = AirTree::parse(case.air_script.clone()) {
+        eprintln!("{}", tree.beautify());
+    }
+
+    let particle = serde_json::to_vec(&minimizer.particle)?;
+    let call_results = serde_json::to_vec(&case.call_results)?;
+    let outcome = serde_json::to_vec(&outcome)?;
+    let anomaly_data = AnomalyData::new(
+        &case.air_script,
+        &particle,
+        &case.prev_data,
+        &case.current_data[0],
+        &call_results,
+        &outcome,
+        execution_time,
+        0,
+    )
+    .with_extra_current_data(&case.current_data[1..]);
+    let anomaly_json = serde_json::to_vec(&anomaly_data)?;
+
+    match args.output {
+        Some(output) => std::fs::write(&output, anomaly_json)
+            .with_context(|| output.to_string_lossy().into_owned())?,
+        None => println!("{}", String::from_utf8_lossy(&anomaly_json)),
+    }
+
+    Ok(())
+}
+
+struct Minimizer<'ctx> {
+    runner: Box<dyn AirRunner>,
+    particle: ParticleParameters<'ctx>,
+    key_pair: KeyPair,
+    tracing_params: String,
+    truncate_traces: bool,
+    runs: usize,
+}
+
+impl Minimizer<'_> {
+    /// Shrink the script, call results and traces while the predicate holds, until none
+    /// of them could be shrunk further.
+    async fn minimize(
+        &mut self,
+        predicate: &Predicate,
+        mut case: TestCase,
+    ) -> eyre::Result<TestCase> {
+        loop {
+            let size = case.size();
+
+            case = self.shrink_air(predicate, case).await?;
+            case = self.shrink_call_results(predicate, case).await?;
+            case = self.shrink_data(predicate, case, DataKind::Prev).await?;
+            // a removed data doesn't shift the data before it
+            for idx in (0..case.current_data.len()).rev() {
+                case = self
+                    .shrink_data(predicate, case, DataKind::Current(idx))
+                    .await?;
+            }
+
+            if case.size() == size {
+                return Ok(case);
+            }
+        }
+    }
+
+    /// Replace instructions with `(null)` or their children, going from the root, so big
+    /// subtrees are removed first.
+    async fn shrink_air(
+        &mut self,
+        predicate: &Predicate,
+        mut case: TestCase,
+    ) -> eyre::Result<TestCase> {
+        let mut tree =
+            AirTree::parse(case.air_script.clone()).context("Failed to parse the script")?;
+
+        let mut position = 0;
+        while position < tree.instruction_count() {
+            let mut shrunk = false;
+
+            for edit in tree.edits(position) {
+                // there is no point in executing scripts the interpreter rejects
+                let Some(candidate_tree) = tree.apply(position, edit) else {
+                    continue;
+                };
+
+                let candidate = TestCase {
+                    air_script: candidate_tree.to_string(),
+                    ..case.clone()
+                };
+                if self.check(predicate, &candidate).await {
+                    tree = candidate_tree;
+                    case = candidate;
+                    shrunk = true;
+                    break;
+                }
+            }
+
+            if !shrunk {
+                position += 1;
+            }
+        }
+
+        Ok(case)
+    }
+
+    /// Remove call results by chunks of decreasing size.
+    async fn shrink_call_results(
+        &mut self,
+        predicate: &Predicate,
+        mut case: TestCase,
+    ) -> eyre::Result<TestCase> {
+        let mut call_results: Vec<_> = case.call_results.clone().into_iter().collect();
+        call_results.sort_by_key(|(id, _)| *id);
+
+        let mut chunk = call_results.len();
+        while chunk > 0 {
+            let mut start = 0;
+            while start < call_results.len() {
+                let mut remaining = call_results.clone();
+                remaining.drain(start..(start + chunk).min(call_results.len()));
+
+                let candidate = TestCase {
+                    call_results: remaining.iter().cloned().collect(),
+                    ..case.clone()
+                };
+                if self.check(predicate, &candidate).await {
+                    call_results = remaining;
+                    case = candidate;
+                } else {
+                    start += chunk;
+                }
+            }
+            chunk /= 2;
+        }
+
+        Ok(case)
+    }
+
+    /// Drop the data at all or truncate its trace by chunks of decreasing size.
+    async fn shrink_data(
+        &mut self,
+        predicate: &Predicate,
+        case: TestCase,
+        kind: DataKind,
+    ) -> eyre::Result<TestCase> {
+        if case.data(kind).is_empty() {
+            return Ok(case);
+        }
+
+        let candidate = case.without_data(kind);
+        if self.check(predicate, &candidate).await {
+            return Ok(candidate);
+        }
+
+        if !self.truncate_traces {
+            return Ok(case);
+        }
+
+        let Ok(envelope) = InterpreterDataEnvelope::try_from_slice(case.data(kind)) else {
+            return Ok(case);
+        };
+        let Ok(mut data) = InterpreterData::try_from_slice(&envelope.inner_data) else {
+            return Ok(case);
+        };
+        let versions = envelope.versions.clone();
+        let mut case = case;
+
+        let mut trace = data.trace.to_vec();
+        let mut chunk = trace.len() / 2;
+        while chunk > 0 {
+            let truncated = trace[..trace.len() - chunk].to_vec();
+            data.trace = truncated.clone().into();
+            let serialized =
+                InterpreterDataEnvelope::from_interpreter_data(&data, versions.clone())
+                    .serialize()
+                    .context("Failed to serialize the truncated data")?;
+
+            let candidate = case.with_data(kind, serialized);
+            if self.check(predicate, &candidate).await {
+                trace = truncated;
+                case = candidate;
+                chunk = chunk.min(trace.len());
+            } else {
+                chunk /= 2;
+            }
+        }
+
+        Ok(case)
+    }
+
+    async fn check(&mut self, predicate: &Predicate, case: &TestCase) -> bool {
+        self.runs += 1;
+
+        match self.execute(case).await {
+            Ok((outcome, execution_time)) => predicate.holds(&outcome, execution_time),
+            Err(error) => {
+                tracing::debug!(%error, "candidate execution failed");
+                false
+            }
+        }
+    }
+
+    /// Execute the case with all its current data at once.
+    async fn execute(&mut self, case: &TestCase) -> eyre::Result<(RawAVMOutcome, Duration)> {
+        let particle = &self.particle;
+        let start = Instant::now();
+
+        let outcome = self
+            .runner
+            .call_tracing(
+                case.air_script.clone(),
+                case.prev_data.clone(),
+                case.current_data.clone(),
+                particle.init_peer_id.clone().into_owned(),
+                particle.timestamp,
+                particle.ttl,
+                particle.current_peer_id.clone().into(),
+                case.call_results.clone(),
+                self.tracing_params.clone(),
+                1,
+                &self.key_pair,
+                particle.particle_id.clone().into_owned(),
+                particle.particle_signature.to_vec(),
+            )
+            .await?;
+
+        Ok((outcome, start.elapsed()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::run::native::create_native_avm_runner;
+    use crate::trace::run::runner::TestInitParameters;
+
+    use air_test_utils::prelude::*;
+    use avm_interface::CallServiceResult;
+
+    fn minimizer() -> Minimizer<'static> {
+        Minimizer {
+            runner: create_native_avm_runner(TestInitParameters::no_limits()).unwrap(),
+            particle: ParticleParameters::new(
+                "init_peer_id".into(),
+                "particle_id".into(),
+                TEST_PARTICLE_SIGNATURE.into(),
+                0,
+                0,
+                "test_peer".into(),
+            ),
+            key_pair: KeyPair::generate_ed25519(),
+            tracing_params: "error".to_owned(),
+            truncate_traces: true,
+            runs: 0,
+        }
+    }
+
+    async fn minimize(case: TestCase) -> TestCase {
+        let mut minimizer = minimizer();
+        let (outcome, _) = minimizer.execute(&case).await.unwrap();
+        let predicate_args = PredicateArgs {
+            same_ret_code: true,
+            same_error: false,
+            min_time: None,
+        };
+        let predicate = Predicate::new(&predicate_args, &outcome);
+
+        minimizer.minimize(&predicate, case).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn unneeded_parts_are_removed() {
+        let air_script = r#"
+            (seq
+                (seq
+                    (ap 1 $s)
+                    (new $t
+                        (ap 2 $t)))
+                (xor
+                    (match 1 2
+                        (null))
+                    (fail 42 "boom")))
+        "#;
+        let mut case = TestCase {
+            air_script: air_script.to_owned(),
+            prev_data: vec![],
+            current_data: vec![vec![]],
+            call_results: [(1, CallServiceResult::ok(json!("result")))].into(),
+        };
+        // data of the previous execution of the script
+        let current_data = minimizer().execute(&case).await.unwrap().0.data;
+        case.current_data = vec![current_data.clone(), current_data];
+
+        let case = minimize(case).await;
+
+        assert_eq!(case.air_script, r#"(fail 42 "boom")"#);
+        assert!(case.prev_data.is_empty());
+        assert_eq!(case.current_data, vec![Vec::<u8>::new()]);
+        assert!(case.call_results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn needed_data_is_kept() {
+        let case = TestCase {
+            air_script: r#"(call "other_peer" ("svc" "f") [] x)"#.to_owned(),
+            prev_data: vec![],
+            current_data: vec![b"not a data".to_vec()],
+            call_results: <_>::default(),
+        };
+
+        let case = minimize(case).await;
+
+        assert_eq!(case.air_script, "(null)");
+        assert_eq!(case.current_data, vec![b"not a data".to_vec()]);
+    }
+}
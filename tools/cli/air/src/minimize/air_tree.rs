@@ -0,0 +1,265 @@
+/*
+ * AquaVM Workflow Engine
+ *
+ * Copyright (C) 2024 Fluence DAO
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation version 3 of the
+ * License.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Shrinking of AIR scripts by editing instructions of their `air-parser` AST.
+
+use air_parser::ast::Instruction;
+
+use std::fmt;
+use std::fmt::Write as _;
+
+/// A valid AIR script, which instructions are addressed by their pre-order positions.
+#[derive(Clone, Debug)]
+pub(crate) struct AirTree {
+    air_script: String,
+}
+
+/// A way to shrink an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Edit {
+    /// Replace the instruction with `(null)`.
+    Nullify,
+    /// Replace the instruction with its nth child instruction, e.g. with a `par` branch.
+    Hoist(usize),
+}
+
+impl AirTree {
+    pub(crate) fn parse(air_script: String) -> eyre::Result<Self> {
+        air_parser::parse_quiet(&air_script).map_err(|e| eyre::eyre!(e))?;
+        Ok(Self { air_script })
+    }
+
+    /// Number of instructions in the script.
+    pub(crate) fn instruction_count(&self) -> usize {
+        count_instructions(&self.ast())
+    }
+
+    /// Edits applicable to the instruction at the pre-order position.
+    pub(crate) fn edits(&self, position: usize) -> Vec<Edit> {
+        let ast = self.ast();
+        let Some(instruction) = find_instruction(&ast, position) else {
+            return vec![];
+        };
+
+        let nullify = match instruction {
+            Instruction::Null(_) => None,
+            _ => Some(Edit::Nullify),
+        };
+        let hoists = (0..children(instruction).len()).map(Edit::Hoist);
+        nullify.into_iter().chain(hoists).collect()
+    }
+
+    /// The script with the instruction at the pre-order position edited, if it is still
+    /// a valid script, e.g. a fold body without `next` can't be hoisted from `seq`.
+    pub(crate) fn apply(&self, position: usize, edit: Edit) -> Option<Self> {
+        let mut printer = Printer {
+            air_script: String::new(),
+            position: 0,
+            edit: Some((position, edit)),
+        };
+        printer.print(&self.ast());
+
+        air_parser::parse_quiet(&printer.air_script).ok()?;
+        Some(Self {
+            air_script: printer.air_script,
+        })
+    }
+
+    /// Human-readable form of the script.
+    pub(crate) fn beautify(&self) -> String {
+        air_beautifier::beautify_to_string(&self.air_script).unwrap_or_else(|_| self.to_string())
+    }
+
+    fn ast(&self) -> Instruction<'_> {
+        air_parser::parse_quiet(&self.air_script).expect("the script is validated on creation")
+    }
+}
+
+impl fmt::Display for AirTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.air_script)
+    }
+}
+
+/// Prints the AST back to AIR, applying the edit on the way.
+struct Printer {
+    air_script: String,
+    position: usize,
+    edit: Option<(usize, Edit)>,
+}
+
+impl Printer {
+    fn print(&mut self, instruction: &Instruction<'_>) {
+        let position = self.position;
+        self.position += 1;
+
+        match self.edit {
+            Some((edit_position, edit)) if edit_position == position => {
+                // the rest of positions don't matter after the only edit
+                self.edit = None;
+                match edit {
+                    Edit::Nullify => self.air_script.push_str("(null)"),
+                    Edit::Hoist(child) => self.print(children(instruction)[child]),
+                }
+            }
+            _ => {
+                // the Display of an instruction is its header without children
+                write!(self.air_script, "({instruction}").unwrap();
+                for child in children(instruction) {
+                    self.air_script.push(' ');
+                    self.print(child);
+                }
+                self.air_script.push(')');
+            }
+        }
+    }
+}
+
+fn children<'a, 'i>(instruction: &'a Instruction<'i>) -> Vec<&'a Instruction<'i>> {
+    match instruction {
+        Instruction::Seq(seq) => vec![&seq.0, &seq.1],
+        Instruction::Par(par) => vec![&par.0, &par.1],
+        Instruction::Xor(xor) => vec![&xor.0, &xor.1],
+        Instruction::Match(match_) => vec![&match_.instruction],
+        Instruction::MisMatch(mismatch) => vec![&mismatch.instruction],
+        Instruction::New(new) => vec![&new.instruction],
+        Instruction::FoldScalar(fold) => fold_children(&fold.instruction, &fold.last_instruction),
+        Instruction::FoldStream(fold) => fold_children(&fold.instruction, &fold.last_instruction),
+        Instruction::FoldStreamMap(fold) => {
+            fold_children(&fold.instruction, &fold.last_instruction)
+        }
+        Instruction::Call(_)
+        | Instruction::Ap(_)
+        | Instruction::ApMap(_)
+        | Instruction::Canon(_)
+        | Instruction::CanonMap(_)
+        | Instruction::CanonStreamMapScalar(_)
+        | Instruction::Fail(_)
+        | Instruction::Never(_)
+        | Instruction::Next(_)
+        | Instruction::Null(_)
+        | Instruction::Error => vec![],
+    }
+}
+
+fn fold_children<'a, 'i>(
+    instruction: &'a Instruction<'i>,
+    last_instruction: &'a Option<std::rc::Rc<Instruction<'i>>>,
+) -> Vec<&'a Instruction<'i>> {
+    std::iter::once(instruction)
+        .chain(last_instruction.as_deref())
+        .collect()
+}
+
+fn count_instructions(instruction: &Instruction<'_>) -> usize {
+    1 + children(instruction)
+        .into_iter()
+        .map(count_instructions)
+        .sum::<usize>()
+}
+
+fn find_instruction<'a, 'i>(
+    instruction: &'a Instruction<'i>,
+    position: usize,
+) -> Option<&'a Instruction<'i>> {
+    if position == 0 {
+        return Some(instruction);
+    }
+
+    let mut position = position - 1;
+    for child in children(instruction) {
+        let count = count_instructions(child);
+        if position < count {
+            return find_instruction(child, position);
+        }
+        position -= count;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"
+        ; the comment is dropped
+        (seq
+            (call "peer" ("svc" "f") [] x)
+            (par
+                (ap x $s)
+                (fold $s i
+                    (seq
+                        (call "peer" ("svc" "g") [i] y)
+                        (next i)))))
+    "#;
+
+    #[test]
+    fn edits_are_addressed_in_pre_order() {
+        let tree = AirTree::parse(SCRIPT.to_owned()).unwrap();
+
+        assert_eq!(tree.instruction_count(), 8);
+        assert_eq!(
+            tree.edits(0),
+            vec![Edit::Nullify, Edit::Hoist(0), Edit::Hoist(1)]
+        );
+        assert_eq!(tree.edits(1), vec![Edit::Nullify]);
+        assert_eq!(tree.edits(4), vec![Edit::Nullify, Edit::Hoist(0)]);
+        assert_eq!(tree.edits(8), vec![]);
+    }
+
+    #[test]
+    fn nullify_keeps_the_rest_of_script() {
+        let tree = AirTree::parse(SCRIPT.to_owned()).unwrap();
+
+        let tree = tree.apply(6, Edit::Nullify).unwrap();
+
+        assert_eq!(
+            tree.to_string(),
+            r#"(seq (call "peer" ("svc" "f") [] x) (par (ap x $s) (fold $s i (seq (null) (next i)))))"#
+        );
+        assert_eq!(tree.instruction_count(), 8);
+        assert_eq!(tree.edits(6), vec![]);
+    }
+
+    #[test]
+    fn hoist_replaces_instruction_with_child() {
+        let tree = AirTree::parse(SCRIPT.to_owned()).unwrap();
+
+        let tree = tree.apply(2, Edit::Hoist(0)).unwrap();
+
+        assert_eq!(
+            tree.to_string(),
+            r#"(seq (call "peer" ("svc" "f") [] x) (ap x $s))"#
+        );
+        assert_eq!(tree.instruction_count(), 3);
+    }
+
+    #[test]
+    fn invalid_edits_are_rejected() {
+        let tree = AirTree::parse(SCRIPT.to_owned()).unwrap();
+
+        // the fold body with the iterator out of the fold
+        assert!(tree.apply(4, Edit::Hoist(0)).is_none());
+        // `x` is not defined without the call
+        assert!(tree.apply(1, Edit::Nullify).is_none());
+        // `$s` is not defined without the `ap`
+        assert!(tree.apply(2, Edit::Hoist(1)).is_none());
+    }
+}
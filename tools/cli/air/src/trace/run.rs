@@ -17,7 +17,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-mod data;
+pub(crate) mod data;
 pub(crate) mod native;
 #[cfg(feature = "near")]
 mod near;
@@ -99,7 +99,7 @@ enum Source {
 
 #[derive(clap::Args, Debug)]
 #[group(required = true, multiple = false)]
-struct Keys {
+pub(crate) struct Keys {
     #[arg(long)]
     random_key: bool,
     #[arg(long)]
@@ -107,7 +107,7 @@ struct Keys {
 }
 
 impl Keys {
-    fn get_keypair(&self) -> eyre::Result<KeyPair> {
+    pub(crate) fn get_keypair(&self) -> eyre::Result<KeyPair> {
         match (self.random_key, self.ed25519_key.as_ref()) {
             (true, None) => Ok(KeyPair::generate_ed25519()),
             (false, Some(path)) => load_keypair_ed25519(path),
@@ -118,7 +118,7 @@ impl Keys {
 
 #[derive(clap::Args, Debug, Copy, Clone)]
 #[group(multiple = false)]
-struct ModeArgs {
+pub(crate) struct ModeArgs {
     #[arg(long)]
     native: bool,
 
@@ -160,7 +160,7 @@ impl From<ModeArgs> for Option<Mode> {
     }
 }
 
-enum Mode {
+pub(crate) enum Mode {
     Native,
 
     #[cfg(feature = "wasm")]
@@ -209,13 +209,19 @@ pub(crate) async fn run(args: Args) -> eyre::Result<()> {
         .get_keypair()
         .context("failed to get the keypair")?;
 
+    // several current data are executed at once, as the interpreter received them
+    let all_current_data: Vec<_> = std::iter::once(&execution_data.current_data)
+        .chain(&execution_data.extra_current_data)
+        .cloned()
+        .collect();
+
     let repeat = args.repeat.unwrap_or(1);
     for _ in 0..repeat {
         let result = runner
             .call_tracing(
                 execution_data.air_script.clone(),
                 execution_data.prev_data.clone(),
-                execution_data.current_data.clone(),
+                all_current_data.clone(),
                 particle.init_peer_id.clone().into_owned(),
                 particle.timestamp,
                 particle.ttl,
@@ -225,6 +231,7 @@ pub(crate) async fn run(args: Args) -> eyre::Result<()> {
                 tracing_json,
                 &key_pair,
                 particle.particle_id.clone().into_owned(),
+                particle.particle_signature.to_vec(),
             )
             .await
             .context("Failed to execute the script")?;
@@ -239,7 +246,7 @@ pub(crate) async fn run(args: Args) -> eyre::Result<()> {
     Ok(())
 }
 
-async fn create_runner(
+pub(crate) async fn create_runner(
     mode: Option<Mode>,
     _air_interpreter_wasm_path: &Path,
     _air_contract_wasm_path: &Path,
@@ -22,6 +22,7 @@ use crate::trace::run::runner::TestInitParameters;
 use super::super::load_data;
 use super::ExecutionData;
 use avm_data_store::AnomalyData;
+use avm_interface::CallResults;
 use avm_interface::ParticleParameters;
 
 use clap::Parser;
@@ -42,6 +43,11 @@ pub(crate) fn load(args: &AnomalyDataArgs) -> eyre::Result<super::ExecutionData<
     let air_script = anomaly_data.air_script.to_string();
     let prev_data = anomaly_data.prev_data.to_vec();
     let current_data = anomaly_data.current_data.to_vec();
+    let extra_current_data = anomaly_data
+        .extra_current_data
+        .into_iter()
+        .map(|data| data.into_vec())
+        .collect();
     let particle: ParticleParameters<'static> = serde_json::from_slice(&anomaly_data.particle)
         .context("Anomaly particle is not a valid JSON")?;
     let test_init_parameters = TestInitParameters::no_limits();
@@ -50,7 +56,21 @@ pub(crate) fn load(args: &AnomalyDataArgs) -> eyre::Result<super::ExecutionData<
         air_script,
         prev_data,
         current_data,
+        extra_current_data,
         particle,
         test_init_parameters,
     })
 }
+
+/// Call results the anomalous call was executed with.
+pub(crate) fn load_call_results(args: &AnomalyDataArgs) -> eyre::Result<CallResults> {
+    let anomaly_json = load_data(&args.anomaly_data_path).context("Failed to read anomaly data")?;
+    let anomaly_data: AnomalyData<'_> =
+        serde_json::from_slice(&anomaly_json).context("Failed to parse anomaly data")?;
+
+    if anomaly_data.call_results.is_empty() {
+        return Ok(<_>::default());
+    }
+    serde_json::from_slice(&anomaly_data.call_results)
+        .context("Anomaly call results are not a valid JSON")
+}
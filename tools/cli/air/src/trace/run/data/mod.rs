@@ -27,6 +27,8 @@ use super::runner::TestInitParameters;
 pub(crate) struct ExecutionData<'ctx> {
     pub(crate) air_script: String,
     pub(crate) current_data: Vec<u8>,
+    /// Current data that followed the first one in a call with several current data.
+    pub(crate) extra_current_data: Vec<Vec<u8>>,
     pub(crate) prev_data: Vec<u8>,
     pub(crate) particle: ParticleParameters<'ctx>,
     pub(crate) test_init_parameters: TestInitParameters,
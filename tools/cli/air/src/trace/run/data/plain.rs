@@ -50,6 +50,12 @@ pub(crate) struct PlainDataArgs {
     #[clap(long = "particle-id")]
     particle_id: Option<String>,
 
+    #[clap(
+        long = "particle-signature",
+        help = "base58-encoded particle signature"
+    )]
+    particle_signature: Option<String>,
+
     #[clap(long = "air-size-limit")]
     air_size_limit: Option<u64>,
 
@@ -77,10 +83,17 @@ pub(crate) fn load(args: &PlainDataArgs) -> eyre::Result<ExecutionData<'_>> {
     let ttl = args.ttl.unwrap_or(u32::MAX);
     let init_peer_id = &args.init_peer_id;
     let current_peer_id = &args.current_peer_id;
+    let particle_signature = match &args.particle_signature {
+        Some(particle_signature) => bs58::decode(particle_signature)
+            .into_vec()
+            .context("failed to decode the base58 particle signature")?,
+        None => vec![],
+    };
 
     let particle = ParticleParameters::new(
         init_peer_id.into(),
         args.particle_id.as_deref().unwrap_or_default().into(),
+        particle_signature.into(),
         timestamp,
         ttl,
         current_peer_id.into(),
@@ -97,6 +110,7 @@ pub(crate) fn load(args: &PlainDataArgs) -> eyre::Result<ExecutionData<'_>> {
         air_script,
         prev_data,
         current_data,
+        extra_current_data: vec![],
         particle,
         test_init_parameters,
     })
@@ -40,7 +40,7 @@ impl AirRunner for NativeAvmRunner {
         &'this mut self,
         air: String,
         prev_data: Vec<u8>,
-        data: Vec<u8>,
+        data: Vec<Vec<u8>>,
         init_peer_id: String,
         timestamp: u64,
         ttl: u32,
@@ -51,6 +51,7 @@ impl AirRunner for NativeAvmRunner {
         _tracing_output_mode: u8,
         keypair: &KeyPair,
         particle_id: String,
+        particle_signature: Vec<u8>,
     ) -> LocalBoxFuture<'this, eyre::Result<RawAVMOutcome>> {
         let keypair = keypair.clone();
         async move {
@@ -70,7 +71,7 @@ impl AirRunner for NativeAvmRunner {
                 hard_limit_enabled,
             } = self.aquavm_runtime_limits;
 
-            let outcome = air::execute_air(
+            let outcome = air::execute_air_multi(
                 air,
                 prev_data,
                 data,
@@ -81,11 +82,22 @@ impl AirRunner for NativeAvmRunner {
                     ttl,
                     key_format,
                     secret_key_bytes,
+                    signer_public_key: vec![],
+                    allowed_key_formats: vec![],
+                    allowed_peers: vec![],
+                    denied_peers: vec![],
+                    known_key_peers: vec![],
+                    known_keys: vec![],
+                    particle_id_salt_denied: false,
+                    call_policy: vec![],
                     particle_id,
+                    particle_signature,
                     air_size_limit,
                     particle_size_limit,
                     call_result_size_limit,
                     hard_limit_enabled,
+                    call_provenance_enabled: false,
+                    call_deduplication_enabled: false,
                 },
                 raw_call_results,
             );
@@ -17,6 +17,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use super::runner::single_current_data;
 use super::runner::AirRunner;
 
 use air_interpreter_interface::InterpreterOutcome;
@@ -46,7 +47,7 @@ impl AirRunner for Risc0Runner {
         &mut self,
         air: String,
         prev_data: Vec<u8>,
-        current_data: Vec<u8>,
+        current_data: Vec<Vec<u8>>,
         init_peer_id: String,
         timestamp: u64,
         ttl: u32,
@@ -56,7 +57,9 @@ impl AirRunner for Risc0Runner {
         _tracing_output_mode: u8,
         keypair: &KeyPair,
         particle_id: String,
+        particle_signature: Vec<u8>,
     ) -> eyre::Result<RawAVMOutcome> {
+        let current_data = single_current_data(current_data)?;
         let key_format = keypair.key_format().into();
         let secret_key_bytes = keypair.secret().expect("Failed to get secret key");
 
@@ -67,7 +70,18 @@ impl AirRunner for Risc0Runner {
             ttl,
             key_format,
             secret_key_bytes,
+            signer_public_key: vec![],
+            allowed_key_formats: vec![],
+            allowed_peers: vec![],
+            denied_peers: vec![],
+            known_key_peers: vec![],
+            known_keys: vec![],
+            particle_id_salt_denied: false,
+            call_policy: vec![],
             particle_id,
+            particle_signature,
+            call_provenance_enabled: false,
+            call_deduplication_enabled: false,
         };
 
         let call_results = into_raw_result(call_results);
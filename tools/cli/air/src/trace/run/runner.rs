@@ -27,12 +27,14 @@ use futures::future::LocalBoxFuture;
 use std::error::Error as StdError;
 
 pub(crate) trait AirRunner {
+    /// Execute the script with the current data, several ones are executed at once
+    /// like `execute_air_multi` does.
     #[allow(clippy::too_many_arguments)]
     fn call_tracing<'this>(
         &'this mut self,
         air: String,
         prev_data: Vec<u8>,
-        data: Vec<u8>,
+        data: Vec<Vec<u8>>,
         init_peer_id: String,
         timestamp: u64,
         ttl: u32,
@@ -42,9 +44,22 @@ pub(crate) trait AirRunner {
         tracing_output_mode: u8,
         key_pair: &KeyPair,
         particle_id: String,
+        particle_signature: Vec<u8>,
     ) -> LocalBoxFuture<'this, eyre::Result<RawAVMOutcome>>;
 }
 
+/// The only current data for runners that can't execute several ones at once.
+#[cfg(any(feature = "near", feature = "risc0"))]
+pub(crate) fn single_current_data(data: Vec<Vec<u8>>) -> eyre::Result<Vec<u8>> {
+    match <[Vec<u8>; 1]>::try_from(data) {
+        Ok([data]) => Ok(data),
+        Err(data) => eyre::bail!(
+            "the runner doesn't support {} current data at once",
+            data.len()
+        ),
+    }
+}
+
 pub(crate) trait DataToHumanReadable {
     fn to_human_readable<'this>(
         &'this mut self,
@@ -21,6 +21,8 @@ use super::runner::AirRunner;
 use super::runner::DataToHumanReadable;
 use crate::trace::run::runner::TestInitParameters;
 use air_test_utils::avm_runner::AVMRunner;
+use air_test_utils::avm_runner::CallSigner;
+use air_test_utils::avm_runner::InterpreterRunner;
 use fluence_keypair::KeyPair;
 use futures::future::LocalBoxFuture;
 use futures::FutureExt;
@@ -37,7 +39,7 @@ impl AirRunner for WasmAvmRunner {
         &'this mut self,
         air: String,
         prev_data: Vec<u8>,
-        data: Vec<u8>,
+        data: Vec<Vec<u8>>,
         init_peer_id: String,
         timestamp: u64,
         ttl: u32,
@@ -47,27 +49,50 @@ impl AirRunner for WasmAvmRunner {
         tracing_output_mode: u8,
         keypair: &KeyPair,
         particle_id: String,
+        particle_signature: Vec<u8>,
     ) -> LocalBoxFuture<'this, eyre::Result<avm_interface::raw_outcome::RawAVMOutcome>> {
         let keypair = keypair.clone();
         async move {
-            let call_tracing = self
-                .0
-                .call_tracing(
-                    air,
-                    prev_data,
-                    data,
-                    init_peer_id,
-                    timestamp,
-                    ttl,
-                    current_peer_id,
-                    call_results,
-                    tracing_params,
-                    tracing_output_mode,
-                    keypair.key_format().into(),
-                    keypair.secret().expect("Failed to get secret"),
-                    particle_id,
-                )
-                .await;
+            // the interpreter traces execution with a single current data only
+            let call_tracing = match <[Vec<u8>; 1]>::try_from(data) {
+                Ok([data]) => {
+                    self.0
+                        .call_tracing(
+                            air,
+                            prev_data,
+                            data,
+                            init_peer_id,
+                            timestamp,
+                            ttl,
+                            current_peer_id,
+                            call_results,
+                            tracing_params,
+                            tracing_output_mode,
+                            keypair.key_format().into(),
+                            keypair.secret().expect("Failed to get secret"),
+                            particle_id,
+                            particle_signature,
+                        )
+                        .await
+                }
+                Err(data) => {
+                    self.0
+                        .execute(
+                            air,
+                            prev_data,
+                            data,
+                            init_peer_id,
+                            timestamp,
+                            ttl,
+                            current_peer_id,
+                            call_results,
+                            CallSigner::KeyPair(&keypair),
+                            particle_id,
+                            particle_signature,
+                        )
+                        .await
+                }
+            };
             let memory_stats = self.0.memory_stats();
             tracing::warn!(memory_size = memory_stats.memory_size);
 